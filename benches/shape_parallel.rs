@@ -0,0 +1,39 @@
+use cosmic_text as ct;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+fn shape_parallel(c: &mut Criterion) {
+    let mut fs = ct::FontSystem::new();
+    let mut buffer = ct::Buffer::new(&mut fs, ct::Metrics::new(10.0, 10.0));
+    let text = include_str!("../sample/arabic.txt")
+        .lines()
+        .chain(include_str!("../sample/hebrew.txt").lines())
+        .cycle()
+        .take(5000)
+        .collect::<Vec<_>>()
+        .join("\n");
+    buffer.set_text(&mut fs, &text, &ct::Attrs::new(), ct::Shaping::Advanced);
+
+    let mut group = c.benchmark_group("Shape 5000 lines");
+    group.sample_size(10);
+
+    group.bench_function("serial", |b| {
+        b.iter(|| {
+            for line in buffer.lines.iter_mut() {
+                line.reset_shaping();
+                line.shape(&mut fs, 8);
+            }
+        })
+    });
+
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            for line in buffer.lines.iter_mut() {
+                line.reset_shaping();
+            }
+            buffer.shape_lines_parallel(&mut fs);
+        })
+    });
+}
+
+criterion_group!(benches, shape_parallel);
+criterion_main!(benches);
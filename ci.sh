@@ -37,3 +37,6 @@ build --all-features
 
 echo Run tests
 cargo test
+
+echo Run tests with parallel shaping and the run cache
+cargo test --features rayon,shape-run-cache
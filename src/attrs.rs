@@ -11,8 +11,41 @@ use crate::{CacheKeyFlags, Metrics};
 
 pub use fontdb::{Family, Stretch, Style, Weight};
 
+/// Serde definitions of the font properties from fontdb, which does not implement serde
+#[cfg(feature = "serde")]
+mod serde_fontdb {
+    use super::{Stretch, Style, Weight};
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(remote = "Stretch")]
+    pub enum StretchDef {
+        UltraCondensed,
+        ExtraCondensed,
+        Condensed,
+        SemiCondensed,
+        Normal,
+        SemiExpanded,
+        Expanded,
+        ExtraExpanded,
+        UltraExpanded,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(remote = "Style")]
+    pub enum StyleDef {
+        Normal,
+        Italic,
+        Oblique,
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(remote = "Weight")]
+    pub struct WeightDef(pub u16);
+}
+
 /// Text color
 #[derive(Clone, Copy, Debug, PartialOrd, Ord, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color(pub u32);
 
 impl Color {
@@ -67,6 +100,7 @@ impl Color {
 
 /// An owned version of [`Family`]
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum FamilyOwned {
     Name(SmolStr),
     Serif,
@@ -103,6 +137,7 @@ impl FamilyOwned {
 /// Metrics, but implementing Eq and Hash using u32 representation of f32
 //TODO: what are the edge cases of this?
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CacheMetrics {
     font_size_bits: u32,
     line_height_bits: u32,
@@ -127,6 +162,7 @@ impl From<CacheMetrics> for Metrics {
 }
 /// A 4-byte `OpenType` feature tag identifier
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FeatureTag([u8; 4]);
 
 impl FeatureTag {
@@ -159,12 +195,14 @@ impl FeatureTag {
 }
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Feature {
     pub tag: FeatureTag,
     pub value: u32,
 }
 
 #[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct FontFeatures {
     pub features: Vec<Feature>,
 }
@@ -194,6 +232,7 @@ impl FontFeatures {
 
 /// A wrapper for letter spacing to get around that f32 doesn't implement Eq and Hash
 #[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LetterSpacing(pub f32);
 
 impl PartialEq for LetterSpacing {
@@ -223,6 +262,44 @@ impl Hash for LetterSpacing {
     }
 }
 
+/// A wrapper for a fixed digit width to get around that f32 doesn't implement Eq and Hash
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DigitWidth(pub f32);
+
+impl PartialEq for DigitWidth {
+    fn eq(&self, other: &Self) -> bool {
+        LetterSpacing(self.0) == LetterSpacing(other.0)
+    }
+}
+
+impl Eq for DigitWidth {}
+
+impl Hash for DigitWidth {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        LetterSpacing(self.0).hash(hasher);
+    }
+}
+
+/// A wrapper for opacity to get around that f32 doesn't implement Eq and Hash
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Opacity(pub f32);
+
+impl PartialEq for Opacity {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Opacity {}
+
+impl Hash for Opacity {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.0.to_bits().hash(hasher);
+    }
+}
+
 /// Text attributes
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Attrs<'a> {
@@ -238,6 +315,14 @@ pub struct Attrs<'a> {
     /// Letter spacing (tracking) in EM
     pub letter_spacing_opt: Option<LetterSpacing>,
     pub font_features: FontFeatures,
+    /// Advance forced on the digits `0-9` in EM
+    pub fixed_digit_width_opt: Option<DigitWidth>,
+    /// Opacity multiplied into the alpha of the drawn glyphs
+    pub opacity_opt: Option<Opacity>,
+    /// Glyph IDs replacing the shaped glyph of single character clusters
+    pub glyph_overrides: Vec<(char, u16)>,
+    /// Disable font fallback, so characters the font does not cover are drawn as `.notdef`
+    pub no_fallback: bool,
 }
 
 impl<'a> Attrs<'a> {
@@ -256,6 +341,10 @@ impl<'a> Attrs<'a> {
             metrics_opt: None,
             letter_spacing_opt: None,
             font_features: FontFeatures::new(),
+            fixed_digit_width_opt: None,
+            opacity_opt: None,
+            glyph_overrides: Vec::new(),
+            no_fallback: false,
         }
     }
 
@@ -319,6 +408,44 @@ impl<'a> Attrs<'a> {
         self
     }
 
+    /// Set the advance of the digits `0-9` in EM, centering each digit glyph within it
+    ///
+    /// Unlike the `tnum` feature, this works with any font, which keeps counters and clocks from
+    /// shifting as their digits change. `None` uses the advances from the font.
+    pub fn fixed_digit_width(mut self, width_opt: Option<f32>) -> Self {
+        self.fixed_digit_width_opt = width_opt.map(DigitWidth);
+        self
+    }
+
+    /// Set the opacity of the drawn glyphs, from 0.0 (invisible) to 1.0 (opaque)
+    ///
+    /// The opacity is multiplied into the alpha of the glyph color, including color glyphs. It
+    /// only affects drawing, so changing it does not reshape the text.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity_opt = Some(Opacity(opacity.clamp(0.0, 1.0)));
+        self
+    }
+
+    /// Set glyph IDs to use for characters, replacing the glyphs chosen by shaping
+    ///
+    /// This reaches glyphs of the font that no standard feature selects. Only clusters made of a
+    /// single character shaped to a single glyph are replaced, and their advance is taken from
+    /// the replacement glyph.
+    pub fn glyph_overrides(mut self, glyph_overrides: Vec<(char, u16)>) -> Self {
+        self.glyph_overrides = glyph_overrides;
+        self
+    }
+
+    /// Set whether font fallback is disabled for this span
+    ///
+    /// Characters that the font of the span does not cover are then drawn with the `.notdef`
+    /// glyph of that font instead of a glyph from another font. The font itself is still chosen
+    /// according to [`crate::FontSystem::missing_family`].
+    pub fn no_fallback(mut self, no_fallback: bool) -> Self {
+        self.no_fallback = no_fallback;
+        self
+    }
+
     /// Check if font matches
     pub fn matches(&self, face: &fontdb::FaceInfo) -> bool {
         //TODO: smarter way of including emoji
@@ -332,6 +459,7 @@ impl<'a> Attrs<'a> {
             && self.stretch == other.stretch
             && self.style == other.style
             && self.weight == other.weight
+            && self.no_fallback == other.no_fallback
     }
 }
 
@@ -357,12 +485,16 @@ impl<'a> From<&Attrs<'a>> for FontMatchAttrs {
 
 /// An owned version of [`Attrs`]
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AttrsOwned {
     //TODO: should this be an option?
     pub color_opt: Option<Color>,
     pub family_owned: FamilyOwned,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fontdb::StretchDef"))]
     pub stretch: Stretch,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fontdb::StyleDef"))]
     pub style: Style,
+    #[cfg_attr(feature = "serde", serde(with = "serde_fontdb::WeightDef"))]
     pub weight: Weight,
     pub metadata: usize,
     pub cache_key_flags: CacheKeyFlags,
@@ -370,6 +502,14 @@ pub struct AttrsOwned {
     /// Letter spacing (tracking) in EM
     pub letter_spacing_opt: Option<LetterSpacing>,
     pub font_features: FontFeatures,
+    /// Advance forced on the digits `0-9` in EM
+    pub fixed_digit_width_opt: Option<DigitWidth>,
+    /// Opacity multiplied into the alpha of the drawn glyphs
+    pub opacity_opt: Option<Opacity>,
+    /// Glyph IDs replacing the shaped glyph of single character clusters
+    pub glyph_overrides: Vec<(char, u16)>,
+    /// Disable font fallback, so characters the font does not cover are drawn as `.notdef`
+    pub no_fallback: bool,
 }
 
 impl AttrsOwned {
@@ -385,6 +525,10 @@ impl AttrsOwned {
             metrics_opt: attrs.metrics_opt,
             letter_spacing_opt: attrs.letter_spacing_opt,
             font_features: attrs.font_features.clone(),
+            fixed_digit_width_opt: attrs.fixed_digit_width_opt,
+            opacity_opt: attrs.opacity_opt,
+            glyph_overrides: attrs.glyph_overrides.clone(),
+            no_fallback: attrs.no_fallback,
         }
     }
 
@@ -400,13 +544,57 @@ impl AttrsOwned {
             metrics_opt: self.metrics_opt,
             letter_spacing_opt: self.letter_spacing_opt,
             font_features: self.font_features.clone(),
+            fixed_digit_width_opt: self.fixed_digit_width_opt,
+            opacity_opt: self.opacity_opt,
+            glyph_overrides: self.glyph_overrides.clone(),
+            no_fallback: self.no_fallback,
+        }
+    }
+}
+
+/// Error returned by [`AttrsList::validate`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum AttrsError {
+    /// A span ends past the end of the text
+    OutOfBounds {
+        /// Range of the span
+        range: Range<usize>,
+        /// Length of the text in bytes
+        len: usize,
+    },
+    /// A span starts or ends inside a character
+    NotCharBoundary {
+        /// Range of the span
+        range: Range<usize>,
+        /// Offset of the span edge that is not on a character boundary
+        index: usize,
+    },
+}
+
+impl core::fmt::Display for AttrsError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::OutOfBounds { range, len } => {
+                write!(
+                    f,
+                    "attrs span {range:?} is past the end of the text ({len})"
+                )
+            }
+            Self::NotCharBoundary { range, index } => write!(
+                f,
+                "attrs span {range:?} has an edge at {index} that is inside a character"
+            ),
         }
     }
 }
 
+#[cfg(feature = "std")]
+impl std::error::Error for AttrsError {}
+
 /// List of text attributes to apply to a line
 //TODO: have this clean up the spans when changes are made
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct AttrsList {
     defaults: AttrsOwned,
     pub(crate) spans: RangeMap<usize, AttrsOwned>,
@@ -497,6 +685,93 @@ impl AttrsList {
         new
     }
 
+    /// Check that the spans fit `text`, such as attributes built separately from their text
+    ///
+    /// Spans never overlap, as [`Self::add_span`] replaces the overlapped parts of previous
+    /// spans, so only their bounds are checked.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error for the first span that ends past the end of the text, or that starts
+    /// or ends inside a character.
+    pub fn validate(&self, text: &str) -> Result<(), AttrsError> {
+        for (range, _) in self.spans.iter() {
+            if range.end > text.len() {
+                return Err(AttrsError::OutOfBounds {
+                    range: range.clone(),
+                    len: text.len(),
+                });
+            }
+            for index in [range.start, range.end] {
+                if !text.is_char_boundary(index) {
+                    return Err(AttrsError::NotCharBoundary {
+                        range: range.clone(),
+                        index,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the opacity for an index, 1.0 if it is not set
+    pub(crate) fn opacity_at(&self, index: usize) -> f32 {
+        self.spans
+            .get(&index)
+            .unwrap_or(&self.defaults)
+            .opacity_opt
+            .map_or(1.0, |opacity| opacity.0)
+    }
+
+    /// Check if the defaults or any span set an opacity
+    pub(crate) fn has_opacity(&self) -> bool {
+        self.defaults.opacity_opt.is_some()
+            || self
+                .spans
+                .iter()
+                .any(|(_, attrs)| attrs.opacity_opt.is_some())
+    }
+
+    /// Check if this list only differs from `other` in opacity, which does not affect shaping
+    pub(crate) fn eq_ignoring_opacity(&self, other: &Self) -> bool {
+        self.eq_ignoring(other, |attrs| attrs.opacity_opt = None)
+    }
+
+    /// Check if this list only differs from `other` in attributes that do not affect glyph
+    /// selection or positioning: metadata, cache key flags, opacity, and color unless
+    /// `color_splits_runs`, see [`crate::ShapeOptions::split_runs_on_color`]
+    pub(crate) fn eq_ignoring_presentation(&self, other: &Self, color_splits_runs: bool) -> bool {
+        fn strip(attrs: &mut AttrsOwned) {
+            attrs.metadata = 0;
+            attrs.cache_key_flags = CacheKeyFlags::empty();
+            attrs.opacity_opt = None;
+        }
+        if color_splits_runs {
+            self.eq_ignoring(other, strip)
+        } else {
+            self.eq_ignoring(other, |attrs| {
+                attrs.color_opt = None;
+                strip(attrs);
+            })
+        }
+    }
+
+    /// Compare with `other` after clearing attributes with `strip`
+    fn eq_ignoring(&self, other: &Self, strip: fn(&mut AttrsOwned)) -> bool {
+        let stripped = |list: &Self| {
+            let mut defaults = list.defaults.clone();
+            strip(&mut defaults);
+            let mut spans = RangeMap::new();
+            for (range, attrs) in list.spans.iter() {
+                let mut attrs = attrs.clone();
+                strip(&mut attrs);
+                spans.insert(range.clone(), attrs);
+            }
+            Self { defaults, spans }
+        };
+        stripped(self) == stripped(other)
+    }
+
     /// Resets the attributes with new defaults.
     pub(crate) fn reset(mut self, default: &Attrs) -> Self {
         self.defaults = AttrsOwned::new(default);
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use unicode_bidi::{bidi_class, BidiClass, BidiInfo, ParagraphInfo};
+use unicode_bidi::{bidi_class, BidiClass, BidiInfo, Level, ParagraphInfo};
 
 /// An iterator over the paragraphs in the input text.
 /// It is equivalent to [`core::str::Lines`] but follows `unicode-bidi` behaviour.
@@ -38,3 +38,61 @@ impl<'text> Iterator for BidiParagraphs<'text> {
         }
     }
 }
+
+/// A text direction
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Direction {
+    /// Left to right
+    #[default]
+    Ltr,
+    /// Right to left
+    Rtl,
+}
+
+/// The base direction of a paragraph, see [`crate::BufferLine::set_base_direction`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BaseDirection {
+    /// Detect the direction from the first strong directional character, using `fallback` for
+    /// paragraphs that have none (for example only digits and punctuation)
+    Auto {
+        /// Direction used when the paragraph has no strong directional character
+        fallback: Direction,
+    },
+    /// Force the given direction
+    Explicit(Direction),
+}
+
+impl Default for BaseDirection {
+    fn default() -> Self {
+        Self::Auto {
+            fallback: Direction::Ltr,
+        }
+    }
+}
+
+impl BaseDirection {
+    /// Resolve the direction of the given paragraph text
+    pub fn resolve(self, text: &str) -> Direction {
+        match self {
+            Self::Auto { fallback } => match unicode_bidi::get_base_direction(text) {
+                unicode_bidi::Direction::Ltr => Direction::Ltr,
+                unicode_bidi::Direction::Rtl => Direction::Rtl,
+                unicode_bidi::Direction::Mixed => fallback,
+            },
+            Self::Explicit(direction) => direction,
+        }
+    }
+
+    /// Paragraph level to pass to `unicode_bidi`, `None` keeps its own detection
+    pub(crate) fn default_level(self, text: &str) -> Option<Level> {
+        match self {
+            Self::Auto {
+                fallback: Direction::Ltr,
+            } => None,
+            _ => Some(match self.resolve(text) {
+                Direction::Ltr => Level::ltr(),
+                Direction::Rtl => Level::rtl(),
+            }),
+        }
+    }
+}
@@ -2,15 +2,28 @@
 
 #[cfg(not(feature = "std"))]
 use alloc::{string::String, vec::Vec};
-use core::{cmp, fmt};
+use core::{cmp, fmt, ops::Range};
+use unicode_script::{Script, UnicodeScript};
 use unicode_segmentation::UnicodeSegmentation;
 
+#[cfg(feature = "profiling")]
+use crate::ShapeStats;
 use crate::{
-    Affinity, Align, Attrs, AttrsList, BidiParagraphs, BorrowedWithFontSystem, BufferLine, Color,
-    Cursor, FontSystem, LayoutCursor, LayoutGlyph, LayoutLine, LineEnding, LineIter, Motion,
-    Scroll, ShapeLine, Shaping, Wrap,
+    math, Affinity, Align, Attrs, AttrsList, BidiParagraphs, BorrowedWithFontSystem, BufferLine,
+    CacheKey, CaretMetrics, Color, Cursor, Family, FamilyOwned, FoldState, FontSystem,
+    LayoutCursor, LayoutGlyph, LayoutLine, LineEnding, LineEndingSummary, LineIter, MarkupFormat,
+    Motion, Rect, Scroll, ScrollAlign, ShapeLine, Shaping, Wrap,
 };
 
+/// Returns true for combining marks, which [`Buffer::set_mark_cursor_stops`] stops before
+///
+/// Marks are recognized by their inherited script, without the joiners and variation selectors
+/// that share it, since stopping before those would split emoji and other sequences.
+fn is_combining_mark(c: char) -> bool {
+    c.script() == Script::Inherited
+        && !matches!(c, '\u{200C}' | '\u{200D}' | '\u{FE00}'..='\u{FE0F}' | '\u{E0100}'..='\u{E01EF}')
+}
+
 /// A line of visible text for rendering
 #[derive(Debug)]
 pub struct LayoutRun<'a> {
@@ -97,6 +110,7 @@ pub struct LayoutRunIter<'b> {
     layout_i: usize,
     total_height: f32,
     line_top: f32,
+    folds: FoldTracker,
 }
 
 impl<'b> LayoutRunIter<'b> {
@@ -107,6 +121,7 @@ impl<'b> LayoutRunIter<'b> {
             layout_i: 0,
             total_height: 0.0,
             line_top: 0.0,
+            folds: FoldTracker::default(),
         }
     }
 }
@@ -116,26 +131,31 @@ impl<'b> Iterator for LayoutRunIter<'b> {
 
     fn next(&mut self) -> Option<Self::Item> {
         while let Some(line) = self.buffer.lines.get(self.line_i) {
+            if self.layout_i == 0 && self.folds.visit(self.line_i, line) {
+                self.line_i += 1;
+                continue;
+            }
             let shape = line.shape_opt()?;
             let layout = line.layout_opt()?;
             while let Some(layout_line) = layout.get(self.layout_i) {
                 self.layout_i += 1;
 
-                let line_height = layout_line
-                    .line_height_opt
-                    .unwrap_or(self.buffer.metrics.line_height);
+                let line_height = self.buffer.layout_line_height(layout_line);
                 self.total_height += line_height;
 
                 let line_top = self.line_top - self.buffer.scroll.vertical;
-                let glyph_height = layout_line.max_ascent + layout_line.max_descent;
-                let centering_offset = (line_height - glyph_height) / 2.0;
-                let line_y = line_top + centering_offset + layout_line.max_ascent;
+                let mut line_y = line_top + layout_line.baseline(line_height);
+                if self.buffer.round_line_height {
+                    line_y = math::roundf(line_y);
+                }
                 if let Some(height) = self.buffer.height_opt {
                     if line_y > height {
                         return None;
                     }
                 }
-                self.line_top += line_height;
+                if !self.buffer.single_line {
+                    self.line_top += line_height;
+                }
                 if line_y < 0.0 {
                     continue;
                 }
@@ -159,6 +179,32 @@ impl<'b> Iterator for LayoutRunIter<'b> {
     }
 }
 
+/// Caret geometry at both ends of a cursor move, see [`Buffer::caret_transition`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CaretTransition {
+    /// Caret before the move
+    pub from: Rect,
+    /// Caret after the move
+    pub to: Rect,
+    /// True if the move changes visual line, so an animation should not slide the caret
+    /// straight between the rectangles
+    pub crosses_lines: bool,
+}
+
+/// The kinds of boundaries a cursor is at, see [`Buffer::boundary_info`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BoundaryInfo {
+    /// The cursor is between grapheme clusters, which is true for every valid cursor
+    pub grapheme: bool,
+    /// The cursor is at the start or end of a word, as used by [`Motion::NextWord`] and
+    /// [`Motion::PreviousWord`]
+    pub word: bool,
+    /// The cursor is at the start or end of a visual line, including where the layout wraps
+    pub visual_line: bool,
+    /// The cursor is at the start or end of its line
+    pub paragraph: bool,
+}
+
 /// Metrics of text
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Metrics {
@@ -200,6 +246,146 @@ impl fmt::Display for Metrics {
     }
 }
 
+/// Result of [`Buffer::layout_clipped`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ClipResult {
+    /// True if some content did not fit in the maximum height
+    pub clipped: bool,
+    /// Cursor at the start of the first layout line that did not fit, if clipped
+    pub cursor_opt: Option<Cursor>,
+    /// Number of layout lines that fit
+    pub layout_lines: usize,
+    /// Total height of the layout lines that fit
+    pub height: f32,
+}
+
+/// Result of [`Buffer::layout_columns`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnLayout {
+    /// Columns from first to last, including columns left empty
+    pub columns: Vec<LayoutColumn>,
+    /// Cursor at the start of the first layout line that did not fit in any column
+    pub overflow_opt: Option<Cursor>,
+}
+
+/// A column of a [`ColumnLayout`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LayoutColumn {
+    /// X of the left edge of the column
+    pub x: f32,
+    /// Layout lines placed in the column, from top to bottom
+    pub lines: Vec<ColumnLine>,
+    /// Total height of the layout lines in the column
+    pub height: f32,
+}
+
+/// A layout line placed in a [`LayoutColumn`]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ColumnLine {
+    /// Index of the buffer line
+    pub line_i: usize,
+    /// Index of the layout line in the buffer line
+    pub layout_i: usize,
+    /// Y of the top of the layout line from the top of the column
+    pub y: f32,
+}
+
+/// Where [`Buffer::hit`] places the cursor for clicks beside the text of a layout line
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ClickPadding {
+    /// Use the visually nearest edge of the text: the cursor beside the leftmost glyph for clicks
+    /// left of the text, and beside the rightmost glyph for clicks right of it
+    #[default]
+    Nearest,
+    /// Use the logical ends of the layout line, following its direction: the end for clicks
+    /// after the text, right of an LTR line or left of an RTL line, and the start for clicks
+    /// before it
+    LineEnd,
+    /// Always use the start of the layout line
+    LineStart,
+}
+
+/// Text that no font could display, see [`Buffer::missing_coverage`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MissingRange {
+    /// Index of the line in the buffer
+    pub line_i: usize,
+    /// Byte range in the line text
+    pub range: Range<usize>,
+    /// Script of the text
+    pub script: Script,
+}
+
+/// Text that no font could display, capped to a number of ranges, see
+/// [`Buffer::missing_coverage_capped`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MissingCoverage {
+    /// The first missing ranges, in buffer order
+    pub ranges: Vec<MissingRange>,
+    /// Number of missing ranges left out of [`Self::ranges`]
+    pub more: usize,
+    /// Distinct scripts of all missing ranges, including those left out, in the order they are
+    /// first found
+    pub scripts: Vec<Script>,
+}
+
+/// A glyph ready to be drawn, see [`Buffer::draw_list`]
+#[derive(Clone, Copy, Debug)]
+pub struct DrawGlyph {
+    /// Font id of the glyph
+    pub font_id: fontdb::ID,
+    /// Glyph id in the font
+    pub glyph_id: u16,
+    /// X position to draw the glyph image at, in whole pixels
+    pub x: i32,
+    /// Y position of the baseline to draw the glyph image at, in whole pixels
+    pub y: i32,
+    /// Font size of the glyph
+    pub font_size: f32,
+    /// Color from the glyph attributes, `None` means the renderer default
+    pub color_opt: Option<Color>,
+    /// Opacity from the glyph attributes, to multiply into the alpha of the color
+    pub opacity: f32,
+    /// Cache key, used to look up the glyph image in [`crate::SwashCache`]
+    pub cache_key: CacheKey,
+}
+
+/// Height of a layout line, optionally rounded to whole pixels
+fn layout_line_height(metrics: Metrics, round: bool, layout_line: &LayoutLine) -> f32 {
+    let line_height = layout_line.line_height_opt.unwrap_or(metrics.line_height);
+    if round {
+        math::roundf(line_height)
+    } else {
+        line_height
+    }
+}
+
+/// The range in `folded`, as returned by [`Buffer::folded_ranges`], that hides `line_i`
+fn folded_range(folded: &[Range<usize>], line_i: usize) -> Option<&Range<usize>> {
+    let i = folded.partition_point(|range| range.end <= line_i);
+    folded.get(i).filter(|range| range.start <= line_i)
+}
+
+/// Tracks the lines hidden by [`FoldState::Collapsed`] while visiting lines in order, without
+/// allocating
+#[derive(Debug, Default)]
+struct FoldTracker {
+    hidden_end: usize,
+}
+
+impl FoldTracker {
+    /// Check if `line_i` is hidden by the lines visited before, then record the region `line`
+    /// starts. Visiting the same line again gives the same result.
+    fn visit(&mut self, line_i: usize, line: &BufferLine) -> bool {
+        let hidden = line_i < self.hidden_end;
+        if let FoldState::Collapsed(count) = line.fold_state() {
+            let end = line_i.saturating_add(1).saturating_add(count);
+            self.hidden_end = cmp::max(self.hidden_end, end);
+        }
+        hidden
+    }
+}
+
 /// A buffer of text that is shaped and laid out
 #[derive(Debug)]
 pub struct Buffer {
@@ -214,20 +400,25 @@ pub struct Buffer {
     wrap: Wrap,
     monospace_width: Option<f32>,
     tab_width: u16,
+    fallback_families: Vec<FamilyOwned>,
+    default_shaping: Shaping,
+    round_line_height: bool,
+    click_padding: ClickPadding,
+    line_fingerprints: Vec<u64>,
+    single_line: bool,
+    line_join: char,
+    single_line_count: usize,
+    mark_cursor_stops: bool,
 }
 
 impl Clone for Buffer {
     fn clone(&self) -> Self {
         Self {
-            lines: self.lines.clone(),
-            metrics: self.metrics,
-            width_opt: self.width_opt,
-            height_opt: self.height_opt,
             scroll: self.scroll,
             redraw: self.redraw,
-            wrap: self.wrap,
-            monospace_width: self.monospace_width,
-            tab_width: self.tab_width,
+            line_fingerprints: self.line_fingerprints.clone(),
+            single_line_count: self.single_line_count,
+            ..self.with_lines(self.lines.clone())
         }
     }
 }
@@ -256,6 +447,15 @@ impl Buffer {
             wrap: Wrap::WordOrGlyph,
             monospace_width: None,
             tab_width: 8,
+            fallback_families: Vec::new(),
+            default_shaping: Shaping::Advanced,
+            round_line_height: false,
+            click_padding: ClickPadding::default(),
+            line_fingerprints: Vec::new(),
+            single_line: false,
+            line_join: ' ',
+            single_line_count: 0,
+            mark_cursor_stops: false,
         }
     }
 
@@ -285,17 +485,24 @@ impl Buffer {
         #[cfg(all(feature = "std", not(target_arch = "wasm32")))]
         let instant = std::time::Instant::now();
 
-        for line in &mut self.lines {
-            if line.shape_opt().is_some() {
+        if self.single_line {
+            for line in &mut self.lines {
                 line.reset_layout();
-                line.layout(
-                    font_system,
-                    self.metrics.font_size,
-                    self.width_opt,
-                    self.wrap,
-                    self.monospace_width,
-                    self.tab_width,
-                );
+            }
+            self.layout_single_line(font_system);
+        } else {
+            for line in &mut self.lines {
+                if line.shape_opt().is_some() {
+                    line.reset_layout();
+                    line.layout(
+                        font_system,
+                        self.metrics.font_size,
+                        self.width_opt,
+                        self.wrap,
+                        self.monospace_width,
+                        self.tab_width,
+                    );
+                }
             }
         }
 
@@ -305,6 +512,71 @@ impl Buffer {
         log::debug!("relayout: {:?}", instant.elapsed());
     }
 
+    /// Lay out all lines one after another on a single visual line, see [`Self::set_single_line`]
+    ///
+    /// Does nothing if every line still has the layout from the previous call.
+    fn layout_single_line(&mut self, font_system: &mut FontSystem) {
+        if self.lines.len() == self.single_line_count
+            && self.lines.iter().all(|line| line.layout_opt().is_some())
+        {
+            return;
+        }
+
+        let mut x = 0.0;
+        let line_count = self.lines.len();
+        for (line_i, line) in self.lines.iter_mut().enumerate() {
+            line.reset_layout();
+            line.layout(
+                font_system,
+                self.metrics.font_size,
+                None,
+                Wrap::None,
+                self.monospace_width,
+                self.tab_width,
+            );
+            let join_glyphs = if line_i + 1 < line_count {
+                let join = ShapeLine::new(
+                    font_system,
+                    self.line_join.encode_utf8(&mut [0; 4]),
+                    &AttrsList::new(&line.attrs_list().defaults()),
+                    Shaping::Advanced,
+                    self.tab_width,
+                )
+                .layout(
+                    self.metrics.font_size,
+                    None,
+                    Wrap::None,
+                    None,
+                    self.monospace_width,
+                );
+                join.into_iter().next().map(|join| join.glyphs)
+            } else {
+                None
+            };
+            let text_len = line.text().len();
+            let layout_line = &mut line.layout_opt_mut().expect("line not laid out")[0];
+            // Glyphs can extend slightly past the line width, keep them clear of the next line
+            layout_line.w = layout_line
+                .glyphs
+                .iter()
+                .fold(layout_line.w, |w, glyph| w.max(glyph.x + glyph.w));
+            for glyph in layout_line.glyphs.iter_mut() {
+                glyph.x += x;
+            }
+            for mut glyph in join_glyphs.into_iter().flatten() {
+                // The join stands in for the line ending, so it covers no text
+                glyph.start = text_len;
+                glyph.end = text_len;
+                glyph.x += x + layout_line.w;
+                layout_line.w += glyph.w;
+                layout_line.glyphs.push(glyph);
+            }
+            x += layout_line.w;
+        }
+        self.single_line_count = line_count;
+        self.redraw = true;
+    }
+
     /// Shape lines until cursor, also scrolling to include cursor in view
     #[allow(clippy::missing_panics_doc)]
     pub fn shape_until_cursor(
@@ -314,6 +586,9 @@ impl Buffer {
         prune: bool,
     ) {
         let metrics = self.metrics;
+        let round_line_height = self.round_line_height;
+        let line_height_of =
+            |layout_line: &LayoutLine| layout_line_height(metrics, round_line_height, layout_line);
         let old_scroll = self.scroll;
 
         let layout_cursor = self
@@ -326,14 +601,9 @@ impl Buffer {
                 .line_layout(font_system, layout_cursor.line)
                 .expect("shape_until_cursor failed to scroll forwards");
             (0..layout_cursor.layout).for_each(|layout_i| {
-                layout_y += layout[layout_i]
-                    .line_height_opt
-                    .unwrap_or(metrics.line_height);
+                layout_y += line_height_of(&layout[layout_i]);
             });
-            layout_y
-                + layout[layout_cursor.layout]
-                    .line_height_opt
-                    .unwrap_or(metrics.line_height)
+            layout_y + line_height_of(&layout[layout_cursor.layout])
         };
 
         if self.scroll.line > layout_cursor.line
@@ -357,7 +627,7 @@ impl Buffer {
                         .line_layout(font_system, line_i)
                         .expect("shape_until_cursor failed to scroll forwards");
                     for layout_line in layout.iter() {
-                        total_height += layout_line.line_height_opt.unwrap_or(metrics.line_height);
+                        total_height += line_height_of(layout_line);
                     }
                     if total_height > height + self.scroll.vertical {
                         self.scroll.line = line_i;
@@ -404,22 +674,138 @@ impl Buffer {
         }
     }
 
+    /// Scroll so that the visual line of `cursor` is placed in the viewport according to `align`
+    ///
+    /// The scroll is clamped so it does not go past the start or end of the buffer, so a cursor
+    /// near either edge may not end up exactly where requested. Without a height,
+    /// [`ScrollAlign::Center`] and [`ScrollAlign::Bottom`] behave like [`ScrollAlign::Top`].
+    /// [`ScrollAlign::Nearest`] is the same as [`Self::shape_until_cursor`].
+    pub fn scroll_to_cursor(
+        &mut self,
+        font_system: &mut FontSystem,
+        cursor: Cursor,
+        align: ScrollAlign,
+    ) {
+        if align == ScrollAlign::Nearest {
+            self.shape_until_cursor(font_system, cursor, false);
+            return;
+        }
+
+        let metrics = self.metrics;
+        let round_line_height = self.round_line_height;
+        let Some(layout_cursor) = self.layout_cursor(font_system, cursor) else {
+            return;
+        };
+        let Some(layout) = self.line_layout(font_system, layout_cursor.line) else {
+            return;
+        };
+        let layout_y: f32 = layout
+            .iter()
+            .take(layout_cursor.layout)
+            .map(|layout_line| layout_line_height(metrics, round_line_height, layout_line))
+            .sum();
+        let line_height = layout
+            .get(layout_cursor.layout)
+            .map_or(metrics.line_height, |layout_line| {
+                layout_line_height(metrics, round_line_height, layout_line)
+            });
+
+        let height = self.height_opt.unwrap_or(line_height);
+        let vertical = match align {
+            ScrollAlign::Center => layout_y + (line_height - height) / 2.0,
+            ScrollAlign::Bottom => layout_y + line_height - height,
+            _ => layout_y,
+        };
+        let old_scroll = self.scroll;
+        self.scroll.line = layout_cursor.line;
+        self.scroll.vertical = vertical;
+        if old_scroll != self.scroll {
+            self.redraw = true;
+        }
+        self.shape_until_scroll(font_system, false);
+    }
+
+    /// Shape every line that is not shaped yet, using all threads of the rayon thread pool
+    ///
+    /// Each thread shapes with a copy of the font system that shares the fonts loaded so far but
+    /// cannot load more. Lines that need a font that was not loaded yet are shaped afterwards on
+    /// the calling thread, which loads it. This is useful after loading a large document, since
+    /// [`Self::shape_until_scroll`] only shapes the lines that are laid out.
+    #[cfg(feature = "rayon")]
+    pub fn shape_lines_parallel(&mut self, font_system: &mut FontSystem) {
+        use rayon::prelude::*;
+        use std::sync::{Mutex, PoisonError};
+
+        let tab_width = self.tab_width;
+        let shared: &FontSystem = font_system;
+        // One copy per pool thread, made when the thread first shapes a line. map_init would
+        // make one per rayon job split, and each copy clones the font database.
+        let workers: Vec<Mutex<Option<FontSystem>>> = (0..rayon::current_num_threads())
+            .map(|_| Mutex::new(None))
+            .collect();
+        let missed: Vec<usize> = self
+            .lines
+            .par_iter_mut()
+            .enumerate()
+            .filter(|(_, line)| !line.shape_is_current(shared, tab_width))
+            .filter_map(|(line_i, line)| {
+                let thread_i = rayon::current_thread_index().unwrap_or(0) % workers.len();
+                let mut worker_opt = workers[thread_i]
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                let worker = worker_opt.get_or_insert_with(|| shared.worker());
+                line.shape(worker, tab_width);
+                if worker.worker_missed_font() {
+                    line.reset_shaping();
+                    Some(line_i)
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        for line_i in missed {
+            self.lines[line_i].shape(font_system, tab_width);
+        }
+    }
+
     /// Shape lines until scroll
     #[allow(clippy::missing_panics_doc)]
     pub fn shape_until_scroll(&mut self, font_system: &mut FontSystem, prune: bool) {
         let metrics = self.metrics;
+        let round_line_height = self.round_line_height;
+        let line_height_of =
+            |layout_line: &LayoutLine| layout_line_height(metrics, round_line_height, layout_line);
         let old_scroll = self.scroll;
 
+        if self.single_line {
+            // Everything is on the first visual line
+            self.layout_single_line(font_system);
+            self.scroll.line = 0;
+            self.scroll.vertical = 0.0;
+            if old_scroll != self.scroll {
+                self.redraw = true;
+            }
+            return;
+        }
+
+        // Only needed when scrolling up
+        let mut folded_opt: Option<Vec<Range<usize>>> = None;
         loop {
             // Adjust scroll.layout to be positive by moving scroll.line backwards
             while self.scroll.vertical < 0.0 {
                 if self.scroll.line > 0 {
                     let line_i = self.scroll.line - 1;
+                    let folded = folded_opt.get_or_insert_with(|| self.folded_ranges());
+                    if let Some(range) = folded_range(folded, line_i) {
+                        // Hidden lines take no space
+                        self.scroll.line = range.start;
+                        continue;
+                    }
                     if let Some(layout) = self.line_layout(font_system, line_i) {
                         let mut layout_height = 0.0;
                         for layout_line in layout.iter() {
-                            layout_height +=
-                                layout_line.line_height_opt.unwrap_or(metrics.line_height);
+                            layout_height += line_height_of(layout_line);
                         }
                         self.scroll.line = line_i;
                         self.scroll.vertical += layout_height;
@@ -438,13 +824,24 @@ impl Buffer {
             let scroll_end = scroll_start + self.height_opt.unwrap_or(f32::INFINITY);
 
             let mut total_height = 0.0;
+            let mut folds = FoldTracker::default();
             for line_i in 0..self.lines.len() {
+                let hidden = folds.visit(line_i, &self.lines[line_i]);
                 if line_i < self.scroll.line {
                     if prune {
                         self.lines[line_i].reset_shaping();
                     }
                     continue;
                 }
+                if hidden {
+                    if prune {
+                        self.lines[line_i].reset_shaping();
+                    }
+                    if line_i == self.scroll.line {
+                        self.scroll.line += 1;
+                    }
+                    continue;
+                }
                 if total_height > scroll_end {
                     if prune {
                         self.lines[line_i].reset_shaping();
@@ -459,7 +856,7 @@ impl Buffer {
                     .line_layout(font_system, line_i)
                     .expect("shape_until_scroll invalid line");
                 for layout_line in layout.iter() {
-                    let line_height = layout_line.line_height_opt.unwrap_or(metrics.line_height);
+                    let line_height = line_height_of(layout_line);
                     layout_height += line_height;
                     total_height += line_height;
                 }
@@ -486,6 +883,241 @@ impl Buffer {
         }
     }
 
+    /// Count the visual lines that fit in `height` from the current scroll position
+    ///
+    /// Lines that are only partially visible are included. Cached layouts are used where
+    /// available, and lines that have not been laid out are estimated as one visual line of the
+    /// buffer line height. Lines hidden by [`Self::folded_ranges`] are skipped, looking only at
+    /// folded regions that start from the scroll line like [`Self::layout_runs`].
+    pub fn lines_in_height(&self, height: f32) -> usize {
+        let mut count = 0;
+        let mut y = -self.scroll.vertical;
+        let mut folds = FoldTracker::default();
+        for (line_i, line) in self.lines.iter().enumerate().skip(self.scroll.line) {
+            if folds.visit(line_i, line) {
+                continue;
+            }
+            let mut count_line = |line_height: f32| {
+                if y >= height {
+                    return false;
+                }
+                if y + line_height > 0.0 {
+                    count += 1;
+                }
+                y += line_height;
+                true
+            };
+            let fits = match line.layout_opt() {
+                Some(layout) => layout
+                    .iter()
+                    .all(|layout_line| count_line(self.layout_line_height(layout_line))),
+                None => {
+                    let line_height = self.metrics.line_height;
+                    count_line(if self.round_line_height {
+                        math::roundf(line_height)
+                    } else {
+                        line_height
+                    })
+                }
+            };
+            if !fits {
+                break;
+            }
+        }
+        count
+    }
+
+    /// Estimate the number of visual lines in the buffer
+    ///
+    /// Cached layouts are used where available, and lines that have not been laid out are
+    /// estimated as one visual line. Use [`Self::visual_line_count_exact`] to lay out all lines
+    /// instead. Lines hidden by [`Self::folded_ranges`] are not counted.
+    pub fn visual_line_count(&self) -> usize {
+        let mut folds = FoldTracker::default();
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(line_i, line)| !folds.visit(*line_i, line))
+            .map(|(_, line)| line.layout_opt().map_or(1, Vec::len))
+            .sum()
+    }
+
+    /// Lay out all lines and count the visual lines in the buffer
+    ///
+    /// Lines hidden by [`Self::folded_ranges`] are neither laid out nor counted.
+    pub fn visual_line_count_exact(&mut self, font_system: &mut FontSystem) -> usize {
+        let mut folds = FoldTracker::default();
+        let mut count = 0;
+        for line_i in 0..self.lines.len() {
+            if folds.visit(line_i, &self.lines[line_i]) {
+                continue;
+            }
+            count += self.line_layout(font_system, line_i).map_or(0, <[_]>::len);
+        }
+        count
+    }
+
+    /// Ranges of line indices hidden by lines with [`FoldState::Collapsed`]
+    ///
+    /// The ranges are sorted and do not overlap: nested and adjacent folded regions are merged,
+    /// and regions are clamped to the end of the buffer. The line that starts a folded region is
+    /// not part of it. Hidden lines are skipped by [`Self::shape_until_scroll`],
+    /// [`Self::layout_clipped`], [`Self::layout_columns`] and the visual line counts.
+    ///
+    /// [`Self::layout_runs`] and [`Self::lines_in_height`] start at the scroll line and only
+    /// look at folded regions from there, so they rely on [`Self::shape_until_scroll`] moving
+    /// the scroll line off hidden lines.
+    pub fn folded_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = Vec::new();
+        for (line_i, line) in self.lines.iter().enumerate() {
+            let FoldState::Collapsed(count) = line.fold_state() else {
+                continue;
+            };
+            let start = line_i + 1;
+            let end = cmp::min(start.saturating_add(count), self.lines.len());
+            if start >= end {
+                continue;
+            }
+            match ranges.last_mut() {
+                Some(last) if start <= last.end => last.end = cmp::max(last.end, end),
+                _ => ranges.push(start..end),
+            }
+        }
+        ranges
+    }
+
+    /// Set the folding state of a line, see [`BufferLine::set_fold_state`]
+    ///
+    /// Unlike setting it on the line directly, this marks the buffer for redraw if the state
+    /// changed. Returns true if the state changed.
+    pub fn set_fold_state(&mut self, line_i: usize, fold_state: FoldState) -> bool {
+        let Some(line) = self.lines.get_mut(line_i) else {
+            return false;
+        };
+        if line.fold_state() == fold_state {
+            return false;
+        }
+        line.set_fold_state(fold_state);
+        self.redraw = true;
+        true
+    }
+
+    /// Lay out lines from the top of the buffer into a box of the given width and maximum height
+    ///
+    /// Layout stops at the first layout line that would not fit inside `max_height`, and the
+    /// returned [`ClipResult`] reports whether content was clipped and where. This sets the buffer
+    /// size to the box and resets the scroll to the top, so that [`Self::layout_runs`] returns the
+    /// visible lines afterwards. Lines hidden by [`Self::folded_ranges`] are skipped.
+    pub fn layout_clipped(
+        &mut self,
+        font_system: &mut FontSystem,
+        width_opt: Option<f32>,
+        max_height: f32,
+    ) -> ClipResult {
+        self.set_scroll(Scroll::default());
+        self.set_size(font_system, width_opt, Some(max_height));
+
+        let metrics = self.metrics;
+        let round_line_height = self.round_line_height;
+        let mut result = ClipResult::default();
+        let mut folds = FoldTracker::default();
+        for line_i in 0..self.lines.len() {
+            if folds.visit(line_i, &self.lines[line_i]) {
+                continue;
+            }
+            let layout = match self.line_layout(font_system, line_i) {
+                Some(some) => some,
+                None => break,
+            };
+            for layout_line in layout.iter() {
+                let line_height = layout_line_height(metrics, round_line_height, layout_line);
+                if result.height + line_height > max_height {
+                    let index = layout_line
+                        .glyphs
+                        .iter()
+                        .map(|glyph| glyph.start)
+                        .min()
+                        .unwrap_or(0);
+                    result.clipped = true;
+                    result.cursor_opt = Some(Cursor::new(line_i, index));
+                    return result;
+                }
+                result.height += line_height;
+                result.layout_lines += 1;
+            }
+        }
+        result
+    }
+
+    /// Flow the lines of the buffer through `column_count` columns of the given width and height,
+    /// like newspaper columns
+    ///
+    /// Each column is filled with layout lines until the next one would pass `column_height`,
+    /// then the text continues at the top of the next column. A layout line taller than a
+    /// column is placed alone in it. Columns are `column_gap` apart. If the text does not fit
+    /// in the last column, [`ColumnLayout::overflow_opt`] is the start of the first layout line
+    /// left out. Lines hidden by [`Self::folded_ranges`] are skipped. This sets the buffer size to
+    /// one column and resets the scroll to the top.
+    pub fn layout_columns(
+        &mut self,
+        font_system: &mut FontSystem,
+        column_width: f32,
+        column_count: usize,
+        column_gap: f32,
+        column_height: f32,
+    ) -> ColumnLayout {
+        self.set_scroll(Scroll::default());
+        self.set_size(font_system, Some(column_width), Some(column_height));
+
+        let metrics = self.metrics;
+        let round_line_height = self.round_line_height;
+        let mut result = ColumnLayout {
+            columns: (0..column_count)
+                .map(|column_i| LayoutColumn {
+                    x: column_i as f32 * (column_width + column_gap),
+                    ..LayoutColumn::default()
+                })
+                .collect(),
+            overflow_opt: None,
+        };
+        let mut column_i = 0;
+        let mut folds = FoldTracker::default();
+        for line_i in 0..self.lines.len() {
+            if folds.visit(line_i, &self.lines[line_i]) {
+                continue;
+            }
+            let layout = match self.line_layout(font_system, line_i) {
+                Some(some) => some,
+                None => break,
+            };
+            for (layout_i, layout_line) in layout.iter().enumerate() {
+                let line_height = layout_line_height(metrics, round_line_height, layout_line);
+                while result.columns.get(column_i).is_some_and(|column| {
+                    !column.lines.is_empty() && column.height + line_height > column_height
+                }) {
+                    column_i += 1;
+                }
+                let Some(column) = result.columns.get_mut(column_i) else {
+                    let index = layout_line
+                        .glyphs
+                        .iter()
+                        .map(|glyph| glyph.start)
+                        .min()
+                        .unwrap_or(0);
+                    result.overflow_opt = Some(Cursor::new(line_i, index));
+                    return result;
+                };
+                column.lines.push(ColumnLine {
+                    line_i,
+                    layout_i,
+                    y: column.height,
+                });
+                column.height += line_height;
+            }
+        }
+        result
+    }
+
     /// Convert a [`Cursor`] to a [`LayoutCursor`]
     pub fn layout_cursor(
         &mut self,
@@ -534,6 +1166,10 @@ impl Buffer {
         font_system: &mut FontSystem,
         line_i: usize,
     ) -> Option<&[LayoutLine]> {
+        if self.single_line {
+            self.layout_single_line(font_system);
+            return self.lines.get(line_i)?.layout_opt().map(Vec::as_slice);
+        }
         let line = self.lines.get_mut(line_i)?;
         Some(line.layout(
             font_system,
@@ -550,6 +1186,15 @@ impl Buffer {
         self.metrics
     }
 
+    /// Get the font size currently used for layout
+    ///
+    /// The buffer does not scale or fit its text by itself, so this is the font size of the
+    /// current [`Metrics`], including any scaling applied through [`Metrics::scale`] before
+    /// they were set.
+    pub fn effective_font_size(&self) -> f32 {
+        self.metrics.font_size
+    }
+
     /// Set the current [`Metrics`]
     ///
     /// # Panics
@@ -559,6 +1204,52 @@ impl Buffer {
         self.set_metrics_and_size(font_system, metrics, self.width_opt, self.height_opt);
     }
 
+    /// Get whether line heights are rounded to whole pixels
+    pub fn round_line_height(&self) -> bool {
+        self.round_line_height
+    }
+
+    /// Set whether line heights are rounded to whole pixels
+    ///
+    /// When enabled, the height of each layout line is rounded and baselines are placed on whole
+    /// pixels, so that line positions accumulate without drift.
+    pub fn set_round_line_height(&mut self, font_system: &mut FontSystem, round_line_height: bool) {
+        if round_line_height != self.round_line_height {
+            self.round_line_height = round_line_height;
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the height of a layout line, taking [`Self::round_line_height`] into account
+    pub fn layout_line_height(&self, layout_line: &LayoutLine) -> f32 {
+        layout_line_height(self.metrics, self.round_line_height, layout_line)
+    }
+
+    /// Get the current [`ClickPadding`]
+    pub fn click_padding(&self) -> ClickPadding {
+        self.click_padding
+    }
+
+    /// Set the [`ClickPadding`] used by [`Self::hit`]
+    pub fn set_click_padding(&mut self, click_padding: ClickPadding) {
+        self.click_padding = click_padding;
+    }
+
+    /// Get whether the cursor stops between a base character and its combining marks
+    pub fn mark_cursor_stops(&self) -> bool {
+        self.mark_cursor_stops
+    }
+
+    /// Set whether the cursor stops between a base character and each of its combining marks
+    ///
+    /// By default [`Motion::Next`] and [`Motion::Previous`] move by whole graphemes. With mark
+    /// stops, they also stop before every combining mark, so single marks can be selected and
+    /// deleted, as orthography editors need.
+    pub fn set_mark_cursor_stops(&mut self, mark_cursor_stops: bool) {
+        self.mark_cursor_stops = mark_cursor_stops;
+    }
+
     /// Get the current [`Wrap`]
     pub fn wrap(&self) -> Wrap {
         self.wrap
@@ -573,6 +1264,42 @@ impl Buffer {
         }
     }
 
+    /// Get whether all lines are laid out on a single visual line
+    pub fn single_line(&self) -> bool {
+        self.single_line
+    }
+
+    /// Set whether all lines are laid out on a single visual line, as in single line inputs
+    ///
+    /// Each line is laid out without wrapping and placed after the previous one, with the
+    /// [`Self::line_join`] character drawn where the line ending was. The lines and their line
+    /// endings are unchanged, so editing still sees the real newlines.
+    pub fn set_single_line(&mut self, font_system: &mut FontSystem, single_line: bool) {
+        if single_line != self.single_line {
+            self.single_line = single_line;
+            self.single_line_count = 0;
+            self.relayout(font_system);
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the character drawn in place of line endings in single line mode
+    pub fn line_join(&self) -> char {
+        self.line_join
+    }
+
+    /// Set the character drawn in place of line endings in single line mode, such as a space or
+    /// a visible marker like `'\u{23CE}'`
+    pub fn set_line_join(&mut self, font_system: &mut FontSystem, line_join: char) {
+        if line_join != self.line_join {
+            self.line_join = line_join;
+            if self.single_line {
+                self.relayout(font_system);
+                self.shape_until_scroll(font_system, false);
+            }
+        }
+    }
+
     /// Get the current `monospace_width`
     pub fn monospace_width(&self) -> Option<f32> {
         self.monospace_width
@@ -615,6 +1342,63 @@ impl Buffer {
         }
     }
 
+    /// Get the families tried for characters the family of their span does not cover
+    pub fn fallback_families(&self) -> &[FamilyOwned] {
+        &self.fallback_families
+    }
+
+    /// Set the families tried, in order, for characters the family of their span does not
+    /// cover, before the system fallback fonts. This applies to all lines, see
+    /// [`BufferLine::set_fallback_families`]
+    pub fn set_fallback_families(&mut self, font_system: &mut FontSystem, families: &[Family]) {
+        let families: Vec<FamilyOwned> = families
+            .iter()
+            .map(|family| FamilyOwned::new(*family))
+            .collect();
+        if families != self.fallback_families {
+            for line in self.lines.iter_mut() {
+                line.set_fallback_families(families.clone());
+            }
+            self.fallback_families = families;
+            self.redraw = true;
+            self.shape_until_scroll(font_system, false);
+        }
+    }
+
+    /// Get the shaping strategy of lines the buffer and editor create, such as lines added past
+    /// the end of the buffer
+    pub fn default_shaping(&self) -> Shaping {
+        self.default_shaping
+    }
+
+    /// Set the shaping strategy of lines the buffer and editor create
+    ///
+    /// Lines that [`Buffer::set_text`] or [`BufferLine::set_shaping`] gave another strategy
+    /// keep it. If `update_lines` is true, lines using the previous default switch to
+    /// `shaping` and are shaped again.
+    pub fn set_default_shaping(
+        &mut self,
+        font_system: &mut FontSystem,
+        shaping: Shaping,
+        update_lines: bool,
+    ) {
+        if shaping != self.default_shaping {
+            let mut reshape = false;
+            if update_lines {
+                for line in self.lines.iter_mut() {
+                    if line.shaping() == self.default_shaping {
+                        reshape |= line.set_shaping(shaping);
+                    }
+                }
+            }
+            self.default_shaping = shaping;
+            if reshape {
+                self.redraw = true;
+                self.shape_until_scroll(font_system, false);
+            }
+        }
+    }
+
     /// Get the current buffer dimensions (width, height)
     pub fn size(&self) -> (Option<f32>, Option<f32>) {
         (self.width_opt, self.height_opt)
@@ -658,6 +1442,266 @@ impl Buffer {
         }
     }
 
+    /// List the text of shaped lines that resolved to `.notdef` with every available font
+    ///
+    /// Adjacent missing clusters of the same script are grouped into a single range. This
+    /// reflects the fonts and fallbacks used when the lines were shaped, so it can be used to
+    /// tell which scripts need more fonts installed.
+    pub fn missing_coverage(&self) -> Vec<MissingRange> {
+        let mut missing_ranges = Vec::new();
+        for (line_i, line) in self.lines.iter().enumerate() {
+            let Some(shape) = line.shape_opt() else {
+                continue;
+            };
+            let mut missing: Vec<Range<usize>> = shape
+                .spans
+                .iter()
+                .flat_map(|span| span.words.iter())
+                .flat_map(|word| word.glyphs.iter())
+                .filter(|glyph| glyph.glyph_id == 0)
+                .map(|glyph| glyph.start..glyph.end)
+                .collect();
+            missing.sort_by_key(|range| range.start);
+            missing.dedup();
+
+            let first_missing_range = missing_ranges.len();
+            for range in missing {
+                let script = line.text()[range.clone()]
+                    .chars()
+                    .map(|c| c.script())
+                    .find(|script| !matches!(script, Script::Common | Script::Inherited))
+                    .unwrap_or(Script::Common);
+                match missing_ranges[first_missing_range..].last_mut() {
+                    Some(MissingRange {
+                        range: last_range,
+                        script: last_script,
+                        ..
+                    }) if last_range.end == range.start && *last_script == script => {
+                        last_range.end = range.end;
+                    }
+                    _ => missing_ranges.push(MissingRange {
+                        line_i,
+                        range,
+                        script,
+                    }),
+                }
+            }
+        }
+        missing_ranges
+    }
+
+    /// List the text that resolved to `.notdef` like [`Self::missing_coverage`], keeping at most
+    /// `max_ranges` ranges
+    ///
+    /// The number of ranges left out is counted in [`MissingCoverage::more`], and the scripts of
+    /// all ranges are still listed, so the report stays small for text with many missing glyphs.
+    pub fn missing_coverage_capped(&self, max_ranges: usize) -> MissingCoverage {
+        let mut ranges = self.missing_coverage();
+        let mut scripts = Vec::new();
+        for range in ranges.iter() {
+            if !scripts.contains(&range.script) {
+                scripts.push(range.script);
+            }
+        }
+        let more = ranges.len().saturating_sub(max_ranges);
+        ranges.truncate(max_ranges);
+        MissingCoverage {
+            ranges,
+            more,
+            scripts,
+        }
+    }
+
+    /// List the distinct fonts of the laid out glyphs, including fallback fonts, in the order
+    /// they are first used
+    ///
+    /// Only lines that have been laid out are included, such as the lines up to the scroll
+    /// position after [`Self::shape_until_scroll`].
+    pub fn used_fonts(&self) -> Vec<fontdb::ID> {
+        let mut font_ids = Vec::new();
+        for glyph in self
+            .lines
+            .iter()
+            .filter_map(BufferLine::layout_opt)
+            .flat_map(|layout| layout.iter())
+            .flat_map(|layout_line| layout_line.glyphs.iter())
+        {
+            if !font_ids.contains(&glyph.font_id) {
+                font_ids.push(glyph.font_id);
+            }
+        }
+        font_ids
+    }
+
+    /// Check if every line takes the plain ASCII fast path, see
+    /// [`BufferLine::is_ascii_fast_path`], with the same font for all lines
+    ///
+    /// Stops at the first line that does not.
+    pub fn is_plain_ascii(&self) -> bool {
+        let Some(first) = self.lines.first() else {
+            return true;
+        };
+        let defaults = first.attrs_list().defaults();
+        self.lines.iter().all(|line| {
+            line.is_ascii_fast_path() && line.attrs_list().defaults().compatible(&defaults)
+        })
+    }
+
+    /// Count the line endings used by the lines of the buffer
+    pub fn line_ending_summary(&self) -> LineEndingSummary {
+        let mut summary = LineEndingSummary::default();
+        for line in &self.lines {
+            summary.add(line.ending());
+        }
+        summary
+    }
+
+    /// Set every line ending other than [`LineEnding::None`] to `ending`
+    ///
+    /// Returns the number of lines that were changed
+    pub fn normalize_line_endings(&mut self, ending: LineEnding) -> usize {
+        let mut changed = 0;
+        for line in &mut self.lines {
+            if line.ending() != LineEnding::None && line.set_ending(ending) {
+                changed += 1;
+            }
+        }
+        if changed > 0 {
+            self.redraw = true;
+        }
+        changed
+    }
+
+    /// Get the generation of the last change to the text or attributes of this buffer
+    ///
+    /// This is the latest [`BufferLine::edit_generation`] of its lines. It increases with every
+    /// edit of the text or attributes, through the buffer, its lines or an editor, and is
+    /// unchanged by scrolling, resizing and layout, so undo logic can compare generations to
+    /// group edits. Removing lines from [`Self::lines`] directly, without editing another line,
+    /// is not tracked.
+    pub fn edit_generation(&self) -> u64 {
+        self.lines
+            .iter()
+            .map(BufferLine::edit_generation)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Release the excess capacity of the cached shaping and layout of every line, see
+    /// [`BufferLine::shrink_caches`]
+    pub fn shrink_caches(&mut self) {
+        for line in &mut self.lines {
+            line.shrink_caches();
+        }
+    }
+
+    /// Get the sum of the [`BufferLine::last_shape_stats`] of every line
+    #[cfg(feature = "profiling")]
+    pub fn total_shape_stats(&self) -> ShapeStats {
+        self.lines
+            .iter()
+            .filter_map(BufferLine::last_shape_stats)
+            .fold(ShapeStats::default(), |total, stats| ShapeStats {
+                duration: total.duration + stats.duration,
+                bytes: total.bytes + stats.bytes,
+                runs: total.runs + stats.runs,
+                glyphs: total.glyphs + stats.glyphs,
+            })
+    }
+
+    /// Get the index and [`BufferLine::last_shape_stats`] of the line that took the longest to
+    /// shape, `None` if no line was shaped
+    #[cfg(feature = "profiling")]
+    pub fn slowest_shaped_line(&self) -> Option<(usize, ShapeStats)> {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter_map(|(line_i, line)| Some((line_i, line.last_shape_stats()?)))
+            .max_by_key(|(_, stats)| stats.duration)
+    }
+
+    /// Get the [`BufferLine::fingerprint`] of every line, in line order
+    ///
+    /// The slice is cached in the buffer and refreshed from the lines on each call, so it stays
+    /// the same between edits and only the entries of changed lines differ after an edit.
+    pub fn line_fingerprints(&mut self) -> &[u64] {
+        self.line_fingerprints.truncate(self.lines.len());
+        for (fingerprint, line) in self.line_fingerprints.iter_mut().zip(&self.lines) {
+            *fingerprint = line.fingerprint();
+        }
+        let len = self.line_fingerprints.len();
+        self.line_fingerprints
+            .extend(self.lines[len..].iter().map(BufferLine::fingerprint));
+        &self.line_fingerprints
+    }
+
+    /// Split the buffer in two at `at`, returning a new buffer with the text after the cursor
+    ///
+    /// The new buffer gets the tail of the cursor line and all following lines, with their
+    /// attributes, line endings and line settings, and the same settings as this buffer. This
+    /// buffer keeps the head, and its last line no longer has a line ending. Both buffers must be
+    /// shaped again before drawing.
+    pub fn split_off(&mut self, at: Cursor) -> Self {
+        let mut new = self.with_lines(Vec::new());
+        if let Some(line) = self.lines.get_mut(at.line) {
+            let index = at.index.min(line.text().len());
+            let tail = line.split_off(index);
+            line.set_ending(LineEnding::None);
+            new.lines.push(tail);
+            new.lines.extend(self.lines.drain(at.line + 1..));
+        } else {
+            let line = match self.lines.last() {
+                Some(last) => last.new_with_settings(
+                    String::new(),
+                    LineEnding::None,
+                    AttrsList::new(&last.attrs_list().defaults()),
+                ),
+                None => {
+                    let mut line = BufferLine::new(
+                        "",
+                        LineEnding::None,
+                        AttrsList::new(&Attrs::new()),
+                        self.default_shaping,
+                    );
+                    line.set_fallback_families(self.fallback_families.clone());
+                    line
+                }
+            };
+            new.lines.push(line);
+        }
+        if self.scroll.line >= self.lines.len() {
+            self.scroll = Scroll::default();
+        }
+        self.single_line_count = 0;
+        self.redraw = true;
+        new
+    }
+
+    /// Create a buffer with `lines` and the settings of this buffer, which is scrolled to the
+    /// top and must be shaped before drawing
+    fn with_lines(&self, lines: Vec<BufferLine>) -> Self {
+        Self {
+            lines,
+            metrics: self.metrics,
+            width_opt: self.width_opt,
+            height_opt: self.height_opt,
+            scroll: Scroll::default(),
+            redraw: true,
+            wrap: self.wrap,
+            monospace_width: self.monospace_width,
+            tab_width: self.tab_width,
+            fallback_families: self.fallback_families.clone(),
+            default_shaping: self.default_shaping,
+            round_line_height: self.round_line_height,
+            click_padding: self.click_padding,
+            line_fingerprints: Vec::new(),
+            single_line: self.single_line,
+            line_join: self.line_join,
+            single_line_count: 0,
+            mark_cursor_stops: self.mark_cursor_stops,
+        }
+    }
+
     /// Get the current scroll location
     pub fn scroll(&self) -> Scroll {
         self.scroll
@@ -696,6 +1740,9 @@ impl Buffer {
                 shaping,
             ));
         }
+        for line in self.lines.iter_mut() {
+            line.set_fallback_families(self.fallback_families.clone());
+        }
         self.scroll = Scroll::default();
         self.shape_until_scroll(font_system, false);
     }
@@ -847,6 +1894,7 @@ impl Buffer {
 
         self.lines.iter_mut().for_each(|line| {
             line.set_align(alignment);
+            line.set_fallback_families(self.fallback_families.clone());
         });
 
         self.scroll = Scroll::default();
@@ -864,6 +1912,141 @@ impl Buffer {
         self.redraw = redraw;
     }
 
+    /// Get the kinds of boundaries `cursor` is at
+    ///
+    /// Wrap boundaries are only known for lines that have been laid out. All flags are false if
+    /// the cursor is not in the buffer or not at a character boundary.
+    pub fn boundary_info(&self, cursor: Cursor) -> BoundaryInfo {
+        let Some(line) = self.lines.get(cursor.line) else {
+            return BoundaryInfo::default();
+        };
+        let text = line.text();
+        let index = cursor.index;
+        if !text.is_char_boundary(index) {
+            return BoundaryInfo::default();
+        }
+        let paragraph = index == 0 || index == text.len();
+        BoundaryInfo {
+            grapheme: paragraph || text.grapheme_indices(true).any(|(i, _)| i == index),
+            word: text
+                .unicode_word_indices()
+                .any(|(i, word)| i == index || i + word.len() == index),
+            visual_line: paragraph || line.wrap_boundaries().contains(&index),
+            paragraph,
+        }
+    }
+
+    /// Get the carets at two cursors, for animating the caret as it moves between them
+    ///
+    /// The rectangles use the coordinates of [`Self::layout_runs`], and are as high as the layout
+    /// lines of the cursors. At a wrap boundary, a cursor with [`Affinity::After`] is placed at
+    /// the start of the next layout line and one with [`Affinity::Before`] at the end of the
+    /// previous one.
+    ///
+    /// Returns `None` if the lines of the cursors, or the lines between them and the scroll
+    /// position, have not been laid out.
+    pub fn caret_transition(
+        &self,
+        from: Cursor,
+        to: Cursor,
+        caret: CaretMetrics,
+    ) -> Option<CaretTransition> {
+        let (from, from_visual) = self.caret_at(from, caret)?;
+        let (to, to_visual) = self.caret_at(to, caret)?;
+        Some(CaretTransition {
+            from,
+            to,
+            crosses_lines: from_visual != to_visual,
+        })
+    }
+
+    /// Get the caret at a cursor, with the line and layout line indices it is on
+    fn caret_at(&self, cursor: Cursor, caret: CaretMetrics) -> Option<(Rect, (usize, usize))> {
+        let line = self.lines.get(cursor.line)?;
+        let layout = line.layout_opt()?;
+
+        // Top of the line relative to the scroll position, like layout runs
+        let mut top = -self.scroll.vertical;
+        if !self.single_line {
+            let (lines, sign) = if cursor.line >= self.scroll.line {
+                (self.lines.get(self.scroll.line..cursor.line)?, 1.0)
+            } else {
+                (self.lines.get(cursor.line..self.scroll.line)?, -1.0)
+            };
+            for line in lines {
+                for layout_line in line.layout_opt()? {
+                    top += sign * self.layout_line_height(layout_line);
+                }
+            }
+        }
+
+        let rect = line.caret_rect(
+            cursor.index,
+            cursor.affinity,
+            self.metrics.line_height,
+            caret,
+        )?;
+        let mut layout_i = line
+            .layout_runs_positioned(self.metrics.line_height)
+            .position(|(y, _)| y == rect.y)?;
+        let mut x = rect.x;
+        let caret_x = |edge: f32| {
+            let x = edge - caret.width / 2.0;
+            if caret.round_position {
+                math::roundf(x)
+            } else {
+                x
+            }
+        };
+        // A wrap boundary is the start of a layout line, and the caret rectangle is on either
+        // side of it depending on the whitespace dropped there
+        let starts_at = |layout_i: usize| {
+            layout.get(layout_i).and_then(|layout_line| {
+                layout_line
+                    .glyphs
+                    .iter()
+                    .min_by_key(|glyph| glyph.start)
+                    .filter(|glyph| glyph.start == cursor.index)
+            })
+        };
+        match cursor.affinity {
+            Affinity::After => {
+                if let Some(glyph) = starts_at(layout_i + 1) {
+                    layout_i += 1;
+                    x = caret_x(if glyph.level.is_rtl() {
+                        glyph.x + glyph.w
+                    } else {
+                        glyph.x
+                    });
+                }
+            }
+            Affinity::Before => {
+                if layout_i > 0 && starts_at(layout_i).is_some() {
+                    layout_i -= 1;
+                    let end = layout[layout_i].glyphs.iter().max_by_key(|glyph| glyph.end);
+                    if let Some(glyph) = end {
+                        x = caret_x(if glyph.level.is_rtl() {
+                            glyph.x
+                        } else {
+                            glyph.x + glyph.w
+                        });
+                    }
+                }
+            }
+        }
+
+        if !self.single_line {
+            for layout_line in &layout[..layout_i] {
+                top += self.layout_line_height(layout_line);
+            }
+        }
+        let height = self.layout_line_height(&layout[layout_i]);
+        Some((
+            Rect::new(x, top, rect.width, height),
+            (cursor.line, layout_i),
+        ))
+    }
+
     /// Get the visible layout runs for rendering and other tasks
     pub fn layout_runs(&self) -> LayoutRunIter {
         LayoutRunIter::new(self)
@@ -887,49 +2070,60 @@ impl Buffer {
                 let new_cursor = Cursor::new(run.line_i, 0);
                 new_cursor_opt = Some(new_cursor);
             } else if y >= line_top && y < line_top + line_height {
-                let mut new_cursor_glyph = run.glyphs.len();
-                let mut new_cursor_char = 0;
-                let mut new_cursor_affinity = Affinity::After;
+                let left = run.glyphs.iter().map(|glyph| glyph.x).reduce(f32::min);
+                let right = run
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.x + glyph.w)
+                    .reduce(f32::max);
+                if let (Some(left), Some(right)) = (left, right) {
+                    if self.single_line && x > right && runs.peek().is_some() {
+                        // The next line continues to the right on the same visual line
+                        continue;
+                    }
+                    if x < left || x > right {
+                        let after = x > right;
+                        let start = run.glyphs.iter().map(|glyph| glyph.start).min();
+                        let end = run.glyphs.iter().map(|glyph| glyph.end).max();
+                        let line_range = start.unwrap_or(0)..end.unwrap_or(0);
+                        // Byte range holding the cursor, and whether the cursor is at its end
+                        let (range, at_end) = match self.click_padding {
+                            ClickPadding::Nearest => {
+                                // The glyph at the clicked edge, whose side depends on its level
+                                let edge_glyph = if after {
+                                    run.glyphs
+                                        .iter()
+                                        .max_by(|a, b| (a.x + a.w).total_cmp(&(b.x + b.w)))
+                                } else {
+                                    run.glyphs.iter().min_by(|a, b| a.x.total_cmp(&b.x))
+                                };
+                                edge_glyph.map_or((line_range, after != run.rtl), |glyph| {
+                                    (glyph.start..glyph.end, after != glyph.level.is_rtl())
+                                })
+                            }
+                            ClickPadding::LineEnd => (line_range, after != run.rtl),
+                            ClickPadding::LineStart => (line_range, false),
+                        };
+                        new_cursor_opt = Some(if at_end {
+                            Cursor::new_with_affinity(run.line_i, range.end, Affinity::Before)
+                        } else {
+                            Cursor::new_with_affinity(run.line_i, range.start, Affinity::After)
+                        });
+                        break;
+                    }
+                }
 
-                let mut first_glyph = true;
+                let mut new_cursor_glyph = run.glyphs.len();
+                let mut new_cursor_hit = None;
 
-                'hit: for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
-                    if first_glyph {
-                        first_glyph = false;
-                        if (run.rtl && x > glyph.x) || (!run.rtl && x < 0.0) {
-                            new_cursor_glyph = 0;
-                            new_cursor_char = 0;
-                        }
+                for (glyph_i, glyph) in run.glyphs.iter().enumerate() {
+                    if glyph_i == 0 && ((run.rtl && x > glyph.x) || (!run.rtl && x < 0.0)) {
+                        new_cursor_glyph = 0;
                     }
                     if x >= glyph.x && x <= glyph.x + glyph.w {
                         new_cursor_glyph = glyph_i;
-
-                        let cluster = &run.text[glyph.start..glyph.end];
-                        let total = cluster.grapheme_indices(true).count();
-                        let mut egc_x = glyph.x;
-                        let egc_w = glyph.w / (total as f32);
-                        for (egc_i, egc) in cluster.grapheme_indices(true) {
-                            if x >= egc_x && x <= egc_x + egc_w {
-                                new_cursor_char = egc_i;
-
-                                let right_half = x >= egc_x + egc_w / 2.0;
-                                if right_half != glyph.level.is_rtl() {
-                                    // If clicking on last half of glyph, move cursor past glyph
-                                    new_cursor_char += egc.len();
-                                    new_cursor_affinity = Affinity::Before;
-                                }
-                                break 'hit;
-                            }
-                            egc_x += egc_w;
-                        }
-
-                        let right_half = x >= glyph.x + glyph.w / 2.0;
-                        if right_half != glyph.level.is_rtl() {
-                            // If clicking on last half of glyph, move cursor past glyph
-                            new_cursor_char = cluster.len();
-                            new_cursor_affinity = Affinity::Before;
-                        }
-                        break 'hit;
+                        new_cursor_hit = Some(glyph.hit(run.text, x));
+                        break;
                     }
                 }
 
@@ -938,8 +2132,10 @@ impl Buffer {
                 match run.glyphs.get(new_cursor_glyph) {
                     Some(glyph) => {
                         // Position at glyph
-                        new_cursor.index = glyph.start + new_cursor_char;
-                        new_cursor.affinity = new_cursor_affinity;
+                        let (index, affinity) =
+                            new_cursor_hit.unwrap_or((glyph.start, Affinity::After));
+                        new_cursor.index = index;
+                        new_cursor.affinity = affinity;
                     }
                     None => {
                         if let Some(glyph) = run.glyphs.last() {
@@ -1020,6 +2216,17 @@ impl Buffer {
                             break;
                         }
                     }
+                    if self.mark_cursor_stops {
+                        // Stop before the last combining mark of the grapheme
+                        if let Some((i, _)) = line.text()[prev_index..cursor.index]
+                            .char_indices()
+                            .skip(1)
+                            .filter(|&(_, ch)| is_combining_mark(ch))
+                            .last()
+                        {
+                            prev_index += i;
+                        }
+                    }
 
                     cursor.index = prev_index;
                     cursor.affinity = Affinity::After;
@@ -1034,6 +2241,18 @@ impl Buffer {
                 let line = self.lines.get(cursor.line)?;
                 if cursor.index < line.text().len() {
                     for (i, c) in line.text().grapheme_indices(true) {
+                        if self.mark_cursor_stops && i <= cursor.index && cursor.index < i + c.len()
+                        {
+                            // Stop before the next combining mark of the grapheme
+                            let offset = cursor.index - i;
+                            cursor.index = c[offset..]
+                                .char_indices()
+                                .skip(1)
+                                .find(|&(_, ch)| is_combining_mark(ch))
+                                .map_or(i + c.len(), |(mark_i, _)| cursor.index + mark_i);
+                            cursor.affinity = Affinity::Before;
+                            break;
+                        }
                         if i == cursor.index {
                             cursor.index += c.len();
                             cursor.affinity = Affinity::Before;
@@ -1327,6 +2546,36 @@ impl Buffer {
         Some((cursor, cursor_x_opt))
     }
 
+    /// Collect the glyphs of the layout runs visible in `viewport`, in layout order
+    ///
+    /// The viewport uses the same coordinates as [`Self::layout_runs`], so it is relative to the
+    /// current scroll position. Glyphs are positioned for drawing at a scale of 1.0.
+    pub fn draw_list(&self, viewport: Rect) -> Vec<DrawGlyph> {
+        let mut draw_list = Vec::new();
+        for run in self.layout_runs() {
+            if run.line_top + run.line_height < viewport.y || run.line_top > viewport.bottom() {
+                continue;
+            }
+            for glyph in run.glyphs.iter() {
+                if glyph.x + glyph.w < viewport.x || glyph.x > viewport.right() {
+                    continue;
+                }
+                let physical_glyph = glyph.physical((0., run.line_y), 1.0);
+                draw_list.push(DrawGlyph {
+                    font_id: glyph.font_id,
+                    glyph_id: glyph.glyph_id,
+                    x: physical_glyph.x,
+                    y: physical_glyph.y,
+                    font_size: glyph.font_size,
+                    color_opt: glyph.color_opt,
+                    opacity: glyph.opacity,
+                    cache_key: physical_glyph.cache_key,
+                });
+            }
+        }
+        draw_list
+    }
+
     /// Draw the buffer
     #[cfg(feature = "swash")]
     pub fn draw<F>(
@@ -1357,13 +2606,70 @@ impl Buffer {
                             run.line_y as i32 + physical_glyph.y + y,
                             1,
                             1,
-                            color,
+                            glyph.apply_opacity(color),
                         );
                     },
                 );
             }
         }
     }
+
+    /// Rasterize the visible glyphs, calling `put_pixel` for each covered pixel
+    ///
+    /// Lines are shaped up to the scroll position first. Alpha masks are tinted with the glyph
+    /// color, or opaque black if it has none, and their coverage is multiplied into its alpha.
+    /// Color glyphs are passed through as RGBA. Pixels without coverage and pixels outside of
+    /// the buffer size are skipped. Overlapping glyphs call `put_pixel` for the same pixel more
+    /// than once, so the callback should blend the color over what is already there.
+    #[cfg(feature = "swash")]
+    pub fn rasterize_to<F>(
+        &mut self,
+        font_system: &mut FontSystem,
+        cache: &mut crate::SwashCache,
+        mut put_pixel: F,
+    ) where
+        F: FnMut(i32, i32, Color),
+    {
+        use swash::scale::image::Content;
+
+        self.shape_until_scroll(font_system, false);
+        // Integer pixels are inside the buffer if they start before its edge
+        let width = self.width_opt.unwrap_or(f32::INFINITY);
+        let height = self.height_opt.unwrap_or(f32::INFINITY);
+        for run in self.layout_runs() {
+            for glyph in run.glyphs.iter() {
+                let physical_glyph = glyph.physical((0., run.line_y), 1.0);
+                let is_mask = matches!(
+                    cache.get_image(font_system, physical_glyph.cache_key),
+                    Some(image) if matches!(image.content, Content::Mask)
+                );
+                let (r, g, b, a) = glyph.draw_color(Color::rgb(0, 0, 0)).as_rgba_tuple();
+                cache.with_pixels(
+                    font_system,
+                    physical_glyph.cache_key,
+                    Color::rgb(r, g, b),
+                    |x, y, color| {
+                        let color = if is_mask {
+                            let alpha = (color.a() as u32 * a as u32 + 127) / 255;
+                            Color::rgba(r, g, b, alpha as u8)
+                        } else {
+                            glyph.apply_opacity(color)
+                        };
+                        let (x, y) = (physical_glyph.x + x, physical_glyph.y + y);
+                        if color.a() == 0
+                            || x < 0
+                            || y < 0
+                            || x as f32 >= width
+                            || y as f32 >= height
+                        {
+                            return;
+                        }
+                        put_pixel(x, y, color);
+                    },
+                );
+            }
+        }
+    }
 }
 
 impl BorrowedWithFontSystem<'_, Buffer> {
@@ -1373,6 +2679,19 @@ impl BorrowedWithFontSystem<'_, Buffer> {
             .shape_until_cursor(self.font_system, cursor, prune);
     }
 
+    /// Scroll so that the visual line of `cursor` is placed in the viewport, see
+    /// [`Buffer::scroll_to_cursor`]
+    pub fn scroll_to_cursor(&mut self, cursor: Cursor, align: ScrollAlign) {
+        self.inner.scroll_to_cursor(self.font_system, cursor, align);
+    }
+
+    /// Shape every line that is not shaped yet on multiple threads, see
+    /// [`Buffer::shape_lines_parallel`]
+    #[cfg(feature = "rayon")]
+    pub fn shape_lines_parallel(&mut self) {
+        self.inner.shape_lines_parallel(self.font_system);
+    }
+
     /// Shape lines until scroll
     pub fn shape_until_scroll(&mut self, prune: bool) {
         self.inner.shape_until_scroll(self.font_system, prune);
@@ -1383,11 +2702,39 @@ impl BorrowedWithFontSystem<'_, Buffer> {
         self.inner.line_shape(self.font_system, line_i)
     }
 
+    /// Lay out lines from the top of the buffer into a box of the given width and maximum height
+    pub fn layout_clipped(&mut self, width_opt: Option<f32>, max_height: f32) -> ClipResult {
+        self.inner
+            .layout_clipped(self.font_system, width_opt, max_height)
+    }
+
+    /// Flow the lines of the buffer through columns of the given width and height
+    pub fn layout_columns(
+        &mut self,
+        column_width: f32,
+        column_count: usize,
+        column_gap: f32,
+        column_height: f32,
+    ) -> ColumnLayout {
+        self.inner.layout_columns(
+            self.font_system,
+            column_width,
+            column_count,
+            column_gap,
+            column_height,
+        )
+    }
+
     /// Lay out the provided line index and return the result
     pub fn line_layout(&mut self, line_i: usize) -> Option<&[LayoutLine]> {
         self.inner.line_layout(self.font_system, line_i)
     }
 
+    /// Lay out all lines and count the visual lines in the buffer
+    pub fn visual_line_count_exact(&mut self) -> usize {
+        self.inner.visual_line_count_exact(self.font_system)
+    }
+
     /// Set the current [`Metrics`]
     ///
     /// # Panics
@@ -1402,6 +2749,22 @@ impl BorrowedWithFontSystem<'_, Buffer> {
         self.inner.set_wrap(self.font_system, wrap);
     }
 
+    /// Set whether all lines are laid out on a single visual line
+    pub fn set_single_line(&mut self, single_line: bool) {
+        self.inner.set_single_line(self.font_system, single_line);
+    }
+
+    /// Set the character drawn in place of line endings in single line mode
+    pub fn set_line_join(&mut self, line_join: char) {
+        self.inner.set_line_join(self.font_system, line_join);
+    }
+
+    /// Set whether line heights are rounded to whole pixels
+    pub fn set_round_line_height(&mut self, round_line_height: bool) {
+        self.inner
+            .set_round_line_height(self.font_system, round_line_height);
+    }
+
     /// Set the current buffer dimensions
     pub fn set_size(&mut self, width_opt: Option<f32>, height_opt: Option<f32>) {
         self.inner.set_size(self.font_system, width_opt, height_opt);
@@ -1427,6 +2790,18 @@ impl BorrowedWithFontSystem<'_, Buffer> {
         self.inner.set_tab_width(self.font_system, tab_width);
     }
 
+    /// Set the families tried, in order, for characters the family of their span does not
+    /// cover, before the system fallback fonts
+    pub fn set_fallback_families(&mut self, families: &[Family]) {
+        self.inner.set_fallback_families(self.font_system, families);
+    }
+
+    /// Set the shaping strategy of lines the buffer and editor create
+    pub fn set_default_shaping(&mut self, shaping: Shaping, update_lines: bool) {
+        self.inner
+            .set_default_shaping(self.font_system, shaping, update_lines);
+    }
+
     /// Set text of buffer, using provided attributes for each line by default
     pub fn set_text(&mut self, text: &str, attrs: &Attrs, shaping: Shaping) {
         self.inner.set_text(self.font_system, text, attrs, shaping);
@@ -1463,6 +2838,18 @@ impl BorrowedWithFontSystem<'_, Buffer> {
             .set_rich_text(self.font_system, spans, default_attrs, shaping, alignment);
     }
 
+    /// Set text of buffer from marked up text, see [`Buffer::set_from_markup`]
+    pub fn set_from_markup(
+        &mut self,
+        markup: &str,
+        default_attrs: &Attrs,
+        shaping: Shaping,
+        format: MarkupFormat,
+    ) {
+        self.inner
+            .set_from_markup(self.font_system, markup, default_attrs, shaping, format);
+    }
+
     /// Apply a [`Motion`] to a [`Cursor`]
     pub fn cursor_motion(
         &mut self,
@@ -1482,4 +2869,13 @@ impl BorrowedWithFontSystem<'_, Buffer> {
     {
         self.inner.draw(self.font_system, cache, color, f);
     }
+
+    /// Rasterize the visible glyphs, see [`Buffer::rasterize_to`]
+    #[cfg(feature = "swash")]
+    pub fn rasterize_to<F>(&mut self, cache: &mut crate::SwashCache, put_pixel: F)
+    where
+        F: FnMut(i32, i32, Color),
+    {
+        self.inner.rasterize_to(self.font_system, cache, put_pixel);
+    }
 }
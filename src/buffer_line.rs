@@ -1,11 +1,429 @@
+use alloc::sync::Arc;
 #[cfg(not(feature = "std"))]
-use alloc::{string::String, vec::Vec};
-use core::mem;
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64;
+#[cfg(not(target_has_atomic = "64"))]
+use core::sync::atomic::AtomicUsize;
+use core::{
+    any::Any,
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+    ops::Range,
+    sync::atomic::Ordering,
+};
+
+use unicode_bidi::{bidi_class, BidiClass};
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
-    Align, Attrs, AttrsList, Cached, FontSystem, LayoutLine, LineEnding, ShapeLine, Shaping, Wrap,
+    math, Affinity, Align, Attrs, AttrsList, AttrsOwned, BaseDirection, BoxLayout, Cached,
+    CaretMetrics, Direction, DropCap, EllipsisSource, FamilyOwned, FontSystem, Hyphenation,
+    Hyphenator, LayoutGlyph, LayoutLine, LayoutOptions, LineEnding, Overflow, ParagraphGlyph,
+    ParagraphLayout, ParagraphLine, Rect, ShapeGlyph, ShapeLine, ShapeOptions, ShapeReuse,
+    ShapeWord, Shaping, TabLeaders, TabStops, TabVisualization, VerticalAlign, Wrap,
 };
 
+/// Type-erased data that can be cloned along with its [`BufferLine`]
+trait UserData: Any + Send + Sync {
+    fn clone_box(&self) -> Box<dyn UserData>;
+
+    fn as_any(&self) -> &dyn Any;
+}
+
+impl<T: Any + Clone + Send + Sync> UserData for T {
+    fn clone_box(&self) -> Box<dyn UserData> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Storage for [`BufferLine::set_user_data`]
+struct UserDataBox(Box<dyn UserData>);
+
+impl Clone for UserDataBox {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl fmt::Debug for UserDataBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UserData(..)")
+    }
+}
+
+/// A shaped cluster of a [`BufferLine`], see [`BufferLine::cluster_at_cursor`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClusterInfo<'a> {
+    /// Byte range of the cluster in the line text
+    pub range: Range<usize>,
+    /// Text of the cluster
+    pub text: &'a str,
+    /// Glyph IDs used to render the cluster, in shaping order
+    pub glyph_ids: Vec<u16>,
+    /// Font that resolved the cluster
+    pub font_id: fontdb::ID,
+    /// True if a dotted circle was inserted as the base of a combining mark without one, see
+    /// [`ShapeGlyph::dotted_circle`]. Its glyph is in `glyph_ids` and `range` is the mark.
+    pub dotted_circle: bool,
+}
+
+/// Where the default attributes of a line created by [`BufferLine::split_off_with`] come from
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum DefaultsPolicy {
+    /// Use the attributes at the split point, so text typed at the start of the new line
+    /// continues the style that was split
+    FromSplitPoint,
+    /// Use the default attributes of the original line
+    #[default]
+    FromOriginalDefaults,
+}
+
+/// Folding state of a line, see [`BufferLine::set_fold_state`]
+///
+/// A folded region starts at the line that holds the state, which stays visible, and covers
+/// the given number of lines after it.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum FoldState {
+    /// The line does not start a folded region
+    #[default]
+    None,
+    /// The line starts a region of that many following lines, which are shown
+    Expanded(usize),
+    /// The line starts a region of that many following lines, which are hidden
+    Collapsed(usize),
+}
+
+/// How [`BufferLine::append_tracked`] reconciles the alignment and wrap width of two lines
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum AppendPolicy {
+    /// Keep the alignment and wrap width of the line appended to, like [`BufferLine::append`]
+    #[default]
+    KeepSelf,
+    /// Use the alignment and wrap width of the appended line
+    KeepOther,
+    /// Fail if the alignment or wrap width of the lines differ
+    Error,
+}
+
+/// How [`BufferLine::push_char`] updated the cached shaping
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PushResult {
+    /// The character was shaped on its own and appended to the cached shaping
+    Incremental,
+    /// Shaping was reset, and only the last word will be shaped again, reusing the others
+    ReshapeEnd,
+    /// Shaping was reset, and the whole line will be shaped again
+    Reshape,
+}
+
+/// Error returned by [`BufferLine::append_tracked`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AppendError {
+    /// The lines disagree on their alignment or wrap width, with [`AppendPolicy::Error`]
+    LayoutMismatch {
+        /// Alignment of the line appended to
+        align: Option<Align>,
+        /// Alignment of the appended line
+        other_align: Option<Align>,
+        /// Wrap width of the line appended to
+        wrap_width: Option<f32>,
+        /// Wrap width of the appended line
+        other_wrap_width: Option<f32>,
+    },
+}
+
+impl fmt::Display for AppendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LayoutMismatch {
+                align,
+                other_align,
+                wrap_width,
+                other_wrap_width,
+            } => write!(
+                f,
+                "appended line layout does not match (align {align:?} and {other_align:?}, \
+                 wrap width {wrap_width:?} and {other_wrap_width:?})"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AppendError {}
+
+/// Error returned by [`BufferLine::try_split_off`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SplitError {
+    /// The index is past the end of the text
+    OutOfBounds {
+        /// Requested split index
+        index: usize,
+        /// Length of the text in bytes
+        len: usize,
+    },
+    /// The index is inside an extended grapheme cluster
+    InsideCluster {
+        /// Requested split index
+        index: usize,
+        /// Byte range of the cluster containing the index
+        cluster: Range<usize>,
+    },
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfBounds { index, len } => {
+                write!(f, "split index {index} is past the end of the text ({len})")
+            }
+            Self::InsideCluster { index, cluster } => write!(
+                f,
+                "split index {index} is inside the grapheme cluster at {cluster:?}"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SplitError {}
+
+/// Reason for a cache invalidation, see [`Invalidation`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum InvalidationReason {
+    /// The text changed
+    Text,
+    /// The line ending changed
+    Ending,
+    /// The attributes list changed
+    AttrsList,
+    /// The shaping strategy changed
+    Shaping,
+    /// The alignment changed
+    Align,
+    /// The wrap width override changed
+    WrapWidth,
+    /// The whitespace trimming of wrapped lines, or trailing whitespace handling in alignment,
+    /// changed
+    Whitespace,
+    /// The drop cap changed
+    DropCap,
+    /// The overflow mode changed
+    Overflow,
+    /// The tab stops changed
+    TabStops,
+    /// The tab visualization marker changed
+    TabVisualization,
+    /// Orphan avoidance changed
+    AvoidOrphans,
+    /// The vertical alignment changed
+    VerticalAlign,
+    /// The fallback families changed
+    FallbackFamilies,
+    /// The hyphenator changed
+    Hyphenator,
+    /// The hyphen character changed
+    HyphenChar,
+    /// The mark stack height limit changed
+    MarkStackHeight,
+    /// The base direction changed
+    BaseDirection,
+    /// The forced cluster boundaries changed
+    ClusterBoundaries,
+    /// The replacement character highlighting changed
+    ReplacementChar,
+    /// The dotted circle insertion changed
+    DottedCircle,
+    /// Splitting shaping runs on color changed
+    SplitRunsOnColor,
+    /// The line was shaped with a different tab width
+    TabWidth,
+    /// The line was shaped before a setting of the font system, such as the locale, changed
+    FontSystem,
+    /// A reset function was called directly
+    Explicit,
+}
+
+/// A cache invalidation of a [`BufferLine`], see [`BufferLine::set_invalidation_logger`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Invalidation {
+    /// Shaping and layout caches were reset
+    Shaping(InvalidationReason),
+    /// Only the layout cache was reset
+    Layout(InvalidationReason),
+}
+
+/// Parameters a layout was computed with, see [`BufferLine::set_layout_cache`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct LayoutKey {
+    font_size: f32,
+    width_opt: Option<f32>,
+    wrap: Wrap,
+    match_mono_width: Option<f32>,
+    tab_width: u16,
+}
+
+/// Number of layouts kept by [`BufferLine::set_layout_cache`]
+const LAYOUT_CACHE_SIZE: usize = 2;
+
+/// Callback receiving the cache invalidations of a [`BufferLine`]
+#[derive(Clone)]
+struct InvalidationLogger(Arc<dyn Fn(Invalidation) + Send + Sync>);
+
+#[derive(Clone)]
+struct SharedHyphenator(Arc<dyn Hyphenator + Send + Sync>);
+
+impl fmt::Debug for SharedHyphenator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Hyphenator { .. }")
+    }
+}
+
+impl fmt::Debug for InvalidationLogger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("InvalidationLogger { .. }")
+    }
+}
+
+/// Statistics of the most recent shaping of a line, see [`BufferLine::last_shape_stats`]
+#[cfg(feature = "profiling")]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ShapeStats {
+    /// Time spent shaping
+    pub duration: core::time::Duration,
+    /// Length of the shaped text in bytes
+    pub bytes: usize,
+    /// Number of bidi runs, see [`ShapeLine::spans`]
+    pub runs: usize,
+    /// Number of glyphs produced
+    pub glyphs: usize,
+}
+
+/// Take the next value of the counter for [`BufferLine::edit_generation`]
+fn next_edit_generation() -> u64 {
+    #[cfg(target_has_atomic = "64")]
+    {
+        static EDIT_GENERATION: AtomicU64 = AtomicU64::new(0);
+        EDIT_GENERATION.fetch_add(1, Ordering::Relaxed) + 1
+    }
+    // Pointer sized atomics are always available, as `Arc` needs them too
+    #[cfg(not(target_has_atomic = "64"))]
+    {
+        static EDIT_GENERATION: AtomicUsize = AtomicUsize::new(0);
+        EDIT_GENERATION.fetch_add(1, Ordering::Relaxed) as u64 + 1
+    }
+}
+
+const LRI: char = '\u{2066}';
+const RLI: char = '\u{2067}';
+const FSI: char = '\u{2068}';
+const PDI: char = '\u{2069}';
+
+/// Get the isolate initiators still open at `index`, outermost first
+///
+/// First strong isolates are resolved to [`LRI`] or [`RLI`] from the text they isolate, so that
+/// reopening them on a split line keeps their direction.
+fn open_isolates(text: &str, index: usize) -> Vec<char> {
+    let mut open: Vec<(usize, char)> = Vec::new();
+    for (i, c) in text[..index].char_indices() {
+        match c {
+            LRI | RLI | FSI => open.push((i, c)),
+            PDI => {
+                open.pop();
+            }
+            _ => {}
+        }
+    }
+    open.into_iter()
+        .map(|(i, c)| {
+            if c != FSI {
+                return c;
+            }
+            // Direction of the first strong character, skipping nested isolates
+            let mut depth = 0;
+            for c in text[i + c.len_utf8()..].chars() {
+                match (c, bidi_class(c)) {
+                    (LRI | RLI | FSI, _) => depth += 1,
+                    (PDI, _) if depth == 0 => break,
+                    (PDI, _) => depth -= 1,
+                    (_, BidiClass::L) if depth == 0 => return LRI,
+                    (_, BidiClass::R | BidiClass::AL) if depth == 0 => return RLI,
+                    _ => {}
+                }
+            }
+            LRI
+        })
+        .collect()
+}
+
+/// Ranges of characters that are usually two cells wide in terminals, from the East Asian Wide and
+/// Fullwidth classes and emoji presentation blocks
+const WIDE_RANGES: &[(char, char)] = &[
+    ('\u{1100}', '\u{115F}'),
+    ('\u{2E80}', '\u{303E}'),
+    ('\u{3041}', '\u{33FF}'),
+    ('\u{3400}', '\u{4DBF}'),
+    ('\u{4E00}', '\u{9FFF}'),
+    ('\u{A000}', '\u{A4CF}'),
+    ('\u{AC00}', '\u{D7A3}'),
+    ('\u{F900}', '\u{FAFF}'),
+    ('\u{FE30}', '\u{FE4F}'),
+    ('\u{FF00}', '\u{FF60}'),
+    ('\u{FFE0}', '\u{FFE6}'),
+    ('\u{1F300}', '\u{1F64F}'),
+    ('\u{1F900}', '\u{1F9FF}'),
+    ('\u{20000}', '\u{2FFFD}'),
+    ('\u{30000}', '\u{3FFFD}'),
+];
+
+/// Get the terminal cell width of a grapheme from its first character alone
+fn static_cell_width(grapheme: &str) -> usize {
+    match grapheme.chars().next() {
+        Some(c) if c.is_control() => 0,
+        Some(c)
+            if WIDE_RANGES
+                .iter()
+                .any(|(start, end)| (*start..=*end).contains(&c)) =>
+        {
+            2
+        }
+        Some(_) => 1,
+        None => 0,
+    }
+}
+
+/// Keep at most `max_lines_opt` lines of `layout`, and end each line that is wider than `width`,
+/// and the last line if lines were dropped, with `ellipsis`
+///
+/// Returns true if text was dropped or replaced by the ellipsis.
+fn ellipsize_layout(
+    layout: &mut Vec<LayoutLine>,
+    ellipsis: &[LayoutGlyph],
+    width: f32,
+    align: Align,
+    rtl: bool,
+    max_lines_opt: Option<usize>,
+) -> bool {
+    let mut dropped = false;
+    if let Some(max_lines) = max_lines_opt {
+        dropped = layout.len() > max_lines;
+        layout.truncate(max_lines);
+    }
+    let last = layout.len().saturating_sub(1);
+    let mut truncated = dropped;
+    for (i, layout_line) in layout.iter_mut().enumerate() {
+        let force = dropped && i == last;
+        truncated |= layout_line.ellipsize(width, align, rtl, ellipsis, force);
+    }
+    truncated
+}
+
 /// A line (or paragraph) of text that is shaped and laid out
 #[derive(Clone, Debug)]
 pub struct BufferLine {
@@ -13,10 +431,44 @@ pub struct BufferLine {
     ending: LineEnding,
     attrs_list: AttrsList,
     align: Option<Align>,
+    line_aligns: Vec<Option<Align>>,
+    base_direction: BaseDirection,
+    forced_cluster_boundaries: Vec<usize>,
+    highlight_replacement_char: bool,
+    insert_dotted_circle: bool,
+    fallback_families: Vec<FamilyOwned>,
+    split_runs_on_color: bool,
+    wrap_width_opt: Option<f32>,
+    mark_stack_height_opt: Option<f32>,
+    trim_wrapped_leading_whitespace: bool,
+    ignore_trailing_whitespace_in_align: bool,
+    drop_cap_opt: Option<DropCap>,
+    overflow: Overflow,
+    tab_stops_opt: Option<TabStops>,
+    tab_visualization: Option<char>,
+    avoid_orphans: bool,
+    vertical_align: VerticalAlign,
+    hyphenator: Option<SharedHyphenator>,
+    hyphen_char: char,
+    /// Hyphens shaped by [`Self::shape_marks`], kept until shaping is reset
+    hyphens_opt: Option<Vec<(Range<usize>, ShapeGlyph)>>,
+    fingerprint: u64,
+    edit_generation: u64,
     shape_opt: Cached<ShapeLine>,
+    shape_reuse: ShapeReuse,
+    shape_tab_width: u16,
+    shape_generation: u64,
+    #[cfg(feature = "profiling")]
+    shape_stats_opt: Option<ShapeStats>,
     layout_opt: Cached<Vec<LayoutLine>>,
+    layout_key_opt: Option<LayoutKey>,
+    layout_cache: Option<Vec<(LayoutKey, Vec<LayoutLine>)>>,
+    wrap_boundaries: Vec<usize>,
     shaping: Shaping,
     metadata: Option<usize>,
+    user_data: Option<UserDataBox>,
+    fold_state: FoldState,
+    invalidation_logger: Option<InvalidationLogger>,
 }
 
 impl BufferLine {
@@ -29,16 +481,51 @@ impl BufferLine {
         attrs_list: AttrsList,
         shaping: Shaping,
     ) -> Self {
-        Self {
+        let mut line = Self {
             text: text.into(),
             ending,
             attrs_list,
             align: None,
+            line_aligns: Vec::new(),
+            base_direction: BaseDirection::default(),
+            forced_cluster_boundaries: Vec::new(),
+            highlight_replacement_char: false,
+            insert_dotted_circle: true,
+            fallback_families: Vec::new(),
+            split_runs_on_color: false,
+            wrap_width_opt: None,
+            mark_stack_height_opt: None,
+            trim_wrapped_leading_whitespace: true,
+            ignore_trailing_whitespace_in_align: false,
+            drop_cap_opt: None,
+            overflow: Overflow::default(),
+            tab_stops_opt: None,
+            tab_visualization: None,
+            avoid_orphans: false,
+            vertical_align: VerticalAlign::Baseline,
+            hyphenator: None,
+            hyphen_char: '-',
+            hyphens_opt: None,
+            fingerprint: 0,
+            edit_generation: 0,
             shape_opt: Cached::Empty,
+            shape_reuse: ShapeReuse::default(),
+            shape_tab_width: 0,
+            shape_generation: 0,
+            #[cfg(feature = "profiling")]
+            shape_stats_opt: None,
             layout_opt: Cached::Empty,
+            layout_key_opt: None,
+            layout_cache: None,
+            wrap_boundaries: Vec::new(),
             shaping,
             metadata: None,
-        }
+            user_data: None,
+            fold_state: FoldState::None,
+            invalidation_logger: None,
+        };
+        line.update_fingerprint();
+        line
     }
 
     /// Resets the current line with new internal values.
@@ -55,10 +542,31 @@ impl BufferLine {
         self.ending = ending;
         self.attrs_list = attrs_list;
         self.align = None;
-        self.shape_opt.set_unused();
-        self.layout_opt.set_unused();
+        self.line_aligns.clear();
+        self.base_direction = BaseDirection::default();
+        self.forced_cluster_boundaries.clear();
+        self.highlight_replacement_char = false;
+        self.split_runs_on_color = false;
+        self.insert_dotted_circle = true;
+        self.fallback_families = Vec::new();
+        self.wrap_width_opt = None;
+        self.mark_stack_height_opt = None;
+        self.trim_wrapped_leading_whitespace = true;
+        self.ignore_trailing_whitespace_in_align = false;
+        self.drop_cap_opt = None;
+        self.overflow = Overflow::default();
+        self.tab_stops_opt = None;
+        self.tab_visualization = None;
+        self.avoid_orphans = false;
+        self.vertical_align = VerticalAlign::Baseline;
+        self.hyphenator = None;
+        self.hyphen_char = '-';
+        self.update_fingerprint();
+        self.invalidate_shaping(InvalidationReason::Text);
         self.shaping = shaping;
         self.metadata = None;
+        self.user_data = None;
+        self.fold_state = FoldState::None;
     }
 
     /// Get current text
@@ -77,18 +585,100 @@ impl BufferLine {
         attrs_list: AttrsList,
     ) -> bool {
         let text = text.as_ref();
+        let reason = if text != self.text {
+            InvalidationReason::Text
+        } else if ending != self.ending {
+            InvalidationReason::Ending
+        } else {
+            InvalidationReason::AttrsList
+        };
         if text != self.text || ending != self.ending || attrs_list != self.attrs_list {
             self.text.clear();
             self.text.push_str(text);
             self.ending = ending;
             self.attrs_list = attrs_list;
-            self.reset();
+            self.metadata = None;
+            self.update_fingerprint();
+            self.invalidate_shaping(reason);
             true
         } else {
             false
         }
     }
 
+    /// Set text, keeping the attributes and shaping of the text that did not change
+    ///
+    /// The longest common prefix and suffix of the current and new text are kept. Attribute
+    /// spans in the suffix are shifted to its new position, and the changed text in between
+    /// takes the attributes of the character before it, as when typing. When the line is
+    /// reshaped, words that do not touch the changed text reuse their glyphs instead of being
+    /// shaped again, unless the edit changed their bidi level, such as when it flips the
+    /// paragraph direction. Lines with forced cluster boundaries are always shaped again.
+    ///
+    /// Will reset shape and layout if it differs from current text and line ending.
+    /// Returns true if the line was reset
+    pub fn set_text_diff<T: AsRef<str>>(&mut self, text: T, ending: LineEnding) -> bool {
+        let text = text.as_ref();
+        if text == self.text {
+            return self.set_ending(ending);
+        }
+
+        let old_len = self.text.len();
+        let mut prefix = self
+            .text
+            .bytes()
+            .zip(text.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !text.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let mut suffix = self
+            .text
+            .bytes()
+            .rev()
+            .zip(text.bytes().rev())
+            .take_while(|(a, b)| a == b)
+            .count()
+            .min(old_len.min(text.len()) - prefix);
+        while !text.is_char_boundary(text.len() - suffix) {
+            suffix -= 1;
+        }
+
+        // Shift the suffix spans, giving the changed text the attributes before it
+        let mut attrs_list = self.attrs_list.clone();
+        let suffix_attrs = attrs_list.split_off(old_len - suffix);
+        attrs_list.split_off(prefix);
+        let changed_len = text.len() - prefix - suffix;
+        let before = AttrsOwned::new(&self.attrs_list.get_span(prefix.saturating_sub(1)));
+        if changed_len > 0 && before != AttrsOwned::new(&attrs_list.defaults()) {
+            attrs_list.add_span(prefix..prefix + changed_len, &before.as_attrs());
+        }
+        for (range, attrs) in suffix_attrs.spans_iter() {
+            attrs_list.add_span(
+                (range.start + prefix + changed_len)..(range.end + prefix + changed_len),
+                &attrs.as_attrs(),
+            );
+        }
+
+        let reuse = match self.shape_opt.get() {
+            Some(shape) if self.forced_cluster_boundaries.is_empty() => {
+                ShapeReuse::new(shape, old_len, text.len(), prefix, suffix)
+            }
+            _ => ShapeReuse::default(),
+        };
+
+        self.text.clear();
+        self.text.push_str(text);
+        self.ending = ending;
+        self.attrs_list = attrs_list;
+        self.metadata = None;
+        self.update_fingerprint();
+        self.invalidate_shaping(InvalidationReason::Text);
+        self.shape_reuse = reuse;
+        true
+    }
+
     /// Consume this line, returning only its text contents as a String.
     pub fn into_text(self) -> String {
         self.text
@@ -106,7 +696,8 @@ impl BufferLine {
     pub fn set_ending(&mut self, ending: LineEnding) -> bool {
         if ending != self.ending {
             self.ending = ending;
-            self.reset_shaping();
+            self.update_fingerprint();
+            self.invalidate_shaping(InvalidationReason::Ending);
             true
         } else {
             false
@@ -120,12 +711,73 @@ impl BufferLine {
 
     /// Set attributes list
     ///
-    /// Will reset shape and layout if it differs from current attributes list.
+    /// Will reset shape and layout if it differs from current attributes list, or only layout
+    /// if just the opacity changed.
     /// Returns true if the line was reset
     pub fn set_attrs_list(&mut self, attrs_list: AttrsList) -> bool {
         if attrs_list != self.attrs_list {
+            // Opacity is only applied to the layout
+            let shaping = !attrs_list.eq_ignoring_opacity(&self.attrs_list);
             self.attrs_list = attrs_list;
-            self.reset_shaping();
+            self.update_fingerprint();
+            if shaping {
+                self.invalidate_shaping(InvalidationReason::AttrsList);
+            } else {
+                self.invalidate_layout(InvalidationReason::AttrsList);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Set attributes list, updating the cached shaping in place when possible
+    ///
+    /// If the new list only differs in presentation attributes (color, metadata, cache key flags,
+    /// and opacity), the glyphs of the cached [`ShapeLine`] are updated and only the layout is
+    /// reset. Otherwise this is the same as [`Self::set_attrs_list`]. Color is not a presentation
+    /// attribute when [`Self::split_runs_on_color`] is set, since it moves run boundaries.
+    /// Returns true if shaping was reset
+    pub fn update_attrs_in_place(&mut self, attrs_list: AttrsList) -> bool {
+        if attrs_list == self.attrs_list {
+            return false;
+        }
+        if !attrs_list.eq_ignoring_presentation(&self.attrs_list, self.split_runs_on_color) {
+            return self.set_attrs_list(attrs_list);
+        }
+        if let Some(shape) = self.shape_opt.get_mut() {
+            for glyph in shape
+                .spans
+                .iter_mut()
+                .flat_map(|span| span.words.iter_mut())
+                .flat_map(|word| word.glyphs.iter_mut())
+            {
+                let attrs = attrs_list.get_span(glyph.start);
+                glyph.color_opt = attrs.color_opt;
+                glyph.metadata = attrs.metadata;
+                glyph.cache_key_flags = attrs.cache_key_flags;
+            }
+        }
+        self.attrs_list = attrs_list;
+        self.hyphens_opt = None;
+        self.update_fingerprint();
+        self.invalidate_layout(InvalidationReason::AttrsList);
+        false
+    }
+
+    /// Get the shaping strategy
+    pub fn shaping(&self) -> Shaping {
+        self.shaping
+    }
+
+    /// Set the shaping strategy
+    ///
+    /// Will reset shape and layout if it differs from current shaping strategy.
+    /// Returns true if the line was reset
+    pub fn set_shaping(&mut self, shaping: Shaping) -> bool {
+        if shaping != self.shaping {
+            self.shaping = shaping;
+            self.invalidate_shaping(InvalidationReason::Shaping);
             true
         } else {
             false
@@ -145,161 +797,1874 @@ impl BufferLine {
     pub fn set_align(&mut self, align: Option<Align>) -> bool {
         if align != self.align {
             self.align = align;
-            self.reset_layout();
+            self.invalidate_layout(InvalidationReason::Align);
             true
         } else {
             false
         }
     }
 
-    /// Append line at end of this line
-    ///
-    /// The wrap setting of the appended line will be lost
-    pub fn append(&mut self, other: Self) {
-        let len = self.text.len();
-        self.text.push_str(other.text());
-
-        if other.attrs_list.defaults() != self.attrs_list.defaults() {
-            // If default formatting does not match, make a new span for it
-            self.attrs_list
-                .add_span(len..len + other.text().len(), &other.attrs_list.defaults());
-        }
-
-        for (other_range, attrs) in other.attrs_list.spans_iter() {
-            // Add previous attrs spans
-            let range = other_range.start + len..other_range.end + len;
-            self.attrs_list.add_span(range, &attrs.as_attrs());
-        }
-
-        self.reset();
+    /// Get the alignments of the wrapped visual lines
+    pub fn line_aligns(&self) -> &[Option<Align>] {
+        &self.line_aligns
     }
 
-    /// Split off new line at index
-    pub fn split_off(&mut self, index: usize) -> Self {
-        let text = self.text.split_off(index);
-        let attrs_list = self.attrs_list.split_off(index);
-        self.reset();
-
-        let mut new = Self::new(text, self.ending, attrs_list, self.shaping);
-        new.align = self.align;
-        new
+    /// Set the alignment of each wrapped visual line, by visual line index
+    ///
+    /// Lines without an entry, or with a `None` entry, use the alignment from [`Self::align`].
+    /// Will reset layout if it differs from current alignments.
+    /// Returns true if the line was reset
+    pub fn set_line_aligns(&mut self, aligns: Vec<Option<Align>>) -> bool {
+        if aligns != self.line_aligns {
+            self.line_aligns = aligns;
+            self.invalidate_layout(InvalidationReason::Align);
+            true
+        } else {
+            false
+        }
     }
 
-    /// Reset shaping, layout, and metadata caches
-    pub fn reset(&mut self) {
-        self.metadata = None;
-        self.reset_shaping();
+    /// Get the base direction
+    pub fn base_direction(&self) -> BaseDirection {
+        self.base_direction
     }
 
-    /// Reset shaping and layout caches
-    pub fn reset_shaping(&mut self) {
-        self.shape_opt.set_unused();
-        self.reset_layout();
+    /// Set the base direction
+    ///
+    /// Will reset shaping and layout if it differs from current base direction.
+    /// Returns true if the line was reset
+    pub fn set_base_direction(&mut self, base_direction: BaseDirection) -> bool {
+        if base_direction != self.base_direction {
+            self.base_direction = base_direction;
+            self.invalidate_shaping(InvalidationReason::BaseDirection);
+            true
+        } else {
+            false
+        }
     }
 
-    /// Reset only layout cache
-    pub fn reset_layout(&mut self) {
-        self.layout_opt.set_unused();
+    /// Get the byte offsets that are forced to be cluster boundaries
+    pub fn forced_cluster_boundaries(&self) -> &[usize] {
+        &self.forced_cluster_boundaries
     }
 
-    /// Shape line, will cache results
-    #[allow(clippy::missing_panics_doc)]
-    pub fn shape(&mut self, font_system: &mut FontSystem, tab_width: u16) -> &ShapeLine {
-        if self.shape_opt.is_unused() {
-            let mut line = self
-                .shape_opt
-                .take_unused()
-                .unwrap_or_else(ShapeLine::empty);
-            line.build(
-                font_system,
-                &self.text,
-                &self.attrs_list,
-                self.shaping,
-                tab_width,
-            );
-            self.shape_opt.set_used(line);
-            self.layout_opt.set_unused();
+    /// Set byte offsets that are forced to be cluster boundaries, such as token edges known to
+    /// an editor. Text on either side of a boundary is shaped separately, so no ligature or
+    /// cluster spans it.
+    ///
+    /// Will reset shaping and layout if it differs from current boundaries.
+    /// Returns true if the line was reset
+    pub fn set_forced_cluster_boundaries(&mut self, boundaries: Vec<usize>) -> bool {
+        if boundaries != self.forced_cluster_boundaries {
+            self.forced_cluster_boundaries = boundaries;
+            self.invalidate_shaping(InvalidationReason::ClusterBoundaries);
+            true
+        } else {
+            false
         }
-        self.shape_opt.get().expect("shape not found")
     }
 
-    /// Get line shaping cache
-    pub fn shape_opt(&self) -> Option<&ShapeLine> {
-        self.shape_opt.get()
+    /// Get whether glyphs of clusters containing U+FFFD are tagged
+    pub fn highlight_replacement_char(&self) -> bool {
+        self.highlight_replacement_char
     }
 
-    /// Layout line, will cache results
-    #[allow(clippy::missing_panics_doc)]
-    pub fn layout(
-        &mut self,
-        font_system: &mut FontSystem,
-        font_size: f32,
-        width_opt: Option<f32>,
-        wrap: Wrap,
-        match_mono_width: Option<f32>,
-        tab_width: u16,
-    ) -> &[LayoutLine] {
-        if self.layout_opt.is_unused() {
-            let align = self.align;
-            let mut layout = self
-                .layout_opt
-                .take_unused()
-                .unwrap_or_else(|| Vec::with_capacity(1));
-            let shape = self.shape(font_system, tab_width);
-            shape.layout_to_buffer(
-                &mut font_system.shape_buffer,
-                font_size,
-                width_opt,
-                wrap,
-                align,
-                &mut layout,
-                match_mono_width,
-            );
-            self.layout_opt.set_used(layout);
+    /// Set whether glyphs of clusters containing U+FFFD are tagged, see
+    /// [`ShapeOptions::highlight_replacement_char`]
+    ///
+    /// Will reset shaping and layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_highlight_replacement_char(&mut self, highlight: bool) -> bool {
+        if highlight != self.highlight_replacement_char {
+            self.highlight_replacement_char = highlight;
+            self.invalidate_shaping(InvalidationReason::ReplacementChar);
+            true
+        } else {
+            false
         }
-        self.layout_opt.get().expect("layout not found")
     }
 
-    /// Get line layout cache
-    pub fn layout_opt(&self) -> Option<&Vec<LayoutLine>> {
-        self.layout_opt.get()
+    /// Get whether a dotted circle is inserted before combining marks without a base
+    pub fn insert_dotted_circle(&self) -> bool {
+        self.insert_dotted_circle
     }
 
-    /// Get line metadata. This will be None if [`BufferLine::set_metadata`] has not been called
-    /// after the last reset of shaping and layout caches
-    pub fn metadata(&self) -> Option<usize> {
-        self.metadata
+    /// Set whether a dotted circle is inserted before combining marks without a base, see
+    /// [`ShapeOptions::insert_dotted_circle`]
+    ///
+    /// Will reset shaping and layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_insert_dotted_circle(&mut self, insert: bool) -> bool {
+        if insert != self.insert_dotted_circle {
+            self.insert_dotted_circle = insert;
+            self.invalidate_shaping(InvalidationReason::DottedCircle);
+            true
+        } else {
+            false
+        }
     }
 
-    /// Set line metadata. This is stored until the next line reset
-    pub fn set_metadata(&mut self, metadata: usize) {
-        self.metadata = Some(metadata);
+    /// Get the families tried for characters the family of their span does not cover
+    pub fn fallback_families(&self) -> &[FamilyOwned] {
+        &self.fallback_families
     }
 
-    /// Makes an empty buffer line.
+    /// Set the families tried, in order, for characters the family of their span does not
+    /// cover, before the system fallback fonts, see [`ShapeOptions::fallback_families`]
     ///
-    /// The buffer line is in an invalid state after this is called. See [`Self::reset_new`].
-    pub(crate) fn empty() -> Self {
-        Self {
-            text: String::default(),
-            ending: LineEnding::default(),
-            attrs_list: AttrsList::new(&Attrs::new()),
-            align: None,
-            shape_opt: Cached::Empty,
-            layout_opt: Cached::Empty,
-            shaping: Shaping::Advanced,
-            metadata: None,
+    /// Will reset shaping and layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_fallback_families(&mut self, families: Vec<FamilyOwned>) -> bool {
+        if families != self.fallback_families {
+            self.fallback_families = families;
+            self.invalidate_shaping(InvalidationReason::FallbackFamilies);
+            true
+        } else {
+            false
         }
     }
 
-    /// Reclaim attributes list memory that isn't needed any longer.
-    ///
-    /// The buffer line is in an invalid state after this is called. See [`Self::reset_new`].
-    pub(crate) fn reclaim_attrs(&mut self) -> AttrsList {
-        mem::replace(&mut self.attrs_list, AttrsList::new(&Attrs::new()))
+    /// Get whether shaping runs are split at color changes
+    pub fn split_runs_on_color(&self) -> bool {
+        self.split_runs_on_color
     }
 
-    /// Reclaim text memory that isn't needed any longer.
+    /// Set whether shaping runs are split at color changes, see
+    /// [`ShapeOptions::split_runs_on_color`]
+    ///
+    /// Will reset shaping and layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_split_runs_on_color(&mut self, split: bool) -> bool {
+        if split != self.split_runs_on_color {
+            self.split_runs_on_color = split;
+            self.invalidate_shaping(InvalidationReason::SplitRunsOnColor);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the wrap width override
+    pub fn wrap_width(&self) -> Option<f32> {
+        self.wrap_width_opt
+    }
+
+    /// Set the wrap width override
+    ///
+    /// When set, this width is used instead of the width passed to [`Self::layout`], both for
+    /// wrapping and for alignment.
+    /// Will reset layout if it differs from current wrap width.
+    /// Returns true if the line was reset
+    pub fn set_wrap_width(&mut self, wrap_width_opt: Option<f32>) -> bool {
+        if wrap_width_opt != self.wrap_width_opt {
+            self.wrap_width_opt = wrap_width_opt;
+            self.invalidate_layout(InvalidationReason::WrapWidth);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get whether whitespace is removed from the start of wrapped continuation lines
+    pub fn trim_wrapped_leading_whitespace(&self) -> bool {
+        self.trim_wrapped_leading_whitespace
+    }
+
+    /// Set whether whitespace is removed from the start of wrapped continuation lines
+    ///
+    /// This is enabled by default, like CSS does. The whitespace stays in the text but takes no
+    /// space in the layout, so clicks before a continuation line place the cursor after it.
+    /// Will reset layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_trim_wrapped_leading_whitespace(&mut self, trim: bool) -> bool {
+        if trim != self.trim_wrapped_leading_whitespace {
+            self.trim_wrapped_leading_whitespace = trim;
+            self.invalidate_layout(InvalidationReason::Whitespace);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get whether whitespace at the end of visual lines is left out of their width in alignment
+    pub fn ignore_trailing_whitespace_in_align(&self) -> bool {
+        self.ignore_trailing_whitespace_in_align
+    }
+
+    /// Set whether whitespace at the end of visual lines is left out of their width in alignment
+    ///
+    /// Trailing spaces and tabs then hang past the aligned edge, so the last visible glyph of a
+    /// right aligned line is flush right. This is disabled by default.
+    /// Will reset layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_ignore_trailing_whitespace_in_align(&mut self, ignore: bool) -> bool {
+        if ignore != self.ignore_trailing_whitespace_in_align {
+            self.ignore_trailing_whitespace_in_align = ignore;
+            self.invalidate_layout(InvalidationReason::Whitespace);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the space reserved for a drop cap
+    pub fn drop_cap(&self) -> Option<DropCap> {
+        self.drop_cap_opt
+    }
+
+    /// Reserve space for a drop cap at the start of the first visual lines
+    ///
+    /// The drop cap stays with this line when it is split, the new line has none.
+    /// Will reset layout if it differs from current drop cap.
+    /// Returns true if the line was reset
+    pub fn set_drop_cap(&mut self, drop_cap_opt: Option<DropCap>) -> bool {
+        if drop_cap_opt != self.drop_cap_opt {
+            self.drop_cap_opt = drop_cap_opt;
+            self.invalidate_layout(InvalidationReason::DropCap);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the tab stops
+    pub fn tab_stops(&self) -> Option<&TabStops> {
+        self.tab_stops_opt.as_ref()
+    }
+
+    /// Set tab stops in pixels, which replace the uniform tab width, see
+    /// [`LayoutOptions::tab_stops`]
+    ///
+    /// Will reset layout if it differs from current tab stops.
+    /// Returns true if the line was reset
+    pub fn set_tab_stops(&mut self, tab_stops_opt: Option<TabStops>) -> bool {
+        if tab_stops_opt != self.tab_stops_opt {
+            self.tab_stops_opt = tab_stops_opt;
+            self.invalidate_layout(InvalidationReason::TabStops);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the marker drawn in tabs
+    pub fn tab_visualization(&self) -> Option<char> {
+        self.tab_visualization
+    }
+
+    /// Set a marker, such as `→` or `·`, drawn at the start of each tab, see
+    /// [`TabVisualization`]
+    ///
+    /// The marker is shaped with the attributes of the tab.
+    /// Will reset layout if it differs from current marker.
+    /// Returns true if the line was reset
+    pub fn set_tab_visualization(&mut self, tab_visualization: Option<char>) -> bool {
+        if tab_visualization != self.tab_visualization {
+            self.tab_visualization = tab_visualization;
+            self.invalidate_layout(InvalidationReason::TabVisualization);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get whether orphans are avoided
+    pub fn avoid_orphans(&self) -> bool {
+        self.avoid_orphans
+    }
+
+    /// Set whether to avoid leaving a single short word on the last line, see
+    /// [`LayoutOptions::avoid_orphans`]
+    ///
+    /// Will reset layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_avoid_orphans(&mut self, avoid_orphans: bool) -> bool {
+        if avoid_orphans != self.avoid_orphans {
+            self.avoid_orphans = avoid_orphans;
+            self.invalidate_layout(InvalidationReason::AvoidOrphans);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the [`VerticalAlign`] of glyphs of different sizes
+    pub fn vertical_align(&self) -> VerticalAlign {
+        self.vertical_align
+    }
+
+    /// Set how glyphs of different sizes are aligned vertically within each layout line
+    ///
+    /// Will reset layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_vertical_align(&mut self, vertical_align: VerticalAlign) -> bool {
+        if vertical_align != self.vertical_align {
+            self.vertical_align = vertical_align;
+            self.invalidate_layout(InvalidationReason::VerticalAlign);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the [`Overflow`] mode
+    pub fn overflow(&self) -> &Overflow {
+        &self.overflow
+    }
+
+    /// Set the [`Overflow`] mode
+    ///
+    /// With [`Overflow::Ellipsis`], the line is laid out on a single layout line and, if it is
+    /// wider than the layout width, its end is replaced by the ellipsis. With
+    /// [`Overflow::Truncate`], the line is wrapped and the layout lines after `max_lines` are
+    /// dropped, ending the last kept line with the ellipsis. The ellipsis is shaped with the
+    /// default attributes of the line.
+    /// Will reset layout if it differs from current overflow mode.
+    /// Returns true if the line was reset
+    pub fn set_overflow(&mut self, overflow: Overflow) -> bool {
+        if overflow != self.overflow {
+            self.overflow = overflow;
+            self.invalidate_layout(InvalidationReason::Overflow);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Set the hyphenator used to break words that do not fit on a line, see [`Hyphenation`]
+    ///
+    /// Hyphenation is only done with [`Shaping::Advanced`]. The hyphen, see
+    /// [`Self::set_hyphen_char`], is shaped with the attributes of the character before the break.
+    /// Hyphenators are compared by pointer.
+    /// Will reset layout if it differs from current hyphenator.
+    /// Returns true if the line was reset
+    pub fn set_hyphenator(
+        &mut self,
+        hyphenator_opt: Option<Arc<dyn Hyphenator + Send + Sync>>,
+    ) -> bool {
+        let same = match (&self.hyphenator, &hyphenator_opt) {
+            (Some(current), Some(hyphenator)) => Arc::ptr_eq(&current.0, hyphenator),
+            (None, None) => true,
+            _ => false,
+        };
+        if same {
+            return false;
+        }
+        self.hyphenator = hyphenator_opt.map(SharedHyphenator);
+        self.invalidate_layout(InvalidationReason::Hyphenator);
+        true
+    }
+
+    /// Get the character inserted at hyphenated breaks
+    pub fn hyphen_char(&self) -> char {
+        self.hyphen_char
+    }
+
+    /// Set the character inserted at hyphenated breaks, `'-'` by default
+    ///
+    /// Some languages use other marks, such as U+2010 HYPHEN or U+058A ARMENIAN HYPHEN. The mark is
+    /// shaped like the default hyphen, and its width is used to decide where words are broken.
+    ///
+    /// Will reset layout if it differs from current setting.
+    /// Returns true if the line was reset
+    pub fn set_hyphen_char(&mut self, hyphen_char: char) -> bool {
+        if hyphen_char != self.hyphen_char {
+            self.hyphen_char = hyphen_char;
+            self.hyphens_opt = None;
+            self.invalidate_layout(InvalidationReason::HyphenChar);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the mark stack height limit
+    pub fn clamp_mark_stack_height(&self) -> Option<f32> {
+        self.mark_stack_height_opt
+    }
+
+    /// Set the mark stack height limit
+    ///
+    /// When set, combining marks stacked further than this many pixels from their base are
+    /// dropped during layout, see [`LayoutLine::clamp_mark_stack_height`].
+    /// Will reset layout if it differs from current limit.
+    /// Returns true if the line was reset
+    pub fn set_clamp_mark_stack_height(&mut self, mark_stack_height_opt: Option<f32>) -> bool {
+        if mark_stack_height_opt != self.mark_stack_height_opt {
+            self.mark_stack_height_opt = mark_stack_height_opt;
+            self.invalidate_layout(InvalidationReason::MarkStackHeight);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Append line at end of this line
+    ///
+    /// The wrap setting of the appended line will be lost
+    pub fn append(&mut self, other: Self) {
+        self.append_text(other);
+    }
+
+    /// Append line at end of this line, reconciling the alignment and wrap width of both lines
+    /// with `policy`
+    ///
+    /// The default attributes of the appended line are kept in a span if they differ from the
+    /// defaults of this line. Returns the byte range of the appended text in this line.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`AppendError::LayoutMismatch`] without changing this line if `policy` is
+    /// [`AppendPolicy::Error`] and the lines disagree on their alignment or wrap width.
+    pub fn append_tracked(
+        &mut self,
+        other: Self,
+        policy: AppendPolicy,
+    ) -> Result<Range<usize>, AppendError> {
+        let differs = other.align != self.align || other.wrap_width_opt != self.wrap_width_opt;
+        match policy {
+            AppendPolicy::KeepSelf => {}
+            AppendPolicy::KeepOther => {
+                self.set_align(other.align);
+                self.set_wrap_width(other.wrap_width_opt);
+            }
+            AppendPolicy::Error if differs => {
+                return Err(AppendError::LayoutMismatch {
+                    align: self.align,
+                    other_align: other.align,
+                    wrap_width: self.wrap_width_opt,
+                    other_wrap_width: other.wrap_width_opt,
+                });
+            }
+            AppendPolicy::Error => {}
+        }
+        Ok(self.append_text(other))
+    }
+
+    /// Append the text and attributes of `other`, returning the byte range of its text
+    fn append_text(&mut self, other: Self) -> Range<usize> {
+        let len = self.text.len();
+        self.text.push_str(other.text());
+
+        if other.attrs_list.defaults() != self.attrs_list.defaults() {
+            // If default formatting does not match, make a new span for it
+            self.attrs_list
+                .add_span(len..len + other.text().len(), &other.attrs_list.defaults());
+        }
+
+        for (other_range, attrs) in other.attrs_list.spans_iter() {
+            // Add previous attrs spans
+            let range = other_range.start + len..other_range.end + len;
+            self.attrs_list.add_span(range, &attrs.as_attrs());
+        }
+
+        self.forced_cluster_boundaries.extend(
+            other
+                .forced_cluster_boundaries
+                .iter()
+                .map(|boundary| boundary + len),
+        );
+
+        self.metadata = None;
+        self.update_fingerprint();
+        self.invalidate_shaping(InvalidationReason::Text);
+        len..self.text.len()
+    }
+
+    /// Append a character with the given attributes, updating the cached shaping if possible
+    ///
+    /// This is meant for text that grows one character at a time, such as terminal output. A
+    /// space, or an ASCII letter or digit after a space, in a shaped left-to-right line starts a
+    /// new word, so it is shaped on its own and appended to the cached shaping, and only layout is
+    /// reset. Otherwise shaping is reset and, if the line was shaped, the next shaping only shapes
+    /// the last word again, like [`Self::set_text_diff`].
+    pub fn push_char(
+        &mut self,
+        font_system: &mut FontSystem,
+        ch: char,
+        attrs: &Attrs,
+    ) -> PushResult {
+        let start = self.text.len();
+        let word_start = match ch {
+            ' ' => true,
+            // Breaks are not allowed between an opening bracket and the spaces after it
+            _ if ch.is_ascii_alphanumeric() => {
+                let before_spaces = self.text.trim_end_matches(' ');
+                before_spaces.len() < start
+                    && !matches!(before_spaces.chars().next_back(), Some('(' | '[' | '{'))
+            }
+            _ => false,
+        };
+        let incremental = word_start
+            && self.forced_cluster_boundaries.is_empty()
+            && self.shape_opt.get().is_some_and(|shape| {
+                !shape.rtl && shape.spans.len() == 1 && shape.spans[0].level.is_ltr()
+            });
+
+        self.text.push(ch);
+        if AttrsOwned::new(attrs) != AttrsOwned::new(&self.attrs_list.defaults()) {
+            self.attrs_list.add_span(start..self.text.len(), attrs);
+        }
+        self.metadata = None;
+        self.update_fingerprint();
+
+        if incremental {
+            let options = ShapeOptions {
+                base_direction: self.base_direction,
+                forced_cluster_boundaries: &self.forced_cluster_boundaries,
+                highlight_replacement_char: self.highlight_replacement_char,
+                insert_dotted_circle: self.insert_dotted_circle,
+                split_runs_on_color: self.split_runs_on_color,
+                fallback_families: &self.fallback_families,
+            };
+            if let Some(shape) = self.shape_opt.get_mut() {
+                shape.push_word(
+                    font_system,
+                    &self.text,
+                    &self.attrs_list,
+                    start..self.text.len(),
+                    ch == ' ',
+                    self.shaping,
+                    &options,
+                );
+            }
+            self.invalidate_layout(InvalidationReason::Text);
+            return PushResult::Incremental;
+        }
+
+        let reuse = match self.shape_opt.get() {
+            Some(shape) if self.forced_cluster_boundaries.is_empty() => {
+                Some(ShapeReuse::new(shape, start, self.text.len(), start, 0))
+            }
+            _ => None,
+        };
+        self.invalidate_shaping(InvalidationReason::Text);
+        match reuse {
+            Some(reuse) => {
+                self.shape_reuse = reuse;
+                PushResult::ReshapeEnd
+            }
+            None => PushResult::Reshape,
+        }
+    }
+
+    /// Split off new line at index
+    pub fn split_off(&mut self, index: usize) -> Self {
+        self.split_off_with(index, DefaultsPolicy::default())
+    }
+
+    /// Split off new line at index, keeping bidi isolates balanced on both lines
+    ///
+    /// Isolates (LRI, RLI and FSI) still open at `index` are closed with PDI at the end of this
+    /// line and reopened at the start of the new line, so each line is shaped with the
+    /// directions of the original text. First strong isolates are reopened as LRI or RLI
+    /// depending on the direction they resolved to.
+    pub fn split_off_bidi_safe(&mut self, index: usize) -> Self {
+        let open = open_isolates(&self.text, index);
+        let mut new = self.split_off(index);
+        if open.is_empty() {
+            return new;
+        }
+
+        let closing: String = open.iter().map(|_| PDI).collect();
+        let closing_attrs = self.attrs_list.get_span(index.saturating_sub(1));
+        self.append_text(Self::new(
+            closing,
+            LineEnding::None,
+            AttrsList::new(&closing_attrs),
+            self.shaping,
+        ));
+
+        let opening: String = open.into_iter().collect();
+        let shift = opening.len();
+        let mut attrs_list = AttrsList::new(&new.attrs_list.defaults());
+        let opening_attrs = new.attrs_list.get_span(0);
+        if opening_attrs != attrs_list.defaults() {
+            attrs_list.add_span(0..shift, &opening_attrs);
+        }
+        for (range, attrs) in new.attrs_list.spans_iter() {
+            attrs_list.add_span(range.start + shift..range.end + shift, &attrs.as_attrs());
+        }
+        new.text.insert_str(0, &opening);
+        new.attrs_list = attrs_list;
+        for boundary in &mut new.forced_cluster_boundaries {
+            *boundary += shift;
+        }
+        new.update_fingerprint();
+        new.invalidate_shaping(InvalidationReason::Text);
+        new
+    }
+
+    /// Split off new line at index, only if it is on an extended grapheme cluster boundary
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SplitError::OutOfBounds`] if the index is past the end of the text, and
+    /// [`SplitError::InsideCluster`] if it would split a grapheme cluster such as an emoji
+    /// sequence or a base character and its combining marks. This line is left unchanged.
+    pub fn try_split_off(&mut self, index: usize) -> Result<Self, SplitError> {
+        let len = self.text.len();
+        if index > len {
+            return Err(SplitError::OutOfBounds { index, len });
+        }
+        if let Some((start, grapheme)) = self
+            .text
+            .grapheme_indices(true)
+            .find(|(start, grapheme)| *start < index && index < start + grapheme.len())
+        {
+            return Err(SplitError::InsideCluster {
+                index,
+                cluster: start..start + grapheme.len(),
+            });
+        }
+        Ok(self.split_off(index))
+    }
+
+    /// Split off new line at index, choosing where its default attributes come from
+    ///
+    /// The new line gets the settings of this line, including whether layouts are cached and the
+    /// invalidation logger. It also takes the fold state, since the folded lines now follow it.
+    pub fn split_off_with(&mut self, index: usize, policy: DefaultsPolicy) -> Self {
+        let split_attrs = match policy {
+            DefaultsPolicy::FromSplitPoint => {
+                Some(AttrsOwned::new(&self.attrs_list.get_span(index)))
+            }
+            DefaultsPolicy::FromOriginalDefaults => None,
+        };
+        let text = self.text.split_off(index);
+        let boundaries = self
+            .forced_cluster_boundaries
+            .iter()
+            .filter(|&&boundary| boundary > index)
+            .map(|boundary| boundary - index)
+            .collect();
+        self.forced_cluster_boundaries
+            .retain(|&boundary| boundary < index);
+        let mut attrs_list = self.attrs_list.split_off(index);
+        if let Some(split_attrs) = split_attrs {
+            // Text that used the original defaults must keep them, so gaps between spans get
+            // their own span
+            let original_defaults = attrs_list.defaults();
+            let mut new_attrs_list = AttrsList::new(&split_attrs.as_attrs());
+            let mut end = 0;
+            for (range, attrs) in attrs_list.spans_iter() {
+                if range.start > end {
+                    new_attrs_list.add_span(end..range.start, &original_defaults);
+                }
+                new_attrs_list.add_span(range.clone(), &attrs.as_attrs());
+                end = range.end;
+            }
+            if text.len() > end {
+                new_attrs_list.add_span(end..text.len(), &original_defaults);
+            }
+            attrs_list = new_attrs_list;
+        }
+        self.metadata = None;
+        self.update_fingerprint();
+        self.invalidate_shaping(InvalidationReason::Text);
+
+        let mut new = self.new_with_settings(text, self.ending, attrs_list);
+        new.forced_cluster_boundaries = boundaries;
+        // The folded lines now follow the new line
+        new.fold_state = mem::take(&mut self.fold_state);
+        new
+    }
+
+    /// Create a line with `text`, `ending` and `attrs_list`, and the settings of this line
+    ///
+    /// The settings include whether layouts are cached and the invalidation logger. The drop cap,
+    /// the fold state, the metadata and the user data stay with this line.
+    pub(crate) fn new_with_settings(
+        &self,
+        text: String,
+        ending: LineEnding,
+        attrs_list: AttrsList,
+    ) -> Self {
+        let mut new = Self::new(text, ending, attrs_list, self.shaping);
+        new.align = self.align;
+        new.line_aligns = self.line_aligns.clone();
+        new.base_direction = self.base_direction;
+        new.highlight_replacement_char = self.highlight_replacement_char;
+        new.insert_dotted_circle = self.insert_dotted_circle;
+        new.fallback_families = self.fallback_families.clone();
+        new.split_runs_on_color = self.split_runs_on_color;
+        new.wrap_width_opt = self.wrap_width_opt;
+        new.mark_stack_height_opt = self.mark_stack_height_opt;
+        new.trim_wrapped_leading_whitespace = self.trim_wrapped_leading_whitespace;
+        new.ignore_trailing_whitespace_in_align = self.ignore_trailing_whitespace_in_align;
+        new.overflow = self.overflow.clone();
+        new.tab_stops_opt = self.tab_stops_opt.clone();
+        new.tab_visualization = self.tab_visualization;
+        new.avoid_orphans = self.avoid_orphans;
+        new.vertical_align = self.vertical_align;
+        new.hyphenator = self.hyphenator.clone();
+        new.hyphen_char = self.hyphen_char;
+        new.layout_cache = self.layout_cache.as_ref().map(|_| Vec::new());
+        new.invalidation_logger = self.invalidation_logger.clone();
+        new
+    }
+
+    /// Hash of the text, line ending and attributes list of this line
+    ///
+    /// This is kept up to date as the line is edited, so it is cheap to call. Lines with equal
+    /// contents have equal fingerprints.
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    /// Generation of the last change to the text, line ending or attributes list of this line
+    ///
+    /// Generations come from a counter shared by all lines, which increases with every change,
+    /// so comparing generations tells whether a line changed without comparing its contents.
+    /// Layout, scrolling and other settings do not change the generation.
+    pub fn edit_generation(&self) -> u64 {
+        self.edit_generation
+    }
+
+    /// Check if the line can take a plain ASCII fast path: its text is ASCII, its direction is
+    /// left-to-right, and every span can be shaped with the font of its default attributes
+    pub fn is_ascii_fast_path(&self) -> bool {
+        let defaults = self.attrs_list.defaults();
+        self.text.is_ascii()
+            && self.base_direction.resolve(&self.text) == Direction::Ltr
+            && self
+                .attrs_list
+                .spans_iter()
+                .all(|(_, attrs)| attrs.as_attrs().compatible(&defaults))
+    }
+
+    fn update_fingerprint(&mut self) {
+        self.edit_generation = next_edit_generation();
+        let mut hasher = rustc_hash::FxHasher::default();
+        self.text.hash(&mut hasher);
+        self.ending.as_str().hash(&mut hasher);
+        self.attrs_list.defaults().hash(&mut hasher);
+        for (range, attrs) in self.attrs_list.spans_iter() {
+            range.hash(&mut hasher);
+            attrs.hash(&mut hasher);
+        }
+        self.fingerprint = hasher.finish();
+    }
+
+    /// Reset shaping, layout, metadata, and user data
+    pub fn reset(&mut self) {
+        self.metadata = None;
+        self.user_data = None;
+        self.reset_shaping();
+    }
+
+    /// Reset shaping and layout caches
+    pub fn reset_shaping(&mut self) {
+        self.invalidate_shaping(InvalidationReason::Explicit);
+    }
+
+    /// Reset only layout cache
+    ///
+    /// With [`Self::set_layout_cache`] enabled, the current layout is kept so that laying out
+    /// again with the same parameters restores it.
+    pub fn reset_layout(&mut self) {
+        let key_opt = self.layout_key_opt;
+        let layout_cache = self.layout_cache.take();
+        self.invalidate_layout(InvalidationReason::Explicit);
+        self.layout_cache = layout_cache;
+        if let (Some(layout_cache), Some(key)) = (&mut self.layout_cache, key_opt) {
+            if let Some(layout) = self.layout_opt.take_unused() {
+                layout_cache.retain(|(cached_key, _)| *cached_key != key);
+                if layout_cache.len() >= LAYOUT_CACHE_SIZE {
+                    layout_cache.remove(0);
+                }
+                layout_cache.push((key, layout));
+            }
+        }
+    }
+
+    /// Get whether layouts for previous parameters are kept, see [`Self::set_layout_cache`]
+    pub fn layout_cache(&self) -> bool {
+        self.layout_cache.is_some()
+    }
+
+    /// Keep the layouts of the last two sets of layout parameters
+    ///
+    /// When the layout is reset with [`Self::reset_layout`], as a [`crate::Buffer`] does when
+    /// its size or metrics change, the layout is kept with the font size, width, wrap, and tab
+    /// width it was computed for. Laying out again with the same parameters then restores it
+    /// instead of computing it again, which makes toggling between two configurations, such as
+    /// an edit and a preview width, instant. Any other change to the line drops the kept layouts.
+    pub fn set_layout_cache(&mut self, enabled: bool) {
+        if enabled != self.layout_cache.is_some() {
+            self.layout_cache = enabled.then(Vec::new);
+        }
+    }
+
+    /// Set a callback that receives every invalidation of a populated cache of this line
+    ///
+    /// This is meant for debugging unexpected reshaping or relayout. When no logger is set,
+    /// invalidations are not tracked.
+    pub fn set_invalidation_logger(
+        &mut self,
+        logger_opt: Option<Arc<dyn Fn(Invalidation) + Send + Sync>>,
+    ) {
+        self.invalidation_logger = logger_opt.map(InvalidationLogger);
+    }
+
+    fn invalidate_shaping(&mut self, reason: InvalidationReason) {
+        if let Some(logger) = &self.invalidation_logger {
+            if self.shape_opt.is_used() || self.layout_opt.is_used() {
+                (logger.0)(Invalidation::Shaping(reason));
+            }
+        }
+        self.shape_opt.set_unused();
+        self.shape_reuse = ShapeReuse::default();
+        self.hyphens_opt = None;
+        self.layout_opt.set_unused();
+        self.clear_layout_cache();
+        self.wrap_boundaries.clear();
+    }
+
+    fn invalidate_layout(&mut self, reason: InvalidationReason) {
+        if let Some(logger) = &self.invalidation_logger {
+            if self.layout_opt.is_used() {
+                (logger.0)(Invalidation::Layout(reason));
+            }
+        }
+        self.layout_opt.set_unused();
+        self.clear_layout_cache();
+        self.wrap_boundaries.clear();
+    }
+
+    fn clear_layout_cache(&mut self) {
+        self.layout_key_opt = None;
+        if let Some(layout_cache) = &mut self.layout_cache {
+            layout_cache.clear();
+        }
+    }
+
+    /// Shape line, will cache results
+    #[allow(clippy::missing_panics_doc)]
+    pub fn shape(&mut self, font_system: &mut FontSystem, tab_width: u16) -> &ShapeLine {
+        if self.shape_opt.is_used() && self.shape_generation != font_system.shaping_generation() {
+            self.invalidate_shaping(InvalidationReason::FontSystem);
+        }
+        if self.shape_opt.is_used() && !self.shape_is_current(font_system, tab_width) {
+            self.invalidate_shaping(InvalidationReason::TabWidth);
+        }
+        if self.shape_opt.is_unused() {
+            #[cfg(feature = "profiling")]
+            let start = std::time::Instant::now();
+            let mut line = self
+                .shape_opt
+                .take_unused()
+                .unwrap_or_else(ShapeLine::empty);
+            line.build_reusing(
+                font_system,
+                &self.text,
+                &self.attrs_list,
+                self.shaping,
+                tab_width,
+                ShapeOptions {
+                    base_direction: self.base_direction,
+                    forced_cluster_boundaries: &self.forced_cluster_boundaries,
+                    highlight_replacement_char: self.highlight_replacement_char,
+                    insert_dotted_circle: self.insert_dotted_circle,
+                    fallback_families: &self.fallback_families,
+                    split_runs_on_color: self.split_runs_on_color,
+                },
+                &mut mem::take(&mut self.shape_reuse),
+            );
+            #[cfg(feature = "profiling")]
+            {
+                self.shape_stats_opt = Some(ShapeStats {
+                    duration: start.elapsed(),
+                    bytes: self.text.len(),
+                    runs: line.spans.len(),
+                    glyphs: line
+                        .spans
+                        .iter()
+                        .flat_map(|span| span.words.iter())
+                        .map(|word| word.glyphs.len())
+                        .sum(),
+                });
+            }
+            self.shape_opt.set_used(line);
+            self.shape_tab_width = tab_width;
+            self.shape_generation = font_system.shaping_generation();
+            self.layout_opt.set_unused();
+            self.clear_layout_cache();
+            self.wrap_boundaries.clear();
+        }
+        self.shape_opt.get().expect("shape not found")
+    }
+
+    /// Returns true if the shaping cache is populated and [`Self::shape`] would use it
+    ///
+    /// The cache must match the given tab width, and the settings of `font_system` that change
+    /// shaping results, such as its locale. Lines without tabs do not depend on the tab width.
+    pub fn shape_is_current(&self, font_system: &FontSystem, tab_width: u16) -> bool {
+        self.shape_opt.is_used()
+            && self.shape_generation == font_system.shaping_generation()
+            && (self.shape_tab_width == tab_width || !self.text.contains('\t'))
+    }
+
+    /// Get the statistics of the most recent call to [`Self::shape`] that shaped the line
+    ///
+    /// Calls returning the cached shaping are not recorded. Returns `None` if the line was never
+    /// shaped.
+    #[cfg(feature = "profiling")]
+    pub fn last_shape_stats(&self) -> Option<ShapeStats> {
+        self.shape_stats_opt
+    }
+
+    /// Get line shaping cache
+    pub fn shape_opt(&self) -> Option<&ShapeLine> {
+        self.shape_opt.get()
+    }
+
+    /// Get the width of the line in terminal cells, with tabs reaching the next multiple of
+    /// `tab_width` cells
+    ///
+    /// Each grapheme is one or two cells wide depending on its shaped advance compared to a cell,
+    /// which is the monospace width of the font, or else the advance of the first ASCII character
+    /// of the line. Graphemes without advance, such as combining marks on their own, take no
+    /// cells. Graphemes the font does not cover, and all graphemes if the line is not shaped or
+    /// has no cell reference, fall back to their Unicode East Asian width.
+    pub fn display_width(&self, tab_width: u16) -> usize {
+        let tab_width = usize::from(tab_width.max(1));
+        let mut glyphs: Vec<&ShapeGlyph> = self
+            .shape_opt
+            .get()
+            .into_iter()
+            .flat_map(|shape| shape.spans.iter())
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+            .collect();
+        glyphs.sort_by_key(|glyph| glyph.start);
+        let cell_opt = glyphs
+            .iter()
+            .find_map(|glyph| glyph.font_monospace_em_width)
+            .or_else(|| {
+                glyphs
+                    .iter()
+                    .find(|glyph| {
+                        glyph.glyph_id != 0
+                            && self
+                                .text
+                                .get(glyph.start..glyph.end)
+                                .is_some_and(|cluster| {
+                                    cluster.len() == 1 && cluster.as_bytes()[0].is_ascii_graphic()
+                                })
+                    })
+                    .map(|glyph| glyph.x_advance)
+            })
+            .filter(|cell| *cell > 0.0);
+
+        let mut width = 0;
+        let mut glyph_i = 0;
+        for (start, grapheme) in self.text.grapheme_indices(true) {
+            let end = start + grapheme.len();
+            let glyph_start = glyph_i;
+            while glyphs.get(glyph_i).is_some_and(|glyph| glyph.start < end) {
+                glyph_i += 1;
+            }
+            if grapheme == "\t" {
+                width += tab_width - width % tab_width;
+                continue;
+            }
+            let cluster = &glyphs[glyph_start..glyph_i];
+            width += match cell_opt {
+                Some(cell) if !cluster.iter().any(|glyph| glyph.glyph_id == 0) => {
+                    let advance: f32 = cluster.iter().map(|glyph| glyph.x_advance).sum();
+                    // Graphemes after the first of a ligature have no glyphs and no advance
+                    if advance > 0.0 {
+                        (math::roundf(advance / cell) as usize).clamp(1, 2)
+                    } else {
+                        0
+                    }
+                }
+                _ => static_cell_width(grapheme),
+            };
+        }
+        width
+    }
+
+    /// Get the shaped cluster containing the given byte index
+    ///
+    /// Returns `None` if the line has not been shaped, or if the index is at or past the end of
+    /// the line.
+    pub fn cluster_at_cursor(&self, byte_index: usize) -> Option<ClusterInfo<'_>> {
+        let shape = self.shape_opt.get()?;
+        let mut info: Option<ClusterInfo> = None;
+        for glyph in shape
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+        {
+            match &mut info {
+                Some(info) if info.range == (glyph.start..glyph.end) => {
+                    info.glyph_ids.push(glyph.glyph_id);
+                    info.dotted_circle |= glyph.dotted_circle;
+                }
+                Some(_) => break,
+                None if glyph.start <= byte_index && byte_index < glyph.end => {
+                    info = Some(ClusterInfo {
+                        range: glyph.start..glyph.end,
+                        text: self.text.get(glyph.start..glyph.end)?,
+                        glyph_ids: vec![glyph.glyph_id],
+                        font_id: glyph.font_id,
+                        dotted_circle: glyph.dotted_circle,
+                    });
+                }
+                None => {}
+            }
+        }
+        info
+    }
+
+    /// Get the insertion caret at the given byte index
+    ///
+    /// The caret is `caret.width` wide and centered on the insertion position, which is
+    /// interpolated between graphemes inside of a cluster. Where the line wraps, `affinity`
+    /// chooses between the end of the previous layout line, with [`Affinity::Before`], and the
+    /// start of the next one. Coordinates are relative to the top left of the line, with layout
+    /// lines stacked by their line height override or `line_height`.
+    ///
+    /// Returns `None` if the line has not been laid out or `byte_index` is not in its text.
+    pub fn caret_rect(
+        &self,
+        byte_index: usize,
+        affinity: Affinity,
+        line_height: f32,
+        caret: CaretMetrics,
+    ) -> Option<Rect> {
+        let layout = self.layout_opt.get()?;
+        let mut y = 0.0;
+        let mut x_opt = None;
+        // The end of a layout line, used if the next one does not start at the index
+        let mut line_end_opt = None;
+        for layout_line in layout.iter() {
+            let height = layout_line.line_height_opt.unwrap_or(line_height);
+            if let Some(glyph) = layout_line
+                .glyphs
+                .iter()
+                .find(|glyph| glyph.start <= byte_index && byte_index < glyph.end)
+            {
+                // Guess offset based on graphemes
+                let cluster = &self.text[glyph.start..glyph.end];
+                let total = cluster.grapheme_indices(true).count();
+                let before = cluster
+                    .grapheme_indices(true)
+                    .filter(|(i, _)| glyph.start + i < byte_index)
+                    .count();
+                let offset = glyph.w * before as f32 / total as f32;
+                let x = if glyph.level.is_rtl() {
+                    glyph.x + glyph.w - offset
+                } else {
+                    glyph.x + offset
+                };
+                x_opt = Some((x, y, height));
+                break;
+            }
+            if line_end_opt.is_some() {
+                break;
+            }
+            // The logical end is the glyph ending last, which is on the left of RTL text
+            if let Some(glyph) = layout_line.glyphs.iter().max_by_key(|glyph| glyph.end) {
+                if glyph.end == byte_index {
+                    let x = if glyph.level.is_rtl() {
+                        glyph.x
+                    } else {
+                        glyph.x + glyph.w
+                    };
+                    line_end_opt = Some((x, y, height));
+                    if affinity == Affinity::Before {
+                        break;
+                    }
+                }
+            } else if byte_index == 0 {
+                x_opt = Some((0.0, y, height));
+                break;
+            }
+            y += height;
+        }
+        let (x, y, height) = x_opt.or(line_end_opt)?;
+        let mut left = x - caret.width / 2.0;
+        if caret.round_position {
+            left = math::roundf(left);
+        }
+        Some(Rect::new(left, y, caret.width, height))
+    }
+
+    /// Get the block caret of overtype mode at the given byte index
+    ///
+    /// The block covers the cluster that typing at `byte_index` would overwrite, which is the
+    /// whole cluster for ligatures. At the end of the line, the block is half of `line_height`
+    /// wide and placed after the text in the direction of the line. Coordinates are relative to
+    /// the top left of the line, with layout lines stacked by their line height override or
+    /// `line_height`.
+    ///
+    /// Returns `None` if the line has not been laid out.
+    pub fn overtype_caret_rect(&self, byte_index: usize, line_height: f32) -> Option<Rect> {
+        let layout = self.layout_opt.get()?;
+        if let Some(rect) = self.glyph_rect_at(byte_index, line_height) {
+            return Some(rect);
+        }
+
+        let (y, last_height) = self.layout_runs_positioned(line_height).last().map_or(
+            (line_height, line_height),
+            |(top, layout_line)| {
+                let height = layout_line.line_height_opt.unwrap_or(line_height);
+                (top + height, height)
+            },
+        );
+        let width = last_height / 2.0;
+        let glyphs = layout
+            .last()
+            .map_or(&[][..], |layout_line| &layout_line.glyphs);
+        let rtl = self.shape_opt.get().is_some_and(|shape| shape.rtl);
+        let x = if rtl {
+            glyphs
+                .iter()
+                .map(|glyph| glyph.x)
+                .reduce(f32::min)
+                .unwrap_or(0.0)
+                - width
+        } else {
+            glyphs
+                .iter()
+                .map(|glyph| glyph.x + glyph.w)
+                .reduce(f32::max)
+                .unwrap_or(0.0)
+        };
+        Some(Rect::new(x, y - last_height, width, last_height))
+    }
+
+    /// Get the bounds of the cluster containing the given byte index
+    ///
+    /// The rectangle is the union of the glyphs of the cluster on its layout line, so a
+    /// ligature is covered whole. Coordinates are relative to the top left of the line, with
+    /// layout lines stacked by their line height override or `line_height`.
+    ///
+    /// Returns `None` if the line has not been laid out or `byte_index` is at the end of the
+    /// line.
+    pub fn glyph_rect_at(&self, byte_index: usize, line_height: f32) -> Option<Rect> {
+        self.layout_runs_positioned(line_height)
+            .find_map(|(y, layout_line)| {
+                let height = layout_line.line_height_opt.unwrap_or(line_height);
+                let mut cluster_opt: Option<(f32, f32)> = None;
+                for glyph in layout_line.glyphs.iter() {
+                    if glyph.start <= byte_index && byte_index < glyph.end {
+                        let (left, right) = cluster_opt.unwrap_or((glyph.x, glyph.x + glyph.w));
+                        cluster_opt = Some((left.min(glyph.x), right.max(glyph.x + glyph.w)));
+                    }
+                }
+                cluster_opt.map(|(left, right)| Rect::new(left, y, right - left, height))
+            })
+    }
+
+    /// Iterate over the layout lines with the y offset of their top from the top of the line
+    ///
+    /// Offsets accumulate the line height override of each layout line, or `line_height` for
+    /// layout lines without one. Nothing is returned if the line has not been laid out.
+    pub fn layout_runs_positioned(
+        &self,
+        line_height: f32,
+    ) -> impl Iterator<Item = (f32, &LayoutLine)> + '_ {
+        let mut y = 0.0;
+        self.layout_opt
+            .get()
+            .into_iter()
+            .flatten()
+            .map(move |layout_line| {
+                let top = y;
+                y += layout_line.line_height_opt.unwrap_or(line_height);
+                (top, layout_line)
+            })
+    }
+
+    /// Get the byte offsets of the visual start and end of the layout line containing
+    /// `byte_index`, for Home and End keys
+    ///
+    /// The start is the edge where the paragraph direction begins, the left edge in LTR
+    /// paragraphs and the right edge in RTL ones. In bidi text these are not the lowest and
+    /// highest offsets of the layout line. At a wrap boundary, `affinity` chooses between the
+    /// end of the previous layout line and the start of the next one.
+    ///
+    /// Returns `None` if the line has not been laid out.
+    pub fn visual_line_bounds(
+        &self,
+        byte_index: usize,
+        affinity: Affinity,
+    ) -> Option<(usize, usize)> {
+        let layout = self.layout_opt.get()?;
+        let rtl = self.shape_opt.get().is_some_and(|shape| shape.rtl);
+        let start_of =
+            |layout_line: &LayoutLine| layout_line.glyphs.iter().map(|glyph| glyph.start).min();
+        let mut found = None;
+        for (layout_i, layout_line) in layout.iter().enumerate() {
+            // Whitespace dropped at a wrap belongs to the line before it
+            let end = layout
+                .get(layout_i + 1)
+                .and_then(start_of)
+                .or_else(|| layout_line.glyphs.iter().map(|glyph| glyph.end).max());
+            let contains = match (start_of(layout_line), end) {
+                (Some(start), Some(end)) => start <= byte_index && byte_index <= end,
+                _ => true,
+            };
+            if contains {
+                found = Some(layout_line);
+                if affinity == Affinity::Before {
+                    break;
+                }
+            }
+        }
+        let layout_line = found.or_else(|| layout.last())?;
+
+        let left = layout_line
+            .glyphs
+            .iter()
+            .min_by(|a, b| a.x.total_cmp(&b.x))
+            .map(|glyph| {
+                if glyph.level.is_rtl() {
+                    glyph.end
+                } else {
+                    glyph.start
+                }
+            });
+        let right = layout_line
+            .glyphs
+            .iter()
+            .max_by(|a, b| (a.x + a.w).total_cmp(&(b.x + b.w)))
+            .map(|glyph| {
+                if glyph.level.is_rtl() {
+                    glyph.start
+                } else {
+                    glyph.end
+                }
+            });
+        let (Some(left), Some(right)) = (left, right) else {
+            return Some((0, 0));
+        };
+        Some(if rtl { (right, left) } else { (left, right) })
+    }
+
+    /// Get the bounds of a byte range, with one rectangle per layout line the range touches
+    ///
+    /// Each rectangle is the union of the glyphs of the range on that layout line, so it also
+    /// covers any text between bidi runs of the range. Clusters partly in the range are included
+    /// whole. Coordinates are relative to the top left of the line, with layout lines stacked by
+    /// their line height override or `line_height`.
+    ///
+    /// Returns an empty list if the line has not been laid out.
+    pub fn range_bounds(&self, range: Range<usize>, line_height: f32) -> Vec<Rect> {
+        let mut rects = Vec::new();
+        let Some(layout) = self.layout_opt.get() else {
+            return rects;
+        };
+        let mut y = 0.0;
+        for layout_line in layout.iter() {
+            let height = layout_line.line_height_opt.unwrap_or(line_height);
+            let mut bounds_opt: Option<(f32, f32)> = None;
+            for glyph in layout_line.glyphs.iter() {
+                if glyph.start < range.end && range.start < glyph.end {
+                    let (left, right) = bounds_opt.unwrap_or((glyph.x, glyph.x + glyph.w));
+                    bounds_opt = Some((left.min(glyph.x), right.max(glyph.x + glyph.w)));
+                }
+            }
+            if let Some((left, right)) = bounds_opt {
+                rects.push(Rect::new(left, y, right - left, height));
+            }
+            y += height;
+        }
+        rects
+    }
+
+    /// Flatten the layout lines of this line into one list of glyphs with absolute positions
+    ///
+    /// Layout lines are stacked by their line height override or `line_height`, the same as
+    /// [`crate::LayoutRunIter`], and glyphs are placed on the baseline of their layout line.
+    /// Positions are relative to the top left of the paragraph.
+    ///
+    /// Returns an empty layout if the line has not been laid out.
+    pub fn paragraph_layout(&self, line_height: f32) -> ParagraphLayout {
+        let mut paragraph = ParagraphLayout::default();
+        let Some(layout) = self.layout_opt.get() else {
+            return paragraph;
+        };
+        for (line_i, layout_line) in layout.iter().enumerate() {
+            let height = layout_line.line_height_opt.unwrap_or(line_height);
+            let baseline = paragraph.h + layout_line.baseline(height);
+            paragraph.lines.push(ParagraphLine {
+                top: paragraph.h,
+                height,
+                baseline,
+                w: layout_line.w,
+            });
+            paragraph
+                .glyphs
+                .extend(layout_line.glyphs.iter().map(|glyph| ParagraphGlyph {
+                    line: line_i,
+                    x: glyph.x,
+                    y: baseline + glyph.y,
+                    glyph: glyph.clone(),
+                }));
+            paragraph.w = paragraph.w.max(layout_line.w);
+            paragraph.h += height;
+        }
+        paragraph
+    }
+
+    /// Get the narrowest width at which the line wraps into at most `target_lines` lines
+    ///
+    /// Laying the line out with [`Wrap::Word`] at the returned width balances the text over the
+    /// lines as evenly as word boundaries allow, like CSS `text-wrap: balance`. If the line
+    /// cannot be wrapped into `target_lines` lines, the width giving the closest line count is
+    /// returned. The probe layouts do not use the wrap width override, overflow or hyphenation
+    /// of the line, and do not change its cached layout.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn balanced_width(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        target_lines: usize,
+        tab_width: u16,
+    ) -> f32 {
+        let target_lines = target_lines.max(1);
+        let shape = self.shape(font_system, tab_width);
+        let mut layout = Vec::with_capacity(target_lines);
+        let mut measure = |width_opt: Option<f32>| {
+            shape.layout_to_buffer(
+                &mut font_system.shape_buffer,
+                font_size,
+                width_opt,
+                Wrap::Word,
+                None,
+                &mut layout,
+                None,
+            );
+            let width = layout
+                .iter()
+                .map(|layout_line| layout_line.w)
+                .fold(0.0, f32::max);
+            (layout.len(), width)
+        };
+
+        let (_, mut best) = measure(None);
+        if target_lines == 1 {
+            return best;
+        }
+        // Lines only merge as the width grows, so search for the narrowest width that fits
+        let mut low = 0.0;
+        let mut high = best;
+        while high - low > 0.5 {
+            let mid = (low + high) / 2.0;
+            let (lines, width) = measure(Some(mid));
+            if lines <= target_lines {
+                high = mid;
+                best = width;
+            } else {
+                low = mid;
+            }
+        }
+        best
+    }
+
+    /// Get how ragged the wrapping of the cached layout is, lower is better
+    ///
+    /// This is the sum of the squared space left between each layout line and the wrap width,
+    /// excluding the last layout line, which may be short. Tools can use it to flag ragged
+    /// paragraphs or to decide whether to offer [`Self::balanced_width`]. Returns `0.0` if the
+    /// line has not been laid out, has no wrap width, or fits on a single layout line.
+    pub fn wrap_badness(&self) -> f32 {
+        let (Some(layout), Some(key)) = (self.layout_opt(), self.layout_key_opt) else {
+            return 0.0;
+        };
+        let Some(width) = key.width_opt.filter(|_| key.wrap != Wrap::None) else {
+            return 0.0;
+        };
+        layout
+            .iter()
+            .take(layout.len().saturating_sub(1))
+            .map(|layout_line| {
+                let slack = (width - layout_line.w).max(0.0);
+                slack * slack
+            })
+            .sum()
+    }
+
+    /// Get the horizontal scale, at most `1.0`, that makes the unwrapped line fit `width`
+    ///
+    /// Scaling the line horizontally by the returned factor, with a transform or by tightening
+    /// its letter spacing, condenses it to `width` instead of wrapping or truncating it. Returns
+    /// `1.0` if the line already fits. The probe layout does not use the wrap width override,
+    /// overflow or hyphenation of the line, and does not change its cached layout.
+    pub fn shrink_factor_to_fit(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width: f32,
+        tab_width: u16,
+    ) -> f32 {
+        let shape = self.shape(font_system, tab_width);
+        let mut layout = Vec::with_capacity(1);
+        shape.layout_to_buffer(
+            &mut font_system.shape_buffer,
+            font_size,
+            None,
+            Wrap::None,
+            None,
+            &mut layout,
+            None,
+        );
+        let natural = layout
+            .iter()
+            .map(|layout_line| layout_line.w)
+            .fold(0.0, f32::max);
+        if natural <= width {
+            1.0
+        } else {
+            width.max(0.0) / natural
+        }
+    }
+
+    /// Lay out the line wrapped to `width`, keeping at most `max_lines` layout lines
+    ///
+    /// This is the multi-line ellipsis of a label with a fixed size: if text is dropped, the end
+    /// of the last kept line is replaced with `ellipsis`, shaped with the default attributes of
+    /// the line. Words are wrapped, and broken between graphemes only if they do not fit on a
+    /// line of their own, so graphemes are never split. A `max_lines` of zero is treated as one.
+    /// The other layout settings of the line, such as alignment, hyphenation and tab stops, apply
+    /// like in [`Self::layout`], except the wrap width override. This is [`Overflow::Truncate`]
+    /// without changing the overflow mode or the cached layout of the line.
+    pub fn layout_in_box(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width: f32,
+        max_lines: usize,
+        ellipsis: &EllipsisSource,
+        tab_width: u16,
+    ) -> BoxLayout {
+        let key = LayoutKey {
+            font_size,
+            width_opt: Some(width),
+            wrap: Wrap::WordOrGlyph,
+            match_mono_width: None,
+            tab_width,
+        };
+        let overflow = Overflow::Truncate {
+            max_lines,
+            ellipsis: ellipsis.clone(),
+        };
+        let mut lines = Vec::with_capacity(1);
+        let truncated = self.layout_uncached(font_system, &key, &overflow, &mut lines);
+        BoxLayout { lines, truncated }
+    }
+
+    /// Layout line, will cache results
+    ///
+    /// The wrap width override set by [`Self::set_wrap_width`] takes precedence over `width_opt`.
+    /// Marks are clamped according to [`Self::set_clamp_mark_stack_height`].
+    #[allow(clippy::missing_panics_doc)]
+    pub fn layout(
+        &mut self,
+        font_system: &mut FontSystem,
+        font_size: f32,
+        width_opt: Option<f32>,
+        wrap: Wrap,
+        match_mono_width: Option<f32>,
+        tab_width: u16,
+    ) -> &[LayoutLine] {
+        let key = LayoutKey {
+            font_size,
+            width_opt: self.wrap_width_opt.or(width_opt),
+            wrap,
+            match_mono_width,
+            tab_width,
+        };
+        if self.layout_opt.is_unused() && self.shape_is_current(font_system, tab_width) {
+            let cached_opt = self.layout_cache.as_mut().and_then(|layout_cache| {
+                let index = layout_cache
+                    .iter()
+                    .position(|(cached_key, _)| *cached_key == key)?;
+                Some(layout_cache.remove(index).1)
+            });
+            if let Some(layout) = cached_opt {
+                self.set_layout(layout, key);
+            }
+        }
+        if self.layout_opt.is_unused() {
+            let mut layout = self
+                .layout_opt
+                .take_unused()
+                .unwrap_or_else(|| Vec::with_capacity(1));
+            let overflow = self.overflow.clone();
+            self.layout_uncached(font_system, &key, &overflow, &mut layout);
+            self.set_layout(layout, key);
+        }
+        self.layout_opt.get().expect("layout not found")
+    }
+
+    /// Lay out the line into `layout` for `key`, with `overflow` instead of the overflow mode of
+    /// the line, and return true if text was dropped or replaced by an ellipsis
+    fn layout_uncached(
+        &mut self,
+        font_system: &mut FontSystem,
+        key: &LayoutKey,
+        overflow: &Overflow,
+        layout: &mut Vec<LayoutLine>,
+    ) -> bool {
+        let LayoutKey {
+            font_size,
+            width_opt,
+            wrap,
+            match_mono_width,
+            tab_width,
+        } = *key;
+        let align = self.align;
+        let line_aligns = mem::take(&mut self.line_aligns);
+        let trim_wrapped_leading_whitespace = self.trim_wrapped_leading_whitespace;
+        let ignore_trailing_whitespace_in_align = self.ignore_trailing_whitespace_in_align;
+        let drop_cap = self.drop_cap_opt;
+        let ellipsis_opt = match (overflow, width_opt) {
+            (Overflow::Ellipsis(source), Some(width)) => Some((source.clone(), width, None)),
+            (
+                Overflow::Truncate {
+                    max_lines,
+                    ellipsis,
+                },
+                Some(width),
+            ) => Some((ellipsis.clone(), width, Some((*max_lines).max(1)))),
+            _ => None,
+        };
+        let wrap = match ellipsis_opt {
+            Some((_, _, None)) => Wrap::None,
+            _ => wrap,
+        };
+        // Shape first, so that hyphens are not reused from outdated shaping
+        self.shape(font_system, tab_width);
+        let hyphenate = self.hyphenator.is_some() && self.shaping == Shaping::Advanced;
+        let hyphens = match self.hyphens_opt.take() {
+            Some(hyphens) if hyphenate => hyphens,
+            _ if hyphenate => self.shape_marks(font_system, self.hyphen_char),
+            _ => Vec::new(),
+        };
+        let tab_markers = match self.tab_visualization {
+            Some(c) => self.shape_marks(font_system, c),
+            None => Vec::new(),
+        };
+        let mut tab_leaders: Vec<(char, Range<usize>, ShapeGlyph)> = Vec::new();
+        if let Some(tab_stops) = &self.tab_stops_opt {
+            for leader in tab_stops.leaders.iter().flatten() {
+                if !tab_leaders.iter().any(|(c, _, _)| c == leader) {
+                    for (range, glyph) in self.shape_marks(font_system, *leader) {
+                        tab_leaders.push((*leader, range, glyph));
+                    }
+                }
+            }
+        }
+        let tab_leader_of = |index: usize, leader: char| {
+            tab_leaders
+                .iter()
+                .find(|(c, range, _)| *c == leader && range.contains(&index))
+                .map(|(_, _, glyph)| glyph.clone())
+        };
+        let tab_marker_of = |index: usize| {
+            tab_markers
+                .iter()
+                .find(|(range, _)| range.contains(&index))
+                .map(|(_, marker)| marker.clone())
+        };
+        let hyphen_of = |index: usize| {
+            hyphens
+                .iter()
+                .find(|(range, _)| range.contains(&index))
+                .map(|(_, hyphen)| hyphen.clone())
+        };
+        self.shape(font_system, tab_width);
+        let shape = self.shape_opt.get().expect("shape not found");
+        let rtl = shape.rtl;
+        shape.layout_to_buffer_with_options(
+            &mut font_system.shape_buffer,
+            font_size,
+            width_opt,
+            wrap,
+            align,
+            layout,
+            match_mono_width,
+            LayoutOptions {
+                line_aligns: &line_aligns,
+                trim_wrapped_leading_whitespace,
+                ignore_trailing_whitespace_in_align,
+                drop_cap,
+                tab_stops: self.tab_stops_opt.as_ref(),
+                tab_visualization: if tab_markers.is_empty() {
+                    None
+                } else {
+                    Some(TabVisualization {
+                        marker: &tab_marker_of,
+                    })
+                },
+                tab_leaders: if tab_leaders.is_empty() {
+                    None
+                } else {
+                    Some(TabLeaders {
+                        leader: &tab_leader_of,
+                    })
+                },
+                avoid_orphans: self.avoid_orphans,
+                vertical_align: self.vertical_align,
+                hyphenation: match &self.hyphenator {
+                    Some(hyphenator) if !hyphens.is_empty() => Some(Hyphenation {
+                        text: &self.text,
+                        hyphenator: &*hyphenator.0,
+                        hyphen: &hyphen_of,
+                    }),
+                    _ => None,
+                },
+            },
+        );
+        self.line_aligns = line_aligns;
+        if hyphenate {
+            self.hyphens_opt = Some(hyphens);
+        }
+        let mut truncated = false;
+        if let Some((source, width, max_lines_opt)) = ellipsis_opt {
+            let ellipsis = ShapeLine::new(
+                font_system,
+                &source.text(),
+                &AttrsList::new(&self.attrs_list.defaults()),
+                self.shaping,
+                tab_width,
+            )
+            .layout(font_size, None, Wrap::None, None, match_mono_width);
+            let align = align.unwrap_or(if rtl { Align::Right } else { Align::Left });
+            truncated = ellipsize_layout(
+                layout,
+                &ellipsis[0].glyphs,
+                width,
+                align,
+                rtl,
+                max_lines_opt,
+            );
+        }
+        if let Some(max_height) = self.mark_stack_height_opt {
+            for layout_line in layout.iter_mut() {
+                layout_line.clamp_mark_stack_height(max_height);
+            }
+        }
+        if self.attrs_list.has_opacity() {
+            for glyph in layout
+                .iter_mut()
+                .flat_map(|layout_line| layout_line.glyphs.iter_mut())
+            {
+                glyph.opacity = self.attrs_list.opacity_at(glyph.start);
+            }
+        }
+        truncated
+    }
+
+    /// Shape a mark, such as a hyphen, with the attributes of each span, followed by the defaults
+    fn shape_marks(
+        &self,
+        font_system: &mut FontSystem,
+        mark: char,
+    ) -> Vec<(Range<usize>, ShapeGlyph)> {
+        let mut text = [0; 4];
+        let text = mark.encode_utf8(&mut text);
+        let mut shape_mark = |attrs: &Attrs| {
+            ShapeWord::new(
+                font_system,
+                text,
+                &AttrsList::new(attrs),
+                0..text.len(),
+                unicode_bidi::Level::ltr(),
+                false,
+                Shaping::Advanced,
+            )
+            .glyphs
+            .into_iter()
+            .next()
+        };
+        let mut marks = Vec::new();
+        for (range, attrs) in self.attrs_list.spans_iter() {
+            if let Some(mark) = shape_mark(&attrs.as_attrs()) {
+                marks.push((range.clone(), mark));
+            }
+        }
+        if let Some(mark) = shape_mark(&self.attrs_list.defaults()) {
+            marks.push((0..usize::MAX, mark));
+        }
+        marks
+    }
+
+    fn set_layout(&mut self, layout: Vec<LayoutLine>, key: LayoutKey) {
+        self.wrap_boundaries.clear();
+        self.wrap_boundaries.extend(
+            layout
+                .iter()
+                .skip(1)
+                .filter_map(|layout_line| layout_line.glyphs.iter().map(|glyph| glyph.start).min()),
+        );
+        self.layout_opt.set_used(layout);
+        self.layout_key_opt = Some(key);
+    }
+
+    /// Release the excess capacity of the cached shaping and layout
+    ///
+    /// Reused caches keep the capacity of the longest line they held, so long-running editors
+    /// can call this when idle. The caches stay valid, and invalidated caches, which are only
+    /// kept for their allocations, are dropped.
+    pub fn shrink_caches(&mut self) {
+        self.shape_opt.clear_unused();
+        self.layout_opt.clear_unused();
+        if let Some(shape) = self.shape_opt.get_mut() {
+            shape.shrink_to_fit();
+        }
+        fn shrink_layout(layout: &mut Vec<LayoutLine>) {
+            for layout_line in layout.iter_mut() {
+                layout_line.glyphs.shrink_to_fit();
+            }
+            layout.shrink_to_fit();
+        }
+        if let Some(layout) = self.layout_opt.get_mut() {
+            shrink_layout(layout);
+        }
+        if let Some(layout_cache) = &mut self.layout_cache {
+            for (_, layout) in layout_cache.iter_mut() {
+                shrink_layout(layout);
+            }
+        }
+        self.wrap_boundaries.shrink_to_fit();
+    }
+
+    /// Get line layout cache
+    pub fn layout_opt(&self) -> Option<&Vec<LayoutLine>> {
+        self.layout_opt.get()
+    }
+
+    /// Get line layout cache mutably, to make manual changes such as
+    /// [`LayoutLine::set_glyph_advance`]
+    ///
+    /// Manual changes are lost the next time the line is laid out, and a changed layout is not
+    /// kept by [`Self::set_layout_cache`].
+    pub fn layout_opt_mut(&mut self) -> Option<&mut Vec<LayoutLine>> {
+        self.layout_key_opt = None;
+        self.layout_opt.get_mut()
+    }
+
+    /// Get the byte offsets at which the current layout wraps onto a new visual line
+    ///
+    /// Each offset is the start of a visual line after the first. This is empty if the line is
+    /// not wrapped or has not been laid out.
+    pub fn wrap_boundaries(&self) -> &[usize] {
+        &self.wrap_boundaries
+    }
+
+    /// Get line metadata. This will be None if [`BufferLine::set_metadata`] has not been called
+    /// after the last reset of shaping and layout caches
+    pub fn metadata(&self) -> Option<usize> {
+        self.metadata
+    }
+
+    /// Set line metadata. This is stored until the next line reset
+    pub fn set_metadata(&mut self, metadata: usize) {
+        self.metadata = Some(metadata);
+    }
+
+    /// Get the user data, if it was set with [`BufferLine::set_user_data`] and has type `T`
+    pub fn user_data<T: Any>(&self) -> Option<&T> {
+        self.user_data
+            .as_ref()
+            .and_then(|user_data| (*user_data.0).as_any().downcast_ref())
+    }
+
+    /// Attach arbitrary data to the line, replacing any previous user data
+    ///
+    /// Unlike [`BufferLine::set_metadata`], it is kept when the text changes. It is cloned with
+    /// the line, cleared by [`BufferLine::reset`] and [`BufferLine::reset_new`], stays on this
+    /// line in [`BufferLine::split_off`], and the user data of the other line is dropped in
+    /// [`BufferLine::append`].
+    pub fn set_user_data<T: Any + Clone + Send + Sync>(&mut self, data: T) {
+        self.user_data = Some(UserDataBox(Box::new(data)));
+    }
+
+    /// Remove the user data
+    pub fn clear_user_data(&mut self) {
+        self.user_data = None;
+    }
+
+    /// Get the folding state of the line
+    pub fn fold_state(&self) -> FoldState {
+        self.fold_state
+    }
+
+    /// Set the folding state of the line, see [`Buffer::folded_ranges`](crate::Buffer::folded_ranges)
+    ///
+    /// This does not reset shaping or layout. It is kept when the text changes and cleared by
+    /// [`BufferLine::reset_new`], and the line created by [`BufferLine::split_off`] does not
+    /// start a folded region. Use [`Buffer::set_fold_state`](crate::Buffer::set_fold_state) to
+    /// also mark the buffer for redraw.
+    pub fn set_fold_state(&mut self, fold_state: FoldState) {
+        self.fold_state = fold_state;
+    }
+
+    /// Makes an empty buffer line.
+    ///
+    /// The buffer line is in an invalid state after this is called. See [`Self::reset_new`].
+    pub(crate) fn empty() -> Self {
+        Self {
+            text: String::default(),
+            ending: LineEnding::default(),
+            attrs_list: AttrsList::new(&Attrs::new()),
+            align: None,
+            line_aligns: Vec::new(),
+            base_direction: BaseDirection::default(),
+            forced_cluster_boundaries: Vec::new(),
+            highlight_replacement_char: false,
+            insert_dotted_circle: true,
+            fallback_families: Vec::new(),
+            split_runs_on_color: false,
+            wrap_width_opt: None,
+            mark_stack_height_opt: None,
+            trim_wrapped_leading_whitespace: true,
+            ignore_trailing_whitespace_in_align: false,
+            drop_cap_opt: None,
+            overflow: Overflow::default(),
+            tab_stops_opt: None,
+            tab_visualization: None,
+            avoid_orphans: false,
+            vertical_align: VerticalAlign::Baseline,
+            hyphenator: None,
+            hyphen_char: '-',
+            hyphens_opt: None,
+            fingerprint: 0,
+            edit_generation: 0,
+            shape_opt: Cached::Empty,
+            shape_reuse: ShapeReuse::default(),
+            shape_tab_width: 0,
+            shape_generation: 0,
+            #[cfg(feature = "profiling")]
+            shape_stats_opt: None,
+            layout_opt: Cached::Empty,
+            layout_key_opt: None,
+            layout_cache: None,
+            wrap_boundaries: Vec::new(),
+            shaping: Shaping::Advanced,
+            metadata: None,
+            user_data: None,
+            fold_state: FoldState::None,
+            invalidation_logger: None,
+        }
+    }
+
+    /// Reclaim attributes list memory that isn't needed any longer.
+    ///
+    /// The buffer line is in an invalid state after this is called. See [`Self::reset_new`].
+    pub(crate) fn reclaim_attrs(&mut self) -> AttrsList {
+        mem::replace(&mut self.attrs_list, AttrsList::new(&Attrs::new()))
+    }
+
+    /// Reclaim text memory that isn't needed any longer.
     ///
     /// The buffer line is in an invalid state after this is called. See [`Self::reset_new`].
     pub(crate) fn reclaim_text(&mut self) -> String {
@@ -308,3 +2673,65 @@ impl BufferLine {
         text
     }
 }
+
+/// The logical content of a [`BufferLine`] that is serialized, see [`BufferLine`]'s serde impls
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename = "BufferLine")]
+struct BufferLineContent<T, A> {
+    format_version: u32,
+    text: T,
+    ending: LineEnding,
+    attrs_list: A,
+    align: Option<Align>,
+    shaping: Shaping,
+    metadata: Option<usize>,
+}
+
+/// Serializes the text, ending, attributes, alignment, shaping and metadata of the line, with the
+/// [`ShapeLine::FORMAT_VERSION`] that wrote them. Caches and other line options are not
+/// serialized.
+#[cfg(feature = "serde")]
+impl serde::Serialize for BufferLine {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BufferLineContent {
+            format_version: ShapeLine::FORMAT_VERSION,
+            text: &self.text,
+            ending: self.ending,
+            attrs_list: &self.attrs_list,
+            align: self.align,
+            shaping: self.shaping,
+            metadata: self.metadata,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Deserializes a line that is not shaped or laid out yet, with the other line options at
+/// their defaults
+///
+/// Lines written with another [`ShapeLine::FORMAT_VERSION`] are rejected.
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for BufferLine {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let content = BufferLineContent::<String, AttrsList>::deserialize(deserializer)?;
+        if content.format_version != ShapeLine::FORMAT_VERSION {
+            return Err(serde::de::Error::custom(format_args!(
+                "format version {} does not match {}",
+                content.format_version,
+                ShapeLine::FORMAT_VERSION
+            )));
+        }
+        let mut line = Self::new(
+            content.text,
+            content.ending,
+            content.attrs_list,
+            content.shaping,
+        );
+        line.set_align(content.align);
+        if let Some(metadata) = content.metadata {
+            line.set_metadata(metadata);
+        }
+        Ok(line)
+    }
+}
@@ -74,6 +74,13 @@ impl<T: Clone + Debug> Cached<T> {
         }
     }
 
+    /// Drops the value if in state `Self::Unused`, releasing its allocations.
+    pub fn clear_unused(&mut self) {
+        if matches!(*self, Self::Unused(_)) {
+            *self = Self::Empty;
+        }
+    }
+
     /// Sets the value to `Self::Used`.
     pub fn set_used(&mut self, val: T) {
         *self = Self::Used(val);
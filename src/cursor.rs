@@ -154,3 +154,17 @@ impl Scroll {
         }
     }
 }
+
+/// Where to place a cursor in the viewport, see [`Buffer::scroll_to_cursor`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum ScrollAlign {
+    /// Place the line of the cursor at the top of the viewport
+    Top,
+    /// Center the line of the cursor in the viewport
+    Center,
+    /// Place the line of the cursor at the bottom of the viewport
+    Bottom,
+    /// Scroll as little as possible to make the cursor visible
+    #[default]
+    Nearest,
+}
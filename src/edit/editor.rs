@@ -370,6 +370,10 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
             // Save cursor for change tracking
             let start = cursor;
 
+            // New lines use the fallback families and default shaping of the buffer
+            let fallback_families = buffer.fallback_families().to_vec();
+            let default_shaping = buffer.default_shaping();
+
             // Ensure there are enough lines in the buffer to handle this cursor
             while cursor.line >= buffer.lines.len() {
                 let ending = buffer
@@ -377,7 +381,7 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                     .last()
                     .map(|line| line.ending())
                     .unwrap_or_default();
-                let line = BufferLine::new(
+                let mut line = BufferLine::new(
                     String::new(),
                     ending,
                     AttrsList::new(&attrs_list.as_ref().map_or_else(
@@ -389,14 +393,17 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                         },
                         |x| x.defaults(),
                     )),
-                    Shaping::Advanced,
+                    default_shaping,
                 );
+                line.set_fallback_families(fallback_families.clone());
                 buffer.lines.push(line);
             }
 
             let line: &mut BufferLine = &mut buffer.lines[cursor.line];
             let insert_line = cursor.line + 1;
             let ending = line.ending();
+            // Lines split from this line keep its shaping, like `BufferLine::split_off`
+            let shaping = line.shaping();
 
             // Collect text after insertion as a line
             let after: BufferLine = line.split_off(cursor.index);
@@ -435,8 +442,9 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
                         .unwrap_or(data_line),
                     ending,
                     final_attrs.split_off(remaining_split_len),
-                    Shaping::Advanced,
+                    shaping,
                 );
+                tmp.set_fallback_families(fallback_families.clone());
                 tmp.append(after);
                 buffer.lines.insert(insert_line, tmp);
                 cursor.line += 1;
@@ -445,14 +453,15 @@ impl<'buffer> Edit<'buffer> for Editor<'buffer> {
             }
             for data_line in lines_iter.rev() {
                 remaining_split_len -= data_line.len();
-                let tmp = BufferLine::new(
+                let mut tmp = BufferLine::new(
                     data_line
                         .strip_suffix(char::is_control)
                         .unwrap_or(data_line),
                     ending,
                     final_attrs.split_off(remaining_split_len),
-                    Shaping::Advanced,
+                    shaping,
                 );
+                tmp.set_fallback_families(fallback_families.clone());
                 buffer.lines.insert(insert_line, tmp);
                 cursor.line += 1;
             }
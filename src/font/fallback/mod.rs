@@ -6,9 +6,10 @@ use alloc::sync::Arc;
 use alloc::vec::Vec;
 use core::{mem, ops::Range};
 use fontdb::Family;
+use unicode_properties::{EmojiStatus, UnicodeEmoji};
 use unicode_script::Script;
 
-use crate::{BuildHasher, Font, FontMatchKey, FontSystem, HashMap, ShapeBuffer};
+use crate::{BuildHasher, FamilyOwned, Font, FontMatchKey, FontSystem, HashMap, ShapeBuffer};
 
 #[cfg(not(any(all(unix, not(target_os = "android")), target_os = "windows")))]
 #[path = "other.rs"]
@@ -76,7 +77,60 @@ pub trait Fallback: Send + Sync {
     fn script_fallback(&self, script: Script, locale: &str) -> &[&'static str];
 }
 
-#[derive(Debug, Default)]
+/// How emoji are resolved when no color emoji font covers them, see
+/// [`FontSystem::set_emoji_fallback`]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub enum EmojiFallback {
+    /// Only try the requested fonts and the preset fallbacks, leaving emoji that they do not
+    /// cover as `.notdef`
+    ///
+    /// Emoji are characters with emoji presentation by default, and emoji characters followed
+    /// by the emoji presentation selector (U+FE0F). Other symbols fall back as usual.
+    None,
+    /// Try every available font, accepting monochrome emoji glyphs
+    #[default]
+    Monochrome,
+    /// Like [`EmojiFallback::Monochrome`], but also request the text presentation of emoji by
+    /// replacing the emoji presentation selector (U+FE0F) with the text presentation selector
+    /// (U+FE0E) when shaping
+    Text,
+}
+
+/// What to do when no font of the requested family matches the style, stretch and weight of
+/// the text, see [`FontSystem::set_missing_family`]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum MissingFamily {
+    /// Silently use the fonts found by fallback
+    #[default]
+    Fallback,
+    /// Use the fonts found by fallback, but flag the glyphs with
+    /// [`crate::ShapeGlyph::missing_family`] so the affected text can be reported, see
+    /// [`crate::ShapeLine::missing_family_ranges`]
+    Error,
+    /// Use this family instead, falling back as usual if it is missing too
+    Substitute(FamilyOwned),
+}
+
+/// Returns true if `text` has a character presented as emoji by default (`Emoji_Presentation`),
+/// or an emoji character followed by the emoji presentation selector (U+FE0F)
+pub(crate) fn has_emoji(text: &str) -> bool {
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let presentation = matches!(
+            c.emoji_status(),
+            EmojiStatus::EmojiPresentation
+                | EmojiStatus::EmojiPresentationAndModifierBase
+                | EmojiStatus::EmojiPresentationAndEmojiComponent
+                | EmojiStatus::EmojiPresentationAndModifierAndEmojiComponent
+        );
+        if presentation || (c.is_emoji_char() && chars.peek() == Some(&'\u{FE0F}')) {
+            return true;
+        }
+    }
+    false
+}
+
+#[derive(Clone, Debug, Default)]
 pub(crate) struct Fallbacks {
     lists: Vec<&'static str>,
     common_fallback_range: Range<usize>,
@@ -321,12 +375,12 @@ impl<'a> FontFallbackIter<'a> {
             }
 
             match (is_mono, default_font_match_key.as_ref()) {
-                (false, None) => break 'DEF_FAM,
+                (false, None) => continue 'DEF_FAM,
                 (false, Some(m_key)) => {
                     if let Some(font) = self.font_system.get_font(m_key.id) {
                         return Some(font);
                     } else {
-                        break 'DEF_FAM;
+                        continue 'DEF_FAM;
                     }
                 }
                 (true, None) => (),
@@ -440,6 +494,11 @@ impl<'a> FontFallbackIter<'a> {
             log::debug!("failed to find family '{}'", common_family);
         }
 
+        if self.font_system.emoji_fallback() == EmojiFallback::None && has_emoji(self.word) {
+            self.end = true;
+            return None;
+        }
+
         //TODO: do we need to do this?
         //TODO: do not evaluate fonts more than once!
         let forbidden_families = fallbacks.forbidden_fallback();
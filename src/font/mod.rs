@@ -16,7 +16,7 @@ use rustybuzz::Face as RustybuzzFace;
 use self_cell::self_cell;
 
 pub(crate) mod fallback;
-pub use fallback::{Fallback, PlatformFallback};
+pub use fallback::{EmojiFallback, Fallback, MissingFamily, PlatformFallback};
 
 pub use self::system::*;
 mod system;
@@ -46,6 +46,8 @@ pub struct Font {
     #[cfg(feature = "peniko")]
     data: peniko::Font,
     id: fontdb::ID,
+    weight: fontdb::Weight,
+    style: fontdb::Style,
     monospace_fallback: Option<FontMonospaceFallback>,
 }
 
@@ -62,6 +64,14 @@ impl Font {
         self.id
     }
 
+    pub fn weight(&self) -> fontdb::Weight {
+        self.weight
+    }
+
+    pub fn style(&self) -> fontdb::Style {
+        self.style
+    }
+
     pub fn monospace_em_width(&self) -> Option<f32> {
         self.monospace_fallback
             .as_ref()
@@ -179,6 +189,8 @@ impl Font {
 
         Some(Self {
             id: info.id,
+            weight: info.weight,
+            style: info.style,
             monospace_fallback,
             #[cfg(feature = "swash")]
             swash: {
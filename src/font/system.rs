@@ -1,17 +1,22 @@
-use crate::{Attrs, Font, FontMatchAttrs, HashMap, ShapeBuffer};
-use alloc::boxed::Box;
+use crate::{
+    Attrs, AttrsList, AttrsOwned, BaseDirection, Direction, Font, FontMatchAttrs, GraphemeShape,
+    HashMap, ShapeBuffer, ShapeLine, ShapeWord, Shaping,
+};
 use alloc::collections::BTreeSet;
 use alloc::string::String;
 use alloc::sync::Arc;
 use alloc::vec::Vec;
-use core::fmt;
 use core::ops::{Deref, DerefMut};
+use core::{fmt, mem};
+use unicode_segmentation::UnicodeSegmentation;
 
 // re-export fontdb and rustybuzz
 pub use fontdb;
 pub use rustybuzz;
 
-use super::fallback::{Fallback, Fallbacks, MonospaceFallbackInfo, PlatformFallback};
+use super::fallback::{
+    EmojiFallback, Fallback, Fallbacks, MissingFamily, MonospaceFallbackInfo, PlatformFallback,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct FontMatchKey {
@@ -79,11 +84,35 @@ impl FontCachedCodepointSupportInfo {
     }
 }
 
+/// How much of a string a font can display, see [`FontSystem::font_covers`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Coverage {
+    /// Every character is covered
+    Full,
+    /// Some characters are covered, the missing ones are listed in order of first appearance,
+    /// up to [`Coverage::MISSING_MAX`]
+    Partial(Vec<char>),
+    /// No character is covered, or the font could not be loaded
+    None,
+}
+
+impl Coverage {
+    /// Maximum number of missing characters listed by [`Coverage::Partial`]
+    pub const MISSING_MAX: usize = 32;
+}
+
 /// Access to the system fonts.
 pub struct FontSystem {
     /// The locale of the system.
     locale: String,
 
+    /// Default shaping language, from the locale given to [`Self::set_locale`].
+    language_opt: Option<rustybuzz::Language>,
+
+    /// Incremented when a setting that changes shaping results changes, so shaped lines are
+    /// redone.
+    shaping_generation: u64,
+
     /// The underlying font database.
     db: fontdb::Database,
 
@@ -104,9 +133,21 @@ pub struct FontSystem {
     /// Cache for font matches.
     font_matches_cache: HashMap<FontMatchAttrs, Arc<Vec<FontMatchKey>>>,
 
+    /// Cache for whether the requested family has a matching font, see [`Self::has_family`].
+    has_family_cache: HashMap<FontMatchAttrs, bool>,
+
+    /// Cache for advances of mask characters, in em units.
+    mask_advance_cache: HashMap<(char, AttrsOwned), f32>,
+
+    /// Cache for the widest advance of the digits `0-9`, in em units.
+    digit_advance_cache: HashMap<AttrsOwned, f32>,
+
     /// Scratch buffer for shaping and laying out.
     pub(crate) shape_buffer: ShapeBuffer,
 
+    /// Line reused by [`Self::shape_into_buffer`].
+    shape_line: ShapeLine,
+
     /// Buffer for use in `FontFallbackIter`.
     pub(crate) monospace_fallbacks_buffer: BTreeSet<MonospaceFallbackInfo>,
 
@@ -115,10 +156,21 @@ pub struct FontSystem {
     pub shape_run_cache: crate::ShapeRunCache,
 
     /// List of fallbacks
-    pub(crate) dyn_fallback: Box<dyn Fallback>,
+    pub(crate) dyn_fallback: Arc<dyn Fallback>,
 
     /// List of fallbacks
     pub(crate) fallbacks: Fallbacks,
+
+    /// How emoji are resolved without a color emoji font
+    emoji_fallback: EmojiFallback,
+
+    /// What to do when the requested family is missing
+    missing_family: MissingFamily,
+
+    /// Set on the copies made by [`Self::worker`], which do not load fonts. True once a font
+    /// that was not loaded has been requested.
+    #[cfg(feature = "rayon")]
+    worker_missed_font: Option<bool>,
 }
 
 impl fmt::Debug for FontSystem {
@@ -206,19 +258,29 @@ impl FontSystem {
         let fallbacks = Fallbacks::new(&impl_fallback, &[], &locale);
 
         Self {
+            language_opt: None,
+            shaping_generation: 0,
             locale,
             db,
             monospace_font_ids,
             per_script_monospace_font_ids,
             font_cache: Default::default(),
             font_matches_cache: Default::default(),
+            has_family_cache: Default::default(),
             font_codepoint_support_info_cache: Default::default(),
+            mask_advance_cache: Default::default(),
+            digit_advance_cache: Default::default(),
             monospace_fallbacks_buffer: BTreeSet::default(),
             #[cfg(feature = "shape-run-cache")]
             shape_run_cache: crate::ShapeRunCache::default(),
             shape_buffer: ShapeBuffer::default(),
-            dyn_fallback: Box::new(impl_fallback),
+            shape_line: ShapeLine::empty(),
+            dyn_fallback: Arc::new(impl_fallback),
             fallbacks,
+            emoji_fallback: EmojiFallback::default(),
+            missing_family: MissingFamily::default(),
+            #[cfg(feature = "rayon")]
+            worker_missed_font: None,
         }
     }
 
@@ -240,16 +302,296 @@ impl FontSystem {
     /// Get a mutable reference to the database.
     pub fn db_mut(&mut self) -> &mut fontdb::Database {
         self.font_matches_cache.clear();
+        self.has_family_cache.clear();
+        self.mask_advance_cache.clear();
+        self.digit_advance_cache.clear();
+        #[cfg(feature = "shape-run-cache")]
+        {
+            self.shape_run_cache = crate::ShapeRunCache::default();
+        }
         &mut self.db
     }
 
+    /// Get how emoji are resolved when no color emoji font covers them.
+    pub fn emoji_fallback(&self) -> EmojiFallback {
+        self.emoji_fallback
+    }
+
+    /// Set how emoji are resolved when no color emoji font covers them.
+    ///
+    /// [`crate::BufferLine`]s that were already shaped are shaped again the next time they are
+    /// used.
+    pub fn set_emoji_fallback(&mut self, emoji_fallback: EmojiFallback) {
+        if emoji_fallback == self.emoji_fallback {
+            return;
+        }
+        self.emoji_fallback = emoji_fallback;
+        self.shaping_generation += 1;
+        self.mask_advance_cache.clear();
+        self.digit_advance_cache.clear();
+        #[cfg(feature = "shape-run-cache")]
+        {
+            self.shape_run_cache = crate::ShapeRunCache::default();
+        }
+    }
+
+    /// Get what is done when the requested family is missing.
+    pub fn missing_family(&self) -> &MissingFamily {
+        &self.missing_family
+    }
+
+    /// Set what is done when no font of the requested family matches the style, stretch and
+    /// weight of the text.
+    ///
+    /// [`crate::BufferLine`]s that were already shaped are shaped again the next time they are
+    /// used.
+    pub fn set_missing_family(&mut self, missing_family: MissingFamily) {
+        if missing_family == self.missing_family {
+            return;
+        }
+        self.missing_family = missing_family;
+        self.shaping_generation += 1;
+        #[cfg(feature = "shape-run-cache")]
+        {
+            self.shape_run_cache = crate::ShapeRunCache::default();
+        }
+    }
+
+    /// Check if one of the font matches of `attrs` is in its family, at any weight, like the
+    /// face font fallback picks from the requested family. Any monospace font is accepted for
+    /// [`fontdb::Family::Monospace`].
+    pub(crate) fn has_family(&mut self, attrs: &Attrs<'_>) -> bool {
+        let key = FontMatchAttrs::from(attrs);
+        if let Some(&has_family) = self.has_family_cache.get(&key) {
+            return has_family;
+        }
+        // Clear the cache first if it reached the size limit
+        if self.has_family_cache.len() >= Self::FONT_MATCHES_CACHE_SIZE_LIMIT {
+            self.has_family_cache.clear();
+        }
+
+        let font_match_keys = self.get_font_matches(attrs);
+        let family_name = self.db.family_name(&attrs.family);
+        let has_family = font_match_keys.iter().any(|m_key| {
+            if attrs.family == fontdb::Family::Monospace && self.is_monospace(m_key.id) {
+                return true;
+            }
+            self.db
+                .face(m_key.id)
+                .is_some_and(|face| face.families.iter().any(|(name, _)| name == family_name))
+        });
+        self.has_family_cache.insert(key, has_family);
+        has_family
+    }
+
+    /// Shape a single grapheme in isolation, without line layout
+    ///
+    /// This is meant for building advance tables, such as the cell widths of a terminal. The
+    /// grapheme is shaped with font fallback like a word of a line would be.
+    pub fn shape_grapheme(&mut self, s: &str, attrs: &Attrs) -> GraphemeShape {
+        let level = match BaseDirection::default().resolve(s) {
+            Direction::Ltr => unicode_bidi::Level::ltr(),
+            Direction::Rtl => unicode_bidi::Level::rtl(),
+        };
+        let word = ShapeWord::new(
+            self,
+            s,
+            &AttrsList::new(attrs),
+            0..s.len(),
+            level,
+            false,
+            Shaping::Advanced,
+        );
+        GraphemeShape {
+            advance: word.glyphs.iter().map(|glyph| glyph.x_advance).sum(),
+            glyph_count: word.glyphs.len(),
+            font_id_opt: word.glyphs.first().map(|glyph| glyph.font_id),
+            single_grapheme: s.graphemes(true).count() == 1,
+        }
+    }
+
+    /// Get the width in pixels of `count` copies of the `mask` character, such as the dots of a
+    /// password field, without shaping the masked text
+    ///
+    /// All mask glyphs are identical, so this is `count` times the advance of one mask
+    /// character, which is cached.
+    pub fn measure_mask(&mut self, mask: char, count: usize, attrs: &Attrs, font_size: f32) -> f32 {
+        let key = (mask, AttrsOwned::new(attrs));
+        let advance = match self.mask_advance_cache.get(&key) {
+            Some(advance) => *advance,
+            None => {
+                let advance = self
+                    .shape_grapheme(mask.encode_utf8(&mut [0; 4]), attrs)
+                    .advance;
+                if self.mask_advance_cache.len() >= Self::FONT_MATCHES_CACHE_SIZE_LIMIT {
+                    self.mask_advance_cache.clear();
+                }
+                self.mask_advance_cache.insert(key, advance);
+                advance
+            }
+        };
+        count as f32 * advance * font_size
+    }
+
+    /// Get the width in pixels of the widest of the digits `0-9`, such as for sizing numeric
+    /// columns that reserve this width for each digit
+    ///
+    /// Each digit is shaped like [`Self::shape_grapheme`], and the widest advance is cached.
+    pub fn max_digit_width(&mut self, attrs: &Attrs, font_size: f32) -> f32 {
+        let key = AttrsOwned::new(attrs);
+        let advance = match self.digit_advance_cache.get(&key) {
+            Some(advance) => *advance,
+            None => {
+                let advance = ('0'..='9')
+                    .map(|digit| {
+                        self.shape_grapheme(digit.encode_utf8(&mut [0; 4]), attrs)
+                            .advance
+                    })
+                    .fold(0.0, f32::max);
+                if self.digit_advance_cache.len() >= Self::FONT_MATCHES_CACHE_SIZE_LIMIT {
+                    self.digit_advance_cache.clear();
+                }
+                self.digit_advance_cache.insert(key, advance);
+                advance
+            }
+        };
+        advance * font_size
+    }
+
+    /// Set the locale, which orders script fallbacks and becomes the default shaping language
+    ///
+    /// For example, shared Han characters use Japanese or Chinese forms depending on the
+    /// locale. The locale given when creating the font system only orders script fallbacks, text
+    /// is shaped without a default language until this is called. [`crate::BufferLine`]s that
+    /// were already shaped are shaped again the next time they are used.
+    pub fn set_locale(&mut self, locale: &str) {
+        let language_opt = locale.parse().ok();
+        if locale == self.locale && language_opt == self.language_opt {
+            return;
+        }
+        // The fallbacks depend on the locale, so lines are reshaped even if the language is the same
+        self.language_opt = language_opt;
+        self.shaping_generation += 1;
+        self.locale = locale.into();
+        self.fallbacks = Fallbacks::new(self.dyn_fallback.as_ref(), &[], &self.locale);
+        self.mask_advance_cache.clear();
+        self.digit_advance_cache.clear();
+        #[cfg(feature = "shape-run-cache")]
+        {
+            self.shape_run_cache = crate::ShapeRunCache::default();
+        }
+    }
+
+    /// Get the default shaping language, from the locale given to [`Self::set_locale`].
+    pub(crate) fn language(&self) -> Option<&rustybuzz::Language> {
+        self.language_opt.as_ref()
+    }
+
+    /// Get the generation of the settings that change shaping results, such as
+    /// [`Self::set_locale`], [`Self::set_emoji_fallback`] and [`Self::set_missing_family`]
+    pub(crate) fn shaping_generation(&self) -> u64 {
+        self.shaping_generation
+    }
+
+    /// Shape a line of text into a [`ShapeLine`] owned by the font system, for one-off
+    /// measurements without creating a [`crate::BufferLine`]
+    ///
+    /// The allocations of the line are reused by the next call. The returned reference borrows
+    /// the font system, so it must be dropped (or the line cloned) before the font system is used
+    /// again, and the next call overwrites the line.
+    pub fn shape_into_buffer(
+        &mut self,
+        text: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        tab_width: u16,
+    ) -> &ShapeLine {
+        let mut line = mem::replace(&mut self.shape_line, ShapeLine::empty());
+        line.build(self, text, attrs_list, shaping, tab_width);
+        self.shape_line = line;
+        &self.shape_line
+    }
+
     /// Consume this [`FontSystem`] and return the locale and database.
     pub fn into_locale_and_db(self) -> (String, fontdb::Database) {
         (self.locale, self.db)
     }
 
+    /// Copy the font system for shaping on another thread, sharing the fonts loaded so far
+    ///
+    /// The copy does not load fonts, so shaping with it is only correct if
+    /// [`Self::worker_missed_font`] is false afterwards. Caches other than the loaded fonts and
+    /// font matches start empty.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn worker(&self) -> Self {
+        Self {
+            locale: self.locale.clone(),
+            language_opt: self.language_opt.clone(),
+            shaping_generation: self.shaping_generation,
+            db: self.db.clone(),
+            font_cache: self.font_cache.clone(),
+            monospace_font_ids: self.monospace_font_ids.clone(),
+            per_script_monospace_font_ids: self.per_script_monospace_font_ids.clone(),
+            font_codepoint_support_info_cache: Default::default(),
+            font_matches_cache: self.font_matches_cache.clone(),
+            has_family_cache: self.has_family_cache.clone(),
+            mask_advance_cache: Default::default(),
+            digit_advance_cache: Default::default(),
+            shape_buffer: ShapeBuffer::default(),
+            shape_line: ShapeLine::empty(),
+            monospace_fallbacks_buffer: BTreeSet::default(),
+            #[cfg(feature = "shape-run-cache")]
+            shape_run_cache: crate::ShapeRunCache::default(),
+            dyn_fallback: self.dyn_fallback.clone(),
+            fallbacks: self.fallbacks.clone(),
+            emoji_fallback: self.emoji_fallback,
+            missing_family: self.missing_family.clone(),
+            worker_missed_font: Some(false),
+        }
+    }
+
+    /// Check if a font that was not loaded was requested from a copy made by [`Self::worker`],
+    /// and clear the flag
+    #[cfg(feature = "rayon")]
+    pub(crate) fn worker_missed_font(&mut self) -> bool {
+        match &mut self.worker_missed_font {
+            Some(missed) => mem::take(missed),
+            None => false,
+        }
+    }
+
+    /// Check if a font that was not loaded was requested from a copy made by [`Self::worker`],
+    /// without clearing the flag
+    #[cfg(all(feature = "rayon", feature = "shape-run-cache"))]
+    pub(crate) fn worker_missed_font_pending(&self) -> bool {
+        self.worker_missed_font == Some(true)
+    }
+
+    /// Record that shaping on a copy made by [`Self::worker`] needs a font that was not loaded,
+    /// returning false if this is not such a copy
+    #[cfg(feature = "rayon")]
+    pub(crate) fn set_worker_missed_font(&mut self) -> bool {
+        match &mut self.worker_missed_font {
+            Some(missed) => {
+                *missed = true;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Get a font by its ID.
     pub fn get_font(&mut self, id: fontdb::ID) -> Option<Arc<Font>> {
+        #[cfg(feature = "rayon")]
+        if let Some(missed) = &mut self.worker_missed_font {
+            return match self.font_cache.get(&id) {
+                Some(font_opt) => font_opt.clone(),
+                None => {
+                    *missed = true;
+                    None
+                }
+            };
+        }
         self.font_cache
             .entry(id)
             .or_insert_with(|| {
@@ -271,6 +613,38 @@ impl FontSystem {
             .clone()
     }
 
+    /// Check whether the font `id` has a glyph for every character of `text`
+    ///
+    /// This reads the character map of the font directly, without shaping, so it does not
+    /// account for fallback or for characters that are only displayed as part of a cluster.
+    /// Control characters are ignored, and empty text is fully covered.
+    pub fn font_covers(&mut self, id: fontdb::ID, text: &str) -> Coverage {
+        let Some(font) = self.get_font(id) else {
+            return Coverage::None;
+        };
+        let face = font.rustybuzz();
+        let mut covered = false;
+        let mut missing = Vec::new();
+        let mut any_missing = false;
+        for c in text.chars().filter(|c| !c.is_control()) {
+            if face.glyph_index(c).is_some() {
+                covered = true;
+            } else {
+                any_missing = true;
+                if missing.len() < Coverage::MISSING_MAX && !missing.contains(&c) {
+                    missing.push(c);
+                }
+            }
+        }
+        if !any_missing {
+            Coverage::Full
+        } else if covered {
+            Coverage::Partial(missing)
+        } else {
+            Coverage::None
+        }
+    }
+
     pub fn is_monospace(&self, id: fontdb::ID) -> bool {
         self.monospace_font_ids.binary_search(&id).is_ok()
     }
@@ -3,6 +3,7 @@
 bitflags::bitflags! {
     /// Flags that change rendering
     #[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[repr(transparent)]
     pub struct CacheKeyFlags: u32 {
         /// Skew by 14 degrees to synthesize italic
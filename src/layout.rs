@@ -1,11 +1,13 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
-use core::fmt::Display;
+use core::{fmt::Display, ops::Range};
 
+use alloc::borrow::Cow;
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{string::String, vec, vec::Vec};
 
-use crate::{math, CacheKey, CacheKeyFlags, Color};
+use crate::{math, Affinity, CacheKey, CacheKeyFlags, Color, Style, Weight};
+use unicode_segmentation::UnicodeSegmentation;
 
 /// A laid out glyph
 #[derive(Clone, Debug)]
@@ -54,6 +56,34 @@ pub struct LayoutGlyph {
     pub metadata: usize,
     /// [`CacheKeyFlags`]
     pub cache_key_flags: CacheKeyFlags,
+    /// Weight of the font face, see [`Self::resolved_attrs`]
+    pub font_weight: Weight,
+    /// Style of the font face, see [`Self::resolved_attrs`]
+    pub font_style: Style,
+    /// True if combining marks of this glyph's cluster were dropped, see
+    /// [`LayoutLine::clamp_mark_stack_height`]
+    pub marks_clamped: bool,
+    /// True if this glyph's cluster contains U+FFFD, see
+    /// [`crate::ShapeOptions::highlight_replacement_char`]
+    pub replacement_char: bool,
+    /// Opacity from [`crate::Attrs::opacity`], 1.0 if not set
+    pub opacity: f32,
+}
+
+/// The attributes a [`LayoutGlyph`] is rendered with once font fallback is resolved, see
+/// [`LayoutGlyph::resolved_attrs`]
+///
+/// Bold is never synthesized, so a face lighter than requested is reported with its own weight.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ResolvedAttrs {
+    /// Font id of the face used for the glyph
+    pub font_id: fontdb::ID,
+    /// Weight of the face used for the glyph
+    pub weight: Weight,
+    /// Style of the face used for the glyph
+    pub style: Style,
+    /// True if italic is synthesized by skewing, see [`CacheKeyFlags::FAKE_ITALIC`]
+    pub synthetic_italic: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -84,6 +114,67 @@ impl LayoutGlyph {
 
         PhysicalGlyph { cache_key, x, y }
     }
+
+    /// Get the font and styling this glyph is actually rendered with, which may differ from the
+    /// requested [`crate::Attrs`] after font fallback
+    pub fn resolved_attrs(&self) -> ResolvedAttrs {
+        ResolvedAttrs {
+            font_id: self.font_id,
+            weight: self.font_weight,
+            style: self.font_style,
+            synthetic_italic: self.cache_key_flags.contains(CacheKeyFlags::FAKE_ITALIC),
+        }
+    }
+
+    /// Get the color to draw this glyph with, using `default` if it has no color override
+    ///
+    /// The alpha of the color is multiplied by [`Self::opacity`].
+    pub fn draw_color(&self, default: Color) -> Color {
+        self.apply_opacity(self.color_opt.unwrap_or(default))
+    }
+
+    /// Multiply the alpha of `color` by [`Self::opacity`], such as for the pixels of a color
+    /// glyph image
+    pub fn apply_opacity(&self, color: Color) -> Color {
+        if self.opacity >= 1.0 {
+            return color;
+        }
+        let (r, g, b, a) = color.as_rgba_tuple();
+        Color::rgba(r, g, b, math::roundf(a as f32 * self.opacity) as u8)
+    }
+
+    /// Get the byte index and affinity of the grapheme boundary nearest to `x` inside this glyph
+    ///
+    /// The width of the glyph is split evenly between the graphemes of its cluster, laid out
+    /// right to left for RTL glyphs.
+    pub(crate) fn hit(&self, text: &str, x: f32) -> (usize, Affinity) {
+        let cluster = text.get(self.start..self.end).unwrap_or("");
+        let total = cluster.graphemes(true).count();
+        if total == 0 {
+            return (self.start, Affinity::After);
+        }
+
+        let rtl = self.level.is_rtl();
+        let egc_w = self.w / (total as f32);
+        for (i, (egc_i, egc)) in cluster.grapheme_indices(true).enumerate() {
+            let left = if rtl {
+                self.x + self.w - ((i + 1) as f32) * egc_w
+            } else {
+                self.x + (i as f32) * egc_w
+            };
+            let inside = if rtl { x >= left } else { x <= left + egc_w };
+            if inside || i + 1 == total {
+                let right_half = x >= left + egc_w / 2.0;
+                return if right_half != rtl {
+                    // If clicking on last half of grapheme, move cursor past it
+                    (self.start + egc_i + egc.len(), Affinity::Before)
+                } else {
+                    (self.start + egc_i, Affinity::After)
+                };
+            }
+        }
+        unreachable!()
+    }
 }
 
 /// A line of laid out glyphs
@@ -95,12 +186,528 @@ pub struct LayoutLine {
     pub max_ascent: f32,
     /// Maximum descent of the glyphs in line
     pub max_descent: f32,
+    /// Maximum line gap of the fonts of the glyphs in line
+    pub max_line_gap: f32,
     /// Maximum line height of any spans in line
     pub line_height_opt: Option<f32>,
     /// Glyphs in line
     pub glyphs: Vec<LayoutGlyph>,
 }
 
+impl LayoutLine {
+    /// Get the height of the line from the metrics of its fonts: the maximum ascent, descent,
+    /// and line gap of its glyphs
+    ///
+    /// This is driven by the tallest run of the line, unlike [`Self::line_height_opt`] which
+    /// only reflects spans with explicit [`crate::Metrics`].
+    pub fn natural_height(&self) -> f32 {
+        self.max_ascent + self.max_descent + self.max_line_gap
+    }
+
+    /// Get the distance from the top of the line to the baseline shared by all of its glyphs,
+    /// for a line `line_height` tall
+    ///
+    /// Glyph positions are relative to this baseline. The ascent and descent of the tallest
+    /// runs are centered in the line, so smaller runs sit on the same baseline.
+    pub fn baseline(&self, line_height: f32) -> f32 {
+        let glyph_height = self.max_ascent + self.max_descent;
+        (line_height - glyph_height) / 2.0 + self.max_ascent
+    }
+
+    /// Get the leftmost and rightmost x covered by the clusters of the byte `range` on this line
+    ///
+    /// Glyphs are in visual order, so in bidi text this spans every reordered run that the range
+    /// touches. Returns `None` if no cluster of this line intersects `range`.
+    pub fn visual_extents(&self, range: Range<usize>) -> Option<(f32, f32)> {
+        let mut extents: Option<(f32, f32)> = None;
+        for glyph in self.glyphs.iter() {
+            if glyph.start < range.end && glyph.end > range.start {
+                let (left, right) = extents.get_or_insert((glyph.x, glyph.x + glyph.w));
+                *left = left.min(glyph.x);
+                *right = right.max(glyph.x + glyph.w);
+            }
+        }
+        extents
+    }
+
+    /// Get the byte index and affinity of the grapheme boundary nearest to `x` on this line
+    ///
+    /// `text` is the text of the [`crate::BufferLine`] this line was laid out from. Points
+    /// inside a glyph snap to the nearest edge of the grapheme under them, splitting ligatures
+    /// evenly between their graphemes; points outside every glyph snap to the nearest glyph.
+    /// Glyphs are in visual order, so clicking on either side of a bidi run boundary lands on
+    /// the logical index at that visual edge. Returns `None` if the line has no glyphs.
+    pub fn hit(&self, text: &str, x: f32) -> Option<(usize, Affinity)> {
+        let glyph = self
+            .glyphs
+            .iter()
+            .find(|glyph| x >= glyph.x && x <= glyph.x + glyph.w)
+            .or_else(|| {
+                self.glyphs.iter().min_by(|a, b| {
+                    let dist =
+                        |glyph: &LayoutGlyph| (glyph.x - x).max(x - (glyph.x + glyph.w)).max(0.0);
+                    dist(a).total_cmp(&dist(b))
+                })
+            })?;
+        Some(glyph.hit(text, x))
+    }
+
+    /// Get the leftmost and rightmost x of the grapheme starting at or containing byte `index`
+    ///
+    /// This is the inverse of [`Self::hit`]. `text` is the text of the [`crate::BufferLine`]
+    /// this line was laid out from. Returns `None` if no cluster of this line contains `index`.
+    pub fn grapheme_extents(&self, text: &str, index: usize) -> Option<(f32, f32)> {
+        let glyph = self
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.start <= index && index < glyph.end)?;
+        let (left, right) = self.visual_extents(glyph.start..glyph.end)?;
+        let cluster = text.get(glyph.start..glyph.end).unwrap_or("");
+        let total = cluster.graphemes(true).count().max(1);
+        let i = cluster
+            .grapheme_indices(true)
+            .take_while(|(egc_i, _)| glyph.start + egc_i <= index)
+            .count()
+            .saturating_sub(1);
+        let egc_w = (right - left) / (total as f32);
+        Some(if glyph.level.is_rtl() {
+            (right - ((i + 1) as f32) * egc_w, right - (i as f32) * egc_w)
+        } else {
+            (left + (i as f32) * egc_w, left + ((i + 1) as f32) * egc_w)
+        })
+    }
+
+    /// Get the byte range and bounding rectangle of each word on this line
+    ///
+    /// `text` is the text of the [`crate::BufferLine`] this line was laid out from. Words are
+    /// found with UAX #29 word segmentation, skipping whitespace and punctuation, and are
+    /// yielded in logical order. A word broken across layout lines is clipped to the part on this
+    /// line. Rectangles span the visual extents of the word and the ascent and descent of the
+    /// line, with the top of the line at y = 0.
+    pub fn word_rects<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (Range<usize>, Rect)> + 'a {
+        let start = self
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.start)
+            .min()
+            .unwrap_or(0);
+        let end = self.glyphs.iter().map(|glyph| glyph.end).max().unwrap_or(0);
+        let height = self.max_ascent + self.max_descent;
+        // Only the text of this line is segmented, which clips words broken across lines
+        let words: Vec<Range<usize>> = text
+            .get(start..end)
+            .unwrap_or_default()
+            .unicode_word_indices()
+            .map(|(i, word)| start + i..start + i + word.len())
+            .collect();
+        // Words are sorted and do not overlap, so each glyph extends the words it intersects
+        let mut extents: Vec<Option<(f32, f32)>> = vec![None; words.len()];
+        for glyph in self.glyphs.iter() {
+            let first = words.partition_point(|range| range.end <= glyph.start);
+            for (range, extents) in words[first..].iter().zip(&mut extents[first..]) {
+                if range.start >= glyph.end {
+                    break;
+                }
+                let (left, right) = extents.get_or_insert((glyph.x, glyph.x + glyph.w));
+                *left = left.min(glyph.x);
+                *right = right.max(glyph.x + glyph.w);
+            }
+        }
+        words
+            .into_iter()
+            .zip(extents)
+            .filter_map(move |(range, extents)| {
+                let (left, right) = extents?;
+                Some((range, Rect::new(left, 0.0, right - left, height)))
+            })
+    }
+
+    /// Set the advance (hitbox width) of the glyph at `glyph_index`, moving the glyphs after it
+    /// on this line and updating the line width
+    ///
+    /// Glyphs are in visual order, so the glyphs that move are the ones to the right of the
+    /// changed glyph. Does nothing if `glyph_index` is out of range.
+    ///
+    /// This is an escape hatch for custom spacing: the layout no longer matches what would be
+    /// produced from the shaping cache, and the change is lost whenever the line is laid out
+    /// again.
+    pub fn set_glyph_advance(&mut self, glyph_index: usize, advance: f32) {
+        let Some(glyph) = self.glyphs.get_mut(glyph_index) else {
+            return;
+        };
+        let delta = advance - glyph.w;
+        glyph.w = advance;
+        for glyph in self.glyphs[glyph_index + 1..].iter_mut() {
+            glyph.x += delta;
+        }
+        self.w += delta;
+    }
+
+    /// Drop combining marks that are stacked more than `max_height` away from their base
+    ///
+    /// Marks are the glyphs of a cluster other than its base, the widest of its glyphs, and their
+    /// distance is their vertical offset in pixels. This keeps overstacked diacritics from
+    /// overflowing into adjacent lines. The remaining glyphs of an affected cluster are flagged
+    /// with [`LayoutGlyph::marks_clamped`]. Returns true if any marks were dropped.
+    pub fn clamp_mark_stack_height(&mut self, max_height: f32) -> bool {
+        // Glyph indices grouped by cluster, keeping the order of the glyphs within each cluster
+        let mut order: Vec<usize> = (0..self.glyphs.len()).collect();
+        order.sort_by_key(|&i| (self.glyphs[i].start, self.glyphs[i].end));
+
+        let mut dropped = vec![false; self.glyphs.len()];
+        // Sorted, since the clusters are visited in order
+        let mut clamped = Vec::new();
+        let mut cluster_start = 0;
+        while cluster_start < order.len() {
+            let glyph = &self.glyphs[order[cluster_start]];
+            let cluster = (glyph.start, glyph.end);
+            let cluster_end = order[cluster_start..]
+                .iter()
+                .position(|&i| (self.glyphs[i].start, self.glyphs[i].end) != cluster)
+                .map_or(order.len(), |len| cluster_start + len);
+            let indices = &order[cluster_start..cluster_end];
+            cluster_start = cluster_end;
+
+            let base = indices.iter().copied().reduce(|base, i| {
+                if self.glyphs[i].w > self.glyphs[base].w {
+                    i
+                } else {
+                    base
+                }
+            });
+            let mut any_dropped = false;
+            for &i in indices {
+                let glyph = &self.glyphs[i];
+                if Some(i) != base && (glyph.font_size * glyph.y_offset).abs() > max_height {
+                    dropped[i] = true;
+                    any_dropped = true;
+                }
+            }
+            if any_dropped {
+                clamped.push(cluster);
+            }
+        }
+        if clamped.is_empty() {
+            return false;
+        }
+
+        let mut i = 0;
+        self.glyphs.retain(|_| {
+            i += 1;
+            !dropped[i - 1]
+        });
+        for glyph in self.glyphs.iter_mut() {
+            if clamped.binary_search(&(glyph.start, glyph.end)).is_ok() {
+                glyph.marks_clamped = true;
+            }
+        }
+        true
+    }
+
+    /// Drop clusters from the logical end of the line until it fits in `width` together with
+    /// `ellipsis`, then place the ellipsis after the remaining text and realign the line
+    ///
+    /// Whole clusters are dropped so graphemes are never split. If the ellipsis alone does not
+    /// fit, every glyph is dropped and no ellipsis is added. The ellipsis glyphs are placed at
+    /// the byte index where the text was cut. A line that fits is left as is unless `force` is
+    /// set, which is used when the text continues on dropped lines. Returns true if the line was
+    /// truncated.
+    pub(crate) fn ellipsize(
+        &mut self,
+        width: f32,
+        align: Align,
+        rtl: bool,
+        ellipsis: &[LayoutGlyph],
+        force: bool,
+    ) -> bool {
+        if self.w <= width && !force {
+            return false;
+        }
+
+        let ellipsis_w: f32 = ellipsis.iter().map(|glyph| glyph.w).sum();
+        // Glyphs grouped by the start of their cluster, with the total width of each cluster
+        let mut clusters: Vec<(usize, usize, f32)> = self
+            .glyphs
+            .iter()
+            .map(|glyph| (glyph.start, glyph.end, glyph.w))
+            .collect();
+        clusters.sort_by_key(|(start, _, _)| *start);
+        clusters.dedup_by(|(start, end, w), (prev_start, prev_end, prev_w)| {
+            if start == prev_start {
+                *prev_end = (*prev_end).max(*end);
+                *prev_w += *w;
+                true
+            } else {
+                false
+            }
+        });
+
+        let mut kept_w: f32 = clusters.iter().map(|(_, _, w)| w).sum();
+        let mut cut = clusters.last().map_or(0, |(_, end, _)| *end);
+        while kept_w + ellipsis_w > width {
+            let Some((start, _, w)) = clusters.pop() else {
+                break;
+            };
+            kept_w -= w;
+            cut = start;
+        }
+        self.glyphs.retain(|glyph| glyph.start < cut);
+
+        // Repack the remaining glyphs in visual order, with the ellipsis at the visual end
+        self.glyphs.sort_by(|a, b| a.x.total_cmp(&b.x));
+        let ellipsis_fits = ellipsis_w <= width;
+        if ellipsis_fits {
+            let level = if rtl {
+                unicode_bidi::Level::rtl()
+            } else {
+                unicode_bidi::Level::ltr()
+            };
+            let ellipsis = ellipsis.iter().map(|glyph| LayoutGlyph {
+                start: cut,
+                end: cut,
+                level,
+                ..glyph.clone()
+            });
+            if rtl {
+                let glyphs = core::mem::take(&mut self.glyphs);
+                self.glyphs.extend(ellipsis);
+                self.glyphs.extend(glyphs);
+            } else {
+                self.glyphs.extend(ellipsis);
+            }
+        }
+
+        self.w = self.glyphs.iter().map(|glyph| glyph.w).sum();
+        let mut x = match (align, rtl) {
+            (Align::Left, _) | (Align::Justified, false) | (Align::End, true) => 0.0,
+            (Align::Center, _) => (width - self.w) / 2.0,
+            (Align::Right, _) | (Align::Justified, true) | (Align::End, false) => width - self.w,
+        };
+        for glyph in self.glyphs.iter_mut() {
+            glyph.x = x;
+            x += glyph.w;
+        }
+        true
+    }
+
+    /// Iterate over the runs of adjacent glyphs with the same bidi level, in visual order
+    ///
+    /// The runs partition the glyphs of the line as reordered by layout, so each one is drawn in a
+    /// single direction. Rectangles span the glyphs of the run and the ascent and descent of the
+    /// line, with the top of the line at y = 0.
+    pub fn level_runs(&self) -> impl Iterator<Item = LevelRun> + '_ {
+        let height = self.max_ascent + self.max_descent;
+        let mut glyphs = self.glyphs.as_slice();
+        core::iter::from_fn(move || {
+            let level = glyphs.first()?.level;
+            let len = glyphs
+                .iter()
+                .position(|glyph| glyph.level != level)
+                .unwrap_or(glyphs.len());
+            let (run, rest) = glyphs.split_at(len);
+            glyphs = rest;
+            let start = run.iter().map(|glyph| glyph.start).min().unwrap_or(0);
+            let end = run.iter().map(|glyph| glyph.end).max().unwrap_or(start);
+            let left = run[0].x;
+            let right = run[len - 1].x + run[len - 1].w;
+            Some(LevelRun {
+                range: start..end,
+                level,
+                rect: Rect::new(left, 0.0, right - left, height),
+            })
+        })
+    }
+
+    /// Iterate over runs of adjacent glyphs that share the same color
+    ///
+    /// Glyphs without a color override use `default_color`. This is useful for renderers that
+    /// batch draw calls by color.
+    pub fn color_runs(
+        &self,
+        default_color: Color,
+    ) -> impl Iterator<Item = (Color, &[LayoutGlyph])> + '_ {
+        let color_of = move |glyph: &LayoutGlyph| glyph.color_opt.unwrap_or(default_color);
+        let mut glyphs = self.glyphs.as_slice();
+        core::iter::from_fn(move || {
+            let color = color_of(glyphs.first()?);
+            let len = glyphs
+                .iter()
+                .position(|glyph| color_of(glyph) != color)
+                .unwrap_or(glyphs.len());
+            let (run, rest) = glyphs.split_at(len);
+            glyphs = rest;
+            Some((color, run))
+        })
+    }
+
+    /// Iterate over runs of adjacent glyphs for which `decorated` is true, such as underlined
+    /// glyphs, to draw one connected decoration per run
+    ///
+    /// Runs ignore color, so underlined spans that only differ in color are joined. They only
+    /// break where the font or font size changes, as that changes the decoration thickness and
+    /// position.
+    pub fn decoration_runs<'a, F>(
+        &'a self,
+        decorated: F,
+    ) -> impl Iterator<Item = &'a [LayoutGlyph]> + 'a
+    where
+        F: Fn(&LayoutGlyph) -> bool + 'a,
+    {
+        let mut glyphs = self.glyphs.as_slice();
+        core::iter::from_fn(move || {
+            let start = glyphs.iter().position(&decorated)?;
+            glyphs = &glyphs[start..];
+            let first = &glyphs[0];
+            let len = glyphs
+                .iter()
+                .position(|glyph| {
+                    !decorated(glyph)
+                        || glyph.font_id != first.font_id
+                        || glyph.font_size != first.font_size
+                })
+                .unwrap_or(glyphs.len());
+            let (run, rest) = glyphs.split_at(len);
+            glyphs = rest;
+            Some(run)
+        })
+    }
+}
+
+/// A run of adjacent glyphs with the same bidi level, see [`LayoutLine::level_runs`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LevelRun {
+    /// Byte range of the clusters of the run
+    pub range: Range<usize>,
+    /// Unicode `BiDi` embedding level of the run, which is left-to-right if it is even
+    pub level: unicode_bidi::Level,
+    /// Rectangle covered by the run, with the top of the line at y = 0
+    pub rect: Rect,
+}
+
+/// The layout lines of a paragraph clamped to a box, see [`crate::BufferLine::layout_in_box`]
+#[derive(Clone, Debug, Default)]
+pub struct BoxLayout {
+    /// Layout lines that fit, the last one ending with the ellipsis if text was truncated
+    pub lines: Vec<LayoutLine>,
+    /// True if some text was dropped or replaced by the ellipsis
+    pub truncated: bool,
+}
+
+/// The layout lines of a paragraph flattened with absolute positions, see
+/// [`crate::BufferLine::paragraph_layout`]
+#[derive(Clone, Debug, Default)]
+pub struct ParagraphLayout {
+    /// Layout lines of the paragraph, from top to bottom
+    pub lines: Vec<ParagraphLine>,
+    /// Glyphs of every layout line, in visual order on each line
+    pub glyphs: Vec<ParagraphGlyph>,
+    /// Width of the widest layout line
+    pub w: f32,
+    /// Total height of the layout lines
+    pub h: f32,
+}
+
+/// A layout line in a [`ParagraphLayout`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParagraphLine {
+    /// Y of the top of the line from the top of the paragraph
+    pub top: f32,
+    /// Height of the line, from its line height override or the default line height
+    pub height: f32,
+    /// Y of the baseline from the top of the paragraph
+    pub baseline: f32,
+    /// Width of the line
+    pub w: f32,
+}
+
+/// A glyph in a [`ParagraphLayout`]
+#[derive(Clone, Debug)]
+pub struct ParagraphGlyph {
+    /// Index of the layout line of the glyph in [`ParagraphLayout::lines`]
+    pub line: usize,
+    /// X of the glyph from the left of the paragraph, including alignment
+    pub x: f32,
+    /// Y of the baseline of the glyph from the top of the paragraph, including vertical
+    /// alignment
+    pub y: f32,
+    /// The laid out glyph
+    pub glyph: LayoutGlyph,
+}
+
+/// An axis-aligned rectangle, in the same coordinate space as the layout
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Rect {
+    /// X coordinate of the left edge
+    pub x: f32,
+    /// Y coordinate of the top edge
+    pub y: f32,
+    /// Width of the rectangle
+    pub width: f32,
+    /// Height of the rectangle
+    pub height: f32,
+}
+
+impl Rect {
+    /// Create a new rectangle
+    pub const fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// X coordinate of the right edge
+    pub fn right(&self) -> f32 {
+        self.x + self.width
+    }
+
+    /// Y coordinate of the bottom edge
+    pub fn bottom(&self) -> f32 {
+        self.y + self.height
+    }
+}
+
+/// Space reserved for a drop cap, see [`crate::BufferLine::set_drop_cap`]
+///
+/// Without a font size the drop cap is drawn by the application and the layout only keeps the
+/// space free.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DropCap {
+    /// Number of visual lines to indent
+    pub lines: u8,
+    /// Width to indent them by, in pixels, on the starting side of the line
+    pub width: f32,
+    /// Font size to lay out the first character of the paragraph at, as the drop cap
+    ///
+    /// The first cluster is then placed at the starting edge of the reserved space, with its
+    /// top level with the top of the first line, and takes no space in the text or in the height
+    /// of the first line. `width` should leave room for it.
+    pub font_size_opt: Option<f32>,
+}
+
+/// Size and placement of an insertion caret, see [`crate::BufferLine::caret_rect`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CaretMetrics {
+    /// Width of the caret in pixels, centered on the insertion position
+    pub width: f32,
+    /// Snap the left edge of the caret to whole pixels, for a crisp caret
+    pub round_position: bool,
+}
+
+impl Default for CaretMetrics {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            round_position: false,
+        }
+    }
+}
+
 /// Wrapping mode
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
 pub enum Wrap {
@@ -111,6 +718,9 @@ pub enum Wrap {
     /// Wraps at the word level
     Word,
     /// Wraps at the word level, or fallback to glyph level if a word can't fit on a line by itself
+    ///
+    /// This is CSS `overflow-wrap: break-word`: only words wider than the line are broken at
+    /// glyphs, after moving them to the start of a new line.
     WordOrGlyph,
 }
 
@@ -125,8 +735,109 @@ impl Display for Wrap {
     }
 }
 
+/// Text used to mark truncated text, see [`Overflow::Ellipsis`]
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum EllipsisSource {
+    /// U+2026 HORIZONTAL ELLIPSIS
+    #[default]
+    HorizontalEllipsis,
+    /// The given number of full stops
+    Dots(usize),
+    /// Any text
+    Custom(String),
+}
+
+impl EllipsisSource {
+    /// Get the text of the ellipsis
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            Self::HorizontalEllipsis => Cow::Borrowed("\u{2026}"),
+            Self::Dots(count) => Cow::Owned(".".repeat(*count)),
+            Self::Custom(text) => Cow::Borrowed(text),
+        }
+    }
+}
+
+/// Tab stops at positions in pixels from the start of each visual line, see
+/// [`crate::LayoutOptions::tab_stops`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TabStops {
+    /// Positions of the first stops, in increasing order
+    pub stops: Vec<f32>,
+    /// Distance between the stops that follow the last position in `stops`, or from the start
+    /// of the line if `stops` is empty. Tabs past the last stop take no space if it is zero.
+    pub interval: f32,
+    /// Fill character of the tabs reaching each of `stops`, such as `.` for the dot leaders of
+    /// a table of contents. Stops without an entry, and the stops every `interval`, have none.
+    pub leaders: Vec<Option<char>>,
+}
+
+impl TabStops {
+    /// Create tab stops at `stops`, followed by stops every `interval` pixels
+    pub fn new(stops: Vec<f32>, interval: f32) -> Self {
+        Self {
+            stops,
+            interval,
+            leaders: Vec::new(),
+        }
+    }
+
+    /// Create tab stops every `interval` pixels
+    pub fn uniform(interval: f32) -> Self {
+        Self::new(Vec::new(), interval)
+    }
+
+    /// Set the fill characters of the tabs reaching each of the stops, see [`Self::leaders`]
+    pub fn with_leaders(mut self, leaders: Vec<Option<char>>) -> Self {
+        self.leaders = leaders;
+        self
+    }
+
+    /// Get the position of the first stop after `x`, or `x` if there is none
+    pub fn next_stop(&self, x: f32) -> f32 {
+        self.next_stop_leader(x).0
+    }
+
+    /// Get the position of the first stop after `x` and its leader, or `x` if there is none
+    pub(crate) fn next_stop_leader(&self, x: f32) -> (f32, Option<char>) {
+        if let Some(i) = self.stops.iter().position(|stop| *stop > x) {
+            return (self.stops[i], self.leaders.get(i).copied().flatten());
+        }
+        if self.interval <= 0.0 {
+            return (x, None);
+        }
+        let last = self.stops.last().copied().unwrap_or(0.0);
+        (
+            last + (math::floorf((x - last) / self.interval) + 1.0) * self.interval,
+            None,
+        )
+    }
+}
+
+/// What to do with text that does not fit the width of a line
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub enum Overflow {
+    /// Lay out all of the text, wrapping it according to [`Wrap`]
+    #[default]
+    Visible,
+    /// Keep the text on a single line, replacing the end that does not fit with an ellipsis
+    Ellipsis(EllipsisSource),
+    /// Wrap the text according to [`Wrap`], keeping at most `max_lines` layout lines
+    ///
+    /// If lines are dropped, the end of the last kept line is replaced with the ellipsis. Kept
+    /// lines that are wider than the width, such as a word that could not be wrapped, also end
+    /// with the ellipsis. A `max_lines` of zero is treated as one.
+    Truncate {
+        /// Maximum number of layout lines
+        max_lines: usize,
+        /// Text marking the truncation
+        ellipsis: EllipsisSource,
+    },
+}
+
 /// Align or justify
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Align {
     Left,
     Right,
@@ -135,6 +846,44 @@ pub enum Align {
     End,
 }
 
+/// Vertical alignment of glyphs of different sizes within a layout line
+///
+/// Each glyph is aligned using the ascent and descent of its font at its size, against the
+/// largest ascent and descent of the line, see [`LayoutLine::max_ascent`] and
+/// [`LayoutLine::max_descent`].
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum VerticalAlign {
+    /// Glyphs share the baseline of the line
+    #[default]
+    Baseline,
+    /// Glyphs are centered vertically between the top and bottom of the line
+    Center,
+    /// The top of the glyphs is aligned to the top of the line
+    Top,
+    /// The bottom of the glyphs is aligned to the bottom of the line
+    Bottom,
+}
+
+impl VerticalAlign {
+    /// Get the offset to add to the y of a glyph with the given ascent and descent, in a line
+    /// with the given maximum ascent and descent
+    pub(crate) fn offset(
+        self,
+        ascent: f32,
+        descent: f32,
+        max_ascent: f32,
+        max_descent: f32,
+    ) -> f32 {
+        match self {
+            Self::Baseline => 0.0,
+            Self::Center => ((max_descent - max_ascent) - (descent - ascent)) / 2.0,
+            Self::Top => ascent - max_ascent,
+            Self::Bottom => max_descent - descent,
+        }
+    }
+}
+
 impl Display for Align {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
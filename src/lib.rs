@@ -129,12 +129,20 @@ mod layout;
 pub use self::line_ending::*;
 mod line_ending;
 
+pub use self::markup::*;
+mod markup;
+
 pub use self::shape::*;
 mod shape;
 
 pub use self::shape_run_cache::*;
 mod shape_run_cache;
 
+#[cfg(feature = "shape-trace")]
+pub use self::shape_trace::*;
+#[cfg(feature = "shape-trace")]
+mod shape_trace;
+
 #[cfg(feature = "swash")]
 pub use self::swash::*;
 #[cfg(feature = "swash")]
@@ -2,6 +2,7 @@ use core::ops::Range;
 
 /// Line ending
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum LineEnding {
     /// Use `\n` for line ending (POSIX-style)
     #[default]
@@ -29,6 +30,58 @@ impl LineEnding {
     }
 }
 
+/// Count of each [`LineEnding`] in a set of lines, see [`crate::Buffer::line_ending_summary`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct LineEndingSummary {
+    /// Number of lines ending with `\n`
+    pub lf: usize,
+    /// Number of lines ending with `\r\n`
+    pub crlf: usize,
+    /// Number of lines ending with `\r`
+    pub cr: usize,
+    /// Number of lines ending with `\n\r`
+    pub lfcr: usize,
+    /// Number of lines without a line ending
+    pub none: usize,
+}
+
+impl LineEndingSummary {
+    /// Count one more line with the given ending
+    pub fn add(&mut self, ending: LineEnding) {
+        *self.count_mut(ending) += 1;
+    }
+
+    /// Get the number of lines with the given ending
+    pub fn count(&self, ending: LineEnding) -> usize {
+        match ending {
+            LineEnding::Lf => self.lf,
+            LineEnding::CrLf => self.crlf,
+            LineEnding::Cr => self.cr,
+            LineEnding::LfCr => self.lfcr,
+            LineEnding::None => self.none,
+        }
+    }
+
+    fn count_mut(&mut self, ending: LineEnding) -> &mut usize {
+        match ending {
+            LineEnding::Lf => &mut self.lf,
+            LineEnding::CrLf => &mut self.crlf,
+            LineEnding::Cr => &mut self.cr,
+            LineEnding::LfCr => &mut self.lfcr,
+            LineEnding::None => &mut self.none,
+        }
+    }
+
+    /// Returns true if more than one kind of line ending is used, ignoring [`LineEnding::None`]
+    pub fn is_mixed(&self) -> bool {
+        [self.lf, self.crlf, self.cr, self.lfcr]
+            .iter()
+            .filter(|count| **count > 0)
+            .count()
+            > 1
+    }
+}
+
 /// Iterator over lines terminated by [`LineEnding`]
 #[derive(Debug)]
 pub struct LineIter<'a> {
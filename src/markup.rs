@@ -0,0 +1,482 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::{string::String, vec::Vec};
+use core::fmt::Write;
+
+use crate::{math, Attrs, Buffer, Color, Cursor, FontSystem, Shaping, Style, Weight};
+
+/// Markup format used by [`Buffer::export_range`] and [`Buffer::set_from_markup`]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum MarkupFormat {
+    /// HTML, using `<b>`, `<i>`, and `<span style="color: ...">` for styling and `<br>` between
+    /// lines
+    Html,
+    /// Markdown, using `**` for bold and `*` for italic, with an empty line between lines
+    Markdown,
+}
+
+/// Format used by [`Buffer::export_range`]
+pub type ExportFormat = MarkupFormat;
+
+fn is_bold(attrs: &Attrs) -> bool {
+    attrs.weight >= Weight::SEMIBOLD
+}
+
+fn is_italic(attrs: &Attrs) -> bool {
+    attrs.style != Style::Normal
+}
+
+fn write_html_color(output: &mut String, color: Color) {
+    let (r, g, b, a) = color.as_rgba_tuple();
+    if a == 0xFF {
+        let _ = write!(output, "#{r:02x}{g:02x}{b:02x}");
+    } else {
+        let _ = write!(output, "rgba({r}, {g}, {b}, {:.3})", f32::from(a) / 255.0);
+    }
+}
+
+fn push_html_escaped(output: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            '\'' => output.push_str("&#39;"),
+            _ => output.push(c),
+        }
+    }
+}
+
+fn push_markdown_escaped(output: &mut String, text: &str) {
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '>' | '#') {
+            output.push('\\');
+        }
+        output.push(c);
+    }
+}
+
+fn push_span(output: &mut String, format: MarkupFormat, text: &str, attrs: &Attrs) {
+    let bold = is_bold(attrs);
+    let italic = is_italic(attrs);
+    match format {
+        MarkupFormat::Html => {
+            if bold {
+                output.push_str("<b>");
+            }
+            if italic {
+                output.push_str("<i>");
+            }
+            if let Some(color) = attrs.color_opt {
+                output.push_str("<span style=\"color: ");
+                write_html_color(output, color);
+                output.push_str("\">");
+            }
+            push_html_escaped(output, text);
+            if attrs.color_opt.is_some() {
+                output.push_str("</span>");
+            }
+            if italic {
+                output.push_str("</i>");
+            }
+            if bold {
+                output.push_str("</b>");
+            }
+        }
+        MarkupFormat::Markdown => {
+            // Emphasis markers must hug the text, so leading and trailing whitespace is kept
+            // outside of them
+            let trimmed = text.trim();
+            if trimmed.is_empty() || !(bold || italic) {
+                push_markdown_escaped(output, text);
+                return;
+            }
+            let marker = match (bold, italic) {
+                (true, true) => "***",
+                (true, false) => "**",
+                _ => "*",
+            };
+            let leading = text.len() - text.trim_start().len();
+            output.push_str(&text[..leading]);
+            output.push_str(marker);
+            push_markdown_escaped(output, trimmed);
+            output.push_str(marker);
+            output.push_str(&text[leading + trimmed.len()..]);
+        }
+    }
+}
+
+/// A style pushed by an opening tag or marker
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum MarkupTag {
+    Bold,
+    Italic,
+    Span(Option<Color>),
+}
+
+impl MarkupTag {
+    fn same_kind(self, other: Self) -> bool {
+        matches!(
+            (self, other),
+            (Self::Bold, Self::Bold)
+                | (Self::Italic, Self::Italic)
+                | (Self::Span(_), Self::Span(_))
+        )
+    }
+}
+
+/// Style resulting from all currently open tags
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct MarkupStyle {
+    bold: bool,
+    italic: bool,
+    color_opt: Option<Color>,
+}
+
+impl MarkupStyle {
+    fn apply<'a>(self, attrs: &Attrs<'a>) -> Attrs<'a> {
+        let mut attrs = attrs.clone();
+        if self.bold {
+            attrs = attrs.weight(Weight::BOLD);
+        }
+        if self.italic {
+            attrs = attrs.style(Style::Italic);
+        }
+        if let Some(color) = self.color_opt {
+            attrs = attrs.color(color);
+        }
+        attrs
+    }
+}
+
+/// Plain text and styles produced by parsing markup
+#[derive(Debug, Default)]
+struct MarkupParser {
+    text: String,
+    /// Start index in `text` and style of each styled run
+    runs: Vec<(usize, MarkupStyle)>,
+    stack: Vec<MarkupTag>,
+}
+
+impl MarkupParser {
+    fn style(&self) -> MarkupStyle {
+        let mut style = MarkupStyle::default();
+        for tag in &self.stack {
+            match *tag {
+                MarkupTag::Bold => style.bold = true,
+                MarkupTag::Italic => style.italic = true,
+                MarkupTag::Span(Some(color)) => style.color_opt = Some(color),
+                MarkupTag::Span(None) => {}
+            }
+        }
+        style
+    }
+
+    fn push_str(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let style = self.style();
+        if self.runs.last().map(|(_, last)| *last) != Some(style) {
+            self.runs.push((self.text.len(), style));
+        }
+        self.text.push_str(text);
+    }
+
+    fn push_char(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    fn open(&mut self, tag: MarkupTag) {
+        self.stack.push(tag);
+    }
+
+    /// Close the innermost open tag of the same kind, ignoring unmatched closing tags
+    fn close(&mut self, tag: MarkupTag) {
+        if let Some(i) = self.stack.iter().rposition(|open| open.same_kind(tag)) {
+            self.stack.remove(i);
+        }
+    }
+
+    fn toggle(&mut self, tag: MarkupTag) {
+        if self.stack.contains(&tag) {
+            self.close(tag);
+        } else {
+            self.open(tag);
+        }
+    }
+
+    fn parse_html(&mut self, markup: &str) {
+        let mut rest = markup;
+        let mut after_break = false;
+        while let Some(c) = rest.chars().next() {
+            if c == '<' {
+                if let Some(end) = rest.find('>') {
+                    let tag = &rest[1..end];
+                    if self.html_tag(tag) {
+                        after_break = tag.trim().trim_end_matches('/').trim() == "br";
+                        rest = &rest[end + 1..];
+                        continue;
+                    }
+                }
+            } else if c == '&' {
+                if let Some((decoded, len)) = parse_html_entity(rest) {
+                    self.push_char(decoded);
+                    rest = &rest[len..];
+                    after_break = false;
+                    continue;
+                }
+            } else if c == '\n' && after_break {
+                // A newline following `<br>` is formatting and does not add another line
+                after_break = false;
+                rest = &rest[1..];
+                continue;
+            }
+            // Anything not understood is kept as text
+            self.push_char(c);
+            rest = &rest[c.len_utf8()..];
+            after_break = false;
+        }
+    }
+
+    /// Handle the contents of an HTML tag, returning false if it is not supported
+    fn html_tag(&mut self, tag: &str) -> bool {
+        let tag = tag.trim();
+        let (closing, tag) = match tag.strip_prefix('/') {
+            Some(tag) => (true, tag.trim_start()),
+            None => (false, tag),
+        };
+        let (name, attributes) = tag
+            .split_once(|c: char| c.is_ascii_whitespace())
+            .unwrap_or((tag, ""));
+        let markup_tag = match name.to_ascii_lowercase().as_str() {
+            "b" | "strong" => MarkupTag::Bold,
+            "i" | "em" => MarkupTag::Italic,
+            "span" => MarkupTag::Span(parse_style_color(attributes)),
+            "br" | "br/" if !closing => {
+                self.push_char('\n');
+                return true;
+            }
+            _ => return false,
+        };
+        if closing {
+            self.close(markup_tag);
+        } else {
+            self.open(markup_tag);
+        }
+        true
+    }
+
+    fn parse_markdown(&mut self, markup: &str) {
+        let mut chars = markup.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some(escaped) => self.push_char(escaped),
+                    None => self.push_char(c),
+                },
+                '*' => {
+                    let mut count = 1;
+                    while chars.next_if_eq(&'*').is_some() {
+                        count += 1;
+                    }
+                    while count > 0 {
+                        let used = count.min(3);
+                        if used != 1 {
+                            self.toggle(MarkupTag::Bold);
+                        }
+                        if used != 2 {
+                            self.toggle(MarkupTag::Italic);
+                        }
+                        count -= used;
+                    }
+                }
+                '\n' => {
+                    let mut count = 1;
+                    while chars.next_if_eq(&'\n').is_some() {
+                        count += 1;
+                    }
+                    if count == 1 {
+                        // A single newline continues the paragraph
+                        self.push_char(' ');
+                    } else {
+                        for _ in 0..count / 2 {
+                            self.push_char('\n');
+                        }
+                    }
+                }
+                _ => self.push_char(c),
+            }
+        }
+    }
+}
+
+fn parse_html_entity(text: &str) -> Option<(char, usize)> {
+    let end = text.get(..12).unwrap_or(text).find(';')?;
+    let entity = &text[1..end];
+    let c = match entity {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let value = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(value)?
+        }
+    };
+    Some((c, end + 1))
+}
+
+/// Parse the color from the `style` attribute of a `span` tag
+fn parse_style_color(attributes: &str) -> Option<Color> {
+    let (_, style) = attributes.split_once("style=")?;
+    let quote = style.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let style = &style[1..];
+    let style = &style[..style.find(quote)?];
+    style.split(';').find_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        if property.trim().eq_ignore_ascii_case("color") {
+            parse_css_color(value.trim())
+        } else {
+            None
+        }
+    })
+}
+
+/// Parse a CSS color in `#rgb`, `#rrggbb`, `#rrggbbaa`, `rgb()` or `rgba()` notation
+fn parse_css_color(value: &str) -> Option<Color> {
+    if let Some(hex) = value.strip_prefix('#') {
+        let digit = |i: usize| u8::from_str_radix(hex.get(i..i + 1)?, 16).ok();
+        let byte = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+        return match hex.len() {
+            3 => Some(Color::rgb(digit(0)? * 17, digit(1)? * 17, digit(2)? * 17)),
+            6 => Some(Color::rgb(byte(0)?, byte(2)?, byte(4)?)),
+            8 => Some(Color::rgba(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+            _ => None,
+        };
+    }
+
+    let args = value
+        .strip_prefix("rgba(")
+        .or_else(|| value.strip_prefix("rgb("))?
+        .strip_suffix(')')?;
+    let mut args = args.split(',').map(str::trim);
+    let r = args.next()?.parse().ok()?;
+    let g = args.next()?.parse().ok()?;
+    let b = args.next()?.parse().ok()?;
+    let a = match args.next() {
+        Some(a) => {
+            let a: f32 = a.parse().ok()?;
+            math::roundf(a.clamp(0.0, 1.0) * 255.0) as u8
+        }
+        None => 0xFF,
+    };
+    if args.next().is_some() {
+        return None;
+    }
+    Some(Color::rgba(r, g, b, a))
+}
+
+impl Buffer {
+    /// Export the text between two cursors as marked up text
+    ///
+    /// Bold, italic, and color attributes are converted to the markup of the given
+    /// [`ExportFormat`]. Markdown has no way to represent colors, so they are dropped. Text is
+    /// escaped as required by the format.
+    pub fn export_range(&self, start: Cursor, end: Cursor, format: ExportFormat) -> String {
+        let (start, end) = if end < start {
+            (end, start)
+        } else {
+            (start, end)
+        };
+
+        let mut output = String::new();
+        let mut boundaries = Vec::new();
+        let end_line = end.line.min(self.lines.len().saturating_sub(1));
+        for line_i in start.line..=end_line {
+            let Some(line) = self.lines.get(line_i) else {
+                break;
+            };
+            let text = line.text();
+            let line_start = if line_i == start.line {
+                start.index.min(text.len())
+            } else {
+                0
+            };
+            let line_end = if line_i == end.line {
+                end.index.min(text.len())
+            } else {
+                text.len()
+            };
+
+            if line_i != start.line {
+                match format {
+                    MarkupFormat::Html => output.push_str("<br>\n"),
+                    MarkupFormat::Markdown => output.push_str("\n\n"),
+                }
+            }
+
+            // Split the range wherever the attributes may change
+            boundaries.clear();
+            boundaries.push(line_start);
+            boundaries.push(line_end);
+            for (range, _) in line.attrs_list().spans_iter() {
+                for index in [range.start, range.end] {
+                    if index > line_start && index < line_end {
+                        boundaries.push(index);
+                    }
+                }
+            }
+            boundaries.sort_unstable();
+            boundaries.dedup();
+
+            for window in boundaries.windows(2) {
+                let (span_start, span_end) = (window[0], window[1]);
+                let Some(span_text) = text.get(span_start..span_end) else {
+                    continue;
+                };
+                let attrs = line.attrs_list().get_span(span_start);
+                push_span(&mut output, format, span_text, &attrs);
+            }
+        }
+        output
+    }
+
+    /// Set text of buffer from marked up text
+    ///
+    /// Only a small subset of each [`MarkupFormat`] is understood: bold, italic, and (for HTML)
+    /// `<span style="color: ...">`, plus line breaks. Nested styles are merged, with the innermost
+    /// color winning. Parsing never fails: unsupported or malformed markup is kept as plain
+    /// text, unmatched closing tags are ignored, and unclosed tags apply until the end of the
+    /// text.
+    pub fn set_from_markup(
+        &mut self,
+        font_system: &mut FontSystem,
+        markup: &str,
+        default_attrs: &Attrs,
+        shaping: Shaping,
+        format: MarkupFormat,
+    ) {
+        let mut parser = MarkupParser::default();
+        match format {
+            MarkupFormat::Html => parser.parse_html(markup),
+            MarkupFormat::Markdown => parser.parse_markdown(markup),
+        }
+
+        let text = &parser.text;
+        let runs = &parser.runs;
+        let spans = runs.iter().enumerate().map(|(i, (start, style))| {
+            let end = runs.get(i + 1).map_or(text.len(), |(end, _)| *end);
+            (&text[*start..end], style.apply(default_attrs))
+        });
+        self.set_rich_text(font_system, spans, default_attrs, shaping, None);
+    }
+}
@@ -3,7 +3,7 @@
 #![allow(clippy::too_many_arguments)]
 
 #[cfg(not(feature = "std"))]
-use alloc::vec::Vec;
+use alloc::{vec, vec::Vec};
 use core::cmp::{max, min};
 use core::fmt;
 use core::mem;
@@ -13,12 +13,14 @@ use unicode_segmentation::UnicodeSegmentation;
 
 use crate::fallback::FontFallbackIter;
 use crate::{
-    math, Align, AttrsList, CacheKeyFlags, Color, Font, FontSystem, LayoutGlyph, LayoutLine,
-    Metrics, Wrap,
+    math, Align, Attrs, AttrsList, BaseDirection, CacheKeyFlags, Color, DropCap, EmojiFallback,
+    Family, FamilyOwned, Font, FontSystem, LayoutGlyph, LayoutLine, Metrics, MissingFamily, Style,
+    TabStops, VerticalAlign, Weight, Wrap,
 };
 
 /// The shaping strategy of some text.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Shaping {
     /// Basic shaping with no font fallback.
     ///
@@ -47,6 +49,8 @@ impl Shaping {
         start_run: usize,
         end_run: usize,
         span_rtl: bool,
+        insert_dotted_circle: bool,
+        fallback_families: &[FamilyOwned],
     ) {
         match self {
             #[cfg(feature = "swash")]
@@ -60,6 +64,8 @@ impl Shaping {
                 start_run,
                 end_run,
                 span_rtl,
+                insert_dotted_circle,
+                fallback_families,
             ),
             #[cfg(feature = "shape-run-cache")]
             Self::Advanced => shape_run_cached(
@@ -70,6 +76,8 @@ impl Shaping {
                 start_run,
                 end_run,
                 span_rtl,
+                insert_dotted_circle,
+                fallback_families,
             ),
         }
     }
@@ -90,12 +98,18 @@ pub struct ShapeBuffer {
     /// Buffer for shape words.
     words: Vec<ShapeWord>,
 
+    /// Buffer for the grapheme starts of a word.
+    egc_starts: Vec<usize>,
+
     /// Buffers for visual lines.
     visual_lines: Vec<VisualLine>,
     cached_visual_lines: Vec<VisualLine>,
 
     /// Buffer for sets of layout glyphs.
     glyph_sets: Vec<Vec<LayoutGlyph>>,
+
+    /// Buffer for the advances of the tabs of a line being laid out.
+    tab_advances: Vec<TabAdvance>,
 }
 
 impl fmt::Debug for ShapeBuffer {
@@ -104,6 +118,23 @@ impl fmt::Debug for ShapeBuffer {
     }
 }
 
+/// Apply [`Attrs::fixed_digit_width`] to a glyph, returning its new advance and X offset
+fn fixed_digit_advance(
+    attrs: &Attrs,
+    c_opt: Option<char>,
+    font_advance: f32,
+    x_advance: f32,
+    x_offset: f32,
+) -> (f32, f32) {
+    match (attrs.fixed_digit_width_opt, c_opt) {
+        // Zero advance glyphs are marks attached to the digit, and are left alone
+        (Some(width), Some(c)) if c.is_ascii_digit() && font_advance != 0.0 => {
+            (width.0, x_offset + (width.0 - font_advance) / 2.0)
+        }
+        _ => (x_advance, x_offset),
+    }
+}
+
 fn shape_fallback(
     scratch: &mut ShapeBuffer,
     glyphs: &mut Vec<ShapeGlyph>,
@@ -113,12 +144,16 @@ fn shape_fallback(
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
+    emoji_fallback: EmojiFallback,
+    language_opt: Option<&rustybuzz::Language>,
+    insert_dotted_circle: bool,
 ) -> Vec<usize> {
     let run = &line[start_run..end_run];
 
     let font_scale = font.rustybuzz().units_per_em() as f32;
     let ascent = font.rustybuzz().ascender() as f32 / font_scale;
     let descent = -font.rustybuzz().descender() as f32 / font_scale;
+    let line_gap = font.rustybuzz().line_gap() as f32 / font_scale;
 
     let mut buffer = scratch.rustybuzz_buffer.take().unwrap_or_default();
     buffer.set_direction(if span_rtl {
@@ -126,16 +161,34 @@ fn shape_fallback(
     } else {
         rustybuzz::Direction::LeftToRight
     });
-    if run.contains('\t') {
+    let text_emoji = emoji_fallback == EmojiFallback::Text && run.contains('\u{FE0F}');
+    if run.contains('\t') || text_emoji {
         // Push string to buffer, replacing tabs with spaces
         //TODO: Find a way to do this with minimal allocating, calling
         // UnicodeBuffer::push_str multiple times causes issues and
         // UnicodeBuffer::add resizes the buffer with every character
-        buffer.push_str(&run.replace('\t', " "));
+        let mut run = run.replace('\t', " ");
+        if text_emoji {
+            // Both selectors have the same UTF-8 length, so cluster indices are preserved
+            run = run.replace('\u{FE0F}', "\u{FE0E}");
+        }
+        buffer.push_str(&run);
     } else {
         buffer.push_str(run);
     }
+    if let Some(language) = language_opt {
+        buffer.set_language(language.clone());
+    }
     buffer.guess_segment_properties();
+    // A combining mark at the start of the line has no base, so it may get a dotted circle
+    let mut flags = rustybuzz::BufferFlags::empty();
+    if start_run == 0 {
+        flags |= rustybuzz::BufferFlags::BEGINNING_OF_TEXT;
+    }
+    if !insert_dotted_circle {
+        flags |= rustybuzz::BufferFlags::DO_NOT_INSERT_DOTTED_CIRCLE;
+    }
+    buffer.set_flags(flags);
 
     let rtl = matches!(buffer.direction(), rustybuzz::Direction::RightToLeft);
     assert_eq!(rtl, span_rtl);
@@ -164,6 +217,8 @@ fn shape_fallback(
     let glyph_positions = glyph_buffer.glyph_positions();
 
     let mut missing = Vec::new();
+    // Glyphs with an override from `Attrs::glyph_overrides`, applied once clusters are known
+    let mut overrides = Vec::new();
     glyphs.reserve(glyph_infos.len());
     let glyph_start = glyphs.len();
     for (info, pos) in glyph_infos.iter().zip(glyph_positions.iter()) {
@@ -174,11 +229,28 @@ fn shape_fallback(
         }
 
         let attrs = attrs_list.get_span(start_glyph);
-        let x_advance = pos.x_advance as f32 / font_scale
-            + attrs.letter_spacing_opt.map_or(0.0, |spacing| spacing.0);
+        if !attrs.glyph_overrides.is_empty() {
+            let c_opt = line[start_glyph..].chars().next();
+            if let Some(&(c, glyph_id)) = attrs
+                .glyph_overrides
+                .iter()
+                .find(|(c, _)| Some(*c) == c_opt)
+            {
+                overrides.push((glyphs.len(), c, glyph_id, attrs.clone(), pos.x_offset));
+            }
+        }
+        let font_advance = pos.x_advance as f32 / font_scale;
+        let x_advance = font_advance + attrs.letter_spacing_opt.map_or(0.0, |spacing| spacing.0);
         let y_advance = pos.y_advance as f32 / font_scale;
         let x_offset = pos.x_offset as f32 / font_scale;
         let y_offset = pos.y_offset as f32 / font_scale;
+        let (x_advance, x_offset) = fixed_digit_advance(
+            &attrs,
+            line[start_glyph..].chars().next(),
+            font_advance,
+            x_advance,
+            x_offset,
+        );
 
         glyphs.push(ShapeGlyph {
             start: start_glyph,
@@ -189,6 +261,7 @@ fn shape_fallback(
             y_offset,
             ascent,
             descent,
+            line_gap,
             font_monospace_em_width: font.monospace_em_width(),
             font_id: font.id(),
             glyph_id: info.glyph_id.try_into().expect("failed to cast glyph ID"),
@@ -196,7 +269,12 @@ fn shape_fallback(
             color_opt: attrs.color_opt,
             metadata: attrs.metadata,
             cache_key_flags: attrs.cache_key_flags,
+            font_weight: font.weight(),
+            font_style: font.style(),
             metrics_opt: attrs.metrics_opt.map(|x| x.into()),
+            replacement_char: false,
+            missing_family: false,
+            dotted_circle: false,
         });
     }
 
@@ -225,6 +303,46 @@ fn shape_fallback(
         }
     }
 
+    // Flag the dotted circles that were inserted, rather than shaped from the text
+    if insert_dotted_circle {
+        if let Some(dotted_circle) = font.rustybuzz().glyph_index('\u{25CC}') {
+            for glyph in glyphs[glyph_start..].iter_mut() {
+                glyph.dotted_circle = glyph.glyph_id == dotted_circle.0
+                    && !line[glyph.start..glyph.end].contains('\u{25CC}');
+            }
+        }
+    }
+
+    for (i, c, glyph_id, attrs, x_offset) in overrides {
+        let glyph = &glyphs[i];
+        let single_glyph = (i == glyph_start || glyphs[i - 1].start != glyph.start)
+            && glyphs
+                .get(i + 1)
+                .map_or(true, |next| next.start != glyph.start);
+        if !single_glyph || glyph.end - glyph.start != c.len_utf8() {
+            continue;
+        }
+        let Some(advance) = font
+            .rustybuzz()
+            .glyph_hor_advance(rustybuzz::ttf_parser::GlyphId(glyph_id))
+        else {
+            continue;
+        };
+        let font_advance = f32::from(advance) / font_scale;
+        let x_advance = font_advance + attrs.letter_spacing_opt.map_or(0.0, |spacing| spacing.0);
+        let (x_advance, x_offset) = fixed_digit_advance(
+            &attrs,
+            Some(c),
+            font_advance,
+            x_advance,
+            x_offset as f32 / font_scale,
+        );
+        let glyph = &mut glyphs[i];
+        glyph.glyph_id = glyph_id;
+        glyph.x_advance = x_advance;
+        glyph.x_offset = x_offset;
+    }
+
     // Restore the buffer to save an allocation.
     scratch.rustybuzz_buffer = Some(glyph_buffer.clear());
 
@@ -239,6 +357,8 @@ fn shape_run(
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
+    insert_dotted_circle: bool,
+    fallback_families: &[FamilyOwned],
 ) {
     // Re-use the previous script buffer if possible.
     let mut scripts = {
@@ -263,27 +383,65 @@ fn shape_run(
 
     let fonts = font_system.get_font_matches(&attrs);
 
-    let default_families = [&attrs.family];
+    let emoji_fallback = font_system.emoji_fallback();
+    let language_opt = font_system.language().cloned();
+    let missing_family = font_system.missing_family().clone();
+    let (family, family_missing) = run_family(font_system, &attrs, &missing_family);
+    // The fallback families are tried after the family of the run, before script fallbacks
+    let run_families = [&family];
+    let fallback_families: Vec<Family> = fallback_families
+        .iter()
+        .map(FamilyOwned::as_family)
+        .collect();
+    let default_families: Vec<&Family> = if fallback_families.is_empty() {
+        Vec::new()
+    } else {
+        run_families
+            .into_iter()
+            .chain(fallback_families.iter())
+            .collect()
+    };
     let mut font_iter = FontFallbackIter::new(
         font_system,
         &fonts,
-        &default_families,
+        if default_families.is_empty() {
+            &run_families
+        } else {
+            &default_families
+        },
         &scripts,
         &line[start_run..end_run],
     );
 
-    let font = font_iter.next().expect("no default font found");
+    let Some(font) = font_iter.next() else {
+        // A worker copy of the font system cannot load the default font, the line is shaped again
+        #[cfg(feature = "rayon")]
+        if font_system.set_worker_missed_font() {
+            return;
+        }
+        panic!("no default font found");
+    };
 
     let glyph_start = glyphs.len();
     let mut missing = {
         let scratch = font_iter.shape_caches();
         shape_fallback(
-            scratch, glyphs, &font, line, attrs_list, start_run, end_run, span_rtl,
+            scratch,
+            glyphs,
+            &font,
+            line,
+            attrs_list,
+            start_run,
+            end_run,
+            span_rtl,
+            emoji_fallback,
+            language_opt.as_ref(),
+            insert_dotted_circle,
         )
     };
 
     //TODO: improve performance!
-    while !missing.is_empty() {
+    while !attrs.no_fallback && !missing.is_empty() {
         let font = match font_iter.next() {
             Some(some) => some,
             None => break,
@@ -304,6 +462,9 @@ fn shape_run(
             start_run,
             end_run,
             span_rtl,
+            emoji_fallback,
+            language_opt.as_ref(),
+            insert_dotted_circle,
         );
 
         // Insert all matching glyphs
@@ -370,10 +531,31 @@ fn shape_run(
     }
     */
 
+    if family_missing {
+        for glyph in glyphs[glyph_start..].iter_mut() {
+            glyph.missing_family = true;
+        }
+    }
+
     // Restore the scripts buffer.
     font_system.shape_buffer.scripts = scripts;
 }
 
+/// Get the family to shape a run with according to [`FontSystem::missing_family`], and whether
+/// the glyphs of the run must be flagged with [`ShapeGlyph::missing_family`]
+fn run_family<'a>(
+    font_system: &mut FontSystem,
+    attrs: &Attrs<'a>,
+    missing_family: &'a MissingFamily,
+) -> (Family<'a>, bool) {
+    match missing_family {
+        MissingFamily::Fallback => (attrs.family, false),
+        _ if font_system.has_family(attrs) => (attrs.family, false),
+        MissingFamily::Error => (attrs.family, true),
+        MissingFamily::Substitute(family) => (family.as_family(), false),
+    }
+}
+
 #[cfg(feature = "shape-run-cache")]
 fn shape_run_cached(
     glyphs: &mut Vec<ShapeGlyph>,
@@ -383,6 +565,8 @@ fn shape_run_cached(
     start_run: usize,
     end_run: usize,
     span_rtl: bool,
+    insert_dotted_circle: bool,
+    fallback_families: &[FamilyOwned],
 ) {
     use crate::{AttrsOwned, ShapeRunKey};
 
@@ -391,6 +575,13 @@ fn shape_run_cached(
         text: line[run_range.clone()].to_string(),
         default_attrs: AttrsOwned::new(&attrs_list.defaults()),
         attrs_spans: Vec::new(),
+        beginning_of_text: start_run == 0,
+        insert_dotted_circle,
+        fallback_families: if fallback_families.is_empty() {
+            Vec::new()
+        } else {
+            fallback_families.to_vec()
+        },
     };
     for (attrs_range, attrs) in attrs_list.spans.overlapping(&run_range) {
         if attrs == &key.default_attrs {
@@ -424,8 +615,15 @@ fn shape_run_cached(
         start_run,
         end_run,
         span_rtl,
+        insert_dotted_circle,
+        fallback_families,
     );
     glyphs.extend_from_slice(&cache_glyphs);
+    // A worker copy of the font system missed a font, so the run is incomplete
+    #[cfg(feature = "rayon")]
+    if font_system.worker_missed_font_pending() {
+        return;
+    }
     for glyph in cache_glyphs.iter_mut() {
         // Adjust glyph start and end to remove run position
         glyph.start -= start_run;
@@ -446,12 +644,23 @@ fn shape_skip(
     let attrs = attrs_list.get_span(start_run);
     let fonts = font_system.get_font_matches(&attrs);
 
-    let default_families = [&attrs.family];
+    let missing_family = font_system.missing_family().clone();
+    let (family, family_missing) = run_family(font_system, &attrs, &missing_family);
+    let default_families = [&family];
     let mut font_iter = FontFallbackIter::new(font_system, &fonts, &default_families, &[], "");
 
-    let font = font_iter.next().expect("no default font found");
+    let Some(font) = font_iter.next() else {
+        // A worker copy of the font system cannot load the default font, the line is shaped again
+        #[cfg(feature = "rayon")]
+        if font_system.set_worker_missed_font() {
+            return;
+        }
+        panic!("no default font found");
+    };
     let font_id = font.id();
     let font_monospace_em_width = font.monospace_em_width();
+    let font_weight = font.weight();
+    let font_style = font.style();
     let font = font.as_swash();
 
     let charmap = font.charmap();
@@ -460,32 +669,46 @@ fn shape_skip(
 
     let ascent = metrics.ascent / f32::from(metrics.units_per_em);
     let descent = metrics.descent / f32::from(metrics.units_per_em);
+    let line_gap = metrics.leading / f32::from(metrics.units_per_em);
 
     glyphs.extend(
         line[start_run..end_run]
             .char_indices()
             .map(|(chr_idx, codepoint)| {
-                let glyph_id = charmap.map(codepoint);
-                let x_advance = glyph_metrics.advance_width(glyph_id)
-                    + attrs.letter_spacing_opt.map_or(0.0, |spacing| spacing.0);
+                let letter_spacing = attrs.letter_spacing_opt.map_or(0.0, |spacing| spacing.0);
                 let attrs = attrs_list.get_span(start_run + chr_idx);
+                let glyph_id = attrs
+                    .glyph_overrides
+                    .iter()
+                    .find(|(c, _)| *c == codepoint)
+                    .map_or_else(|| charmap.map(codepoint), |&(_, glyph_id)| glyph_id);
+                let font_advance = glyph_metrics.advance_width(glyph_id);
+                let x_advance = font_advance + letter_spacing;
+                let (x_advance, x_offset) =
+                    fixed_digit_advance(&attrs, Some(codepoint), font_advance, x_advance, 0.0);
 
                 ShapeGlyph {
                     start: chr_idx + start_run,
                     end: chr_idx + start_run + codepoint.len_utf8(),
                     x_advance,
                     y_advance: 0.0,
-                    x_offset: 0.0,
+                    x_offset,
                     y_offset: 0.0,
                     ascent,
                     descent,
+                    line_gap,
                     font_monospace_em_width,
                     font_id,
                     glyph_id,
                     color_opt: attrs.color_opt,
                     metadata: attrs.metadata,
                     cache_key_flags: attrs.cache_key_flags,
+                    font_weight,
+                    font_style,
                     metrics_opt: attrs.metrics_opt.map(|x| x.into()),
+                    replacement_char: false,
+                    missing_family: family_missing,
+                    dotted_circle: false,
                 }
             }),
     );
@@ -502,13 +725,28 @@ pub struct ShapeGlyph {
     pub y_offset: f32,
     pub ascent: f32,
     pub descent: f32,
+    /// Line gap recommended by the font, in em
+    pub line_gap: f32,
     pub font_monospace_em_width: Option<f32>,
     pub font_id: fontdb::ID,
     pub glyph_id: u16,
     pub color_opt: Option<Color>,
     pub metadata: usize,
     pub cache_key_flags: CacheKeyFlags,
+    /// Weight of the font face the glyph was shaped with
+    pub font_weight: Weight,
+    /// Style of the font face the glyph was shaped with
+    pub font_style: Style,
     pub metrics_opt: Option<Metrics>,
+    /// True if this glyph's cluster contains U+FFFD, see
+    /// [`ShapeOptions::highlight_replacement_char`]
+    pub replacement_char: bool,
+    /// True if the requested family was missing when this glyph was shaped with
+    /// [`MissingFamily::Error`]
+    pub missing_family: bool,
+    /// True if this glyph is a dotted circle inserted as the base of a combining mark without
+    /// one, see [`ShapeOptions::insert_dotted_circle`]. It maps to the bytes of the mark.
+    pub dotted_circle: bool,
 }
 
 impl ShapeGlyph {
@@ -537,6 +775,11 @@ impl ShapeGlyph {
             color_opt: self.color_opt,
             metadata: self.metadata,
             cache_key_flags: self.cache_key_flags,
+            font_weight: self.font_weight,
+            font_style: self.font_style,
+            marks_clamped: false,
+            replacement_char: self.replacement_char,
+            opacity: 1.0,
         }
     }
 
@@ -547,6 +790,19 @@ impl ShapeGlyph {
     }
 }
 
+/// A grapheme shaped in isolation, see [`FontSystem::shape_grapheme`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GraphemeShape {
+    /// Total advance of the glyphs, in units of the font size
+    pub advance: f32,
+    /// Number of glyphs produced
+    pub glyph_count: usize,
+    /// Font that resolved the first glyph, `None` if no glyphs were produced
+    pub font_id_opt: Option<fontdb::ID>,
+    /// True if the shaped text is exactly one extended grapheme cluster
+    pub single_grapheme: bool,
+}
+
 /// A shaped word (for word wrapping)
 #[derive(Clone, Debug)]
 pub struct ShapeWord {
@@ -602,6 +858,33 @@ impl ShapeWord {
         level: unicode_bidi::Level,
         blank: bool,
         shaping: Shaping,
+    ) {
+        self.build_with_options(
+            font_system,
+            line,
+            attrs_list,
+            word_range,
+            level,
+            blank,
+            shaping,
+            &ShapeOptions::default(),
+        );
+    }
+
+    /// See [`Self::build`], with shaping runs also split at every byte offset in
+    /// [`ShapeOptions::forced_cluster_boundaries`], and at color changes with
+    /// [`ShapeOptions::split_runs_on_color`].
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn build_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        word_range: Range<usize>,
+        level: unicode_bidi::Level,
+        blank: bool,
+        shaping: Shaping,
+        options: &ShapeOptions,
     ) {
         let word = &line[word_range.clone()];
 
@@ -618,10 +901,16 @@ impl ShapeWord {
 
         let mut start_run = word_range.start;
         let mut attrs = attrs_list.defaults();
+        let mut egc_starts = mem::take(&mut font_system.shape_buffer.egc_starts);
+        egc_starts.clear();
         for (egc_i, _egc) in word.grapheme_indices(true) {
             let start_egc = word_range.start + egc_i;
+            egc_starts.push(start_egc);
             let attrs_egc = attrs_list.get_span(start_egc);
-            if !attrs.compatible(&attrs_egc) {
+            let forced = start_egc > start_run
+                && (options.forced_cluster_boundaries.contains(&start_egc)
+                    || (options.split_runs_on_color && attrs.color_opt != attrs_egc.color_opt));
+            if forced || !attrs.compatible(&attrs_egc) {
                 shaping.run(
                     &mut glyphs,
                     font_system,
@@ -630,6 +919,8 @@ impl ShapeWord {
                     start_run,
                     start_egc,
                     span_rtl,
+                    options.insert_dotted_circle,
+                    options.fallback_families,
                 );
 
                 start_run = start_egc;
@@ -645,9 +936,25 @@ impl ShapeWord {
                 start_run,
                 word_range.end,
                 span_rtl,
+                options.insert_dotted_circle,
+                options.fallback_families,
             );
         }
 
+        // Widen clusters that end inside of a grapheme, such as the characters of an emoji ZWJ
+        // sequence with basic shaping or from different fallback fonts, to the whole grapheme
+        egc_starts.push(word_range.end);
+        for glyph in glyphs.iter_mut() {
+            if let Err(i) = egc_starts.binary_search(&glyph.start) {
+                glyph.start = egc_starts[i.saturating_sub(1)];
+            }
+            if let Err(i) = egc_starts.binary_search(&glyph.end) {
+                glyph.end = egc_starts[i.min(egc_starts.len() - 1)];
+            }
+        }
+        // Restore the grapheme starts buffer.
+        font_system.shape_buffer.egc_starts = egc_starts;
+
         self.blank = blank;
         self.glyphs = glyphs;
     }
@@ -662,6 +969,84 @@ impl ShapeWord {
     }
 }
 
+/// Words shaped for a previous version of a line that can be reused for its unchanged text, see
+/// [`crate::BufferLine::set_text_diff`]
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ShapeReuse {
+    // Words by their byte range in the new text, sorted by start
+    words: Vec<(Range<usize>, unicode_bidi::Level, Option<ShapeWord>)>,
+}
+
+impl ShapeReuse {
+    /// Keep the words of `line`, shaped from text of `old_len` bytes, that end before the first
+    /// `prefix` bytes or start after the last `suffix` bytes, which are unchanged in the new text
+    /// of `new_len` bytes. Words touching the changed text are shaped again.
+    pub(crate) fn new(
+        line: &ShapeLine,
+        old_len: usize,
+        new_len: usize,
+        prefix: usize,
+        suffix: usize,
+    ) -> Self {
+        let mut words = Vec::new();
+        for span in line.spans.iter() {
+            for word in span.words.iter() {
+                let Some(start) = word.glyphs.iter().map(|glyph| glyph.start).min() else {
+                    continue;
+                };
+                let end = word
+                    .glyphs
+                    .iter()
+                    .map(|glyph| glyph.end)
+                    .max()
+                    .unwrap_or(start);
+                let mut word = word.clone();
+                // Words are stored as they were built, before glyphs are reversed in RTL lines
+                if line.rtl {
+                    word.glyphs.reverse();
+                }
+                if end < prefix {
+                    words.push((start..end, span.level, Some(word)));
+                } else if start + suffix > old_len {
+                    let shift = |index: usize| index + new_len - old_len;
+                    for glyph in word.glyphs.iter_mut() {
+                        glyph.start = shift(glyph.start);
+                        glyph.end = shift(glyph.end);
+                    }
+                    words.push((shift(start)..shift(end), span.level, Some(word)));
+                }
+            }
+        }
+        words.sort_by_key(|(range, _, _)| range.start);
+        Self { words }
+    }
+
+    /// Take the word shaped for `range` of `line` with the same level and blankness
+    ///
+    /// Tabs are not reused, since their advance depends on the text before them.
+    fn take(
+        &mut self,
+        line: &str,
+        range: &Range<usize>,
+        level: unicode_bidi::Level,
+        blank: bool,
+    ) -> Option<ShapeWord> {
+        let i = self
+            .words
+            .binary_search_by_key(&range.start, |(range, _, _)| range.start)
+            .ok()?;
+        let (word_range, word_level, word) = &mut self.words[i];
+        if word_range != range
+            || *word_level != level
+            || word.as_ref()?.blank != blank
+            || line.get(range.clone()) == Some("\t")
+        {
+            return None;
+        }
+        word.take()
+    }
+}
+
 /// A shaped span (for bidirectional processing)
 #[derive(Clone, Debug)]
 pub struct ShapeSpan {
@@ -715,6 +1100,34 @@ impl ShapeSpan {
         line_rtl: bool,
         level: unicode_bidi::Level,
         shaping: Shaping,
+    ) {
+        self.build_with_options(
+            font_system,
+            line,
+            attrs_list,
+            span_range,
+            line_rtl,
+            level,
+            shaping,
+            &ShapeOptions::default(),
+            &mut ShapeReuse::default(),
+        );
+    }
+
+    /// See [`Self::build`] and [`ShapeWord::build_with_options`], taking words from `reuse`
+    /// instead of shaping them when possible.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn build_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        span_range: Range<usize>,
+        line_rtl: bool,
+        level: unicode_bidi::Level,
+        shaping: Shaping,
+        options: &ShapeOptions,
+        reuse: &mut ShapeReuse,
     ) {
         let span = &line[span_range.start..span_range.end];
 
@@ -751,28 +1164,39 @@ impl ShapeSpan {
                 }
             }
             if start_word < start_lb {
-                let mut word = cached_words.pop().unwrap_or_else(ShapeWord::empty);
-                word.build(
-                    font_system,
-                    line,
-                    attrs_list,
-                    (span_range.start + start_word)..(span_range.start + start_lb),
-                    level,
-                    false,
-                    shaping,
-                );
-                words.push(word);
+                let word_range = (span_range.start + start_word)..(span_range.start + start_lb);
+                if let Some(word) = reuse.take(line, &word_range, level, false) {
+                    words.push(word);
+                } else {
+                    let mut word = cached_words.pop().unwrap_or_else(ShapeWord::empty);
+                    word.build_with_options(
+                        font_system,
+                        line,
+                        attrs_list,
+                        word_range,
+                        level,
+                        false,
+                        shaping,
+                        options,
+                    );
+                    words.push(word);
+                }
             }
             if start_lb < end_lb {
                 for (i, c) in span[start_lb..end_lb].char_indices() {
                     // assert!(c.is_whitespace());
+                    let word_range = (span_range.start + start_lb + i)
+                        ..(span_range.start + start_lb + i + c.len_utf8());
+                    if let Some(word) = reuse.take(line, &word_range, level, true) {
+                        words.push(word);
+                        continue;
+                    }
                     let mut word = cached_words.pop().unwrap_or_else(ShapeWord::empty);
                     word.build(
                         font_system,
                         line,
                         attrs_list,
-                        (span_range.start + start_lb + i)
-                            ..(span_range.start + start_lb + i + c.len_utf8()),
+                        word_range,
                         level,
                         true,
                         shaping,
@@ -803,14 +1227,230 @@ impl ShapeSpan {
     }
 }
 
+/// Extra options for [`ShapeLine::build_with_options`]
+#[derive(Clone, Copy, Debug)]
+pub struct ShapeOptions<'a> {
+    /// How the paragraph direction is chosen
+    pub base_direction: BaseDirection,
+    /// Byte offsets that are always cluster edges. Text on either side of an offset is shaped
+    /// separately, so no ligature or cluster spans it.
+    pub forced_cluster_boundaries: &'a [usize],
+    /// Tag glyphs of clusters containing U+FFFD REPLACEMENT CHARACTER, usually the result of
+    /// decoding malformed UTF-8, so that renderers can mark them. See
+    /// [`ShapeGlyph::replacement_char`] and [`ShapeLine::replacement_char_positions`].
+    pub highlight_replacement_char: bool,
+    /// Insert a dotted circle (U+25CC) base before a combining mark that starts the line, and in
+    /// broken clusters of complex scripts, if the font has one. The inserted glyph has the same
+    /// byte range as the mark, so it maps back to the original text. Defaults to true.
+    pub insert_dotted_circle: bool,
+    /// Split shaping runs wherever the color changes, even within the same font and script, so
+    /// that no ligature or cluster spans two colors. Terminal emulators use this to color each
+    /// cell separately.
+    pub split_runs_on_color: bool,
+    /// Families tried in order for characters the family of their span does not cover, before
+    /// the fallback fonts chosen for their script
+    pub fallback_families: &'a [FamilyOwned],
+}
+
+impl Default for ShapeOptions<'_> {
+    fn default() -> Self {
+        Self {
+            base_direction: BaseDirection::default(),
+            forced_cluster_boundaries: &[],
+            highlight_replacement_char: false,
+            insert_dotted_circle: true,
+            split_runs_on_color: false,
+            fallback_families: &[],
+        }
+    }
+}
+
+/// Finds where words may be broken with a hyphen, see [`Hyphenation`]
+pub trait Hyphenator {
+    /// Get the byte offsets in `word` where it may be broken with a hyphen
+    fn break_points(&self, word: &str) -> Vec<usize>;
+}
+
+/// Hyphenation of words that do not fit on a line, see [`LayoutOptions::hyphenation`]
+///
+/// Words are only hyphenated with [`Wrap::Word`] and [`Wrap::WordOrGlyph`], in left-to-right
+/// text. A word that does not fit on the rest of a line is broken at the last break point where
+/// its start and a hyphen fit, and the rest of a word that is wider than a line is broken again.
+/// If no break point fits, the word wraps as it would without hyphenation. With
+/// [`Wrap::WordOrGlyph`], words wider than a line are still broken at glyphs instead.
+///
+/// The hyphen is added at the end of the line with an empty byte range at the break.
+#[derive(Clone, Copy)]
+pub struct Hyphenation<'a> {
+    /// Text of the line that was shaped
+    pub text: &'a str,
+    /// Finds the break points of words
+    pub hyphenator: &'a dyn Hyphenator,
+    /// Get the shaped hyphen to insert after the glyph starting at a byte offset of `text`
+    pub hyphen: &'a dyn Fn(usize) -> Option<ShapeGlyph>,
+}
+
+impl fmt::Debug for Hyphenation<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("Hyphenation { .. }")
+    }
+}
+
+impl Hyphenation<'_> {
+    /// Find the last break point of `word` after glyph `from_glyph` where the glyphs from
+    /// `from_glyph` to the break and a hyphen fit in `available`
+    ///
+    /// Returns the index of the first glyph after the break, the width of the glyphs before it,
+    /// and the hyphen with the byte offset of the break.
+    fn find_break(
+        &self,
+        word: &ShapeWord,
+        from_glyph: usize,
+        font_size: f32,
+        available: f32,
+    ) -> Option<(usize, f32, (ShapeGlyph, usize))> {
+        let start = word.glyphs.first()?.start;
+        let end = word.glyphs.last()?.end;
+        let mut best: Option<(usize, f32, (ShapeGlyph, usize))> = None;
+        for break_point in self.hyphenator.break_points(self.text.get(start..end)?) {
+            let offset = start + break_point;
+            let Some(glyph_i) = word.glyphs.iter().position(|glyph| glyph.start == offset) else {
+                continue;
+            };
+            if glyph_i <= from_glyph || best.as_ref().is_some_and(|(best_i, ..)| *best_i >= glyph_i)
+            {
+                continue;
+            }
+            let Some(hyphen) = (self.hyphen)(word.glyphs[glyph_i - 1].start) else {
+                continue;
+            };
+            let width: f32 = word.glyphs[from_glyph..glyph_i]
+                .iter()
+                .map(|glyph| glyph.width(font_size))
+                .sum();
+            if width + hyphen.width(font_size) <= available {
+                best = Some((glyph_i, width, (hyphen, offset)));
+            }
+        }
+        best
+    }
+}
+
+/// Marker glyphs drawn in tabs, see [`LayoutOptions::tab_visualization`]
+///
+/// Code editors use this to visualize tabs. The marker is placed after the tab glyph, at the
+/// starting edge of the tab's expanded region, and kept inside it when it fits. It has an empty
+/// byte range at the start of the tab and no width, so the advance and cluster mapping of the
+/// tab and the metrics of the line are unchanged.
+#[derive(Clone, Copy)]
+pub struct TabVisualization<'a> {
+    /// Get the shaped marker, such as `→` or `·`, to draw in the tab at a byte offset
+    pub marker: &'a dyn Fn(usize) -> Option<ShapeGlyph>,
+}
+
+impl fmt::Debug for TabVisualization<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("TabVisualization { .. }")
+    }
+}
+
+/// Leader glyphs filling tabs, see [`LayoutOptions::tab_leaders`] and [`TabStops::leaders`]
+///
+/// The leader of the stop a tab reaches is repeated as many times as it fits in the tab, ending
+/// at the stop, so that no leader overflows. The leaders have an empty byte range at the start
+/// of the tab and no width, so the advance and cluster mapping of the tab are unchanged.
+#[derive(Clone, Copy)]
+pub struct TabLeaders<'a> {
+    /// Get the shaped leader character, such as `.`, to fill the tab at a byte offset with
+    pub leader: &'a dyn Fn(usize, char) -> Option<ShapeGlyph>,
+}
+
+impl fmt::Debug for TabLeaders<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad("TabLeaders { .. }")
+    }
+}
+
+/// Extra options for [`ShapeLine::layout_to_buffer_with_options`]
+#[derive(Clone, Copy, Debug)]
+pub struct LayoutOptions<'a> {
+    /// Alignment of each visual line, by visual line index. Lines without an entry, or with a
+    /// `None` entry, use the alignment passed to the layout function.
+    pub line_aligns: &'a [Option<Align>],
+    /// Remove whitespace from the start of wrapped continuation lines, like CSS does. The
+    /// whitespace stays in the text but takes no space in the layout.
+    pub trim_wrapped_leading_whitespace: bool,
+    /// Leave the whitespace at the end of each visual line out of its width when aligning it, so
+    /// that trailing spaces and tabs hang past the aligned edge
+    pub ignore_trailing_whitespace_in_align: bool,
+    /// Space reserved at the start of the first visual lines for a drop cap
+    pub drop_cap: Option<DropCap>,
+    /// Hyphenation of words that do not fit on a line
+    pub hyphenation: Option<Hyphenation<'a>>,
+    /// Tab stops in pixels, replacing the uniform stops every `tab_width` spaces used otherwise
+    ///
+    /// Tabs are placed while wrapping, measuring from the start of their visual line in its
+    /// reading direction, so they follow the right edge of right-to-left lines. Like spaces, a
+    /// tab that reaches past the width is kept at the end of its line and the text after it
+    /// wraps.
+    pub tab_stops: Option<&'a TabStops>,
+    /// Marker glyphs drawn in tabs
+    pub tab_visualization: Option<TabVisualization<'a>>,
+    /// Leader glyphs filling the tabs that reach a stop with a leader in [`Self::tab_stops`]
+    pub tab_leaders: Option<TabLeaders<'a>>,
+    /// Avoid an orphan, a last line holding a single short word
+    ///
+    /// With [`Wrap::Word`] and [`Wrap::WordOrGlyph`], when the last line would hold a single word
+    /// narrower than a third of the width, the line before it is narrowed so that its last word
+    /// moves down. This is only kept if the line count does not grow.
+    pub avoid_orphans: bool,
+    /// Vertical alignment of glyphs of different sizes within each visual line
+    pub vertical_align: VerticalAlign,
+}
+
+impl LayoutOptions<'_> {
+    /// Check if `glyph` is part of a drop cap laid out at its own font size, the first cluster
+    /// of the paragraph
+    fn is_drop_cap(&self, glyph: &ShapeGlyph) -> bool {
+        glyph.start == 0
+            && self
+                .drop_cap
+                .is_some_and(|drop_cap| drop_cap.font_size_opt.is_some())
+    }
+}
+
+impl Default for LayoutOptions<'_> {
+    fn default() -> Self {
+        Self {
+            line_aligns: &[],
+            trim_wrapped_leading_whitespace: true,
+            ignore_trailing_whitespace_in_align: false,
+            drop_cap: None,
+            hyphenation: None,
+            tab_stops: None,
+            tab_visualization: None,
+            tab_leaders: None,
+            avoid_orphans: false,
+            vertical_align: VerticalAlign::Baseline,
+        }
+    }
+}
+
 /// A shaped line (or paragraph)
 #[derive(Clone, Debug)]
 pub struct ShapeLine {
     pub rtl: bool,
     pub spans: Vec<ShapeSpan>,
     pub metrics_opt: Option<Metrics>,
+    /// Byte offset of each tab in the line, with the distance between its uniform stops from
+    /// the tab width, in units of its font size, see [`LayoutOptions::tab_stops`]
+    tabs: Vec<(usize, f32)>,
 }
 
+/// Advance of a tab placed by the tab stops while wrapping a line: its byte offset, its advance
+/// in units of its font size, and the leader of the stop it reaches
+type TabAdvance = (usize, f32, Option<char>);
+
 // Visual Line Ranges: (span_index, (first_word_index, first_glyph_index), (last_word_index, last_glyph_index))
 type VlRange = (usize, (usize, usize), (usize, usize));
 
@@ -819,6 +1459,8 @@ struct VisualLine {
     ranges: Vec<VlRange>,
     spaces: u32,
     w: f32,
+    // Hyphen added at the end of the line, with the byte offset of the break
+    hyphen_opt: Option<(ShapeGlyph, usize)>,
 }
 
 impl VisualLine {
@@ -826,10 +1468,24 @@ impl VisualLine {
         self.ranges.clear();
         self.spaces = 0;
         self.w = 0.;
+        self.hyphen_opt = None;
     }
 }
 
+/// Get the [`ShapeLine::FORMAT_VERSION`] of this build of the crate
+pub const fn shape_format_version() -> u32 {
+    ShapeLine::FORMAT_VERSION
+}
+
 impl ShapeLine {
+    /// Version of the data layout of [`ShapeLine`] and the types it contains, such as
+    /// [`ShapeGlyph`]
+    ///
+    /// This is increased whenever their fields change, so that serialized or FFI data can be
+    /// checked and rejected when it comes from an incompatible version. It is written in the
+    /// serde output of [`crate::BufferLine`], which rejects lines of another version.
+    pub const FORMAT_VERSION: u32 = 6;
+
     /// Creates an empty line.
     ///
     /// The returned line is in an invalid state until [`Self::build_in_buffer`] is called.
@@ -838,7 +1494,20 @@ impl ShapeLine {
             rtl: false,
             spans: Vec::default(),
             metrics_opt: None,
+            tabs: Vec::new(),
+        }
+    }
+
+    /// Release the excess capacity of the spans, words and glyphs of this line
+    pub(crate) fn shrink_to_fit(&mut self) {
+        for span in &mut self.spans {
+            for word in &mut span.words {
+                word.glyphs.shrink_to_fit();
+            }
+            span.words.shrink_to_fit();
         }
+        self.spans.shrink_to_fit();
+        self.tabs.shrink_to_fit();
     }
 
     /// Shape a line into a set of spans, using a scratch buffer. If [`unicode_bidi::BidiInfo`]
@@ -873,6 +1542,53 @@ impl ShapeLine {
         attrs_list: &AttrsList,
         shaping: Shaping,
         tab_width: u16,
+    ) {
+        self.build_with_options(
+            font_system,
+            line,
+            attrs_list,
+            shaping,
+            tab_width,
+            ShapeOptions::default(),
+        );
+    }
+
+    /// See [`Self::build`], with extra [`ShapeOptions`].
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `line` contains multiple paragraphs that do not have matching direction
+    pub fn build_with_options(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        tab_width: u16,
+        options: ShapeOptions,
+    ) {
+        self.build_reusing(
+            font_system,
+            line,
+            attrs_list,
+            shaping,
+            tab_width,
+            options,
+            &mut ShapeReuse::default(),
+        );
+    }
+
+    /// See [`Self::build_with_options`], taking words from `reuse` instead of shaping them when
+    /// possible.
+    pub(crate) fn build_reusing(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        tab_width: u16,
+        options: ShapeOptions,
+        reuse: &mut ShapeReuse,
     ) {
         let mut spans = mem::take(&mut self.spans);
 
@@ -881,7 +1597,7 @@ impl ShapeLine {
         cached_spans.clear();
         cached_spans.extend(spans.drain(..).rev());
 
-        let bidi = unicode_bidi::BidiInfo::new(line, None);
+        let bidi = unicode_bidi::BidiInfo::new(line, options.base_direction.default_level(line));
         let rtl = if bidi.paragraphs.is_empty() {
             false
         } else {
@@ -912,7 +1628,7 @@ impl ShapeLine {
                 if new_level != run_level {
                     // End of the previous run, start of a new one.
                     let mut span = cached_spans.pop().unwrap_or_else(ShapeSpan::empty);
-                    span.build(
+                    span.build_with_options(
                         font_system,
                         line,
                         attrs_list,
@@ -920,6 +1636,8 @@ impl ShapeLine {
                         line_rtl,
                         run_level,
                         shaping,
+                        &options,
+                        reuse,
                     );
                     spans.push(span);
                     start = i;
@@ -927,7 +1645,7 @@ impl ShapeLine {
                 }
             }
             let mut span = cached_spans.pop().unwrap_or_else(ShapeSpan::empty);
-            span.build(
+            span.build_with_options(
                 font_system,
                 line,
                 attrs_list,
@@ -935,25 +1653,42 @@ impl ShapeLine {
                 line_rtl,
                 run_level,
                 shaping,
+                &options,
+                reuse,
             );
             spans.push(span);
         }
 
-        // Adjust for tabs
+        // Adjust for tabs, as if the line was not wrapped. Layout places them again from the
+        // start of each visual line.
+        self.tabs.clear();
         let mut x = 0.0;
         for span in spans.iter_mut() {
             for word in span.words.iter_mut() {
                 for glyph in word.glyphs.iter_mut() {
                     if line.get(glyph.start..glyph.end) == Some("\t") {
                         // Tabs are shaped as spaces, so they will always have the x_advance of a space.
-                        let tab_x_advance = (tab_width as f32) * glyph.x_advance;
-                        let tab_stop = (math::floorf(x / tab_x_advance) + 1.0) * tab_x_advance;
-                        glyph.x_advance = tab_stop - x;
+                        let interval = (tab_width as f32) * glyph.x_advance;
+                        glyph.x_advance = TabStops::uniform(interval).next_stop(x) - x;
+                        self.tabs.push((glyph.start, interval));
                     }
                     x += glyph.x_advance;
                 }
             }
         }
+        self.tabs.sort_unstable_by_key(|(start, _)| *start);
+
+        if options.highlight_replacement_char && line.contains('\u{FFFD}') {
+            for span in spans.iter_mut() {
+                for word in span.words.iter_mut() {
+                    for glyph in word.glyphs.iter_mut() {
+                        glyph.replacement_char = line
+                            .get(glyph.start..glyph.end)
+                            .is_some_and(|cluster| cluster.contains('\u{FFFD}'));
+                    }
+                }
+            }
+        }
 
         self.rtl = rtl;
         self.spans = spans;
@@ -963,6 +1698,106 @@ impl ShapeLine {
         font_system.shape_buffer.spans = cached_spans;
     }
 
+    /// Shape the word at `word_range`, at the end of `line`, and append it to the last span
+    ///
+    /// This is only correct if the rest of `line` was shaped into this line, which has a single
+    /// left-to-right span, and `word_range` starts at a word boundary, such as after a space.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn push_word(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        word_range: Range<usize>,
+        blank: bool,
+        shaping: Shaping,
+        options: &ShapeOptions,
+    ) {
+        let Some(span) = self.spans.last_mut() else {
+            return;
+        };
+        // Blank words are shaped without options, like in ShapeSpan::build_with_options
+        let default_options = ShapeOptions::default();
+        let mut word = ShapeWord::empty();
+        word.build_with_options(
+            font_system,
+            line,
+            attrs_list,
+            word_range,
+            span.level,
+            blank,
+            shaping,
+            if blank { &default_options } else { options },
+        );
+        span.words.push(word);
+    }
+
+    /// Get the start byte offsets of the clusters containing U+FFFD, in logical order
+    ///
+    /// Empty unless the line was shaped with [`ShapeOptions::highlight_replacement_char`].
+    pub fn replacement_char_positions(&self) -> Vec<usize> {
+        let mut positions: Vec<usize> = self
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+            .filter(|glyph| glyph.replacement_char)
+            .map(|glyph| glyph.start)
+            .collect();
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+
+    /// Get the byte ranges of text whose requested family was missing, in logical order with
+    /// adjacent ranges merged
+    ///
+    /// Empty unless the line was shaped with [`MissingFamily::Error`].
+    pub fn missing_family_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+            .filter(|glyph| glyph.missing_family)
+            .map(|glyph| glyph.start..glyph.end)
+            .collect();
+        ranges.sort_unstable_by_key(|range| (range.start, range.end));
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        merged
+    }
+
+    /// Get the byte range and total advance of each cluster, in logical order
+    ///
+    /// Advances are in em units and include letter spacing, so caret positions can be computed
+    /// for any font size by scaling, without laying out the line. Glyphs with a
+    /// [`ShapeGlyph::metrics_opt`] override are still in em units of their own font size.
+    pub fn cluster_advances(&self) -> impl Iterator<Item = (Range<usize>, f32)> {
+        let mut clusters: Vec<(Range<usize>, f32)> = self
+            .spans
+            .iter()
+            .flat_map(|span| span.words.iter())
+            .flat_map(|word| word.glyphs.iter())
+            .map(|glyph| (glyph.start..glyph.end, glyph.x_advance))
+            .collect();
+        clusters.sort_by_key(|(range, _)| (range.start, range.end));
+        clusters.dedup_by(|(range, advance), (prev_range, prev_advance)| {
+            if range == prev_range {
+                *prev_advance += *advance;
+                true
+            } else {
+                false
+            }
+        });
+        clusters.into_iter()
+    }
+
     // A modified version of first part of unicode_bidi::bidi_info::visual_run
     fn adjust_levels(para: &unicode_bidi::Paragraph) -> Vec<unicode_bidi::Level> {
         use unicode_bidi::BidiClass::*;
@@ -1080,6 +1915,19 @@ impl ShapeLine {
         runs
     }
 
+    /// Get the number of runs the line was itemized into when shaping
+    ///
+    /// Lines are itemized by bidi level, so text mixing directions, such as Arabic and Latin,
+    /// has several runs.
+    pub fn run_count(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Returns true if the line was shaped as a single run, see [`Self::run_count`]
+    pub fn is_single_run(&self) -> bool {
+        self.spans.len() <= 1
+    }
+
     pub fn layout(
         &self,
         font_size: f32,
@@ -1110,6 +1958,364 @@ impl ShapeLine {
         align: Option<Align>,
         layout_lines: &mut Vec<LayoutLine>,
         match_mono_width: Option<f32>,
+    ) {
+        self.layout_to_buffer_with_options(
+            scratch,
+            font_size,
+            width_opt,
+            wrap,
+            align,
+            layout_lines,
+            match_mono_width,
+            LayoutOptions::default(),
+        );
+    }
+
+    /// Get the width of the blank words, spaces and tabs alike, at the logical end of a visual line
+    fn trailing_blank_width(
+        &self,
+        visual_line: &VisualLine,
+        font_size: f32,
+        tab_advances: &[TabAdvance],
+    ) -> f32 {
+        let mut width = 0.0;
+        for &(span_index, (starting_word, _), (ending_word, ending_glyph)) in
+            visual_line.ranges.iter().rev()
+        {
+            let span = &self.spans[span_index];
+            let words = &span.words[starting_word..ending_word + usize::from(ending_glyph != 0)];
+            for i in 0..words.len() {
+                // Words of spans against the line direction are stored reversed
+                let word = if span.level.is_rtl() == self.rtl {
+                    &words[words.len() - 1 - i]
+                } else {
+                    &words[i]
+                };
+                if !word.blank {
+                    return width;
+                }
+                for glyph in word.glyphs.iter() {
+                    width += glyph.metrics_opt.map_or(font_size, |x| x.font_size)
+                        * Self::x_advance_of(glyph, tab_advances);
+                }
+            }
+        }
+        width
+    }
+
+    /// Get the width of `glyph` placed `x` pixels from the start of its visual line
+    ///
+    /// The advance of a tab is set to reach the next of the tab stops, or of its uniform stops
+    /// from the tab width, and recorded in `tab_advances`. A drop cap with a font size takes no
+    /// width, it is placed in the space reserved for it.
+    fn glyph_width_at(
+        &self,
+        glyph: &ShapeGlyph,
+        font_size: f32,
+        x: f32,
+        options: &LayoutOptions<'_>,
+        tab_advances: &mut Vec<TabAdvance>,
+    ) -> f32 {
+        if options.is_drop_cap(glyph) {
+            return 0.0;
+        }
+        let Ok(tab_i) = self
+            .tabs
+            .binary_search_by_key(&glyph.start, |(start, _)| *start)
+        else {
+            return glyph.width(font_size);
+        };
+        let glyph_font_size = glyph.metrics_opt.map_or(font_size, |m| m.font_size);
+        let (stop, leader_opt) = match options.tab_stops {
+            Some(tab_stops) => tab_stops.next_stop_leader(x),
+            None => TabStops::uniform(self.tabs[tab_i].1 * glyph_font_size).next_stop_leader(x),
+        };
+        let x_advance = (stop - x) / glyph_font_size;
+        let tab_advance = (glyph.start, x_advance, leader_opt);
+        match tab_advances.binary_search_by_key(&glyph.start, |(start, _, _)| *start) {
+            Ok(i) => tab_advances[i] = tab_advance,
+            Err(i) => tab_advances.insert(i, tab_advance),
+        }
+        glyph_font_size * x_advance
+    }
+
+    /// Get the width of `word` placed `x` pixels from the start of its visual line, see
+    /// [`Self::glyph_width_at`]
+    fn word_width_at(
+        &self,
+        word: &ShapeWord,
+        font_size: f32,
+        x: f32,
+        options: &LayoutOptions<'_>,
+        tab_advances: &mut Vec<TabAdvance>,
+    ) -> f32 {
+        if self.tabs.is_empty() && !word.glyphs.iter().any(|glyph| options.is_drop_cap(glyph)) {
+            return word.width(font_size);
+        }
+        let mut width = 0.0;
+        for glyph in word.glyphs.iter() {
+            width += self.glyph_width_at(glyph, font_size, x + width, options, tab_advances);
+        }
+        width
+    }
+
+    /// Get the advance of `glyph` in units of its font size, as placed by the tab stops if it
+    /// is a tab in `tab_advances`
+    fn x_advance_of(glyph: &ShapeGlyph, tab_advances: &[TabAdvance]) -> f32 {
+        if tab_advances.is_empty() {
+            return glyph.x_advance;
+        }
+        match tab_advances.binary_search_by_key(&glyph.start, |(start, _, _)| *start) {
+            Ok(i) => tab_advances[i].1,
+            Err(_) => glyph.x_advance,
+        }
+    }
+
+    /// Insert leader glyphs after each tab glyph with a leader in `tab_advances`, see
+    /// [`TabLeaders`]
+    fn add_tab_leaders(
+        layout_lines: &mut [LayoutLine],
+        tab_advances: &[TabAdvance],
+        tab_leaders: TabLeaders,
+    ) {
+        for layout_line in layout_lines.iter_mut() {
+            let mut i = 0;
+            while i < layout_line.glyphs.len() {
+                let tab = &layout_line.glyphs[i];
+                i += 1;
+                // Skip inserted glyphs, such as tab markers, which have an empty range
+                if tab.start == tab.end {
+                    continue;
+                }
+                let Ok(tab_i) =
+                    tab_advances.binary_search_by_key(&tab.start, |(start, _, _)| *start)
+                else {
+                    continue;
+                };
+                let Some(leader) = tab_advances[tab_i]
+                    .2
+                    .and_then(|leader| (tab_leaders.leader)(tab.start, leader))
+                else {
+                    continue;
+                };
+                let font_size = leader.metrics_opt.map_or(tab.font_size, |x| x.font_size);
+                let leader_w = font_size * leader.x_advance;
+                if leader_w <= 0.0 {
+                    continue;
+                }
+                // Leaders end at the stop, which is the end of the tab in its direction
+                let count = math::floorf(tab.w / leader_w) as usize;
+                let x = if tab.level.is_rtl() {
+                    tab.x
+                } else {
+                    tab.x + tab.w - count as f32 * leader_w
+                };
+                let (start, line_height_opt, y, level) =
+                    (tab.start, tab.line_height_opt, tab.y, tab.level);
+                for n in 0..count {
+                    let mut glyph = leader.layout(
+                        font_size,
+                        line_height_opt,
+                        x + n as f32 * leader_w,
+                        y,
+                        0.0,
+                        level,
+                    );
+                    glyph.start = start;
+                    glyph.end = start;
+                    layout_line.glyphs.insert(i, glyph);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Insert a marker glyph after each tab glyph, see [`TabVisualization`]
+    fn add_tab_markers(
+        &self,
+        layout_lines: &mut [LayoutLine],
+        tab_visualization: TabVisualization,
+    ) {
+        for layout_line in layout_lines.iter_mut() {
+            let mut i = 0;
+            while i < layout_line.glyphs.len() {
+                let tab = &layout_line.glyphs[i];
+                i += 1;
+                // Skip inserted glyphs, such as tab leaders, which have an empty range
+                if tab.start == tab.end
+                    || self
+                        .tabs
+                        .binary_search_by_key(&tab.start, |(start, _)| *start)
+                        .is_err()
+                {
+                    continue;
+                }
+                let Some(marker) = (tab_visualization.marker)(tab.start) else {
+                    continue;
+                };
+                let font_size = marker.metrics_opt.map_or(tab.font_size, |x| x.font_size);
+                let space = (tab.w - font_size * marker.x_advance).max(0.0);
+                let x = if tab.level.is_rtl() {
+                    tab.x + space
+                } else {
+                    tab.x
+                };
+                let mut glyph =
+                    marker.layout(font_size, tab.line_height_opt, x, tab.y, 0.0, tab.level);
+                glyph.start = tab.start;
+                glyph.end = tab.start;
+                layout_line.glyphs.insert(i, glyph);
+                i += 1;
+            }
+        }
+    }
+
+    /// See [`Self::layout_to_buffer`], with extra [`LayoutOptions`].
+    pub fn layout_to_buffer_with_options(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_size: f32,
+        width_opt: Option<f32>,
+        wrap: Wrap,
+        align: Option<Align>,
+        layout_lines: &mut Vec<LayoutLine>,
+        match_mono_width: Option<f32>,
+        options: LayoutOptions<'_>,
+    ) {
+        if let Some(tab_visualization) = options.tab_visualization {
+            if !self.tabs.is_empty() {
+                self.layout_to_buffer_with_options(
+                    scratch,
+                    font_size,
+                    width_opt,
+                    wrap,
+                    align,
+                    layout_lines,
+                    match_mono_width,
+                    LayoutOptions {
+                        tab_visualization: None,
+                        ..options
+                    },
+                );
+                self.add_tab_markers(layout_lines, tab_visualization);
+                return;
+            }
+        }
+
+        if options.avoid_orphans && matches!(wrap, Wrap::Word | Wrap::WordOrGlyph) {
+            if let Some(width) = width_opt {
+                self.layout_avoiding_orphans(
+                    scratch,
+                    font_size,
+                    width,
+                    wrap,
+                    align,
+                    layout_lines,
+                    match_mono_width,
+                    options,
+                );
+                return;
+            }
+        }
+
+        self.layout_lines_to_buffer(
+            scratch,
+            font_size,
+            width_opt,
+            wrap,
+            align,
+            layout_lines,
+            match_mono_width,
+            options,
+            None,
+        );
+    }
+
+    fn layout_avoiding_orphans(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_size: f32,
+        width: f32,
+        wrap: Wrap,
+        align: Option<Align>,
+        layout_lines: &mut Vec<LayoutLine>,
+        match_mono_width: Option<f32>,
+        options: LayoutOptions<'_>,
+    ) {
+        // Justified lines are as wide as the layout, so measure the lines unjustified
+        let probe_align = match align {
+            Some(Align::Justified) => Some(Align::Left),
+            _ => align,
+        };
+        let mut layout = |layout_lines: &mut Vec<LayoutLine>,
+                          align: Option<Align>,
+                          narrow_opt: Option<(usize, f32)>| {
+            self.layout_lines_to_buffer(
+                scratch,
+                font_size,
+                Some(width),
+                wrap,
+                align,
+                layout_lines,
+                match_mono_width,
+                options,
+                narrow_opt,
+            );
+        };
+
+        layout(layout_lines, probe_align, None);
+        match self.orphan_narrowing(layout_lines, width) {
+            Some(narrow) => {
+                let count = layout_lines.len();
+                layout(layout_lines, align, Some(narrow));
+                if layout_lines.len() != count {
+                    layout(layout_lines, align, None);
+                }
+            }
+            None if probe_align != align => layout(layout_lines, align, None),
+            None => {}
+        }
+    }
+
+    /// Get the index and narrowed width of the line before the last one if the last line holds a
+    /// single word narrower than a third of `width`
+    fn orphan_narrowing(&self, layout_lines: &[LayoutLine], width: f32) -> Option<(usize, f32)> {
+        let [.., previous, last] = layout_lines else {
+            return None;
+        };
+        let line_start = |line: &LayoutLine| line.glyphs.iter().map(|glyph| glyph.start).min();
+        let previous_start = line_start(previous)?;
+        let last_start = line_start(last)?;
+        let words_in = |range: Range<usize>| {
+            self.spans
+                .iter()
+                .flat_map(|span| span.words.iter())
+                .filter(|word| !word.blank)
+                .filter_map(|word| word.glyphs.iter().map(|glyph| glyph.start).min())
+                .filter(|start| range.contains(start))
+                .count()
+        };
+        if words_in(last_start..usize::MAX) != 1
+            || words_in(previous_start..last_start) < 2
+            || last.w * 3.0 > width
+        {
+            return None;
+        }
+        // Lines do not count their trailing blank, so this moves the last word down
+        Some((layout_lines.len() - 2, previous.w - 0.5))
+    }
+
+    fn layout_lines_to_buffer(
+        &self,
+        scratch: &mut ShapeBuffer,
+        font_size: f32,
+        width_opt: Option<f32>,
+        wrap: Wrap,
+        align: Option<Align>,
+        layout_lines: &mut Vec<LayoutLine>,
+        match_mono_width: Option<f32>,
+        options: LayoutOptions<'_>,
+        narrow_opt: Option<(usize, f32)>,
     ) {
         // For each visual line a list of  (span index,  and range of words in that span)
         // Note that a BiDi visual line could have multiple spans or parts of them
@@ -1153,12 +2359,38 @@ impl ShapeLine {
         // let mut current_visual_line: Vec<VlRange> = Vec::with_capacity(1);
         let mut current_visual_line = cached_visual_lines.pop().unwrap_or_default();
 
+        // Tabs are placed while wrapping, from the width of the visual line before them. They are
+        // at the paragraph level (UAX #9 rule L1), so the text before a tab in logical order on
+        // its visual line is also before it visually, whatever the direction of its runs.
+        let mut tab_advances = mem::take(&mut scratch.tab_advances);
+        tab_advances.clear();
+
+        // Width of the start of a visual line that is reserved for a drop cap
+        let indent_of = |visual_line_i: usize| match options.drop_cap {
+            Some(drop_cap) if visual_line_i < usize::from(drop_cap.lines) => drop_cap.width,
+            _ => 0.0,
+        };
+        // Width available to the text of a visual line
+        let limit_of = |visual_line_i: usize| {
+            let limit = width_opt.map_or(f32::INFINITY, |width| width - indent_of(visual_line_i));
+            match narrow_opt {
+                Some((narrow_i, narrow)) if narrow_i == visual_line_i => limit.min(narrow),
+                _ => limit,
+            }
+        };
+
         if wrap == Wrap::None {
             for (span_index, span) in self.spans.iter().enumerate() {
                 let mut word_range_width = 0.;
                 let mut number_of_blanks: u32 = 0;
                 for word in span.words.iter() {
-                    let word_width = word.width(font_size);
+                    let word_width = self.word_width_at(
+                        word,
+                        font_size,
+                        current_visual_line.w + word_range_width,
+                        &options,
+                        &mut tab_advances,
+                    );
                     word_range_width += word_width;
                     if word.blank {
                         number_of_blanks += 1;
@@ -1184,17 +2416,34 @@ impl ShapeLine {
                     // incongruent directions
                     let mut fitting_start = (span.words.len(), 0);
                     for (i, word) in span.words.iter().enumerate().rev() {
-                        let word_width = word.width(font_size);
+                        if word.blank
+                            && options.trim_wrapped_leading_whitespace
+                            && !visual_lines.is_empty()
+                            && current_visual_line.ranges.is_empty()
+                            && fitting_start == (i + 1, 0)
+                        {
+                            // Leading whitespace of a continuation line
+                            fitting_start = (i, 0);
+                            continue;
+                        }
+
+                        let word_width = self.word_width_at(
+                            word,
+                            font_size,
+                            current_visual_line.w + word_range_width,
+                            &options,
+                            &mut tab_advances,
+                        );
 
                         // Addition in the same order used to compute the final width, so that
                         // relayouts with that width as the `line_width` will produce the same
                         // wrapping results.
                         if current_visual_line.w + (word_range_width + word_width)
-                            <= width_opt.unwrap_or(f32::INFINITY)
+                            <= limit_of(visual_lines.len())
                             // Include one blank word over the width limit since it won't be
                             // counted in the final width
                             || (word.blank
-                                && (current_visual_line.w + word_range_width) <= width_opt.unwrap_or(f32::INFINITY))
+                                && (current_visual_line.w + word_range_width) <= limit_of(visual_lines.len()))
                         {
                             // fits
                             if word.blank {
@@ -1205,12 +2454,12 @@ impl ShapeLine {
                             continue;
                         } else if wrap == Wrap::Glyph
                             // Make sure that the word is able to fit on it's own line, if not, fall back to Glyph wrapping.
-                            || (wrap == Wrap::WordOrGlyph && word_width > width_opt.unwrap_or(f32::INFINITY))
+                            || (wrap == Wrap::WordOrGlyph && word_width > limit_of(visual_lines.len()))
                         {
                             // Commit the current line so that the word starts on the next line.
                             if word_range_width > 0.
                                 && wrap == Wrap::WordOrGlyph
-                                && word_width > width_opt.unwrap_or(f32::INFINITY)
+                                && word_width > limit_of(visual_lines.len())
                             {
                                 add_to_visual_line(
                                     &mut current_visual_line,
@@ -1231,9 +2480,15 @@ impl ShapeLine {
                             }
 
                             for (glyph_i, glyph) in word.glyphs.iter().enumerate().rev() {
-                                let glyph_width = glyph.width(font_size);
+                                let glyph_width = self.glyph_width_at(
+                                    glyph,
+                                    font_size,
+                                    current_visual_line.w + word_range_width,
+                                    &options,
+                                    &mut tab_advances,
+                                );
                                 if current_visual_line.w + (word_range_width + glyph_width)
-                                    <= width_opt.unwrap_or(f32::INFINITY)
+                                    <= limit_of(visual_lines.len())
                                 {
                                     word_range_width += glyph_width;
                                     continue;
@@ -1251,7 +2506,14 @@ impl ShapeLine {
                                         cached_visual_lines.pop().unwrap_or_default();
 
                                     number_of_blanks = 0;
-                                    word_range_width = glyph_width;
+                                    // Tabs are placed again at the start of the new line
+                                    word_range_width = self.glyph_width_at(
+                                        glyph,
+                                        font_size,
+                                        0.0,
+                                        &options,
+                                        &mut tab_advances,
+                                    );
                                     fitting_start = (i, glyph_i + 1);
                                 }
                             }
@@ -1297,7 +2559,14 @@ impl ShapeLine {
                                 word_range_width = 0.;
                                 fitting_start = (i, 0);
                             } else {
-                                word_range_width = word_width;
+                                // Tabs in the word are placed again if it starts a new line
+                                word_range_width = self.word_width_at(
+                                    word,
+                                    font_size,
+                                    current_visual_line.w,
+                                    &options,
+                                    &mut tab_advances,
+                                );
                                 fitting_start = (i + 1, 0);
                             }
                         }
@@ -1312,15 +2581,33 @@ impl ShapeLine {
                     );
                 } else {
                     // congruent direction
+                    let hyphenation_opt = options.hyphenation.filter(|_| !self.rtl);
                     let mut fitting_start = (0, 0);
                     for (i, word) in span.words.iter().enumerate() {
-                        let word_width = word.width(font_size);
+                        if word.blank
+                            && options.trim_wrapped_leading_whitespace
+                            && !visual_lines.is_empty()
+                            && current_visual_line.ranges.is_empty()
+                            && fitting_start == (i, 0)
+                        {
+                            // Leading whitespace of a continuation line
+                            fitting_start = (i + 1, 0);
+                            continue;
+                        }
+
+                        let word_width = self.word_width_at(
+                            word,
+                            font_size,
+                            current_visual_line.w + word_range_width,
+                            &options,
+                            &mut tab_advances,
+                        );
                         if current_visual_line.w + (word_range_width + word_width)
-                            <= width_opt.unwrap_or(f32::INFINITY)
+                            <= limit_of(visual_lines.len())
                             // Include one blank word over the width limit since it won't be
                             // counted in the final width.
                             || (word.blank
-                                && (current_visual_line.w + word_range_width) <= width_opt.unwrap_or(f32::INFINITY))
+                                && (current_visual_line.w + word_range_width) <= limit_of(visual_lines.len()))
                         {
                             // fits
                             if word.blank {
@@ -1331,12 +2618,12 @@ impl ShapeLine {
                             continue;
                         } else if wrap == Wrap::Glyph
                             // Make sure that the word is able to fit on it's own line, if not, fall back to Glyph wrapping.
-                            || (wrap == Wrap::WordOrGlyph && word_width > width_opt.unwrap_or(f32::INFINITY))
+                            || (wrap == Wrap::WordOrGlyph && word_width > limit_of(visual_lines.len()))
                         {
                             // Commit the current line so that the word starts on the next line.
                             if word_range_width > 0.
                                 && wrap == Wrap::WordOrGlyph
-                                && word_width > width_opt.unwrap_or(f32::INFINITY)
+                                && word_width > limit_of(visual_lines.len())
                             {
                                 add_to_visual_line(
                                     &mut current_visual_line,
@@ -1357,9 +2644,15 @@ impl ShapeLine {
                             }
 
                             for (glyph_i, glyph) in word.glyphs.iter().enumerate() {
-                                let glyph_width = glyph.width(font_size);
+                                let glyph_width = self.glyph_width_at(
+                                    glyph,
+                                    font_size,
+                                    current_visual_line.w + word_range_width,
+                                    &options,
+                                    &mut tab_advances,
+                                );
                                 if current_visual_line.w + (word_range_width + glyph_width)
-                                    <= width_opt.unwrap_or(f32::INFINITY)
+                                    <= limit_of(visual_lines.len())
                                 {
                                     word_range_width += glyph_width;
                                     continue;
@@ -1377,15 +2670,51 @@ impl ShapeLine {
                                         cached_visual_lines.pop().unwrap_or_default();
 
                                     number_of_blanks = 0;
-                                    word_range_width = glyph_width;
+                                    // Tabs are placed again at the start of the new line
+                                    word_range_width = self.glyph_width_at(
+                                        glyph,
+                                        font_size,
+                                        0.0,
+                                        &options,
+                                        &mut tab_advances,
+                                    );
                                     fitting_start = (i, glyph_i);
                                 }
                             }
                         } else {
                             // Wrap::Word, Wrap::WordOrGlyph
 
+                            // Hyphenate the word to fill the rest of the current line
+                            let hyphen_break_opt = match hyphenation_opt {
+                                Some(hyphenation) if !word.blank => hyphenation.find_break(
+                                    word,
+                                    0,
+                                    font_size,
+                                    limit_of(visual_lines.len())
+                                        - current_visual_line.w
+                                        - word_range_width,
+                                ),
+                                _ => None,
+                            };
+                            let hyphenated = hyphen_break_opt.is_some();
+                            if let Some((glyph_i, width, hyphen)) = hyphen_break_opt {
+                                add_to_visual_line(
+                                    &mut current_visual_line,
+                                    span_index,
+                                    fitting_start,
+                                    (i, glyph_i),
+                                    word_range_width + width + hyphen.0.width(font_size),
+                                    number_of_blanks,
+                                );
+                                current_visual_line.hyphen_opt = Some(hyphen);
+                                visual_lines.push(current_visual_line);
+                                current_visual_line = cached_visual_lines.pop().unwrap_or_default();
+                                number_of_blanks = 0;
+                                word_range_width = word_width - width;
+                                fitting_start = (i, glyph_i);
+                            }
                             // If we had a previous range, commit that line before the next word.
-                            if word_range_width > 0. {
+                            else if word_range_width > 0. {
                                 // Current word causing a wrap is not whitespace, so we ignore the
                                 // previous word if it's a whitespace.
                                 let trailing_blank = i > 0 && span.words[i - 1].blank;
@@ -1416,13 +2745,53 @@ impl ShapeLine {
                                 number_of_blanks = 0;
                             }
 
-                            if word.blank {
+                            if hyphenated {
+                                // The rest of the word already starts the new line
+                            } else if word.blank {
                                 word_range_width = 0.;
                                 fitting_start = (i + 1, 0);
                             } else {
-                                word_range_width = word_width;
+                                // Tabs in the word are placed again if it starts a new line
+                                word_range_width = self.word_width_at(
+                                    word,
+                                    font_size,
+                                    current_visual_line.w,
+                                    &options,
+                                    &mut tab_advances,
+                                );
                                 fitting_start = (i, 0);
                             }
+
+                            // Hyphenate the rest of the word while it is wider than a line
+                            while let Some(hyphenation) =
+                                hyphenation_opt.filter(|_| !word.blank && fitting_start.0 == i)
+                            {
+                                if word_range_width <= limit_of(visual_lines.len()) {
+                                    break;
+                                }
+                                let Some((glyph_i, width, hyphen)) = hyphenation.find_break(
+                                    word,
+                                    fitting_start.1,
+                                    font_size,
+                                    limit_of(visual_lines.len()),
+                                ) else {
+                                    break;
+                                };
+                                add_to_visual_line(
+                                    &mut current_visual_line,
+                                    span_index,
+                                    fitting_start,
+                                    (i, glyph_i),
+                                    width + hyphen.0.width(font_size),
+                                    number_of_blanks,
+                                );
+                                current_visual_line.hyphen_opt = Some(hyphen);
+                                visual_lines.push(current_visual_line);
+                                current_visual_line = cached_visual_lines.pop().unwrap_or_default();
+                                number_of_blanks = 0;
+                                word_range_width -= width;
+                                fitting_start = (i, glyph_i);
+                            }
                         }
                     }
                     add_to_visual_line(
@@ -1445,7 +2814,7 @@ impl ShapeLine {
         }
 
         // Create the LayoutLines using the ranges inside visual lines
-        let align = align.unwrap_or({
+        let default_align = align.unwrap_or({
             if self.rtl {
                 Align::Right
             } else {
@@ -1457,8 +2826,8 @@ impl ShapeLine {
             Some(width) => width,
             None => {
                 let mut width: f32 = 0.0;
-                for visual_line in visual_lines.iter() {
-                    width = width.max(visual_line.w);
+                for (index, visual_line) in visual_lines.iter().enumerate() {
+                    width = width.max(indent_of(index) + visual_line.w);
                 }
                 width
             }
@@ -1475,17 +2844,40 @@ impl ShapeLine {
             let mut glyphs = cached_glyph_sets
                 .pop()
                 .unwrap_or_else(|| Vec::with_capacity(1));
-            let mut x = start_x;
+            let indent = indent_of(index);
+            let available_width = line_width - indent;
+            let mut x = if self.rtl {
+                start_x - indent
+            } else {
+                start_x + indent
+            };
             let mut y = 0.;
+            // Ascent and descent of each glyph, to align them vertically
+            let mut extents = Vec::new();
             let mut max_ascent: f32 = 0.;
             let mut max_descent: f32 = 0.;
+            let mut max_line_gap: f32 = 0.;
+            // Glyphs of a drop cap with a font size, placed from the starting edge of the line
+            let mut drop_cap_glyphs = Vec::new();
+            let mut drop_cap_x = start_x;
+            let align = options
+                .line_aligns
+                .get(index)
+                .copied()
+                .flatten()
+                .unwrap_or(default_align);
+            let aligned_w = if options.ignore_trailing_whitespace_in_align {
+                visual_line.w - self.trailing_blank_width(visual_line, font_size, &tab_advances)
+            } else {
+                visual_line.w
+            };
             let alignment_correction = match (align, self.rtl) {
-                (Align::Left, true) => line_width - visual_line.w,
+                (Align::Left, true) => available_width - aligned_w,
                 (Align::Left, false) => 0.,
                 (Align::Right, true) => 0.,
-                (Align::Right, false) => line_width - visual_line.w,
-                (Align::Center, _) => (line_width - visual_line.w) / 2.0,
-                (Align::End, _) => line_width - visual_line.w,
+                (Align::Right, false) => available_width - aligned_w,
+                (Align::Center, _) => (available_width - aligned_w) / 2.0,
+                (Align::End, _) => available_width - aligned_w,
                 (Align::Justified, _) => 0.,
             };
 
@@ -1515,7 +2907,7 @@ impl ShapeLine {
                 // Don't justify the last line in a paragraph.
                 && index != number_of_visual_lines - 1
             {
-                (line_width - visual_line.w) / visual_line.spaces as f32
+                (available_width - visual_line.w) / visual_line.spaces as f32
             } else {
                 0.
             };
@@ -1536,6 +2928,35 @@ impl ShapeLine {
                         };
 
                         for glyph in included_glyphs {
+                            if let Some(drop_cap_size) = options
+                                .drop_cap
+                                .and_then(|drop_cap| drop_cap.font_size_opt)
+                                .filter(|_| options.is_drop_cap(glyph))
+                            {
+                                let x_advance = (drop_cap_size * glyph.x_advance).round();
+                                if self.rtl {
+                                    drop_cap_x -= x_advance;
+                                }
+                                drop_cap_glyphs.push((glyphs.len(), drop_cap_size * glyph.ascent));
+                                glyphs.push(glyph.layout(
+                                    drop_cap_size,
+                                    None,
+                                    drop_cap_x,
+                                    0.0,
+                                    x_advance,
+                                    span.level,
+                                ));
+                                if !self.rtl {
+                                    drop_cap_x += x_advance;
+                                }
+                                // Keep the extents in step with the glyphs, the drop cap is not
+                                // aligned with the line
+                                if options.vertical_align != VerticalAlign::Baseline {
+                                    extents.push((0.0, 0.0));
+                                }
+                                continue;
+                            }
+
                             // Use overridden font size
                             let font_size = glyph.metrics_opt.map_or(font_size, |x| x.font_size);
 
@@ -1559,7 +2980,8 @@ impl ShapeLine {
                                 _ => font_size,
                             };
 
-                            let mut x_advance = glyph_font_size * glyph.x_advance
+                            let mut x_advance = glyph_font_size
+                                * Self::x_advance_of(glyph, &tab_advances)
                                 + if word.blank {
                                     justification_expansion
                                 } else {
@@ -1582,8 +3004,14 @@ impl ShapeLine {
                                 x += x_advance;
                             }
                             y += y_advance;
-                            max_ascent = max_ascent.max(glyph_font_size * glyph.ascent);
-                            max_descent = max_descent.max(glyph_font_size * glyph.descent);
+                            let ascent = glyph_font_size * glyph.ascent;
+                            let descent = glyph_font_size * glyph.descent;
+                            if options.vertical_align != VerticalAlign::Baseline {
+                                extents.push((ascent, descent));
+                            }
+                            max_ascent = max_ascent.max(ascent);
+                            max_descent = max_descent.max(descent);
+                            max_line_gap = max_line_gap.max(glyph_font_size * glyph.line_gap);
                         }
                     }
                 }
@@ -1600,6 +3028,47 @@ impl ShapeLine {
                 }
             }
 
+            // Hyphenation is only done in LTR lines, so the hyphen goes on the right
+            if let Some((hyphen, offset)) = &visual_line.hyphen_opt {
+                // The same width as used to find the break
+                let x_advance = hyphen.width(font_size);
+                let font_size = hyphen.metrics_opt.map_or(font_size, |x| x.font_size);
+                let mut glyph = hyphen.layout(
+                    font_size,
+                    hyphen.metrics_opt.map(|x| x.line_height),
+                    x,
+                    y,
+                    x_advance,
+                    unicode_bidi::Level::ltr(),
+                );
+                glyph.start = *offset;
+                glyph.end = *offset;
+                glyphs.push(glyph);
+                x += x_advance;
+                let ascent = font_size * hyphen.ascent;
+                let descent = font_size * hyphen.descent;
+                if options.vertical_align != VerticalAlign::Baseline {
+                    extents.push((ascent, descent));
+                }
+                max_ascent = max_ascent.max(ascent);
+                max_descent = max_descent.max(descent);
+                max_line_gap = max_line_gap.max(font_size * hyphen.line_gap);
+            }
+
+            for (glyph, (ascent, descent)) in glyphs.iter_mut().zip(extents) {
+                glyph.y += options
+                    .vertical_align
+                    .offset(ascent, descent, max_ascent, max_descent);
+            }
+
+            // Hang the drop cap from the top of the first line, down over the lines after it
+            let drop_cap_ascent = drop_cap_glyphs
+                .iter()
+                .fold(0.0f32, |max, &(_, ascent)| max.max(ascent));
+            for &(i, _) in drop_cap_glyphs.iter() {
+                glyphs[i].y = drop_cap_ascent - max_ascent;
+            }
+
             let mut line_height_opt: Option<f32> = None;
             for glyph in glyphs.iter() {
                 if let Some(glyph_line_height) = glyph.line_height_opt {
@@ -1614,12 +3083,13 @@ impl ShapeLine {
                 w: if align != Align::Justified {
                     visual_line.w
                 } else if self.rtl {
-                    start_x - x
+                    start_x - indent - x
                 } else {
-                    x
+                    x - indent
                 },
                 max_ascent,
                 max_descent,
+                max_line_gap,
                 line_height_opt,
                 glyphs,
             });
@@ -1631,12 +3101,23 @@ impl ShapeLine {
                 w: 0.0,
                 max_ascent: 0.0,
                 max_descent: 0.0,
+                max_line_gap: 0.0,
                 line_height_opt: self.metrics_opt.map(|x| x.line_height),
                 glyphs: Default::default(),
             });
         }
 
+        if let Some(tab_leaders) = options.tab_leaders {
+            if tab_advances
+                .iter()
+                .any(|(_, _, leader_opt)| leader_opt.is_some())
+            {
+                Self::add_tab_leaders(layout_lines, &tab_advances, tab_leaders);
+            }
+        }
+
         // Restore the buffer to the scratch set to prevent reallocations.
+        scratch.tab_advances = tab_advances;
         scratch.visual_lines = visual_lines;
         scratch.visual_lines.append(&mut cached_visual_lines);
         scratch.cached_visual_lines = cached_visual_lines;
@@ -2,7 +2,7 @@
 use alloc::{string::String, vec::Vec};
 use core::ops::Range;
 
-use crate::{AttrsOwned, HashMap, ShapeGlyph};
+use crate::{AttrsOwned, FamilyOwned, HashMap, ShapeGlyph};
 
 /// Key for caching shape runs.
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
@@ -10,6 +10,12 @@ pub struct ShapeRunKey {
     pub text: String,
     pub default_attrs: AttrsOwned,
     pub attrs_spans: Vec<(Range<usize>, AttrsOwned)>,
+    /// True if the run starts the line, so a mark at its start has no base
+    pub beginning_of_text: bool,
+    /// True if a dotted circle may be inserted before a mark without a base
+    pub insert_dotted_circle: bool,
+    /// Families tried before the script fallbacks, see [`crate::ShapeOptions::fallback_families`]
+    pub fallback_families: Vec<FamilyOwned>,
 }
 
 /// A helper structure for caching shape runs.
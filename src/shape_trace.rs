@@ -0,0 +1,183 @@
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{AttrsList, FeatureTag, FontSystem, ShapeLine, Shaping};
+
+/// The `OpenType` layout table a traced feature comes from
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShapeTraceTable {
+    /// Glyph substitution, such as ligatures
+    Gsub,
+    /// Glyph positioning, such as kerning and mark placement
+    Gpos,
+}
+
+/// A glyph of a traced run, in font units
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ShapeTraceGlyph {
+    pub glyph_id: u16,
+    pub x_advance: i32,
+    pub x_offset: i32,
+    pub y_offset: i32,
+}
+
+/// A feature that changed the shaping of a run, see [`ShapeLine::build_with_trace`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShapeTraceStep {
+    /// Byte range of the run in the line
+    pub range: Range<usize>,
+    /// Font the run was shaped with
+    pub font_id: fontdb::ID,
+    /// Table that lists the feature
+    pub table: ShapeTraceTable,
+    /// Tag of the feature
+    pub feature: FeatureTag,
+    /// Glyphs of the run shaped with the feature disabled
+    pub glyphs_without: Vec<ShapeTraceGlyph>,
+    /// Glyphs of the run shaped with the feature, as in the shaped line
+    pub glyphs_with: Vec<ShapeTraceGlyph>,
+}
+
+/// The features that changed the shaping of a line, see [`ShapeLine::build_with_trace`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ShapeTrace {
+    /// Steps in the order of the runs in the line, then of the features in the font
+    pub steps: Vec<ShapeTraceStep>,
+}
+
+impl ShapeTrace {
+    /// Check if `feature` changed the shaping of any run
+    pub fn applied(&self, feature: FeatureTag) -> bool {
+        self.steps.iter().any(|step| step.feature == feature)
+    }
+
+    /// Reshape `range` of `line` with `font_id`, once with all features and once with each
+    /// feature of the font disabled, and record the features that made a difference
+    fn trace_run(
+        &mut self,
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        range: Range<usize>,
+        font_id: fontdb::ID,
+        rtl: bool,
+    ) {
+        let Some(font) = font_system.get_font(font_id) else {
+            return;
+        };
+        let face = font.rustybuzz();
+        let features: Vec<rustybuzz::Feature> = attrs_list
+            .get_span(range.start)
+            .font_features
+            .features
+            .iter()
+            .map(|feature| {
+                rustybuzz::Feature::new(
+                    rustybuzz::ttf_parser::Tag::from_bytes(feature.tag.as_bytes()),
+                    feature.value,
+                    0..usize::MAX,
+                )
+            })
+            .collect();
+        let shape = |disabled: Option<rustybuzz::ttf_parser::Tag>| {
+            let mut buffer = rustybuzz::UnicodeBuffer::new();
+            buffer.push_str(&line[range.clone()]);
+            buffer.set_direction(if rtl {
+                rustybuzz::Direction::RightToLeft
+            } else {
+                rustybuzz::Direction::LeftToRight
+            });
+            buffer.guess_segment_properties();
+            let mut features = features.clone();
+            if let Some(tag) = disabled {
+                features.push(rustybuzz::Feature::new(tag, 0, 0..usize::MAX));
+            }
+            let glyph_buffer = rustybuzz::shape(face, &features, buffer);
+            glyph_buffer
+                .glyph_infos()
+                .iter()
+                .zip(glyph_buffer.glyph_positions())
+                .map(|(info, pos)| ShapeTraceGlyph {
+                    glyph_id: info.glyph_id as u16,
+                    x_advance: pos.x_advance,
+                    x_offset: pos.x_offset,
+                    y_offset: pos.y_offset,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let glyphs_with = shape(None);
+        let tables = [
+            (ShapeTraceTable::Gsub, face.tables().gsub),
+            (ShapeTraceTable::Gpos, face.tables().gpos),
+        ];
+        for (table, layout_table) in tables {
+            let mut tags = Vec::new();
+            for feature in layout_table.into_iter().flat_map(|table| table.features) {
+                if !tags.contains(&feature.tag) {
+                    tags.push(feature.tag);
+                }
+            }
+            for tag in tags {
+                let glyphs_without = shape(Some(tag));
+                if glyphs_without != glyphs_with {
+                    self.steps.push(ShapeTraceStep {
+                        range: range.clone(),
+                        font_id,
+                        table,
+                        feature: FeatureTag::new(&tag.to_bytes()),
+                        glyphs_without,
+                        glyphs_with: glyphs_with.clone(),
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl ShapeLine {
+    /// Shape a line like [`Self::new`], and trace the `OpenType` features that changed the
+    /// shaping of each run
+    ///
+    /// Each run of glyphs shaped with the same font inside a word is shaped again with each
+    /// `GSUB` and `GPOS` feature of the font disabled in turn. A feature is recorded when its
+    /// glyphs differ from the glyphs shaped with all features. This is meant for debugging fonts
+    /// and is slow, since every run is shaped once per feature of its font.
+    pub fn build_with_trace(
+        font_system: &mut FontSystem,
+        line: &str,
+        attrs_list: &AttrsList,
+        shaping: Shaping,
+        tab_width: u16,
+    ) -> (Self, ShapeTrace) {
+        let shape = Self::new(font_system, line, attrs_list, shaping, tab_width);
+        let mut trace = ShapeTrace::default();
+        for span in shape.spans.iter() {
+            for word in span.words.iter().filter(|word| !word.blank) {
+                let mut glyphs = word.glyphs.as_slice();
+                while let Some(first) = glyphs.first() {
+                    let len = glyphs
+                        .iter()
+                        .position(|glyph| glyph.font_id != first.font_id)
+                        .unwrap_or(glyphs.len());
+                    let (run, rest) = glyphs.split_at(len);
+                    glyphs = rest;
+                    let start = run.iter().map(|glyph| glyph.start).min().unwrap_or(0);
+                    let end = run.iter().map(|glyph| glyph.end).max().unwrap_or(start);
+                    trace.trace_run(
+                        font_system,
+                        line,
+                        attrs_list,
+                        start..end,
+                        first.font_id,
+                        span.level.is_rtl(),
+                    );
+                }
+            }
+        }
+        (shape, trace)
+    }
+}
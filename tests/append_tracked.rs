@@ -0,0 +1,80 @@
+use cosmic_text::{
+    Align, AppendError, AppendPolicy, Attrs, AttrsList, BufferLine, Color, LineEnding, Shaping,
+};
+
+fn line(text: &str, attrs: &Attrs, align: Option<Align>) -> BufferLine {
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(attrs),
+        Shaping::Advanced,
+    );
+    line.set_align(align);
+    line
+}
+
+#[test]
+fn append_tracked_reports_range() {
+    let red = Attrs::new().color(Color::rgb(0xFF, 0, 0));
+    let mut first = line("Hello ", &Attrs::new(), None);
+    let second = line("world", &red, None);
+
+    let range = first
+        .append_tracked(second, AppendPolicy::KeepSelf)
+        .unwrap();
+    assert_eq!(range, 6..11);
+    assert_eq!(&first.text()[range.clone()], "world");
+
+    // The defaults of the appended line are kept in a span over its range
+    let spans: Vec<_> = first.attrs_list().spans_iter().collect();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(*spans[0].0, range);
+    assert_eq!(spans[0].1.as_attrs(), red);
+}
+
+#[test]
+fn append_tracked_align_policy() {
+    let attrs = Attrs::new();
+
+    let mut first = line("abc", &attrs, Some(Align::Left));
+    first
+        .append_tracked(
+            line("def", &attrs, Some(Align::Right)),
+            AppendPolicy::KeepSelf,
+        )
+        .unwrap();
+    assert_eq!(first.align(), Some(Align::Left));
+
+    let mut first = line("abc", &attrs, Some(Align::Left));
+    let range = first
+        .append_tracked(
+            line("def", &attrs, Some(Align::Right)),
+            AppendPolicy::KeepOther,
+        )
+        .unwrap();
+    assert_eq!(range, 3..6);
+    assert_eq!(first.align(), Some(Align::Right));
+
+    let mut first = line("abc", &attrs, Some(Align::Left));
+    let err = first
+        .append_tracked(line("def", &attrs, Some(Align::Right)), AppendPolicy::Error)
+        .unwrap_err();
+    assert_eq!(
+        err,
+        AppendError::LayoutMismatch {
+            align: Some(Align::Left),
+            other_align: Some(Align::Right),
+            wrap_width: None,
+            other_wrap_width: None,
+        }
+    );
+    assert_eq!(first.text(), "abc");
+
+    // Matching lines are appended with the error policy
+    let mut first = line("abc", &attrs, Some(Align::Right));
+    assert_eq!(
+        first.append_tracked(line("def", &attrs, Some(Align::Right)), AppendPolicy::Error),
+        Ok(3..6)
+    );
+    assert_eq!(first.text(), "abcdef");
+}
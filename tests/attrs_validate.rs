@@ -0,0 +1,58 @@
+use cosmic_text::{Attrs, AttrsError, AttrsList, Color};
+
+fn list(spans: &[(usize, usize)]) -> AttrsList {
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    for (i, &(start, end)) in spans.iter().enumerate() {
+        let attrs = Attrs::new().color(Color::rgb(i as u8, 0, 0));
+        attrs_list.add_span(start..end, &attrs);
+    }
+    attrs_list
+}
+
+#[test]
+fn attrs_validate_ok() {
+    assert_eq!(list(&[]).validate(""), Ok(()));
+    assert_eq!(list(&[(0, 2), (4, 7)]).validate("abcdéf"), Ok(()));
+}
+
+#[test]
+fn attrs_validate_out_of_bounds() {
+    assert_eq!(
+        list(&[(0, 2), (3, 8)]).validate("abcdef"),
+        Err(AttrsError::OutOfBounds {
+            range: 3..8,
+            len: 6
+        })
+    );
+}
+
+#[test]
+fn attrs_validate_not_char_boundary() {
+    // "é" is two bytes, at 1..3
+    assert_eq!(
+        list(&[(2, 4)]).validate("aéb"),
+        Err(AttrsError::NotCharBoundary {
+            range: 2..4,
+            index: 2
+        })
+    );
+    assert_eq!(
+        list(&[(0, 2)]).validate("aéb"),
+        Err(AttrsError::NotCharBoundary {
+            range: 0..2,
+            index: 2
+        })
+    );
+}
+
+#[test]
+fn attrs_validate_overlapping() {
+    // Overlapping spans replace the overlapped part of earlier spans, so they stay valid
+    let attrs_list = list(&[(0, 4), (2, 6)]);
+    let ranges: Vec<_> = attrs_list
+        .spans_iter()
+        .map(|(range, _)| range.clone())
+        .collect();
+    assert_eq!(ranges, [0..2, 2..6]);
+    assert_eq!(attrs_list.validate("abcdef"), Ok(()));
+}
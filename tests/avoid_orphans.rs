@@ -0,0 +1,57 @@
+use cosmic_text::{
+    Align, Attrs, AttrsList, BufferLine, FontSystem, LayoutLine, LineEnding, Shaping, Wrap,
+};
+
+const TEXT: &str = "The quick brown fox jumps over the lazy dog at the end.";
+
+fn line_texts(text: &str, width: f32, align: Option<Align>, avoid_orphans: bool) -> Vec<&str> {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_align(align);
+    line.set_avoid_orphans(avoid_orphans);
+    let layout = line.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8);
+    let starts: Vec<usize> = layout.iter().map(start_of).collect();
+    starts
+        .iter()
+        .zip(starts.iter().skip(1).chain([&text.len()]))
+        .map(|(start, end)| text[*start..*end].trim_end())
+        .collect()
+}
+
+fn start_of(line: &LayoutLine) -> usize {
+    line.glyphs.iter().map(|glyph| glyph.start).min().unwrap()
+}
+
+#[test]
+fn avoid_orphans_pulls_word_down() {
+    for align in [None, Some(Align::Center), Some(Align::Justified)] {
+        let plain = line_texts(TEXT, 200.0, align, false);
+        assert_eq!(plain.last(), Some(&"end."));
+
+        let lines = line_texts(TEXT, 200.0, align, true);
+        assert_eq!(lines.len(), plain.len());
+        assert_eq!(lines.last(), Some(&"the end."));
+        assert_eq!(lines[..lines.len() - 2], plain[..plain.len() - 2]);
+    }
+}
+
+#[test]
+fn avoid_orphans_keeps_fitting_layouts() {
+    // The last line already holds several words
+    let plain = line_texts(TEXT, 150.0, None, false);
+    assert_eq!(line_texts(TEXT, 150.0, None, true), plain);
+
+    // The line before the orphan has a single word to give
+    let text = "Incomprehensibilities end.";
+    let plain = line_texts(text, 150.0, None, false);
+    assert_eq!(plain, ["Incomprehensibilities", "end."]);
+    assert_eq!(line_texts(text, 150.0, None, true), plain);
+
+    // A single line has no orphan
+    assert_eq!(line_texts(TEXT, 1000.0, None, true), [TEXT]);
+}
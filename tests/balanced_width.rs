@@ -0,0 +1,50 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn balanced_width_two_lines() {
+    let mut font_system = FontSystem::new();
+    let mut line = line("the quick brown fox jumps over the lazy dog");
+
+    let natural = line.balanced_width(&mut font_system, 14.0, 1, 8);
+    let width = line.balanced_width(&mut font_system, 14.0, 2, 8);
+    assert!(width < natural);
+    assert!(width >= natural / 2.0);
+
+    let layout = line.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8);
+    assert_eq!(layout.len(), 2);
+    // Both lines are close in width, unlike greedy wrapping at the natural width
+    let (first, second) = (layout[0].w, layout[1].w);
+    assert!((first - second).abs() < natural / 4.0);
+    line.reset_layout();
+
+    // A narrower width needs more lines
+    let layout = line.layout(
+        &mut font_system,
+        14.0,
+        Some(width - 1.0),
+        Wrap::Word,
+        None,
+        8,
+    );
+    assert!(layout.len() > 2);
+}
+
+#[test]
+fn balanced_width_infeasible() {
+    let mut font_system = FontSystem::new();
+    let mut line = line("two words");
+
+    // Only two lines are possible, so the closest is wrapping every word
+    let width = line.balanced_width(&mut font_system, 14.0, 5, 8);
+    let layout = line.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8);
+    assert_eq!(layout.len(), 2);
+}
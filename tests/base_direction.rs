@@ -0,0 +1,46 @@
+use cosmic_text::{
+    Attrs, AttrsList, BaseDirection, BufferLine, Direction, FontSystem, LineEnding, Shaping, Wrap,
+};
+
+#[test]
+fn base_direction_auto_fallback() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "123 456",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+
+    // Without strong characters the paragraph is LTR by default
+    assert!(!line.shape(&mut font_system, 8).rtl);
+
+    assert!(line.set_base_direction(BaseDirection::Auto {
+        fallback: Direction::Rtl
+    }));
+    assert!(line.shape(&mut font_system, 8).rtl);
+
+    let layout = line.layout(&mut font_system, 14.0, Some(200.0), Wrap::None, None, 8);
+    assert_eq!(layout.len(), 1);
+    let glyphs = &layout[0].glyphs;
+
+    // Aligned right by default
+    let right = glyphs
+        .iter()
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    assert!((right - 200.0).abs() < 1.0, "right edge {right}");
+
+    // Numbers keep their internal order but are reordered as a whole
+    let mut visual: Vec<(f32, usize)> = glyphs.iter().map(|glyph| (glyph.x, glyph.start)).collect();
+    visual.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let visual: Vec<usize> = visual.into_iter().map(|(_, start)| start).collect();
+    assert_eq!(visual, [4, 5, 6, 3, 0, 1, 2]);
+
+    // A strong character wins over the fallback
+    line.set_text("abc 123", LineEnding::None, AttrsList::new(&Attrs::new()));
+    assert!(!line.shape(&mut font_system, 8).rtl);
+
+    assert!(line.set_base_direction(BaseDirection::Explicit(Direction::Rtl)));
+    assert!(line.shape(&mut font_system, 8).rtl);
+}
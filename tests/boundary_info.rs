@@ -0,0 +1,68 @@
+use cosmic_text::{Attrs, BoundaryInfo, Buffer, Cursor, FontSystem, Metrics, Shaping, Wrap};
+
+#[test]
+fn boundary_info_word_mid_line() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello world e\u{301}\nnext",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    // End of "hello", in the middle of the line
+    assert_eq!(
+        buffer.boundary_info(Cursor::new(0, 5)),
+        BoundaryInfo {
+            grapheme: true,
+            word: true,
+            visual_line: false,
+            paragraph: false,
+        }
+    );
+    // Inside of "hello"
+    assert_eq!(
+        buffer.boundary_info(Cursor::new(0, 2)),
+        BoundaryInfo {
+            grapheme: true,
+            ..BoundaryInfo::default()
+        }
+    );
+    // Between the base and the combining mark
+    assert!(!buffer.boundary_info(Cursor::new(0, 13)).grapheme);
+    // End of the line
+    assert_eq!(
+        buffer.boundary_info(Cursor::new(0, 15)),
+        BoundaryInfo {
+            grapheme: true,
+            word: true,
+            visual_line: true,
+            paragraph: true,
+        }
+    );
+    // Not in the buffer
+    assert_eq!(
+        buffer.boundary_info(Cursor::new(2, 0)),
+        BoundaryInfo::default()
+    );
+}
+
+#[test]
+fn boundary_info_wrap() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_wrap(&mut font_system, Wrap::Word);
+    buffer.set_size(&mut font_system, Some(60.0), None);
+    buffer.set_text(
+        &mut font_system,
+        "hello world again",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    let wrap = buffer.lines[0].wrap_boundaries()[0];
+    let info = buffer.boundary_info(Cursor::new(0, wrap));
+    assert!(info.visual_line);
+    assert!(info.word);
+    assert!(!info.paragraph);
+}
@@ -0,0 +1,55 @@
+use cosmic_text::{
+    Attrs, AttrsList, Buffer, Color, Cursor, FontSystem, LineEnding, Metrics, Shaping,
+};
+
+#[test]
+fn buffer_split_off_preserves_text_and_attrs() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "first line\nsecond line\nthird line",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    // Color "second line" red, across the split point
+    let red = Attrs::new().color(Color::rgb(0xFF, 0, 0));
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(0..11, &red);
+    let ending = buffer.lines[1].ending();
+    buffer.lines[1].set_text("second line", ending, attrs_list);
+
+    buffer.shape_until_scroll(&mut font_system, false);
+    let mut tail = buffer.split_off(Cursor::new(1, 7));
+
+    let texts = |buffer: &Buffer| -> Vec<String> {
+        buffer
+            .lines
+            .iter()
+            .map(|line| line.text().to_string())
+            .collect()
+    };
+    assert_eq!(texts(&buffer), ["first line", "second "]);
+    assert_eq!(texts(&tail), ["line", "third line"]);
+
+    // Endings: the head ends the document now, the tail keeps the original ending
+    assert_eq!(buffer.lines[1].ending(), LineEnding::None);
+    assert_eq!(tail.lines[0].ending(), LineEnding::Lf);
+    assert_eq!(tail.lines[1].ending(), LineEnding::None);
+
+    // Attributes on both sides of the split point are kept
+    assert_eq!(buffer.lines[1].attrs_list().get_span(0), red);
+    assert_eq!(buffer.lines[1].attrs_list().get_span(6), red);
+    assert_eq!(tail.lines[0].attrs_list().get_span(0), red);
+    assert_eq!(tail.lines[0].attrs_list().get_span(3), red);
+    assert_eq!(tail.lines[1].attrs_list().get_span(0), Attrs::new());
+
+    assert_eq!(tail.metrics(), buffer.metrics());
+
+    // Both buffers lay out again from their new lines
+    buffer.shape_until_scroll(&mut font_system, false);
+    tail.shape_until_scroll(&mut font_system, false);
+    assert_eq!(buffer.layout_runs().count(), 2);
+    assert_eq!(tail.layout_runs().count(), 2);
+    assert_eq!(buffer.layout_runs().last().unwrap().glyphs.len(), 7);
+}
@@ -0,0 +1,115 @@
+use cosmic_text::{
+    Affinity, Attrs, AttrsList, BufferLine, CaretMetrics, FontSystem, LineEnding, Rect, Shaping,
+    Wrap,
+};
+
+fn laid_out(font_system: &mut FontSystem, text: &str) -> BufferLine {
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.layout(font_system, 14.0, None, Wrap::None, None, 8);
+    line
+}
+
+#[test]
+fn caret_metrics_centered() {
+    let mut font_system = FontSystem::new();
+    let line = laid_out(&mut font_system, "abc");
+    let glyphs = &line.layout_opt().unwrap()[0].glyphs;
+
+    // A 2px caret is centered on the insertion position
+    let caret = CaretMetrics {
+        width: 2.0,
+        round_position: false,
+    };
+    assert_eq!(
+        line.caret_rect(1, Affinity::Before, 20.0, caret),
+        Some(Rect::new(glyphs[1].x - 1.0, 0.0, 2.0, 20.0))
+    );
+
+    // The end of the line is after the last glyph
+    let rect = line
+        .caret_rect(3, Affinity::Before, 20.0, CaretMetrics::default())
+        .unwrap();
+    assert_eq!(rect.x, glyphs[2].x + glyphs[2].w - 0.5);
+}
+
+#[test]
+fn caret_metrics_pixel_snapped() {
+    let mut font_system = FontSystem::new();
+    // Positions inside of a ligature are interpolated, so they are not on whole pixels
+    let line = laid_out(&mut font_system, "office");
+    let caret = CaretMetrics {
+        width: 2.0,
+        round_position: false,
+    };
+    let snapped = CaretMetrics {
+        width: 2.0,
+        round_position: true,
+    };
+    for index in 0..=line.text().len() {
+        let Some(rect) = line.caret_rect(index, Affinity::Before, 20.0, caret) else {
+            continue;
+        };
+        let snapped = line
+            .caret_rect(index, Affinity::Before, 20.0, snapped)
+            .unwrap();
+        assert_eq!(snapped.x, rect.x.round());
+        assert_eq!(snapped.width, 2.0);
+        assert!((snapped.x - rect.x).abs() <= 0.5);
+    }
+    let rect = line.caret_rect(2, Affinity::Before, 20.0, caret).unwrap();
+    assert_ne!(rect.x, rect.x.round());
+}
+
+#[test]
+fn caret_metrics_rtl_line_end() {
+    let mut font_system = FontSystem::new();
+    let text = "שלום";
+    let line = laid_out(&mut font_system, text);
+    let glyphs = &line.layout_opt().unwrap()[0].glyphs;
+    let left = glyphs.iter().map(|glyph| glyph.x).fold(f32::MAX, f32::min);
+    let right = glyphs
+        .iter()
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(f32::MIN, f32::max);
+
+    // The logical end of RTL text is on its left, and its start on its right
+    let caret = CaretMetrics::default();
+    let end = line
+        .caret_rect(text.len(), Affinity::Before, 20.0, caret)
+        .unwrap();
+    assert_eq!(end.x, left - 0.5);
+    let start = line.caret_rect(0, Affinity::Before, 20.0, caret).unwrap();
+    assert_eq!(start.x, right - 0.5);
+}
+
+#[test]
+fn caret_metrics_wrap_affinity() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "aaaaaaaa",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    // Glyph wrapping breaks inside the word, without whitespace at the boundary
+    let layout = line.layout(&mut font_system, 14.0, Some(50.0), Wrap::Glyph, None, 8);
+    assert_eq!(layout.len(), 2);
+    let wrap = layout[1].glyphs[0].start;
+    let next_x = layout[1].glyphs[0].x;
+
+    // Before the wrap the caret ends the first layout line, after it the caret starts the next
+    let caret = CaretMetrics::default();
+    let before = line
+        .caret_rect(wrap, Affinity::Before, 20.0, caret)
+        .unwrap();
+    assert_eq!(before.y, 0.0);
+    assert!(before.x > next_x);
+    let after = line.caret_rect(wrap, Affinity::After, 20.0, caret).unwrap();
+    assert_eq!(after.y, 20.0);
+    assert_eq!(after.x, next_x - 0.5);
+}
@@ -0,0 +1,83 @@
+use cosmic_text::{Affinity, Attrs, Buffer, CaretMetrics, Cursor, FontSystem, Metrics, Shaping};
+
+fn buffer(font_system: &mut FontSystem, text: &str) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, Some(50.0), None);
+    buffer.set_text(font_system, text, &Attrs::new(), Shaping::Advanced);
+    buffer
+}
+
+fn glyph_x(buffer: &Buffer, index: usize) -> (f32, f32) {
+    buffer
+        .layout_runs()
+        .find_map(|run| {
+            run.glyphs
+                .iter()
+                .find(|glyph| glyph.start == index)
+                .map(|glyph| (glyph.x, run.line_top))
+        })
+        .unwrap()
+}
+
+#[test]
+fn caret_transition_across_wrap() {
+    let mut font_system = FontSystem::new();
+    let buffer = buffer(&mut font_system, "Hello world");
+    assert_eq!(buffer.layout_runs().count(), 2);
+    let caret = CaretMetrics::default();
+
+    let transition = buffer
+        .caret_transition(Cursor::new(0, 1), Cursor::new(0, 8), caret)
+        .unwrap();
+    assert!(transition.crosses_lines);
+    let (x, top) = glyph_x(&buffer, 1);
+    assert_eq!(transition.from.x, x - 0.5);
+    assert_eq!(transition.from.y, top);
+    let (x, top) = glyph_x(&buffer, 8);
+    assert_eq!(transition.to.x, x - 0.5);
+    assert_eq!(transition.to.y, top);
+    assert_eq!(top, 20.0);
+    assert_eq!(transition.to.height, 20.0);
+
+    // Moving within a line does not cross lines
+    let transition = buffer
+        .caret_transition(Cursor::new(0, 7), Cursor::new(0, 9), caret)
+        .unwrap();
+    assert!(!transition.crosses_lines);
+}
+
+#[test]
+fn caret_transition_wrap_affinity() {
+    let mut font_system = FontSystem::new();
+    let buffer = buffer(&mut font_system, "Hello world");
+    let caret = CaretMetrics::default();
+
+    // The same index on both sides of the wrap boundary
+    let transition = buffer
+        .caret_transition(
+            Cursor::new_with_affinity(0, 6, Affinity::Before),
+            Cursor::new_with_affinity(0, 6, Affinity::After),
+            caret,
+        )
+        .unwrap();
+    assert!(transition.crosses_lines);
+    assert_eq!(transition.from.y, 0.0);
+    assert!(transition.from.x > 0.0);
+    assert_eq!(transition.to.y, 20.0);
+    assert_eq!(transition.to.x, glyph_x(&buffer, 6).0 - 0.5);
+}
+
+#[test]
+fn caret_transition_across_lines() {
+    let mut font_system = FontSystem::new();
+    let buffer = buffer(&mut font_system, "Hello world\nabc");
+    let transition = buffer
+        .caret_transition(
+            Cursor::new(0, 0),
+            Cursor::new(1, 3),
+            CaretMetrics::default(),
+        )
+        .unwrap();
+    assert!(transition.crosses_lines);
+    assert_eq!(transition.to.y, 40.0);
+}
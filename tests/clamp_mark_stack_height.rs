@@ -0,0 +1,67 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+fn mark_extent(line: &mut BufferLine, font_system: &mut FontSystem) -> (f32, bool) {
+    let layout = line.layout(font_system, 14.0, None, Wrap::None, None, 8);
+    let glyphs = &layout[0].glyphs;
+    let extent = glyphs
+        .iter()
+        .map(|glyph| (glyph.font_size * glyph.y_offset).abs())
+        .fold(0.0, f32::max);
+    (extent, glyphs.iter().any(|glyph| glyph.marks_clamped))
+}
+
+#[test]
+fn clamp_mark_stack_height() {
+    let mut font_system = FontSystem::new();
+    let text = format!("a{}", "\u{301}".repeat(10));
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+
+    let (unclamped, clamped) = mark_extent(&mut line, &mut font_system);
+    assert!(unclamped > 10.0, "marks are not stacked: {unclamped}");
+    assert!(!clamped);
+
+    assert!(line.set_clamp_mark_stack_height(Some(5.0)));
+    let (extent, clamped) = mark_extent(&mut line, &mut font_system);
+    assert!(extent <= 5.0, "mark extent {extent}");
+    assert!(clamped);
+
+    // Clamping is deterministic
+    let glyphs = line.layout_opt().unwrap()[0].glyphs.clone();
+    line.reset_layout();
+    line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+    let relaid = &line.layout_opt().unwrap()[0].glyphs;
+    assert_eq!(glyphs.len(), relaid.len());
+    for (a, b) in glyphs.iter().zip(relaid) {
+        assert_eq!((a.glyph_id, a.x, a.y_offset), (b.glyph_id, b.x, b.y_offset));
+    }
+}
+
+#[test]
+fn clamp_mark_stack_height_with_letter_spacing() {
+    // Letter spacing gives the marks an advance, they are still found by their cluster
+    let mut font_system = FontSystem::new();
+    let text = format!("a{}b", "\u{301}".repeat(10));
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new().letter_spacing(2.0)),
+        Shaping::Advanced,
+    );
+    let (unclamped, _) = mark_extent(&mut line, &mut font_system);
+    assert!(unclamped > 10.0, "marks are not stacked: {unclamped}");
+
+    assert!(line.set_clamp_mark_stack_height(Some(5.0)));
+    let (extent, clamped) = mark_extent(&mut line, &mut font_system);
+    assert!(extent <= 5.0, "mark extent {extent}");
+    assert!(clamped);
+    let glyphs = &line.layout_opt().unwrap()[0].glyphs;
+    assert!(glyphs.iter().any(|glyph| glyph.start == 0 && glyph.w > 0.0));
+    assert!(glyphs
+        .iter()
+        .any(|glyph| glyph.start > 0 && !glyph.marks_clamped));
+}
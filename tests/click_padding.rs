@@ -0,0 +1,69 @@
+use cosmic_text::{Align, Attrs, Buffer, ClickPadding, Cursor, FontSystem, Metrics, Shaping};
+
+fn hit(text: &str, align: Option<Align>, click_padding: ClickPadding, x: f32) -> usize {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(200.0), None);
+    buffer.set_text(text, &Attrs::new(), Shaping::Advanced);
+    buffer.lines[0].set_align(align);
+    buffer.set_click_padding(click_padding);
+    buffer.shape_until_scroll(false);
+    let cursor: Cursor = buffer.hit(x, 10.0).unwrap();
+    assert_eq!(cursor.line, 0);
+    cursor.index
+}
+
+#[test]
+fn click_padding_left_align() {
+    assert_eq!(hit("hello", None, ClickPadding::Nearest, 150.0), 5);
+    assert_eq!(hit("hello", None, ClickPadding::LineEnd, 150.0), 5);
+    assert_eq!(hit("hello", None, ClickPadding::LineStart, 150.0), 0);
+}
+
+#[test]
+fn click_padding_center_align() {
+    let center = Some(Align::Center);
+    assert_eq!(hit("hello", center, ClickPadding::Nearest, 5.0), 0);
+    assert_eq!(hit("hello", center, ClickPadding::Nearest, 195.0), 5);
+    assert_eq!(hit("hello", center, ClickPadding::LineEnd, 5.0), 0);
+    assert_eq!(hit("hello", center, ClickPadding::LineEnd, 195.0), 5);
+    assert_eq!(hit("hello", center, ClickPadding::LineStart, 195.0), 0);
+}
+
+#[test]
+fn click_padding_right_align() {
+    let right = Some(Align::Right);
+    assert_eq!(hit("hello", right, ClickPadding::Nearest, 5.0), 0);
+    assert_eq!(hit("hello", right, ClickPadding::LineEnd, 5.0), 0);
+    assert_eq!(hit("hello", right, ClickPadding::LineStart, 5.0), 0);
+}
+
+#[test]
+fn click_padding_rtl() {
+    let text = "שלום";
+    // Right aligned by default, so the padding on the left is after the end of the text
+    assert_eq!(hit(text, None, ClickPadding::Nearest, 5.0), text.len());
+    assert_eq!(hit(text, None, ClickPadding::LineEnd, 5.0), text.len());
+    assert_eq!(hit(text, None, ClickPadding::LineStart, 5.0), 0);
+    assert_eq!(
+        hit(text, Some(Align::Left), ClickPadding::Nearest, 195.0),
+        0
+    );
+}
+
+#[test]
+fn click_padding_mixed_direction() {
+    // The RTL word is at the right of the LTR line, its first letter is the rightmost glyph
+    let text = "abc שלום";
+    assert_eq!(hit(text, None, ClickPadding::Nearest, 195.0), 4);
+    assert_eq!(hit(text, None, ClickPadding::LineEnd, 195.0), text.len());
+}
+
+#[test]
+fn click_padding_default_nearest() {
+    assert_eq!(ClickPadding::default(), ClickPadding::Nearest);
+    let mut font_system = FontSystem::new();
+    let buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    assert_eq!(buffer.click_padding(), ClickPadding::Nearest);
+}
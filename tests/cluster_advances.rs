@@ -0,0 +1,49 @@
+use cosmic_text::{Attrs, AttrsList, FontSystem, ShapeLine, Shaping};
+
+fn em_width(line: &ShapeLine) -> f32 {
+    line.spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .map(|word| word.width(1.0))
+        .sum()
+}
+
+#[test]
+fn cluster_advances_sum_to_line_width() {
+    let mut font_system = FontSystem::new();
+    let attrs_list = AttrsList::new(&Attrs::new());
+
+    for text in ["Hello, world!", "e\u{301}ffi tab\tend", "abc שלום def"] {
+        let line = ShapeLine::new(&mut font_system, text, &attrs_list, Shaping::Advanced, 8);
+        let clusters: Vec<_> = line.cluster_advances().collect();
+
+        // Clusters cover the text in logical order
+        let mut end = 0;
+        for (range, advance) in clusters.iter() {
+            assert_eq!(range.start, end, "{text:?}");
+            assert!(*advance >= 0.0, "{text:?}");
+            end = range.end;
+        }
+        assert_eq!(end, text.len(), "{text:?}");
+
+        let sum: f32 = clusters.iter().map(|(_, advance)| advance).sum();
+        assert!((sum - em_width(&line)).abs() < 1e-4, "{text:?}");
+    }
+}
+
+#[test]
+fn cluster_advances_merge_cluster_glyphs() {
+    let mut font_system = FontSystem::new();
+    let attrs_list = AttrsList::new(&Attrs::new());
+
+    // The base and the combining mark are one cluster
+    let line = ShapeLine::new(
+        &mut font_system,
+        "e\u{301}x",
+        &attrs_list,
+        Shaping::Advanced,
+        8,
+    );
+    let ranges: Vec<_> = line.cluster_advances().map(|(range, _)| range).collect();
+    assert_eq!(ranges, [0..3, 3..4]);
+}
@@ -0,0 +1,34 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping};
+
+#[test]
+fn cluster_at_cursor_emoji() {
+    let mut font_system = FontSystem::new();
+    let text = "a👍🏽b";
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+
+    // Not shaped yet
+    assert_eq!(line.cluster_at_cursor(0), None);
+
+    line.shape(&mut font_system, 8);
+
+    // Every byte of the emoji sequence resolves to the whole cluster
+    let emoji_range = 1..text.len() - 1;
+    for index in emoji_range.clone() {
+        let info = line.cluster_at_cursor(index).unwrap();
+        assert_eq!(info.range, emoji_range);
+        assert_eq!(info.text, "👍🏽");
+        assert!(!info.glyph_ids.is_empty());
+    }
+
+    let info = line.cluster_at_cursor(0).unwrap();
+    assert_eq!(info.range, 0..1);
+    assert_eq!(info.text, "a");
+    assert_eq!(info.glyph_ids.len(), 1);
+
+    assert_eq!(line.cluster_at_cursor(text.len()), None);
+}
@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, AttrsList, Color, FontSystem, ShapeLine, Shaping, Wrap};
+
+#[test]
+fn color_runs_boundaries() {
+    let mut font_system = FontSystem::new();
+    let red = Color::rgb(0xFF, 0, 0);
+    let blue = Color::rgb(0, 0, 0xFF);
+    let default_color = Color::rgb(0, 0, 0);
+
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(2..4, &Attrs::new().color(red));
+    attrs_list.add_span(4..5, &Attrs::new().color(blue));
+    attrs_list.add_span(6..8, &Attrs::new().color(default_color));
+
+    let line = ShapeLine::new(
+        &mut font_system,
+        "aabbcdee",
+        &attrs_list,
+        Shaping::Advanced,
+        8,
+    );
+    let layout = line.layout(14.0, None, Wrap::None, None, None);
+
+    let runs: Vec<_> = layout[0]
+        .color_runs(default_color)
+        .map(|(color, glyphs)| {
+            (
+                color,
+                glyphs.first().unwrap().start,
+                glyphs.last().unwrap().end,
+            )
+        })
+        .collect();
+    // The explicit default color merges with the unstyled glyph before it
+    assert_eq!(
+        runs,
+        vec![
+            (default_color, 0, 2),
+            (red, 2, 4),
+            (blue, 4, 5),
+            (default_color, 5, 8),
+        ]
+    );
+}
@@ -0,0 +1,38 @@
+use cosmic_text::{Attrs, AttrsList, Color, FontSystem, ShapeLine, Shaping, Wrap};
+
+const UNDERLINE: usize = 1;
+
+#[test]
+fn decoration_runs_join_colors() {
+    let mut font_system = FontSystem::new();
+
+    // Two underlined spans with different colors, then plain text
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(
+        0..4,
+        &Attrs::new()
+            .color(Color::rgb(0xFF, 0, 0))
+            .metadata(UNDERLINE),
+    );
+    attrs_list.add_span(
+        4..8,
+        &Attrs::new()
+            .color(Color::rgb(0, 0, 0xFF))
+            .metadata(UNDERLINE),
+    );
+
+    let line = ShapeLine::new(
+        &mut font_system,
+        "linkpath rest",
+        &attrs_list,
+        Shaping::Advanced,
+        8,
+    );
+    let layout = line.layout(14.0, None, Wrap::None, None, None);
+
+    let runs: Vec<_> = layout[0]
+        .decoration_runs(|glyph| glyph.metadata == UNDERLINE)
+        .map(|glyphs| (glyphs.first().unwrap().start, glyphs.last().unwrap().end))
+        .collect();
+    assert_eq!(runs, vec![(0, 8)]);
+}
@@ -0,0 +1,59 @@
+use cosmic_text::{Attrs, Buffer, Cursor, Edit, Editor, FontSystem, Metrics, Shaping};
+
+fn buffer(font_system: &mut FontSystem) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, "one\ntwo", &Attrs::new(), Shaping::Advanced);
+    buffer
+}
+
+#[test]
+fn default_shaping_new_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system);
+    assert_eq!(buffer.default_shaping(), Shaping::Advanced);
+    buffer.set_default_shaping(&mut font_system, Shaping::Basic, false);
+    assert_eq!(buffer.default_shaping(), Shaping::Basic);
+    // Existing lines are left alone
+    assert_eq!(buffer.lines[0].shaping(), Shaping::Advanced);
+
+    // Lines added past the end use the default
+    let mut editor = Editor::new(&mut buffer);
+    editor.insert_at(Cursor::new(3, 0), "four", None);
+    drop(editor);
+    assert_eq!(buffer.lines.len(), 4);
+    assert_eq!(buffer.lines[1].shaping(), Shaping::Advanced);
+    assert_eq!(buffer.lines[2].shaping(), Shaping::Basic);
+    assert_eq!(buffer.lines[3].shaping(), Shaping::Basic);
+
+    // Lines split from a line keep its shaping
+    let mut editor = Editor::new(&mut buffer);
+    editor.insert_at(Cursor::new(0, 3), "\nnew", None);
+    drop(editor);
+    assert_eq!(buffer.lines[1].text(), "new");
+    assert_eq!(buffer.lines[1].shaping(), Shaping::Advanced);
+
+    let tail = buffer.split_off(Cursor::new(10, 0));
+    assert_eq!(tail.lines[0].shaping(), Shaping::Basic);
+}
+
+#[test]
+fn default_shaping_update_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system);
+    buffer.set_default_shaping(&mut font_system, Shaping::Basic, true);
+    for line in buffer.lines.iter() {
+        assert_eq!(line.shaping(), Shaping::Basic);
+        assert!(line.shape_opt().is_some());
+    }
+
+    // Without updating, lines keep their shaping
+    buffer.set_default_shaping(&mut font_system, Shaping::Advanced, false);
+    assert_eq!(buffer.lines[0].shaping(), Shaping::Basic);
+
+    // Only lines using the previous default are switched
+    buffer.lines[1].set_shaping(Shaping::Advanced);
+    buffer.set_default_shaping(&mut font_system, Shaping::Basic, false);
+    buffer.set_default_shaping(&mut font_system, Shaping::Advanced, true);
+    assert_eq!(buffer.lines[0].shaping(), Shaping::Advanced);
+    assert_eq!(buffer.lines[1].shaping(), Shaping::Advanced);
+}
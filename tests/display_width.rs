@@ -0,0 +1,44 @@
+use cosmic_text::{fontdb, Attrs, AttrsList, BufferLine, Family, FontSystem, LineEnding, Shaping};
+
+/// Only Fira Mono from the repository fonts
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/FiraMono-Medium.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new().family(Family::Name("Fira Mono"))),
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn display_width_cjk_and_combining() {
+    let mut font_system = font_system();
+    // Two wide ideographs, and e with a combining acute accent in one cell
+    let mut line = line("a中文e\u{301}!");
+    line.shape(&mut font_system, 8);
+    assert_eq!(line.display_width(8), 7);
+}
+
+#[test]
+fn display_width_tabs() {
+    let mut font_system = font_system();
+    let mut line = line("ab\tc\t中");
+    line.shape(&mut font_system, 4);
+    assert_eq!(line.display_width(4), 10);
+    assert_eq!(line.display_width(8), 18);
+}
+
+#[test]
+fn display_width_unshaped() {
+    // Without shaping, graphemes fall back to their Unicode width
+    assert_eq!(line("a中e\u{301}").display_width(8), 4);
+    assert_eq!(line("").display_width(8), 0);
+}
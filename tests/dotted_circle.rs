@@ -0,0 +1,67 @@
+use cosmic_text::{fontdb, Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+/// Only Noto Sans from the repository fonts, which has a dotted circle
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/NotoSans-Regular.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn dotted_circle_id(font_system: &mut FontSystem) -> (fontdb::ID, u16) {
+    let id = font_system.db().faces().next().unwrap().id;
+    let font = font_system.get_font(id).unwrap();
+    let glyph_id = font.rustybuzz().glyph_index('\u{25CC}').unwrap().0;
+    (id, glyph_id)
+}
+
+#[test]
+fn dotted_circle_before_leading_mark() {
+    let mut font_system = font_system();
+    let (font_id, dotted_circle) = dotted_circle_id(&mut font_system);
+
+    // A combining acute accent without a base
+    let mut line = BufferLine::new(
+        "\u{301}a",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let glyphs = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0].glyphs;
+    let circle = glyphs
+        .iter()
+        .find(|glyph| glyph.font_id == font_id && glyph.glyph_id == dotted_circle)
+        .unwrap();
+    // The inserted base maps back to the bytes of the mark
+    assert_eq!((circle.start, circle.end), (0, 2));
+    let cluster = line.cluster_at_cursor(0).unwrap();
+    assert!(cluster.dotted_circle);
+    assert!(cluster.glyph_ids.contains(&dotted_circle));
+    assert!(!line.cluster_at_cursor(2).unwrap().dotted_circle);
+
+    assert!(line.set_insert_dotted_circle(false));
+    let glyphs = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0].glyphs;
+    assert!(!glyphs
+        .iter()
+        .any(|glyph| glyph.font_id == font_id && glyph.glyph_id == dotted_circle));
+    assert!(!line.cluster_at_cursor(0).unwrap().dotted_circle);
+}
+
+#[test]
+fn dotted_circle_in_text_is_not_inserted() {
+    let mut font_system = font_system();
+    let (_, dotted_circle) = dotted_circle_id(&mut font_system);
+
+    // A dotted circle typed as the base of the mark
+    let mut line = BufferLine::new(
+        "\u{25CC}\u{301}",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.shape(&mut font_system, 8);
+    let cluster = line.cluster_at_cursor(0).unwrap();
+    assert!(cluster.glyph_ids.contains(&dotted_circle));
+    assert!(!cluster.dotted_circle);
+}
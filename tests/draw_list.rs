@@ -0,0 +1,42 @@
+use cosmic_text::{Attrs, Buffer, Color, FontSystem, Metrics, Rect, Shaping};
+
+#[test]
+fn draw_list_visible_glyphs() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(500.0), Some(100.0));
+    buffer.set_rich_text(
+        [
+            ("hello ", Attrs::new()),
+            ("world\n", Attrs::new().color(Color::rgb(0xFF, 0, 0))),
+            ("שלום עולם\n", Attrs::new()),
+            ("third line", Attrs::new()),
+        ],
+        &Attrs::new(),
+        Shaping::Advanced,
+        None,
+    );
+    buffer.shape_until_scroll(false);
+
+    let visible: usize = buffer.layout_runs().map(|run| run.glyphs.len()).sum();
+    let draw_list = buffer.draw_list(Rect::new(0.0, 0.0, 500.0, 100.0));
+    assert_eq!(draw_list.len(), visible);
+    assert_eq!(
+        draw_list
+            .iter()
+            .filter(|glyph| glyph.color_opt == Some(Color::rgb(0xFF, 0, 0)))
+            .count(),
+        "world".len()
+    );
+    for glyph in &draw_list {
+        assert_eq!(glyph.cache_key.glyph_id, glyph.glyph_id);
+        assert_eq!(glyph.cache_key.font_id, glyph.font_id);
+    }
+
+    // Only the first line
+    let first = buffer.layout_runs().next().unwrap().glyphs.len();
+    let draw_list = buffer.draw_list(Rect::new(0.0, 0.0, 500.0, 10.0));
+    assert_eq!(draw_list.len(), first);
+    assert!(draw_list.iter().all(|glyph| glyph.y < 20));
+}
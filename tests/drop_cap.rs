@@ -0,0 +1,81 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, DropCap, FontSystem, LineEnding, Shaping, Wrap};
+
+const TEXT: &str = "Once upon a time there was a line of text long enough to wrap onto many lines";
+
+fn left_edges(font_system: &mut FontSystem, drop_cap: Option<DropCap>) -> Vec<f32> {
+    let mut line = BufferLine::new(
+        TEXT,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_drop_cap(drop_cap);
+    line.layout(font_system, 14.0, Some(200.0), Wrap::Word, None, 8)
+        .iter()
+        .map(|layout_line| {
+            assert!(layout_line
+                .glyphs
+                .iter()
+                .all(|glyph| glyph.x + glyph.w <= 200.5));
+            layout_line.glyphs[0].x
+        })
+        .collect()
+}
+
+#[test]
+fn drop_cap_indents_first_lines() {
+    let mut font_system = FontSystem::new();
+    let drop_cap = DropCap {
+        lines: 2,
+        width: 40.0,
+        font_size_opt: None,
+    };
+
+    let plain = left_edges(&mut font_system, None);
+    assert!(plain.iter().all(|&x| x == 0.0));
+
+    let indented = left_edges(&mut font_system, Some(drop_cap));
+    assert!(indented.len() > 3);
+    assert_eq!(indented[0], 40.0);
+    assert_eq!(indented[1], 40.0);
+    assert!(indented[2..].iter().all(|&x| x == 0.0));
+    // The indented lines hold less text, so the paragraph needs at least as many lines
+    assert!(indented.len() >= plain.len());
+}
+
+#[test]
+fn drop_cap_with_font_size_lays_out_first_character() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        TEXT,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let plain = line.layout(&mut font_system, 14.0, Some(200.0), Wrap::Word, None, 8)[0].clone();
+
+    line.set_drop_cap(Some(DropCap {
+        lines: 2,
+        width: 40.0,
+        font_size_opt: Some(36.0),
+    }));
+    let layout = line.layout(&mut font_system, 14.0, Some(200.0), Wrap::Word, None, 8);
+    let first = &layout[0];
+    let cap = &first.glyphs[0];
+    assert_eq!((cap.start, cap.end), (0, 1));
+    assert_eq!(cap.font_size, 36.0);
+    assert_eq!(cap.x, 0.0);
+    assert!(cap.w > 14.0);
+    // Hung from the top of the first line, which keeps its own height
+    assert!(cap.y > 0.0);
+    assert_eq!(first.max_ascent, plain.max_ascent);
+    assert_eq!(first.max_descent, plain.max_descent);
+    // The rest of the text starts after the reserved space, without the width of the drop cap
+    assert_eq!(first.glyphs[1].x, 40.0);
+    assert!(first.glyphs.iter().all(|glyph| glyph.x + glyph.w <= 200.5));
+    assert!(first.w < plain.w);
+    assert!(layout[1..].iter().all(|layout_line| layout_line
+        .glyphs
+        .iter()
+        .all(|glyph| glyph.font_size == 14.0)));
+}
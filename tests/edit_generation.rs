@@ -0,0 +1,59 @@
+use cosmic_text::{
+    Action, Attrs, AttrsList, Buffer, Cursor, Edit, Editor, FontSystem, Metrics, Motion, Scroll,
+    Selection, Shaping, Weight,
+};
+
+#[test]
+fn edit_generation_bumps_on_edits_only() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(&mut font_system, Some(200.0), Some(40.0));
+    buffer.set_text(
+        &mut font_system,
+        "first line\nsecond line\nthird line\nfourth line",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(&mut font_system, false);
+    let generation = buffer.edit_generation();
+    assert!(generation > 0);
+
+    // Scrolling, resizing and relayout leave it unchanged
+    buffer.set_scroll(Scroll::new(2, 0.0, 0.0));
+    buffer.shape_until_scroll(&mut font_system, false);
+    buffer.set_size(&mut font_system, Some(50.0), Some(100.0));
+    buffer.shape_until_scroll(&mut font_system, false);
+    for line in buffer.lines.iter_mut() {
+        line.reset_layout();
+    }
+    buffer.shape_until_scroll(&mut font_system, false);
+    assert_eq!(buffer.edit_generation(), generation);
+
+    // Attributes changes bump it
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(0..5, &Attrs::new().weight(Weight::BOLD));
+    buffer.lines[1].set_attrs_list(attrs_list);
+    let generation_attrs = buffer.edit_generation();
+    assert!(generation_attrs > generation);
+
+    // Editor edits bump it
+    let mut editor = Editor::new(&mut buffer);
+    editor.set_cursor(Cursor::new(0, 5));
+    editor.insert_string("!", None);
+    let generation_insert = editor.with_buffer(|buffer| buffer.edit_generation());
+    assert!(generation_insert > generation_attrs);
+
+    // Deleting lines bumps it too, since the remaining line is joined
+    editor.set_selection(Selection::Normal(Cursor::new(1, 0)));
+    editor.set_cursor(Cursor::new(3, 0));
+    editor.action(&mut font_system, Action::Delete);
+    let generation_delete = editor.with_buffer(|buffer| buffer.edit_generation());
+    assert!(generation_delete > generation_insert);
+
+    // Moving the cursor does not
+    editor.action(&mut font_system, Action::Motion(Motion::Home));
+    assert_eq!(
+        editor.with_buffer(|buffer| buffer.edit_generation()),
+        generation_delete
+    );
+}
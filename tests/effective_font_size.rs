@@ -0,0 +1,12 @@
+use cosmic_text::{Buffer, FontSystem, Metrics};
+
+#[test]
+fn effective_font_size_follows_metrics() {
+    let mut font_system = FontSystem::new();
+    let metrics = Metrics::new(14.0, 20.0);
+    let mut buffer = Buffer::new(&mut font_system, metrics);
+    assert_eq!(buffer.effective_font_size(), 14.0);
+
+    buffer.set_metrics(&mut font_system, metrics.scale(2.0));
+    assert_eq!(buffer.effective_font_size(), 28.0);
+}
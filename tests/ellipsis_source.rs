@@ -0,0 +1,48 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, EllipsisSource, FontSystem, LineEnding, Overflow, Shaping, Wrap,
+};
+
+const TEXT: &str = "the quick brown fox jumps over the lazy dog";
+
+fn ellipsized(font_system: &mut FontSystem, source: EllipsisSource) -> (usize, usize) {
+    let mut line = BufferLine::new(
+        TEXT,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_overflow(Overflow::Ellipsis(source));
+    let layout = line.layout(font_system, 14.0, Some(150.0), Wrap::Word, None, 8);
+    assert_eq!(layout.len(), 1);
+    assert!(layout[0].w <= 150.0);
+    let glyphs = &layout[0].glyphs;
+    let cut = glyphs.last().unwrap().start;
+    let ellipsis_glyphs = glyphs
+        .iter()
+        .filter(|glyph| glyph.start == glyph.end)
+        .count();
+    assert!(glyphs[glyphs.len() - ellipsis_glyphs..]
+        .iter()
+        .all(|glyph| glyph.start == cut && glyph.end == cut));
+    (cut, ellipsis_glyphs)
+}
+
+#[test]
+fn ellipsis_source_width() {
+    let mut font_system = FontSystem::new();
+
+    let (ellipsis_cut, ellipsis_glyphs) =
+        ellipsized(&mut font_system, EllipsisSource::HorizontalEllipsis);
+    assert_eq!(ellipsis_glyphs, 1);
+    assert!(ellipsis_cut > 0 && ellipsis_cut < TEXT.len());
+
+    // Spaced dots are wider than U+2026, so more text is dropped
+    let (dots_cut, dots_glyphs) = ellipsized(
+        &mut font_system,
+        EllipsisSource::Custom(String::from(" . . . ")),
+    );
+    assert_eq!(dots_glyphs, 7);
+    assert!(dots_cut < ellipsis_cut);
+
+    assert_eq!(EllipsisSource::Dots(3).text(), "...");
+}
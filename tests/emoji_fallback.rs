@@ -0,0 +1,109 @@
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, BufferLine, EmojiFallback, Fallback, Family, FontSystem, LineEnding,
+    Shaping,
+};
+use unicode_script::Script;
+
+/// No preset fallbacks, so only [`EmojiFallback`] decides whether other fonts are tried
+struct NoFallback;
+
+impl Fallback for NoFallback {
+    fn common_fallback(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn forbidden_fallback(&self) -> &[&'static str] {
+        &[]
+    }
+
+    fn script_fallback(&self, _script: Script, _locale: &str) -> &[&'static str] {
+        &[]
+    }
+}
+
+/// Noto Sans Hebrew, which has no heart, and Noto Sans, which has a monochrome heart, from the
+/// repository fonts
+fn font_system() -> FontSystem {
+    let mut db = fontdb::Database::new();
+    for path in ["fonts/NotoSansHebrew.ttf", "fonts/NotoSans-Regular.ttf"] {
+        db.load_font_data(std::fs::read(path).unwrap());
+    }
+    FontSystem::new_with_locale_and_db_and_fallback("en-US".into(), db, NoFallback)
+}
+
+/// Get the family and glyph IDs of the heart
+fn heart(font_system: &mut FontSystem) -> (String, Vec<u16>) {
+    let mut line = BufferLine::new(
+        "\u{2665}\u{FE0F}",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new().family(Family::Name("Noto Sans Hebrew"))),
+        Shaping::Advanced,
+    );
+    line.shape(font_system, 8);
+    let cluster = line.cluster_at_cursor(0).unwrap();
+    let face = font_system.db().face(cluster.font_id).unwrap();
+    (face.families[0].0.clone(), cluster.glyph_ids)
+}
+
+#[test]
+fn emoji_fallback_none_keeps_notdef() {
+    let mut font_system = font_system();
+    assert_eq!(font_system.emoji_fallback(), EmojiFallback::Monochrome);
+    font_system.set_emoji_fallback(EmojiFallback::None);
+    let (_, glyphs) = heart(&mut font_system);
+    assert!(glyphs.contains(&0), "{glyphs:?}");
+}
+
+#[test]
+fn emoji_fallback_monochrome_and_text() {
+    let mut font_system = font_system();
+    let (family, monochrome) = heart(&mut font_system);
+    assert_eq!(family, "Noto Sans");
+    assert!(!monochrome.is_empty());
+    assert!(!monochrome.contains(&0), "{monochrome:?}");
+
+    font_system.set_emoji_fallback(EmojiFallback::Text);
+    let (family, text) = heart(&mut font_system);
+    assert_eq!(family, "Noto Sans");
+    assert!(!text.contains(&0), "{text:?}");
+}
+
+#[test]
+fn emoji_fallback_change_reshapes_runs() {
+    let mut font_system = font_system();
+    let (_, monochrome) = heart(&mut font_system);
+
+    // Runs shaped before the change are not reused
+    font_system.set_emoji_fallback(EmojiFallback::None);
+    let (_, none) = heart(&mut font_system);
+    assert!(none.contains(&0), "{none:?}");
+
+    font_system.set_emoji_fallback(EmojiFallback::Monochrome);
+    assert_eq!(heart(&mut font_system).1, monochrome);
+}
+
+#[test]
+fn emoji_fallback_none_only_stops_emoji_presentation() {
+    let mut font_system = font_system();
+    font_system.set_emoji_fallback(EmojiFallback::None);
+    let mut glyphs_of = |text: &str| {
+        let mut line = BufferLine::new(
+            text,
+            LineEnding::None,
+            AttrsList::new(&Attrs::new().family(Family::Name("Noto Sans Hebrew"))),
+            Shaping::Advanced,
+        );
+        line.shape(&mut font_system, 8);
+        line.cluster_at_cursor(0).unwrap().glyph_ids
+    };
+
+    // The heart suit has text presentation by default, so it falls back like other symbols
+    let heart = glyphs_of("\u{2665}");
+    assert!(!heart.contains(&0), "{heart:?}");
+    // Unless the emoji presentation selector asks for an emoji
+    let heart = glyphs_of("\u{2665}\u{FE0F}");
+    assert!(heart.contains(&0), "{heart:?}");
+    // The umbrella with rain drops has emoji presentation by default
+    let umbrella = glyphs_of("\u{2614}");
+    assert!(umbrella.contains(&0), "{umbrella:?}");
+}
@@ -0,0 +1,59 @@
+use cosmic_text::{
+    Attrs, AttrsList, Buffer, BufferLine, Color, Cursor, ExportFormat, LineEnding, Metrics,
+    Shaping, Style, Weight,
+};
+
+fn buffer() -> Buffer {
+    let mut buffer = Buffer::new_empty(Metrics::new(14.0, 20.0));
+
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(
+        6..10,
+        &Attrs::new()
+            .weight(Weight::BOLD)
+            .color(Color::rgb(0xFF, 0x00, 0x00)),
+    );
+    buffer.lines.push(BufferLine::new(
+        "Hello <big> world",
+        LineEnding::Lf,
+        attrs_list,
+        Shaping::Advanced,
+    ));
+
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(0..6, &Attrs::new().style(Style::Italic));
+    buffer.lines.push(BufferLine::new(
+        "second line",
+        LineEnding::None,
+        attrs_list,
+        Shaping::Advanced,
+    ));
+
+    buffer
+}
+
+#[test]
+fn export_range_html() {
+    let buffer = buffer();
+    assert_eq!(
+        buffer.export_range(Cursor::new(0, 0), Cursor::new(0, 11), ExportFormat::Html),
+        "Hello <b><span style=\"color: #ff0000\">&lt;big</span></b>&gt;",
+    );
+    assert_eq!(
+        buffer.export_range(Cursor::new(0, 12), Cursor::new(1, 11), ExportFormat::Html),
+        "world<br>\n<i>second</i> line",
+    );
+}
+
+#[test]
+fn export_range_markdown() {
+    let buffer = buffer();
+    assert_eq!(
+        buffer.export_range(
+            Cursor::new(1, 11),
+            Cursor::new(0, 6),
+            ExportFormat::Markdown
+        ),
+        "**\\<big**\\> world\n\n*second* line",
+    );
+}
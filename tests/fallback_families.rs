@@ -0,0 +1,107 @@
+use cosmic_text::{
+    fontdb, Attrs, Buffer, Cursor, Family, FamilyOwned, FontSystem, Metrics, Shaping,
+};
+
+/// Fira Mono, Inter and Noto Sans, which have no Hebrew, and Noto Sans Hebrew, from the
+/// repository fonts
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    for path in [
+        "fonts/FiraMono-Medium.ttf",
+        "fonts/Inter-Regular.ttf",
+        "fonts/NotoSans-Regular.ttf",
+        "fonts/NotoSansHebrew.ttf",
+    ] {
+        let font = std::fs::read(path).unwrap();
+        font_system.db_mut().load_font_data(font);
+    }
+    font_system
+}
+
+/// Get the family of the first and second character of `text`
+fn families(fallback_families: &[Family], text: &str, family: Family) -> (String, String) {
+    let mut font_system = font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_fallback_families(&mut font_system, fallback_families);
+    buffer.set_text(
+        &mut font_system,
+        text,
+        &Attrs::new().family(family),
+        Shaping::Advanced,
+    );
+    let glyphs = buffer.layout_runs().next().unwrap().glyphs;
+    let family = |index: usize| {
+        let glyph = glyphs.iter().find(|glyph| glyph.start == index).unwrap();
+        let face = font_system.db().face(glyph.font_id).unwrap();
+        face.families[0].0.clone()
+    };
+    (family(0), family(1))
+}
+
+#[test]
+fn fallback_families_second_family() {
+    // Inter has no Hebrew either, so Noto Sans Hebrew is used
+    let (a, alef) = families(
+        &[Family::Name("Inter"), Family::Name("Noto Sans Hebrew")],
+        "a\u{5D0}",
+        Family::Name("Fira Mono"),
+    );
+    assert_eq!(a, "Fira Mono");
+    assert_eq!(alef, "Noto Sans Hebrew");
+}
+
+#[test]
+fn fallback_families_order() {
+    let missing = Family::Name("No Such Family");
+    let (a, _) = families(
+        &[Family::Name("Noto Sans"), Family::Name("Inter")],
+        "ab",
+        missing,
+    );
+    assert_eq!(a, "Noto Sans");
+    let (a, _) = families(
+        &[Family::Name("Inter"), Family::Name("Noto Sans")],
+        "ab",
+        missing,
+    );
+    assert_eq!(a, "Inter");
+}
+
+#[test]
+fn fallback_families_invalidates_shaping() {
+    let mut font_system = font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_fallback_families(&mut font_system, &[Family::Name("Noto Sans")]);
+    buffer.set_text(
+        &mut font_system,
+        "a",
+        &Attrs::new().family(Family::Name("No Such Family")),
+        Shaping::Advanced,
+    );
+    buffer.shape_until_scroll(&mut font_system, false);
+    assert!(buffer.lines[0].shape_opt().is_some());
+    buffer.set_fallback_families(&mut font_system, &[Family::Name("Inter")]);
+    assert_eq!(
+        buffer.lines[0].fallback_families(),
+        &[FamilyOwned::Name("Inter".into())]
+    );
+    let glyph = buffer.layout_runs().next().unwrap().glyphs[0].font_id;
+    let face = font_system.db().face(glyph).unwrap();
+    assert_eq!(face.families[0].0, "Inter");
+}
+
+#[test]
+fn fallback_families_split_off_past_end() {
+    let mut font_system = font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_fallback_families(&mut font_system, &[Family::Name("Inter")]);
+    buffer.set_text(&mut font_system, "a", &Attrs::new(), Shaping::Advanced);
+    // Splitting after the last line starts the new buffer with an empty line
+    let tail = buffer.split_off(Cursor::new(1, 0));
+    assert_eq!(tail.lines.len(), 1);
+    assert_eq!(
+        tail.lines[0].fallback_families(),
+        &[FamilyOwned::Name("Inter".into())]
+    );
+}
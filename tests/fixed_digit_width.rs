@@ -0,0 +1,33 @@
+use cosmic_text::{Attrs, AttrsList, FontSystem, ShapeLine, Shaping, Wrap};
+
+#[test]
+fn fixed_digit_width_forces_advance() {
+    let mut font_system = FontSystem::new();
+    let font_size = 20.0;
+    let text = "0123456789:1";
+    let attrs_list = AttrsList::new(&Attrs::new().fixed_digit_width(Some(0.7)));
+
+    for shaping in [Shaping::Advanced, Shaping::Basic] {
+        let line = ShapeLine::new(&mut font_system, text, &attrs_list, shaping, 8);
+        let layout = line.layout(font_size, None, Wrap::None, None, None);
+        for glyph in &layout[0].glyphs {
+            let c = text[glyph.start..glyph.end].chars().next().unwrap();
+            if c.is_ascii_digit() {
+                assert_eq!(glyph.w, 0.7 * font_size, "digit {c:?} with {shaping:?}");
+            } else {
+                assert_ne!(glyph.w, 0.7 * font_size, "{c:?} with {shaping:?}");
+            }
+        }
+    }
+
+    // Without the override, digit advances come from the font
+    let line = ShapeLine::new(
+        &mut font_system,
+        "1",
+        &AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+        8,
+    );
+    let layout = line.layout(font_size, None, Wrap::None, None, None);
+    assert_ne!(layout[0].glyphs[0].w, 0.7 * font_size);
+}
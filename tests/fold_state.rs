@@ -0,0 +1,106 @@
+use cosmic_text::{Attrs, Buffer, FoldState, FontSystem, Metrics, Shaping};
+
+fn buffer(font_system: &mut FontSystem, text: &str) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, Some(200.0), Some(200.0));
+    buffer.set_text(font_system, text, &Attrs::new(), Shaping::Advanced);
+    buffer
+}
+
+fn run_lines(buffer: &Buffer) -> Vec<usize> {
+    buffer.layout_runs().map(|run| run.line_i).collect()
+}
+
+#[test]
+fn fold_state_skips_collapsed_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system, "a\nb\nc\nd");
+    assert_eq!(buffer.lines[0].fold_state(), FoldState::None);
+    buffer.lines[0].set_fold_state(FoldState::Collapsed(2));
+    assert_eq!(buffer.folded_ranges(), vec![1..3]);
+
+    buffer.shape_until_scroll(&mut font_system, false);
+    assert_eq!(run_lines(&buffer), vec![0, 3]);
+    assert_eq!(buffer.visual_line_count(), 2);
+    assert_eq!(buffer.visual_line_count_exact(&mut font_system), 2);
+    let y: Vec<f32> = buffer.layout_runs().map(|run| run.line_top).collect();
+    assert_eq!(y, vec![0.0, 20.0]);
+
+    // Expanding shows the lines again
+    buffer.lines[0].set_fold_state(FoldState::Expanded(2));
+    assert!(buffer.folded_ranges().is_empty());
+    buffer.shape_until_scroll(&mut font_system, false);
+    assert_eq!(run_lines(&buffer), vec![0, 1, 2, 3]);
+}
+
+#[test]
+fn fold_state_nested_and_clamped() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system, "a\nb\nc\nd\ne\nf");
+    buffer.lines[0].set_fold_state(FoldState::Collapsed(2));
+    buffer.lines[1].set_fold_state(FoldState::Collapsed(3));
+    buffer.lines[5].set_fold_state(FoldState::Collapsed(4));
+    assert_eq!(buffer.folded_ranges(), vec![1..5]);
+
+    // An expanded region inside a collapsed one stays hidden
+    buffer.lines[1].set_fold_state(FoldState::Expanded(3));
+    buffer.lines[3].set_fold_state(FoldState::Collapsed(10));
+    assert_eq!(buffer.folded_ranges(), vec![1..3, 4..6]);
+    buffer.shape_until_scroll(&mut font_system, false);
+    assert_eq!(run_lines(&buffer), vec![0, 3]);
+}
+
+#[test]
+fn fold_state_cleared_by_reset_new() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system, "a\nb");
+    buffer.lines[0].set_fold_state(FoldState::Collapsed(1));
+    buffer.set_text(&mut font_system, "a\nb", &Attrs::new(), Shaping::Advanced);
+    assert!(buffer.folded_ranges().is_empty());
+}
+
+#[test]
+fn fold_state_set_on_buffer_redraws() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system, "a\nb\nc");
+    buffer.set_redraw(false);
+    assert!(buffer.set_fold_state(0, FoldState::Collapsed(1)));
+    assert!(buffer.redraw());
+    buffer.set_redraw(false);
+    assert!(!buffer.set_fold_state(0, FoldState::Collapsed(1)));
+    assert!(!buffer.redraw());
+    assert!(!buffer.set_fold_state(5, FoldState::Collapsed(1)));
+}
+
+#[test]
+fn fold_state_clipped_and_columns() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system, "a\nb\nc\nd");
+    buffer.set_fold_state(1, FoldState::Collapsed(1));
+
+    let clip = buffer.layout_clipped(&mut font_system, Some(200.0), 200.0);
+    assert_eq!(clip.layout_lines, 3);
+    assert_eq!(clip.height, 60.0);
+
+    let columns = buffer.layout_columns(&mut font_system, 100.0, 2, 10.0, 40.0);
+    let lines: Vec<Vec<usize>> = columns
+        .columns
+        .iter()
+        .map(|column| column.lines.iter().map(|line| line.line_i).collect())
+        .collect();
+    assert_eq!(lines, vec![vec![0, 1], vec![3]]);
+    assert_eq!(columns.overflow_opt, None);
+}
+
+#[test]
+fn fold_state_nested_from_scroll_line() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system, "a\nb\nc\nd\ne\nf");
+    // The region of line 1 is extended by line 2, which it hides
+    buffer.set_fold_state(1, FoldState::Collapsed(1));
+    buffer.set_fold_state(2, FoldState::Collapsed(2));
+    assert_eq!(buffer.folded_ranges(), vec![2..5]);
+    buffer.shape_until_scroll(&mut font_system, false);
+    assert_eq!(run_lines(&buffer), vec![0, 1, 5]);
+    assert_eq!(buffer.lines_in_height(200.0), 3);
+}
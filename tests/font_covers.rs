@@ -0,0 +1,18 @@
+use cosmic_text::{fontdb, Coverage, FontSystem};
+
+#[test]
+fn font_covers_latin_font_with_cjk_text() {
+    // Only Noto Sans from the repository fonts
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/NotoSans-Regular.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let id = font_system.db().faces().next().unwrap().id;
+
+    assert_eq!(font_system.font_covers(id, "Hello\n"), Coverage::Full);
+    assert_eq!(
+        font_system.font_covers(id, "Hi 漢字 漢"),
+        Coverage::Partial(vec!['漢', '字'])
+    );
+    assert_eq!(font_system.font_covers(id, "漢字"), Coverage::None);
+}
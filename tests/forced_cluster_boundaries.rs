@@ -0,0 +1,28 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, Family, FontSystem, LineEnding, Shaping};
+
+fn glyph_count(font_system: &mut FontSystem, boundaries: Vec<usize>) -> usize {
+    let mut line = BufferLine::new(
+        "fi",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new().family(Family::Serif)),
+        Shaping::Advanced,
+    );
+    line.set_forced_cluster_boundaries(boundaries);
+    line.shape(font_system, 8)
+        .spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .map(|word| word.glyphs.len())
+        .sum()
+}
+
+#[test]
+fn forced_cluster_boundary_prevents_ligature() {
+    let mut font_system = FontSystem::new();
+
+    // The "fi" ligature is a single glyph
+    assert_eq!(glyph_count(&mut font_system, Vec::new()), 1);
+
+    // A boundary between "f" and "i" keeps them as separate clusters
+    assert_eq!(glyph_count(&mut font_system, vec![1]), 2);
+}
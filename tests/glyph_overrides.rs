@@ -0,0 +1,66 @@
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, BufferLine, Family, FontSystem, LayoutGlyph, LineEnding, Shaping,
+    Wrap,
+};
+
+/// Only Noto Sans from the repository fonts
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/NotoSans-Regular.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+fn glyphs(
+    font_system: &mut FontSystem,
+    text: &str,
+    attrs: &Attrs,
+    shaping: Shaping,
+) -> Vec<LayoutGlyph> {
+    let mut line = BufferLine::new(text, LineEnding::None, AttrsList::new(attrs), shaping);
+    line.layout(font_system, 20.0, None, Wrap::None, None, 8)[0]
+        .glyphs
+        .clone()
+}
+
+#[test]
+fn glyph_overrides_single_char() {
+    let mut font_system = font_system();
+    let attrs = Attrs::new().family(Family::Name("Noto Sans"));
+    let b = glyphs(&mut font_system, "b", &attrs, Shaping::Advanced)[0].clone();
+    let plain = glyphs(&mut font_system, "cat", &attrs, Shaping::Advanced);
+    assert_ne!(plain[1].glyph_id, b.glyph_id);
+
+    let overridden = attrs.clone().glyph_overrides(vec![('a', b.glyph_id)]);
+    for shaping in [Shaping::Advanced, Shaping::Basic] {
+        let glyphs = glyphs(&mut font_system, "cat", &overridden, shaping);
+        assert_eq!(glyphs.len(), 3);
+        assert_eq!(glyphs[0].glyph_id, plain[0].glyph_id, "{shaping:?}");
+        assert_eq!(glyphs[1].glyph_id, b.glyph_id, "{shaping:?}");
+        assert_eq!(glyphs[2].glyph_id, plain[2].glyph_id, "{shaping:?}");
+        // The advance comes from the replacement glyph
+        assert_eq!(glyphs[1].w, b.w, "{shaping:?}");
+        assert_eq!(glyphs[2].x, glyphs[1].x + b.w, "{shaping:?}");
+    }
+}
+
+#[test]
+fn glyph_overrides_multi_char_cluster() {
+    let mut font_system = font_system();
+    let attrs = Attrs::new().family(Family::Name("Noto Sans"));
+    let b = glyphs(&mut font_system, "b", &attrs, Shaping::Advanced)[0].glyph_id;
+    let overridden = attrs.clone().glyph_overrides(vec![('a', b)]);
+
+    // A base character with a combining mark is left alone
+    let plain = glyphs(&mut font_system, "a\u{301}", &attrs, Shaping::Advanced);
+    let glyphs = glyphs(&mut font_system, "a\u{301}", &overridden, Shaping::Advanced);
+    let ids = |glyphs: &[LayoutGlyph]| {
+        glyphs
+            .iter()
+            .map(|glyph| glyph.glyph_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(ids(&glyphs), ids(&plain));
+    assert!(!ids(&glyphs).contains(&b));
+}
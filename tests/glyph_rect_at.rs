@@ -0,0 +1,55 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Rect, Shaping, Wrap};
+
+fn laid_out(font_system: &mut FontSystem, text: &str, width_opt: Option<f32>) -> BufferLine {
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.layout(font_system, 14.0, width_opt, Wrap::Word, None, 8);
+    line
+}
+
+fn glyph_rect(line: &BufferLine, layout_i: usize, byte_index: usize, y: f32) -> Rect {
+    let glyph = line.layout_opt().unwrap()[layout_i]
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start == byte_index)
+        .unwrap();
+    Rect::new(glyph.x, y, glyph.w, 20.0)
+}
+
+#[test]
+fn glyph_rect_at_mid_line() {
+    let mut font_system = FontSystem::new();
+    let line = laid_out(&mut font_system, "Hello world", None);
+    assert_eq!(
+        line.glyph_rect_at(4, 20.0),
+        Some(glyph_rect(&line, 0, 4, 0.0))
+    );
+    assert_eq!(line.glyph_rect_at(11, 20.0), None);
+}
+
+#[test]
+fn glyph_rect_at_rtl() {
+    let mut font_system = FontSystem::new();
+    let line = laid_out(&mut font_system, "שלום עולם", None);
+    // Hebrew letters are two bytes each
+    let rect = line.glyph_rect_at(2, 20.0).unwrap();
+    assert_eq!(rect, glyph_rect(&line, 0, 2, 0.0));
+    // The second letter is to the left of the first one
+    let first = line.glyph_rect_at(0, 20.0).unwrap();
+    assert!(rect.x + rect.width <= first.x + 0.01, "{rect:?} {first:?}");
+}
+
+#[test]
+fn glyph_rect_at_wrapped() {
+    let mut font_system = FontSystem::new();
+    let line = laid_out(&mut font_system, "Hello world", Some(50.0));
+    assert_eq!(line.layout_opt().unwrap().len(), 2);
+    assert_eq!(
+        line.glyph_rect_at(7, 20.0),
+        Some(glyph_rect(&line, 1, 7, 20.0))
+    );
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, FontSystem, Hyphenator, LayoutLine, LineEnding, Shaping, Wrap,
+};
+
+/// Allows breaks every two bytes of a word
+struct EveryTwo;
+
+impl Hyphenator for EveryTwo {
+    fn break_points(&self, word: &str) -> Vec<usize> {
+        (2..word.len()).step_by(2).collect()
+    }
+}
+
+fn layout(width: f32, hyphen_char: char) -> Vec<LayoutLine> {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "incomprehensibilities",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_hyphenator(Some(Arc::new(EveryTwo)));
+    line.set_hyphen_char(hyphen_char);
+    line.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8)
+        .to_vec()
+}
+
+/// The byte offset of the hyphen at the end of the first line, and its glyph
+fn first_break(lines: &[LayoutLine]) -> (usize, u16) {
+    let hyphen = lines[0].glyphs.last().unwrap();
+    assert_eq!(hyphen.start, hyphen.end);
+    (hyphen.start, hyphen.glyph_id)
+}
+
+#[test]
+fn hyphen_char_default() {
+    let mut line = BufferLine::new(
+        "",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    assert_eq!(line.hyphen_char(), '-');
+    assert!(line.set_hyphen_char('\u{2010}'));
+    assert!(!line.set_hyphen_char('\u{2010}'));
+}
+
+#[test]
+fn hyphen_char_width_affects_break() {
+    let hyphen = layout(100.0, '-');
+    let (hyphen_break, hyphen_glyph) = first_break(&hyphen);
+
+    // At exactly the width of that line, a wider em dash no longer fits at the same break
+    let width = hyphen[0].w;
+    let (same_break, _) = first_break(&layout(width, '-'));
+    assert_eq!(same_break, hyphen_break);
+    let dash = layout(width, '\u{2014}');
+    let (dash_break, dash_glyph) = first_break(&dash);
+    assert_ne!(dash_glyph, hyphen_glyph);
+    assert!(dash_break < hyphen_break, "{dash_break} {hyphen_break}");
+    assert!(dash[0].w <= width);
+}
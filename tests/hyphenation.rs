@@ -0,0 +1,113 @@
+use std::sync::Arc;
+
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, FontSystem, Hyphenator, LayoutLine, LineEnding, Shaping, Wrap,
+};
+
+/// Allows breaks every four bytes of a word
+struct EveryFour;
+
+impl Hyphenator for EveryFour {
+    fn break_points(&self, word: &str) -> Vec<usize> {
+        (4..word.len()).step_by(4).collect()
+    }
+}
+
+fn layout(shaping: Shaping, width: f32, hyphenate: bool) -> Vec<LayoutLine> {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "the incomprehensibilities",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        shaping,
+    );
+    if hyphenate {
+        line.set_hyphenator(Some(Arc::new(EveryFour)));
+    }
+    line.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8)
+        .to_vec()
+}
+
+fn hyphens(line: &LayoutLine) -> Vec<usize> {
+    line.glyphs
+        .iter()
+        .filter(|glyph| glyph.start == glyph.end)
+        .map(|glyph| glyph.start)
+        .collect()
+}
+
+#[test]
+fn hyphenation_breaks_long_word() {
+    let plain = layout(Shaping::Advanced, 120.0, false);
+    assert_eq!(plain.len(), 2);
+    assert!(plain.iter().all(|line| hyphens(line).is_empty()));
+
+    let lines = layout(Shaping::Advanced, 120.0, true);
+    assert!(lines.len() >= 2);
+    // The word starts on the first line, which ends with a hyphen at a break point
+    let first = &lines[0];
+    let breaks = hyphens(first);
+    assert_eq!(breaks.len(), 1);
+    assert_eq!((breaks[0] - 4) % 4, 0);
+    assert!(breaks[0] > 4);
+    assert_eq!(first.glyphs.last().unwrap().start, breaks[0]);
+    assert!(first.w <= 120.0);
+    // The next line continues after the break
+    assert_eq!(lines[1].glyphs.first().unwrap().start, breaks[0]);
+    // Every line fits and all text is laid out exactly once
+    let mut covered = 0;
+    for line in &lines {
+        assert!(line.w <= 120.0);
+        for glyph in line.glyphs.iter().filter(|glyph| glyph.start != glyph.end) {
+            covered += glyph.end - glyph.start;
+        }
+    }
+    assert_eq!(covered, "the incomprehensibilities".len());
+}
+
+#[test]
+fn hyphenation_without_fitting_break() {
+    // Too narrow for any break point and a hyphen, so words wrap as usual
+    let plain = layout(Shaping::Advanced, 20.0, false);
+    let lines = layout(Shaping::Advanced, 20.0, true);
+    assert_eq!(lines.len(), plain.len());
+    assert!(lines.iter().all(|line| hyphens(line).is_empty()));
+}
+
+#[test]
+fn hyphenation_disabled_for_basic_shaping() {
+    let lines = layout(Shaping::Basic, 120.0, true);
+    assert!(lines.iter().all(|line| hyphens(line).is_empty()));
+}
+
+#[test]
+fn hyphenation_survives_relayout() {
+    let mut font_system = FontSystem::new();
+    let hyphenator: Arc<dyn Hyphenator + Send + Sync> = Arc::new(EveryFour);
+    let mut line = BufferLine::new(
+        "the incomprehensibilities",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    assert!(line.set_hyphenator(Some(hyphenator.clone())));
+    assert!(!line.set_hyphenator(Some(hyphenator)));
+
+    let first = line
+        .layout(&mut font_system, 14.0, Some(120.0), Wrap::Word, None, 8)
+        .to_vec();
+    // Resizing reuses the shaped hyphen and lays out the same lines again
+    line.layout(&mut font_system, 14.0, Some(60.0), Wrap::Word, None, 8);
+    let again = line
+        .layout(&mut font_system, 14.0, Some(120.0), Wrap::Word, None, 8)
+        .to_vec();
+    assert_eq!(first.len(), again.len());
+    for (a, b) in first.iter().zip(&again) {
+        assert_eq!(hyphens(a), hyphens(b));
+        assert_eq!(a.w, b.w);
+        assert!(a.w <= 120.0);
+    }
+
+    assert!(line.set_hyphenator(None));
+    assert!(!line.set_hyphenator(None));
+}
@@ -0,0 +1,54 @@
+use std::sync::{Arc, Mutex};
+
+use cosmic_text::{
+    Align, Attrs, AttrsList, BufferLine, Color, FontSystem, Invalidation, InvalidationReason,
+    LineEnding, Shaping, Wrap,
+};
+
+#[test]
+fn invalidation_logger_reports_reasons() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "hello",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let log_clone = log.clone();
+    line.set_invalidation_logger(Some(Arc::new(move |invalidation| {
+        log_clone.lock().unwrap().push(invalidation);
+    })));
+    let mut layout = |line: &mut BufferLine| {
+        line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+    };
+
+    // Nothing is reported while caches are empty
+    line.set_align(Some(Align::Center));
+    assert!(log.lock().unwrap().is_empty());
+
+    layout(&mut line);
+    line.set_align(Some(Align::Right));
+    assert_eq!(
+        log.lock().unwrap().drain(..).collect::<Vec<_>>(),
+        [Invalidation::Layout(InvalidationReason::Align)]
+    );
+
+    // Colors are part of the shaping cache, so a color-only change currently reshapes
+    layout(&mut line);
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(0..2, &Attrs::new().color(Color::rgb(0xFF, 0, 0)));
+    line.set_attrs_list(attrs_list);
+    assert_eq!(
+        log.lock().unwrap().drain(..).collect::<Vec<_>>(),
+        [Invalidation::Shaping(InvalidationReason::AttrsList)]
+    );
+
+    layout(&mut line);
+    line.reset_layout();
+    line.reset_layout();
+    assert_eq!(
+        log.lock().unwrap().drain(..).collect::<Vec<_>>(),
+        [Invalidation::Layout(InvalidationReason::Explicit)]
+    );
+}
@@ -0,0 +1,70 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, Family, FontSystem, LineEnding, Shaping, Wrap};
+
+fn relayout(line: &mut BufferLine, font_system: &mut FontSystem, width: f32) -> Vec<*const ()> {
+    line.reset_layout();
+    line.layout(font_system, 14.0, Some(width), Wrap::Word, None, 8)
+        .iter()
+        .map(|layout_line| layout_line.glyphs.as_ptr().cast())
+        .collect()
+}
+
+#[test]
+fn layout_cache_switches_between_widths() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "a paragraph that wraps differently at the edit and preview widths",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_layout_cache(true);
+
+    let edit = relayout(&mut line, &mut font_system, 100.0);
+    let preview = relayout(&mut line, &mut font_system, 300.0);
+    assert_ne!(edit.len(), preview.len());
+    assert_ne!(edit[0], preview[0]);
+
+    // Switching back restores the kept layouts instead of computing new ones
+    for _ in 0..2 {
+        assert_eq!(relayout(&mut line, &mut font_system, 100.0), edit);
+        assert_eq!(relayout(&mut line, &mut font_system, 300.0), preview);
+    }
+
+    // A text change drops the kept layouts
+    let attrs_list = line.attrs_list().clone();
+    line.set_text("a different paragraph", LineEnding::None, attrs_list);
+    line.layout(&mut font_system, 14.0, Some(300.0), Wrap::Word, None, 8);
+    relayout(&mut line, &mut font_system, 100.0);
+    let text_len = line.text().len();
+    assert!(line
+        .layout_opt()
+        .unwrap()
+        .iter()
+        .all(|layout_line| layout_line.glyphs.iter().all(|glyph| glyph.end <= text_len)));
+}
+
+#[test]
+fn layout_cache_reshapes_after_locale_change() {
+    let mut font_system = FontSystem::new();
+    // Serbian and Russian use different forms of the Cyrillic be
+    font_system.set_locale("ru-RU");
+    let mut line = BufferLine::new(
+        "б",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new().family(Family::Serif)),
+        Shaping::Advanced,
+    );
+    line.set_layout_cache(true);
+    let glyph_id = |line: &mut BufferLine, font_system: &mut FontSystem, width: f32| {
+        line.reset_layout();
+        line.layout(font_system, 14.0, Some(width), Wrap::Word, None, 8)[0].glyphs[0].glyph_id
+    };
+
+    let russian = glyph_id(&mut line, &mut font_system, 100.0);
+    glyph_id(&mut line, &mut font_system, 300.0);
+    font_system.set_locale("sr-RS");
+    // The kept layout was built from shaping with the previous locale
+    let serbian = glyph_id(&mut line, &mut font_system, 100.0);
+    assert_ne!(russian, serbian);
+    assert_eq!(glyph_id(&mut line, &mut font_system, 300.0), serbian);
+}
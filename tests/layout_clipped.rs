@@ -0,0 +1,23 @@
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, Shaping};
+
+#[test]
+fn layout_clipped_reports_overflow() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("one\ntwo\nthree\nfour", &Attrs::new(), Shaping::Advanced);
+
+    // Room for two and a half lines
+    let result = buffer.layout_clipped(None, 50.0);
+    assert!(result.clipped);
+    assert_eq!(result.cursor_opt, Some(Cursor::new(2, 0)));
+    assert_eq!(result.layout_lines, 2);
+    assert_eq!(result.height, 40.0);
+    assert_eq!(buffer.layout_runs().count(), 2);
+
+    // Everything fits
+    let result = buffer.layout_clipped(None, 80.0);
+    assert!(!result.clipped);
+    assert_eq!(result.cursor_opt, None);
+    assert_eq!(result.layout_lines, 4);
+}
@@ -0,0 +1,56 @@
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, Shaping};
+
+const TEXT: &str = "The quick brown fox jumps over the lazy dog and keeps on running \
+    through the fields until the sun goes down behind the hills";
+
+#[test]
+fn layout_columns_two_columns() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text(TEXT, &Attrs::new(), Shaping::Advanced);
+
+    let columns = buffer.layout_columns(120.0, 2, 10.0, 1000.0);
+    let total = columns.columns[0].lines.len();
+    assert!(total >= 4, "{total}");
+    assert!(columns.columns[1].lines.is_empty());
+
+    // Half the lines per column, rounded up
+    let per_column = total.div_ceil(2);
+    let columns = buffer.layout_columns(120.0, 2, 10.0, per_column as f32 * 20.0 + 5.0);
+    assert_eq!(columns.overflow_opt, None);
+    let [first, second] = &columns.columns[..] else {
+        panic!("expected two columns");
+    };
+    assert_eq!(first.x, 0.0);
+    assert_eq!(second.x, 130.0);
+    assert_eq!(first.lines.len(), per_column);
+    assert_eq!(second.lines.len(), total - per_column);
+    assert_eq!(first.height, per_column as f32 * 20.0);
+
+    // Lines continue in order at the top of the second column
+    assert_eq!(second.lines[0].y, 0.0);
+    assert_eq!(second.lines[0].line_i, 0);
+    assert_eq!(second.lines[0].layout_i, per_column);
+    for (i, line) in first.lines.iter().enumerate() {
+        assert_eq!((line.layout_i, line.y), (i, i as f32 * 20.0));
+    }
+}
+
+#[test]
+fn layout_columns_overflow() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text(
+        "one\ntwo\nthree\nfour\nfive",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    // Two lines per column
+    let columns = buffer.layout_columns(100.0, 2, 10.0, 45.0);
+    assert_eq!(columns.columns[0].lines.len(), 2);
+    assert_eq!(columns.columns[1].lines.len(), 2);
+    assert_eq!(columns.overflow_opt, Some(Cursor::new(4, 0)));
+}
@@ -0,0 +1,71 @@
+use cosmic_text::{
+    Affinity, Attrs, AttrsList, BufferLine, FontSystem, LayoutLine, LineEnding, Shaping, Wrap,
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+fn layout(text: &str) -> LayoutLine {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let layout = line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+    assert_eq!(layout.len(), 1);
+    layout[0].clone()
+}
+
+#[test]
+fn hit_bidi_boundary() {
+    let text = "abc مرحبا def";
+    let arabic = text.find('م').unwrap()..text.find(" def").unwrap();
+    let line = layout(text);
+
+    // The space before the Arabic word is visually followed by the logically last Arabic letter
+    let space = line.glyphs.iter().find(|glyph| glyph.start == 3).unwrap();
+    let boundary = space.x + space.w;
+    let next = line
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.x >= boundary - 0.01)
+        .unwrap();
+    assert!(next.level.is_rtl());
+    assert_eq!(next.end, arabic.end);
+
+    assert_eq!(line.hit(text, boundary - 0.5), Some((4, Affinity::Before)));
+    assert_eq!(
+        line.hit(text, boundary + 0.5),
+        Some((arabic.end, Affinity::Before))
+    );
+}
+
+#[test]
+fn hit_outside_line() {
+    let text = "abc مرحبا def";
+    let line = layout(text);
+
+    assert_eq!(line.hit(text, -10.0), Some((0, Affinity::After)));
+    assert_eq!(
+        line.hit(text, line.w + 10.0),
+        Some((text.len(), Affinity::Before))
+    );
+    assert_eq!(layout("").hit("", 0.0), None);
+}
+
+#[test]
+fn grapheme_extents_round_trip() {
+    for text in ["abc مرحبا def", "ffi e\u{301} שלום"] {
+        let line = layout(text);
+        for (index, egc) in text.grapheme_indices(true) {
+            let (left, right) = line.grapheme_extents(text, index).unwrap();
+            assert!(left < right, "{text:?} {index}");
+
+            let (hit, _) = line.hit(text, (left + right) / 2.0).unwrap();
+            assert!(
+                hit == index || hit == index + egc.len(),
+                "{text:?} index {index} hit {hit}"
+            );
+        }
+    }
+}
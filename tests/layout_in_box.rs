@@ -0,0 +1,134 @@
+use cosmic_text::{
+    Align, Attrs, AttrsList, BufferLine, EllipsisSource, FontSystem, LayoutLine, LineEnding,
+    Overflow, Shaping, Wrap,
+};
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn layout_in_box_clamps_with_ellipsis() {
+    let mut font_system = FontSystem::new();
+    let mut line = line(
+        "The quick brown fox jumps over the lazy dog, then runs far away into the quiet woods.",
+    );
+    let layout = line.layout_in_box(
+        &mut font_system,
+        14.0,
+        120.0,
+        2,
+        &EllipsisSource::HorizontalEllipsis,
+        8,
+    );
+    assert!(layout.truncated);
+    assert_eq!(layout.lines.len(), 2);
+    for layout_line in layout.lines.iter() {
+        assert!(layout_line.w <= 120.0, "{}", layout_line.w);
+    }
+
+    // The first line is untouched, and the last one ends with the ellipsis
+    let first = &layout.lines[0];
+    assert!(first.glyphs.iter().all(|glyph| glyph.start < glyph.end));
+    let last = layout.lines[1].glyphs.last().unwrap();
+    assert_eq!(last.start, last.end);
+    let kept_end = layout.lines[1]
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.start < glyph.end)
+        .map(|glyph| glyph.end)
+        .max()
+        .unwrap();
+    assert!(kept_end < line.text().len());
+
+    // The cached layout of the line is not changed
+    assert!(line.layout_opt().is_none());
+}
+
+#[test]
+fn layout_in_box_fits() {
+    let mut font_system = FontSystem::new();
+    let mut line = line("Short label");
+    let layout = line.layout_in_box(
+        &mut font_system,
+        14.0,
+        200.0,
+        2,
+        &EllipsisSource::HorizontalEllipsis,
+        8,
+    );
+    assert!(!layout.truncated);
+    assert_eq!(layout.lines.len(), 1);
+    assert!(layout.lines[0]
+        .glyphs
+        .iter()
+        .all(|glyph| glyph.start < glyph.end));
+}
+
+#[test]
+fn layout_in_box_keeps_graphemes() {
+    let mut font_system = FontSystem::new();
+    // Combining acute accents after each letter, with no break opportunities
+    let text = "e\u{301}".repeat(40);
+    let mut line = line(&text);
+    let layout = line.layout_in_box(
+        &mut font_system,
+        14.0,
+        60.0,
+        1,
+        &EllipsisSource::HorizontalEllipsis,
+        8,
+    );
+    assert!(layout.truncated);
+    assert_eq!(layout.lines.len(), 1);
+    for glyph in layout.lines[0].glyphs.iter() {
+        assert!(text.is_char_boundary(glyph.start));
+        assert_eq!(glyph.start % 3, 0, "{}", glyph.start);
+        assert_eq!(glyph.end % 3, 0, "{}", glyph.end);
+    }
+}
+
+#[test]
+fn layout_in_box_matches_layout() {
+    let mut font_system = FontSystem::new();
+    let mut line = line(
+        "The quick brown fox jumps over the lazy dog, then runs far away into the quiet woods.",
+    );
+    line.set_align(Some(Align::Center));
+    let boxed = line.layout_in_box(
+        &mut font_system,
+        14.0,
+        120.0,
+        2,
+        &EllipsisSource::HorizontalEllipsis,
+        8,
+    );
+
+    // The line settings apply, like a layout with the same overflow mode
+    line.set_overflow(Overflow::Truncate {
+        max_lines: 2,
+        ellipsis: EllipsisSource::HorizontalEllipsis,
+    });
+    let layout = line.layout(
+        &mut font_system,
+        14.0,
+        Some(120.0),
+        Wrap::WordOrGlyph,
+        None,
+        8,
+    );
+    let positions = |lines: &[LayoutLine]| -> Vec<(usize, usize, f32)> {
+        lines
+            .iter()
+            .flat_map(|layout_line| layout_line.glyphs.iter())
+            .map(|glyph| (glyph.start, glyph.end, glyph.x))
+            .collect()
+    };
+    assert_eq!(positions(&boxed.lines), positions(layout));
+    assert!(boxed.lines[0].glyphs[0].x > 0.0);
+}
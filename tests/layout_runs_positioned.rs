@@ -0,0 +1,34 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Metrics, Shaping, Wrap};
+
+#[test]
+fn layout_runs_positioned_mixed_heights() {
+    let mut font_system = FontSystem::new();
+    let text = "small words here BIG WORDS HERE small again";
+    let big_start = text.find("BIG").unwrap();
+    let big_end = text.find(" small again").unwrap();
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(
+        big_start..big_end,
+        &Attrs::new().metrics(Metrics::new(30.0, 40.0)),
+    );
+    let mut line = BufferLine::new(text, LineEnding::None, attrs_list, Shaping::Advanced);
+
+    // Not laid out yet
+    assert_eq!(line.layout_runs_positioned(20.0).count(), 0);
+
+    line.layout(&mut font_system, 14.0, Some(120.0), Wrap::Word, None, 8);
+    let positioned: Vec<_> = line.layout_runs_positioned(20.0).collect();
+    let layout = line.layout_opt().unwrap();
+    assert_eq!(positioned.len(), layout.len());
+    assert!(layout.len() > 2);
+    assert!(layout
+        .iter()
+        .any(|layout_line| layout_line.line_height_opt == Some(40.0)));
+
+    let mut y = 0.0;
+    for ((top, layout_line), expected) in positioned.iter().zip(layout.iter()) {
+        assert!(core::ptr::eq(*layout_line, expected));
+        assert_eq!(*top, y);
+        y += expected.line_height_opt.unwrap_or(20.0);
+    }
+}
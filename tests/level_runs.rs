@@ -0,0 +1,55 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn level_runs_mixed_direction() {
+    let mut font_system = FontSystem::new();
+    let text = "abc אבג def";
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let layout = line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+    assert_eq!(layout.len(), 1);
+    let layout_line = &layout[0];
+    let runs: Vec<_> = layout_line.level_runs().collect();
+
+    // The Hebrew word is a right-to-left run between two left-to-right runs
+    assert_eq!(runs.len(), 3);
+    let hebrew = text.find('א').unwrap();
+    let hebrew_end = hebrew + "אבג".len();
+    assert_eq!(runs[0].range, 0..hebrew);
+    assert_eq!(runs[1].range, hebrew..hebrew_end);
+    assert_eq!(runs[2].range, hebrew_end..text.len());
+    assert!(runs[0].level.is_ltr());
+    assert!(runs[1].level.is_rtl());
+    assert!(runs[2].level.is_ltr());
+
+    // The runs are adjacent, in visual order, and cover the whole line
+    let height = layout_line.max_ascent + layout_line.max_descent;
+    assert_eq!(runs[0].rect.x, 0.0);
+    for pair in runs.windows(2) {
+        assert!((pair[0].rect.right() - pair[1].rect.x).abs() < 0.01);
+    }
+    let last = layout_line.glyphs.last().unwrap();
+    assert!((runs[2].rect.right() - (last.x + last.w)).abs() < 0.01);
+    for run in &runs {
+        assert_eq!(run.rect.y, 0.0);
+        assert_eq!(run.rect.height, height);
+        let (left, right) = layout_line.visual_extents(run.range.clone()).unwrap();
+        assert!((run.rect.x - left).abs() < 0.01);
+        assert!((run.rect.right() - right).abs() < 0.01);
+    }
+    let glyphs: usize = runs
+        .iter()
+        .map(|run| {
+            layout_line
+                .glyphs
+                .iter()
+                .filter(|glyph| run.range.contains(&glyph.start))
+                .count()
+        })
+        .sum();
+    assert_eq!(glyphs, layout_line.glyphs.len());
+}
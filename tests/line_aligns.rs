@@ -0,0 +1,44 @@
+use cosmic_text::{Align, Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn line_aligns_per_visual_line() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "aaaa bbbbbbbb cc",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let word_width = {
+        let layout = line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+        layout[0].w
+    };
+    // Narrow enough to put each word on its own line
+    let width = word_width / 2.0 + 1.0;
+
+    assert!(line.set_line_aligns(vec![
+        Some(Align::Left),
+        Some(Align::Center),
+        Some(Align::Right),
+    ]));
+    assert!(!line.set_line_aligns(vec![
+        Some(Align::Left),
+        Some(Align::Center),
+        Some(Align::Right),
+    ]));
+
+    let layout = line.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8);
+    assert_eq!(layout.len(), 3);
+    let left_edge = |index: usize| layout[index].glyphs[0].x;
+    assert_eq!(left_edge(0), 0.0);
+    assert!((left_edge(1) - (width - layout[1].w) / 2.0).abs() < 0.01);
+    assert!((left_edge(2) - (width - layout[2].w)).abs() < 0.01);
+
+    // Missing entries use the line alignment
+    line.set_line_aligns(vec![None, Some(Align::Left)]);
+    line.set_align(Some(Align::Right));
+    let layout = line.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8);
+    assert!((layout[0].glyphs[0].x - (width - layout[0].w)).abs() < 0.01);
+    assert_eq!(layout[1].glyphs[0].x, 0.0);
+    assert!((layout[2].glyphs[0].x - (width - layout[2].w)).abs() < 0.01);
+}
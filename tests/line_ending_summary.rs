@@ -0,0 +1,36 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, BufferLine, LineEnding, Metrics, Shaping};
+
+#[test]
+fn line_ending_summary_and_normalize() {
+    let mut buffer = Buffer::new_empty(Metrics::new(14.0, 20.0));
+    for ending in [
+        LineEnding::Lf,
+        LineEnding::CrLf,
+        LineEnding::Lf,
+        LineEnding::Cr,
+        LineEnding::None,
+    ] {
+        buffer.lines.push(BufferLine::new(
+            "line",
+            ending,
+            AttrsList::new(&Attrs::new()),
+            Shaping::Advanced,
+        ));
+    }
+
+    let summary = buffer.line_ending_summary();
+    assert!(summary.is_mixed());
+    assert_eq!(summary.count(LineEnding::Lf), 2);
+    assert_eq!(summary.count(LineEnding::CrLf), 1);
+    assert_eq!(summary.count(LineEnding::Cr), 1);
+    assert_eq!(summary.count(LineEnding::LfCr), 0);
+    assert_eq!(summary.count(LineEnding::None), 1);
+
+    assert_eq!(buffer.normalize_line_endings(LineEnding::CrLf), 3);
+    let summary = buffer.line_ending_summary();
+    assert!(!summary.is_mixed());
+    assert_eq!(summary.count(LineEnding::CrLf), 4);
+    assert_eq!(summary.count(LineEnding::None), 1);
+
+    assert_eq!(buffer.normalize_line_endings(LineEnding::CrLf), 0);
+}
@@ -0,0 +1,31 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn line_fingerprints_single_edit() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "two\none\ntwo\nthree",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let before = buffer.line_fingerprints().to_vec();
+    assert_eq!(before.len(), 4);
+    // Lines with equal contents have equal fingerprints
+    assert_eq!(before[0], before[2]);
+    assert_ne!(before[0], before[1]);
+    // Stable between edits
+    assert_eq!(buffer.line_fingerprints(), &before[..]);
+
+    let line = &mut buffer.lines[1];
+    let (ending, attrs_list) = (line.ending(), line.attrs_list().clone());
+    line.set_text("one!", ending, attrs_list);
+
+    let after = buffer.line_fingerprints();
+    assert_eq!(after.len(), 4);
+    for (line_i, (before, after)) in before.iter().zip(after).enumerate() {
+        assert_eq!(before == after, line_i != 1, "line {line_i}");
+    }
+}
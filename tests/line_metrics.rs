@@ -0,0 +1,65 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn mixed_font_sizes_share_baseline() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(12.0, 16.0));
+    let big = Metrics::new(48.0, 60.0);
+    buffer.set_rich_text(
+        &mut font_system,
+        [("small ", Attrs::new()), ("BIG", Attrs::new().metrics(big))],
+        &Attrs::new(),
+        Shaping::Advanced,
+        None,
+    );
+    buffer.shape_until_scroll(&mut font_system, false);
+
+    let layout_line = &buffer.lines[0].layout_opt().unwrap()[0];
+    let small = &layout_line.glyphs[0];
+    let large = layout_line.glyphs.last().unwrap();
+    assert_eq!(small.font_size, 12.0);
+    assert_eq!(large.font_size, 48.0);
+
+    // The vertical metrics come from the 48px run
+    let shape = buffer.lines[0].shape_opt().unwrap();
+    let large_shape = shape.spans[0].words.last().unwrap().glyphs.last().unwrap();
+    assert_eq!(layout_line.max_ascent, 48.0 * large_shape.ascent);
+    assert_eq!(layout_line.max_descent, 48.0 * large_shape.descent);
+    assert_eq!(layout_line.max_line_gap, 48.0 * large_shape.line_gap);
+    assert_eq!(
+        layout_line.natural_height(),
+        layout_line.max_ascent + layout_line.max_descent + layout_line.max_line_gap
+    );
+    assert!(layout_line.natural_height() > 48.0);
+    assert_eq!(layout_line.line_height_opt, Some(60.0));
+
+    // Both runs sit on the baseline of the line
+    let run = buffer.layout_runs().next().unwrap();
+    assert_eq!(run.line_height, 60.0);
+    assert_eq!(run.line_y, layout_line.baseline(60.0));
+    let physical = |glyph: &cosmic_text::LayoutGlyph| glyph.physical((0.0, run.line_y), 1.0).y;
+    assert_eq!(small.y, 0.0);
+    assert_eq!(large.y, 0.0);
+    assert_eq!(physical(small), physical(large));
+}
+
+#[test]
+fn line_metrics_without_glyphs() {
+    let mut font_system = FontSystem::new();
+    let mut line = cosmic_text::BufferLine::new(
+        "",
+        cosmic_text::LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let layout = line.layout(
+        &mut font_system,
+        14.0,
+        None,
+        cosmic_text::Wrap::None,
+        None,
+        8,
+    );
+    assert_eq!(layout[0].natural_height(), 0.0);
+    assert_eq!(layout[0].baseline(20.0), 10.0);
+}
@@ -0,0 +1,28 @@
+use cosmic_text::{Attrs, AttrsList, Buffer, FontSystem, Metrics, Scroll, Shaping};
+
+#[test]
+fn lines_in_height_mixed_line_heights() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(10.0, 10.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("a\nb\nc\nd\ne\nf", &Attrs::new(), Shaping::Advanced);
+
+    // Make the second line 30px tall
+    let tall = Attrs::new().metrics(Metrics::new(10.0, 30.0));
+    buffer.lines[1].set_attrs_list(AttrsList::new(&tall));
+    buffer.shape_until_scroll(false);
+
+    // 10 + 30 = 40px for two lines
+    assert_eq!(buffer.lines_in_height(40.0), 2);
+    // A partially visible third line counts
+    assert_eq!(buffer.lines_in_height(41.0), 3);
+    assert_eq!(buffer.lines_in_height(1.0), 1);
+    assert_eq!(buffer.lines_in_height(0.0), 0);
+    // 10 + 30 + 4 * 10 = 80px for everything
+    assert_eq!(buffer.lines_in_height(1000.0), 6);
+
+    // Counting starts at the scroll position, including a partially scrolled line
+    buffer.set_scroll(Scroll::new(1, 25.0, 0.0));
+    assert_eq!(buffer.lines_in_height(10.0), 2);
+    assert_eq!(buffer.lines_in_height(5.0), 1);
+}
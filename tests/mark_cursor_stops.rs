@@ -0,0 +1,62 @@
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, Motion, Shaping};
+
+fn stops(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    motion: Motion,
+    start: usize,
+) -> Vec<usize> {
+    let mut cursor = Cursor::new(0, start);
+    let mut indices = Vec::new();
+    while let Some((next, _)) = buffer.cursor_motion(font_system, cursor, None, motion) {
+        if next.index == cursor.index {
+            break;
+        }
+        cursor = next;
+        indices.push(cursor.index);
+    }
+    indices
+}
+
+#[test]
+fn mark_cursor_stops_between_base_and_mark() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    // "e" + combining acute, then "x"
+    buffer.set_text(
+        &mut font_system,
+        "e\u{301}x",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    // Graphemes by default
+    assert_eq!(
+        stops(&mut buffer, &mut font_system, Motion::Next, 0),
+        [3, 4]
+    );
+    assert_eq!(
+        stops(&mut buffer, &mut font_system, Motion::Previous, 4),
+        [3, 0]
+    );
+
+    // A stop between the base and the mark
+    buffer.set_mark_cursor_stops(true);
+    assert_eq!(
+        stops(&mut buffer, &mut font_system, Motion::Next, 0),
+        [1, 3, 4]
+    );
+    assert_eq!(
+        stops(&mut buffer, &mut font_system, Motion::Previous, 4),
+        [3, 1, 0]
+    );
+
+    // Joiners are not marks, emoji sequences stay whole
+    buffer.set_text(
+        &mut font_system,
+        "\u{1F469}\u{200D}\u{1F52C}",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    assert_eq!(stops(&mut buffer, &mut font_system, Motion::Next, 0), [11]);
+}
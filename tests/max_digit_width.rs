@@ -0,0 +1,53 @@
+use cosmic_text::{fontdb, Attrs, AttrsList, Family, FontSystem, Shaping, Wrap};
+
+/// Noto Sans and Inter from the repository fonts
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    for path in ["fonts/NotoSans-Regular.ttf", "fonts/Inter-Regular.ttf"] {
+        let font = std::fs::read(path).unwrap();
+        font_system.db_mut().load_font_data(font);
+    }
+    font_system
+}
+
+#[test]
+fn max_digit_width_matches_shaped_digits() {
+    let mut font_system = font_system();
+    for attrs in [
+        Attrs::new(),
+        Attrs::new().family(Family::Name("Inter")),
+        Attrs::new().fixed_digit_width(Some(0.8)),
+    ] {
+        for font_size in [12.0, 20.0] {
+            let expected = ('0'..='9')
+                .map(|digit| {
+                    font_system
+                        .shape_into_buffer(
+                            &digit.to_string(),
+                            &AttrsList::new(&attrs),
+                            Shaping::Advanced,
+                            8,
+                        )
+                        .layout(font_size, None, Wrap::None, None, None)[0]
+                        .w
+                })
+                .fold(0.0, f32::max);
+            let measured = font_system.max_digit_width(&attrs, font_size);
+            assert!(expected > 0.0);
+            assert!(
+                (measured - expected).abs() < 0.01,
+                "{font_size}: {measured} != {expected}"
+            );
+        }
+    }
+}
+
+#[test]
+fn max_digit_width_cached() {
+    let mut font_system = font_system();
+    let attrs = Attrs::new();
+    let first = font_system.max_digit_width(&attrs, 16.0);
+    assert_eq!(font_system.max_digit_width(&attrs, 16.0), first);
+    assert_eq!(font_system.max_digit_width(&attrs, 32.0), first * 2.0);
+}
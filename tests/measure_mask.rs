@@ -0,0 +1,22 @@
+use cosmic_text::{Attrs, AttrsList, FontSystem, Shaping, Wrap};
+
+#[test]
+fn measure_mask_matches_shaped_dots() {
+    let mut font_system = FontSystem::new();
+    let attrs = Attrs::new();
+    for (mask, count) in [('\u{2022}', 8), ('*', 3), ('\u{2022}', 0)] {
+        let text: String = core::iter::repeat(mask).take(count).collect();
+        let shaped = font_system
+            .shape_into_buffer(&text, &AttrsList::new(&attrs), Shaping::Advanced, 8)
+            .layout(16.0, None, Wrap::None, None, None);
+        let expected = shaped[0].w;
+        let measured = font_system.measure_mask(mask, count, &attrs, 16.0);
+        assert!(
+            (measured - expected).abs() < 0.01,
+            "{mask:?} x {count}: {measured} != {expected}"
+        );
+    }
+    // Cached advances give the same result
+    let first = font_system.measure_mask('\u{2022}', 5, &attrs, 16.0);
+    assert_eq!(font_system.measure_mask('\u{2022}', 5, &attrs, 16.0), first);
+}
@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+use unicode_script::Script;
+
+#[test]
+fn missing_coverage_groups_by_script() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("covered\nabc 𓀀𓀁 def", &Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+
+    // Egyptian hieroglyphs are not covered by any of the usual system fonts
+    let missing = buffer.missing_coverage();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].line_i, 1);
+    let text = buffer.lines[1].text();
+    assert_eq!(&text[missing[0].range.clone()], "𓀀𓀁");
+    assert_eq!(missing[0].script, Script::Egyptian_Hieroglyphs);
+}
+
+#[test]
+fn missing_coverage_capped() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    // Twenty separate ranges of hieroglyphs, then one of cuneiform
+    let text = format!("{}𒀀", "𓀀 a ".repeat(20));
+    buffer.set_text(&text, &Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+    assert_eq!(buffer.missing_coverage().len(), 21);
+
+    let capped = buffer.missing_coverage_capped(5);
+    assert_eq!(capped.ranges, buffer.missing_coverage()[..5]);
+    assert_eq!(capped.more, 16);
+    // The cuneiform range is left out, but its script is still reported
+    assert_eq!(
+        capped.scripts,
+        [Script::Egyptian_Hieroglyphs, Script::Cuneiform]
+    );
+
+    let all = buffer.missing_coverage_capped(100);
+    assert_eq!(all.ranges.len(), 21);
+    assert_eq!(all.more, 0);
+}
@@ -0,0 +1,111 @@
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, Family, FamilyOwned, FontSystem, MissingFamily, ShapeLine, Shaping,
+    Weight,
+};
+
+const TEXT: &str = "one two three";
+
+/// Only the given fonts from the repository
+fn font_system(paths: &[&str]) -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    for path in paths {
+        let font = std::fs::read(path).unwrap();
+        font_system.db_mut().load_font_data(font);
+    }
+    font_system
+}
+
+fn shape(font_system: &mut FontSystem, shaping: Shaping) -> ShapeLine {
+    let mut attrs_list = AttrsList::new(&Attrs::new().family(Family::Name("Noto Sans")));
+    attrs_list.add_span(4..7, &Attrs::new().family(Family::Name("No Such Family")));
+    ShapeLine::new(font_system, TEXT, &attrs_list, shaping, 8)
+}
+
+fn families(font_system: &FontSystem, line: &ShapeLine) -> Vec<(usize, String)> {
+    line.spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .flat_map(|word| word.glyphs.iter())
+        .map(|glyph| {
+            let face = font_system.db().face(glyph.font_id).unwrap();
+            (glyph.start, face.families[0].0.clone())
+        })
+        .collect()
+}
+
+#[test]
+fn missing_family_fallback_is_silent() {
+    let mut font_system = font_system(&["fonts/NotoSans-Regular.ttf"]);
+    assert_eq!(font_system.missing_family(), &MissingFamily::Fallback);
+
+    let line = shape(&mut font_system, Shaping::Advanced);
+    assert!(line.missing_family_ranges().is_empty());
+}
+
+#[test]
+fn missing_family_error_flags_span() {
+    let mut font_system = font_system(&["fonts/NotoSans-Regular.ttf"]);
+    font_system.set_missing_family(MissingFamily::Error);
+
+    for shaping in [Shaping::Basic, Shaping::Advanced] {
+        let line = shape(&mut font_system, shaping);
+        let ranges = line.missing_family_ranges();
+        assert_eq!(ranges.len(), 1, "{shaping:?}");
+        assert_eq!(ranges[0], 4..7, "{shaping:?}");
+    }
+}
+
+#[test]
+fn missing_family_substitute() {
+    let mut font_system = font_system(&["fonts/NotoSans-Regular.ttf", "fonts/Inter-Regular.ttf"]);
+    font_system.set_missing_family(MissingFamily::Substitute(FamilyOwned::Name("Inter".into())));
+
+    let line = shape(&mut font_system, Shaping::Advanced);
+    assert!(line.missing_family_ranges().is_empty());
+    for (start, family) in families(&font_system, &line) {
+        let expected = if (4..7).contains(&start) {
+            "Inter"
+        } else {
+            "Noto Sans"
+        };
+        assert_eq!(family, expected, "{start}");
+    }
+}
+
+#[test]
+fn missing_family_error_accepts_other_weight() {
+    let mut font_system = font_system(&["fonts/NotoSans-Regular.ttf"]);
+    font_system.set_missing_family(MissingFamily::Error);
+
+    // Only the regular face is loaded, which is used with synthetic bold
+    let attrs = Attrs::new()
+        .family(Family::Name("Noto Sans"))
+        .weight(Weight::BOLD);
+    let line = ShapeLine::new(
+        &mut font_system,
+        "Hello",
+        &AttrsList::new(&attrs),
+        Shaping::Advanced,
+        8,
+    );
+    assert!(line.missing_family_ranges().is_empty());
+    for (_, family) in families(&font_system, &line) {
+        assert_eq!(family, "Noto Sans");
+    }
+}
+
+#[test]
+fn missing_family_error_sees_loaded_fonts() {
+    let mut font_system = font_system(&["fonts/NotoSans-Regular.ttf"]);
+    font_system.set_missing_family(MissingFamily::Error);
+
+    let attrs_list = AttrsList::new(&Attrs::new().family(Family::Name("Inter")));
+    let line = ShapeLine::new(&mut font_system, TEXT, &attrs_list, Shaping::Advanced, 8);
+    assert_eq!(line.missing_family_ranges(), vec![0..TEXT.len()]);
+
+    let font = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    let line = ShapeLine::new(&mut font_system, TEXT, &attrs_list, Shaping::Advanced, 8);
+    assert!(line.missing_family_ranges().is_empty());
+}
@@ -0,0 +1,58 @@
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, BufferLine, Family, FontSystem, LayoutGlyph, LineEnding, Shaping,
+    Wrap,
+};
+
+fn glyphs(text: &str, attrs_list: AttrsList) -> Vec<LayoutGlyph> {
+    // Fira Mono, Noto Sans and Noto Sans Hebrew from the repository fonts
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    for path in [
+        "fonts/FiraMono-Medium.ttf",
+        "fonts/NotoSans-Regular.ttf",
+        "fonts/NotoSansHebrew.ttf",
+    ] {
+        let font = std::fs::read(path).unwrap();
+        font_system.db_mut().load_font_data(font);
+    }
+    let mut line = BufferLine::new(text, LineEnding::None, attrs_list, Shaping::Advanced);
+    line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8)[0]
+        .glyphs
+        .clone()
+}
+
+fn glyph_at(glyphs: &[LayoutGlyph], index: usize) -> &LayoutGlyph {
+    glyphs.iter().find(|glyph| glyph.start == index).unwrap()
+}
+
+#[test]
+fn no_fallback_cjk_notdef() {
+    let attrs = Attrs::new()
+        .family(Family::Name("Noto Sans"))
+        .no_fallback(true);
+    let glyphs = glyphs("a中b", AttrsList::new(&attrs));
+    let a = glyph_at(&glyphs, 0);
+    let han = glyph_at(&glyphs, 1);
+    assert_eq!(han.glyph_id, 0);
+    assert_eq!(han.font_id, a.font_id);
+    assert_eq!(glyph_at(&glyphs, 4).font_id, a.font_id);
+}
+
+#[test]
+fn no_fallback_per_span() {
+    // Fira Mono has no Hebrew, Noto Sans Hebrew does
+    let mono = Attrs::new().family(Family::Name("Fira Mono"));
+    let text = "\u{5D0} \u{5D0}";
+
+    let fallback = glyphs(text, AttrsList::new(&mono));
+    assert_ne!(glyph_at(&fallback, 0).glyph_id, 0);
+
+    let mut attrs_list = AttrsList::new(&mono);
+    attrs_list.add_span(0..2, &mono.clone().no_fallback(true));
+    let glyphs = glyphs(text, attrs_list);
+    let strict = glyph_at(&glyphs, 0);
+    let fallback = glyph_at(&glyphs, 3);
+    assert_eq!(strict.glyph_id, 0);
+    assert_ne!(fallback.glyph_id, 0);
+    assert_ne!(strict.font_id, fallback.font_id);
+}
@@ -0,0 +1,32 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, Color, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn opacity_scales_draw_color_alpha() {
+    let mut font_system = FontSystem::new();
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(
+        2..4,
+        &Attrs::new()
+            .color(Color::rgba(0xFF, 0, 0, 200))
+            .opacity(0.5),
+    );
+    let mut line = BufferLine::new("abcd", LineEnding::None, attrs_list, Shaping::Advanced);
+
+    let glyphs = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0].glyphs;
+    let default = Color::rgb(0, 0, 0);
+    assert_eq!(glyphs[0].draw_color(default), default);
+    // Composes with the alpha of the span color
+    assert_eq!(glyphs[2].draw_color(default), Color::rgba(0xFF, 0, 0, 100));
+    assert_eq!(glyphs[3].draw_color(default), Color::rgba(0xFF, 0, 0, 100));
+
+    // Changing only the opacity keeps the shaping
+    let mut attrs_list = AttrsList::new(&Attrs::new().opacity(0.25));
+    attrs_list.add_span(2..4, &Attrs::new().color(Color::rgba(0xFF, 0, 0, 200)));
+    assert!(line.set_attrs_list(attrs_list));
+    assert!(line.shape_is_current(&font_system, 8));
+    assert!(line.layout_opt().is_none());
+
+    let glyphs = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0].glyphs;
+    assert_eq!(glyphs[0].draw_color(default), Color::rgba(0, 0, 0, 64));
+    assert_eq!(glyphs[2].draw_color(default), Color::rgba(0xFF, 0, 0, 200));
+}
@@ -0,0 +1,77 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Rect, Shaping, Wrap};
+
+fn laid_out(font_system: &mut FontSystem, text: &str) -> BufferLine {
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.layout(font_system, 14.0, None, Wrap::None, None, 8);
+    line
+}
+
+#[test]
+fn overtype_caret_rect_ligature() {
+    let mut font_system = FontSystem::new();
+    let line = laid_out(&mut font_system, "office");
+    let glyphs = &line.layout_opt().unwrap()[0].glyphs;
+
+    // The "ffi" ligature, when the font has one
+    let cluster: Vec<_> = glyphs
+        .iter()
+        .filter(|glyph| glyph.start <= 1 && 1 < glyph.end)
+        .collect();
+    let left = cluster.iter().map(|glyph| glyph.x).fold(f32::MAX, f32::min);
+    let right = cluster
+        .iter()
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    let expected = Rect::new(left, 0.0, right - left, 20.0);
+    assert_eq!(line.overtype_caret_rect(1, 20.0), Some(expected));
+    // Any index inside the cluster covers the whole cluster
+    let end = cluster[0].end;
+    assert_eq!(line.overtype_caret_rect(end - 1, 20.0), Some(expected));
+}
+
+#[test]
+fn overtype_caret_rect_line_end() {
+    let mut font_system = FontSystem::new();
+
+    let line = laid_out(&mut font_system, "abc");
+    let right = line.layout_opt().unwrap()[0]
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    assert_eq!(
+        line.overtype_caret_rect(3, 20.0),
+        Some(Rect::new(right, 0.0, 10.0, 20.0))
+    );
+
+    // RTL lines put the block on the left of the text
+    let line = laid_out(&mut font_system, "שלום");
+    let rect = line.overtype_caret_rect("שלום".len(), 20.0).unwrap();
+    let left = line.layout_opt().unwrap()[0]
+        .glyphs
+        .iter()
+        .map(|glyph| glyph.x)
+        .fold(f32::MAX, f32::min);
+    assert_eq!(rect, Rect::new(left - 10.0, 0.0, 10.0, 20.0));
+
+    // The first character of an RTL line is on the right
+    let first = line.overtype_caret_rect(0, 20.0).unwrap();
+    assert!(first.x > rect.x);
+    assert!(line.layout_opt().unwrap()[0]
+        .glyphs
+        .iter()
+        .all(|glyph| glyph.x + glyph.w <= first.right() + 0.01));
+
+    let unlaid = BufferLine::new(
+        "abc",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    assert_eq!(unlaid.overtype_caret_rect(0, 20.0), None);
+}
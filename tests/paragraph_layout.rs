@@ -0,0 +1,60 @@
+use cosmic_text::{Align, Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn paragraph_layout_steps_by_line_height() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "one two three four five six",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_align(Some(Align::Center));
+    let width = 90.0;
+    let layout_lines = line
+        .layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8)
+        .to_vec();
+    assert_eq!(layout_lines.len(), 3);
+
+    let paragraph = line.paragraph_layout(20.0);
+    assert_eq!(paragraph.lines.len(), 3);
+    assert_eq!(paragraph.h, 60.0);
+    assert_eq!(
+        paragraph.glyphs.len(),
+        layout_lines
+            .iter()
+            .map(|layout_line| layout_line.glyphs.len())
+            .sum::<usize>()
+    );
+
+    let first_y = paragraph.glyphs[0].y;
+    for glyph in paragraph.glyphs.iter() {
+        assert_eq!(glyph.y, first_y + 20.0 * glyph.line as f32);
+        assert_eq!(glyph.x, glyph.glyph.x);
+    }
+    for (line_i, line) in paragraph.lines.iter().enumerate() {
+        assert_eq!(line.top, 20.0 * line_i as f32);
+        assert!(line.baseline > line.top && line.baseline < line.top + line.height);
+        // Centered lines keep their alignment offset
+        let first = paragraph
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.line == line_i)
+            .unwrap();
+        assert!((first.x - (width - line.w) / 2.0).abs() < 1.0);
+    }
+}
+
+#[test]
+fn paragraph_layout_without_layout() {
+    let line = BufferLine::new(
+        "text",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let paragraph = line.paragraph_layout(20.0);
+    assert!(paragraph.lines.is_empty());
+    assert!(paragraph.glyphs.is_empty());
+    assert_eq!(paragraph.h, 0.0);
+}
@@ -0,0 +1,54 @@
+use cosmic_text::{
+    Attrs, AttrsList, BaseDirection, Buffer, Color, Cursor, Direction, Edit, Editor, Family,
+    FontSystem, Metrics, Shaping,
+};
+
+fn buffer(font_system: &mut FontSystem, text: &str) -> Buffer {
+    let mut buffer = Buffer::new_empty(Metrics::new(14.0, 20.0));
+    buffer.set_text(font_system, text, &Attrs::new(), Shaping::Advanced);
+    buffer
+}
+
+#[test]
+fn plain_ascii_flips_on_insert() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = buffer(&mut font_system, "fn main() {\n\tlet x = 1;\n}\n");
+    assert!(buffer.is_plain_ascii());
+
+    let mut editor = Editor::new(&mut buffer);
+    editor.set_cursor(Cursor::new(1, 5));
+    editor.insert_string("é", None);
+    drop(editor);
+    assert!(!buffer.is_plain_ascii());
+    assert!(!buffer.lines[1].is_ascii_fast_path());
+    assert!(buffer.lines[0].is_ascii_fast_path());
+}
+
+#[test]
+fn plain_ascii_direction_and_fonts() {
+    let mut font_system = FontSystem::new();
+
+    let mut rtl = buffer(&mut font_system, "abc\ndef");
+    rtl.lines[1].set_base_direction(BaseDirection::Explicit(Direction::Rtl));
+    assert!(!rtl.is_plain_ascii());
+
+    // Colors do not change the font, other families do
+    let mut colored = buffer(&mut font_system, "abc\ndef");
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(0..1, &Attrs::new().color(Color::rgb(255, 0, 0)));
+    colored.lines[0].set_attrs_list(attrs_list);
+    assert!(colored.is_plain_ascii());
+
+    let mut mono = buffer(&mut font_system, "abc\ndef");
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(0..1, &Attrs::new().family(Family::Monospace));
+    mono.lines[0].set_attrs_list(attrs_list);
+    assert!(!mono.is_plain_ascii());
+
+    let mut line_family = buffer(&mut font_system, "abc\ndef");
+    line_family.lines[1].set_attrs_list(AttrsList::new(&Attrs::new().family(Family::Serif)));
+    assert!(line_family.lines[1].is_ascii_fast_path());
+    assert!(!line_family.is_plain_ascii());
+
+    assert!(Buffer::new_empty(Metrics::new(14.0, 20.0)).is_plain_ascii());
+}
@@ -0,0 +1,126 @@
+use std::sync::{Arc, Mutex};
+
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, Color, FontSystem, Invalidation, LineEnding, PushResult,
+    ShapeLine, Shaping,
+};
+
+fn glyphs(shape: &ShapeLine) -> Vec<(usize, usize, u16, f32)> {
+    shape
+        .spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .flat_map(|word| word.glyphs.iter())
+        .map(|glyph| (glyph.start, glyph.end, glyph.glyph_id, glyph.x_advance))
+        .collect()
+}
+
+fn push_all(
+    font_system: &mut FontSystem,
+    text: &str,
+    attrs: &Attrs,
+) -> (BufferLine, Vec<PushResult>) {
+    let mut line = BufferLine::new(
+        "",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.shape(font_system, 8);
+    let mut results = Vec::new();
+    for ch in text.chars() {
+        results.push(line.push_char(font_system, ch, attrs));
+        line.shape(font_system, 8);
+    }
+    (line, results)
+}
+
+fn shaped(font_system: &mut FontSystem, line: &BufferLine) -> Vec<(usize, usize, u16, f32)> {
+    let mut fresh = BufferLine::new(
+        line.text(),
+        LineEnding::None,
+        line.attrs_list().clone(),
+        Shaping::Advanced,
+    );
+    glyphs(fresh.shape(font_system, 8))
+}
+
+#[test]
+fn push_char_ascii_without_full_reshapes() {
+    let mut font_system = FontSystem::new();
+    let text = "ls -la /tmp && echo done 42";
+    let reshapes = Arc::new(Mutex::new(0));
+
+    let mut line = BufferLine::new(
+        "",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let counter = reshapes.clone();
+    line.set_invalidation_logger(Some(Arc::new(move |invalidation| {
+        if matches!(invalidation, Invalidation::Shaping(_)) {
+            *counter.lock().unwrap() += 1;
+        }
+    })));
+    line.shape(&mut font_system, 8);
+    let mut incremental = 0;
+    for ch in text.chars() {
+        let before = *reshapes.lock().unwrap();
+        let result = line.push_char(&mut font_system, ch, &Attrs::new());
+        assert_ne!(result, PushResult::Reshape, "{ch:?}");
+        if result == PushResult::Incremental {
+            incremental += 1;
+            assert_eq!(*reshapes.lock().unwrap(), before, "{ch:?}");
+        }
+        line.shape(&mut font_system, 8);
+    }
+    assert_eq!(line.text(), text);
+    // The six spaces and the three words starting with a letter or digit after them
+    assert_eq!(incremental, 9);
+    let pushed = glyphs(line.shape(&mut font_system, 8));
+    assert_eq!(pushed, shaped(&mut font_system, &line));
+}
+
+#[test]
+fn push_char_attrs() {
+    let mut font_system = FontSystem::new();
+    let red = Attrs::new().color(Color::rgb(0xFF, 0, 0));
+    let (mut line, results) = push_all(&mut font_system, "ab c", &red);
+    assert_eq!(
+        results,
+        [
+            PushResult::ReshapeEnd,
+            PushResult::ReshapeEnd,
+            PushResult::Incremental,
+            PushResult::Incremental,
+        ]
+    );
+    assert_eq!(line.attrs_list().get_span(3).color_opt, red.color_opt);
+    let pushed = glyphs(line.shape(&mut font_system, 8));
+    assert_eq!(pushed, shaped(&mut font_system, &line));
+}
+
+#[test]
+fn push_char_combining_mark_reshapes_end() {
+    let mut font_system = FontSystem::new();
+    let (mut line, results) = push_all(&mut font_system, "cafe\u{301} ( x", &Attrs::new());
+    // The mark joins the last cluster, and a letter after an opening bracket and space does not
+    // start a word
+    assert_eq!(results[4], PushResult::ReshapeEnd);
+    assert_eq!(results[8], PushResult::ReshapeEnd);
+    let pushed = glyphs(line.shape(&mut font_system, 8));
+    assert_eq!(pushed, shaped(&mut font_system, &line));
+
+    // Without cached shaping, the whole line is shaped again
+    let mut line = BufferLine::new(
+        "abc",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    assert_eq!(
+        line.push_char(&mut font_system, ' ', &Attrs::new()),
+        PushResult::Reshape
+    );
+}
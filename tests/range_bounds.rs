@@ -0,0 +1,52 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn range_bounds_across_wrap_and_bidi() {
+    let mut font_system = FontSystem::new();
+    let text = "one two שלום עולם three four";
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let full_width = line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8)[0].w;
+    line.reset_layout();
+    let layout = line.layout(
+        &mut font_system,
+        14.0,
+        Some(full_width * 0.6),
+        Wrap::Word,
+        None,
+        8,
+    );
+    assert_eq!(layout.len(), 2);
+
+    // From "two" into the Hebrew words, then wrapping into "three"
+    let start = text.find("two").unwrap();
+    let end = text.find("three").unwrap() + "thr".len();
+    let rects = line.range_bounds(start..end, 20.0);
+    assert_eq!(rects.len(), 2);
+
+    for (index, rect) in rects.iter().enumerate() {
+        assert_eq!(rect.y, index as f32 * 20.0);
+        assert_eq!(rect.height, 20.0);
+        let layout_line = &line.layout_opt().unwrap()[index];
+        for glyph in layout_line.glyphs.iter() {
+            let in_range = glyph.start < end && start < glyph.end;
+            if in_range {
+                assert!(glyph.x >= rect.x && glyph.x + glyph.w <= rect.right());
+            } else {
+                // Glyphs before "two" and after "thr" are outside the bounds
+                assert!(glyph.x + glyph.w <= rect.x || glyph.x >= rect.right());
+            }
+        }
+    }
+
+    // Exactly the range extents, not extended to the line edges
+    assert!(rects[0].x > 0.0);
+    assert_eq!(rects[1].x, 0.0);
+    assert!(rects[1].width < full_width * 0.6 / 2.0);
+
+    assert!(line.range_bounds(0..0, 20.0).is_empty());
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "swash")]
+
+use std::collections::HashMap;
+
+use cosmic_text::{Attrs, Buffer, Color, FontSystem, Metrics, Shaping, SwashCache};
+
+#[test]
+fn rasterize_to_known_pixels() {
+    let mut font_system = FontSystem::new();
+    let mut swash_cache = SwashCache::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(32.0, 40.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(60.0), Some(40.0));
+    buffer.set_rich_text(
+        [
+            ("I", Attrs::new()),
+            ("I", Attrs::new().color(Color::rgba(0xFF, 0, 0, 0x80))),
+        ],
+        &Attrs::new(),
+        Shaping::Advanced,
+        None,
+    );
+
+    let mut pixels = HashMap::new();
+    buffer.rasterize_to(&mut swash_cache, |x, y, color| {
+        assert!((0..60).contains(&x) && (0..40).contains(&y));
+        assert_ne!(color.a(), 0);
+        pixels.insert((x, y), color);
+    });
+
+    let run = buffer.layout_runs().next().unwrap();
+    let line_y = run.line_y as i32;
+    let first = &run.glyphs[0];
+    let second = &run.glyphs[1];
+
+    // The middle of each stem is fully covered, tinted with the glyph color
+    let stem_y = line_y - 10;
+    let first_x = (first.x + first.w / 2.0) as i32;
+    let second_x = (second.x + second.w / 2.0) as i32;
+    assert_eq!(pixels.get(&(first_x, stem_y)), Some(&Color::rgb(0, 0, 0)));
+    assert_eq!(
+        pixels.get(&(second_x, stem_y)),
+        Some(&Color::rgba(0xFF, 0, 0, 0x80))
+    );
+
+    // Nothing is drawn below the baseline or left of the first glyph
+    assert!(!pixels.contains_key(&(first_x, line_y + 4)));
+    assert!(!pixels.contains_key(&(0, stem_y)));
+}
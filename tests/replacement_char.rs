@@ -0,0 +1,34 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn replacement_char_positions() {
+    let mut font_system = FontSystem::new();
+    let text = "ab\u{FFFD}cd \u{FFFD}\u{FFFD}";
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+
+    // Not tagged by default
+    assert!(line
+        .shape(&mut font_system, 8)
+        .replacement_char_positions()
+        .is_empty());
+
+    assert!(line.set_highlight_replacement_char(true));
+    assert_eq!(
+        line.shape(&mut font_system, 8).replacement_char_positions(),
+        vec![2, 8, 11]
+    );
+
+    let layout = line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8);
+    let tagged: Vec<_> = layout[0]
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.replacement_char)
+        .map(|glyph| glyph.start)
+        .collect();
+    assert_eq!(tagged, vec![2, 8, 11]);
+}
@@ -0,0 +1,62 @@
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, BufferLine, CacheKeyFlags, Family, FontSystem, LineEnding,
+    ResolvedAttrs, Shaping, Style, Weight, Wrap,
+};
+
+fn resolve(attrs: &Attrs) -> (FontSystem, ResolvedAttrs) {
+    // Fira Mono, which is medium, and Noto Sans, which is regular, from the repository fonts
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    for path in ["fonts/FiraMono-Medium.ttf", "fonts/NotoSans-Regular.ttf"] {
+        let font = std::fs::read(path).unwrap();
+        font_system.db_mut().load_font_data(font);
+    }
+    let mut line = BufferLine::new(
+        "a",
+        LineEnding::None,
+        AttrsList::new(attrs),
+        Shaping::Advanced,
+    );
+    let resolved = line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8)[0].glyphs[0]
+        .resolved_attrs();
+    (font_system, resolved)
+}
+
+#[test]
+fn resolved_attrs_lighter_face() {
+    // No bold face is loaded, so a lighter face is used and reported with its own weight
+    let attrs = Attrs::new()
+        .family(Family::Name("Noto Sans"))
+        .weight(Weight::BOLD);
+    let (font_system, resolved) = resolve(&attrs);
+    let face = font_system.db().face(resolved.font_id).unwrap();
+    assert_eq!(face.weight, resolved.weight);
+    assert_ne!(resolved.weight, Weight::BOLD);
+    assert_eq!(resolved.style, Style::Normal);
+    assert!(!resolved.synthetic_italic);
+}
+
+#[test]
+fn resolved_attrs_real_faces() {
+    let attrs = Attrs::new()
+        .family(Family::Name("Fira Mono"))
+        .weight(Weight::MEDIUM);
+    let (_, resolved) = resolve(&attrs);
+    assert_eq!(resolved.weight, Weight::MEDIUM);
+    assert_eq!(resolved.style, Style::Normal);
+    assert!(!resolved.synthetic_italic);
+
+    let (_, regular) = resolve(&Attrs::new().family(Family::Name("Noto Sans")));
+    assert_eq!(regular.weight, Weight::NORMAL);
+    assert_eq!(regular.style, Style::Normal);
+}
+
+#[test]
+fn resolved_attrs_synthetic_italic() {
+    let attrs = Attrs::new()
+        .family(Family::Name("Noto Sans"))
+        .cache_key_flags(CacheKeyFlags::FAKE_ITALIC);
+    let (_, resolved) = resolve(&attrs);
+    assert_eq!(resolved.style, Style::Normal);
+    assert!(resolved.synthetic_italic);
+}
@@ -0,0 +1,41 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn round_line_height_integer_baselines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 17.3));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_round_line_height(true);
+    buffer.set_text(
+        "one\ntwo\nthree\nfour\nfive\nsix",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+
+    let runs: Vec<_> = buffer
+        .layout_runs()
+        .map(|run| (run.line_i, run.line_top, run.line_y, run.line_height))
+        .collect();
+    assert_eq!(runs.len(), 6);
+
+    let mut expected_top = 0.0;
+    for &(_, line_top, line_y, line_height) in &runs {
+        assert_eq!(line_height, 17.0);
+        assert_eq!(line_top, expected_top);
+        assert_eq!(
+            line_y.fract(),
+            0.0,
+            "baseline {line_y} is not on a whole pixel"
+        );
+        expected_top += line_height;
+    }
+
+    // Hit testing follows the rounded positions
+    for &(line_i, line_top, _, line_height) in &runs {
+        assert_eq!(buffer.hit(0.0, line_top).unwrap().line, line_i);
+        assert_eq!(
+            buffer.hit(0.0, line_top + line_height - 0.5).unwrap().line,
+            line_i
+        );
+    }
+}
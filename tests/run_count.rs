@@ -0,0 +1,27 @@
+use cosmic_text::{Attrs, AttrsList, FontSystem, ShapeLine, Shaping};
+
+#[test]
+fn run_count_by_direction() {
+    let mut font_system = FontSystem::new();
+    let attrs_list = AttrsList::new(&Attrs::new());
+
+    let latin = ShapeLine::new(
+        &mut font_system,
+        "hello world",
+        &attrs_list,
+        Shaping::Advanced,
+        8,
+    );
+    assert!(latin.is_single_run());
+    assert_eq!(latin.run_count(), 1);
+
+    let mixed = ShapeLine::new(
+        &mut font_system,
+        "hello مرحبا world",
+        &attrs_list,
+        Shaping::Advanced,
+        8,
+    );
+    assert!(!mixed.is_single_run());
+    assert_eq!(mixed.run_count(), 3);
+}
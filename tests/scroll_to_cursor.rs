@@ -0,0 +1,41 @@
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, ScrollAlign, Shaping};
+
+fn line_top(buffer: &Buffer, line_i: usize) -> Option<f32> {
+    buffer
+        .layout_runs()
+        .find(|run| run.line_i == line_i)
+        .map(|run| run.line_top)
+}
+
+#[test]
+fn scroll_to_cursor_alignments() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(200.0), Some(100.0));
+    let text = (0..100)
+        .map(|i| format!("line {i}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    buffer.set_text(&text, &Attrs::new(), Shaping::Advanced);
+    buffer.shape_until_scroll(false);
+
+    buffer.scroll_to_cursor(Cursor::new(50, 2), ScrollAlign::Center);
+    assert_eq!(line_top(&buffer, 50), Some(40.0));
+
+    buffer.scroll_to_cursor(Cursor::new(50, 2), ScrollAlign::Top);
+    assert_eq!(line_top(&buffer, 50), Some(0.0));
+
+    buffer.scroll_to_cursor(Cursor::new(50, 2), ScrollAlign::Bottom);
+    assert_eq!(line_top(&buffer, 50), Some(80.0));
+
+    // Nearest does not move a visible cursor
+    buffer.scroll_to_cursor(Cursor::new(48, 0), ScrollAlign::Nearest);
+    assert_eq!(line_top(&buffer, 50), Some(80.0));
+
+    // Clamped at both ends of the document
+    buffer.scroll_to_cursor(Cursor::new(1, 0), ScrollAlign::Center);
+    assert_eq!(line_top(&buffer, 0), Some(0.0));
+    buffer.scroll_to_cursor(Cursor::new(99, 0), ScrollAlign::Center);
+    assert_eq!(line_top(&buffer, 99), Some(80.0));
+}
@@ -0,0 +1,69 @@
+#![cfg(feature = "serde")]
+
+use cosmic_text::{
+    fontdb, Align, Attrs, AttrsList, BufferLine, CacheKeyFlags, Color, Family, FontSystem,
+    LineEnding, Shaping, Weight, Wrap,
+};
+
+#[test]
+fn serde_buffer_line_round_trip() {
+    // Noto Sans and Inter from the repository fonts
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    for path in ["fonts/NotoSans-Regular.ttf", "fonts/Inter-Regular.ttf"] {
+        let font = std::fs::read(path).unwrap();
+        font_system.db_mut().load_font_data(font);
+    }
+
+    let family = String::from("Inter");
+    let mut attrs_list = AttrsList::new(&Attrs::new().color(Color::rgb(0x10, 0x20, 0x30)));
+    attrs_list.add_span(
+        4..9,
+        &Attrs::new()
+            .family(Family::Name(&family))
+            .weight(Weight::BOLD)
+            .metadata(7),
+    );
+    attrs_list.add_span(
+        10..15,
+        &Attrs::new()
+            .cache_key_flags(CacheKeyFlags::FAKE_ITALIC)
+            .letter_spacing(0.1),
+    );
+
+    let mut line = BufferLine::new(
+        "the quick brown fox",
+        LineEnding::CrLf,
+        attrs_list,
+        Shaping::Advanced,
+    );
+    line.set_align(Some(Align::Center));
+    line.set_metadata(42);
+    line.layout(&mut font_system, 14.0, Some(100.0), Wrap::Word, None, 8);
+
+    let json = serde_json::to_string(&line).unwrap();
+    drop(family);
+    let mut restored: BufferLine = serde_json::from_str(&json).unwrap();
+
+    assert_eq!(restored.text(), line.text());
+    assert_eq!(restored.ending(), LineEnding::CrLf);
+    assert_eq!(restored.attrs_list(), line.attrs_list());
+    assert_eq!(restored.align(), Some(Align::Center));
+    assert_eq!(restored.metadata(), Some(42));
+    // Caches are rebuilt lazily
+    assert!(restored.shape_opt().is_none());
+    assert!(restored.layout_opt().is_none());
+
+    let restored_layout = restored
+        .layout(&mut font_system, 14.0, Some(100.0), Wrap::Word, None, 8)
+        .to_vec();
+    let layout = line.layout_opt().unwrap();
+    assert_eq!(restored_layout.len(), layout.len());
+    for (restored_line, layout_line) in restored_layout.iter().zip(layout.iter()) {
+        assert_eq!(restored_line.w, layout_line.w);
+        assert_eq!(
+            format!("{:?}", restored_line.glyphs),
+            format!("{:?}", layout_line.glyphs)
+        );
+    }
+}
@@ -0,0 +1,112 @@
+use cosmic_text::{Attrs, AttrsOwned, Buffer, Color, FontSystem, MarkupFormat, Metrics, Shaping};
+use cosmic_text::{Style, Weight};
+
+fn spans(buffer: &Buffer, line_i: usize) -> Vec<(String, AttrsOwned)> {
+    let line = &buffer.lines[line_i];
+    let text = line.text();
+    let mut boundaries = vec![0, text.len()];
+    for (range, _) in line.attrs_list().spans_iter() {
+        boundaries.push(range.start);
+        boundaries.push(range.end);
+    }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries
+        .windows(2)
+        .map(|w| {
+            (
+                text[w[0]..w[1]].to_string(),
+                AttrsOwned::new(&line.attrs_list().get_span(w[0])),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn set_from_markup_nested_html() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let attrs = Attrs::new();
+    buffer.set_from_markup(
+        "a <b>b <i>bi <span style=\"color: #ff0000\">red</span></i></b> &amp; c<br>\nline",
+        &attrs,
+        Shaping::Advanced,
+        MarkupFormat::Html,
+    );
+
+    assert_eq!(buffer.lines.len(), 2);
+    assert_eq!(buffer.lines[0].text(), "a b bi red & c");
+    assert_eq!(buffer.lines[1].text(), "line");
+
+    let bold = attrs.clone().weight(Weight::BOLD);
+    let bold_italic = bold.clone().style(Style::Italic);
+    let red = bold_italic.clone().color(Color::rgb(0xFF, 0, 0));
+    assert_eq!(
+        spans(&buffer, 0),
+        vec![
+            ("a ".to_string(), AttrsOwned::new(&attrs)),
+            ("b ".to_string(), AttrsOwned::new(&bold)),
+            ("bi ".to_string(), AttrsOwned::new(&bold_italic)),
+            ("red".to_string(), AttrsOwned::new(&red)),
+            (" & c".to_string(), AttrsOwned::new(&attrs)),
+        ]
+    );
+}
+
+#[test]
+fn set_from_markup_malformed_html() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let attrs = Attrs::new();
+    buffer.set_from_markup(
+        "1 < 2 </i><u>x</u> &bogus; <b>bold",
+        &attrs,
+        Shaping::Advanced,
+        MarkupFormat::Html,
+    );
+
+    // Unknown tags and entities are kept, the unmatched close is dropped, and the unclosed tag
+    // runs to the end
+    assert_eq!(buffer.lines[0].text(), "1 < 2 <u>x</u> &bogus; bold");
+    let spans = spans(&buffer, 0);
+    assert_eq!(spans.len(), 2);
+    assert_eq!(
+        spans[1],
+        (
+            "bold".to_string(),
+            AttrsOwned::new(&attrs.clone().weight(Weight::BOLD))
+        )
+    );
+}
+
+#[test]
+fn set_from_markup_markdown() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let attrs = Attrs::new();
+    buffer.set_from_markup(
+        "**b *bi*** \\*\nsoft\n\nnext",
+        &attrs,
+        Shaping::Advanced,
+        MarkupFormat::Markdown,
+    );
+
+    assert_eq!(buffer.lines.len(), 2);
+    assert_eq!(buffer.lines[0].text(), "b bi * soft");
+    assert_eq!(buffer.lines[1].text(), "next");
+    let bold = attrs.clone().weight(Weight::BOLD);
+    assert_eq!(
+        spans(&buffer, 0),
+        vec![
+            ("b ".to_string(), AttrsOwned::new(&bold)),
+            (
+                "bi".to_string(),
+                AttrsOwned::new(&bold.clone().style(Style::Italic))
+            ),
+            (" * soft".to_string(), AttrsOwned::new(&attrs)),
+        ]
+    );
+}
@@ -0,0 +1,35 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn set_glyph_advance_shifts_following_glyphs() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(&mut font_system, "abcd", &Attrs::new(), Shaping::Advanced);
+
+    let layout_line = &mut buffer.lines[0].layout_opt_mut().unwrap()[0];
+    let before: Vec<_> = layout_line.glyphs.iter().map(|g| (g.x, g.w)).collect();
+    let w = layout_line.w;
+
+    layout_line.set_glyph_advance(1, before[1].1 + 10.0);
+
+    let after: Vec<_> = layout_line.glyphs.iter().map(|g| (g.x, g.w)).collect();
+    assert_eq!(after[0], before[0]);
+    assert_eq!(after[1], (before[1].0, before[1].1 + 10.0));
+    assert_eq!(after[2], (before[2].0 + 10.0, before[2].1));
+    assert_eq!(after[3], (before[3].0 + 10.0, before[3].1));
+    assert_eq!(layout_line.w, w + 10.0);
+
+    // Out of range is ignored
+    layout_line.set_glyph_advance(4, 100.0);
+    assert_eq!(layout_line.w, w + 10.0);
+
+    // Hit testing uses the new positions
+    let y = 10.0;
+    assert_eq!(buffer.hit(after[2].0 + 1.0, y).map(|c| c.index), Some(2));
+    assert_eq!(
+        buffer
+            .hit(after[1].0 + after[1].1 - 1.0, y)
+            .map(|c| c.index),
+        Some(2)
+    );
+}
@@ -0,0 +1,98 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, EmojiFallback, Family, FontSystem, LineEnding, MissingFamily,
+    ShapeLine, Shaping,
+};
+
+fn glyph_id(font_system: &mut FontSystem, text: &str) -> u16 {
+    // Serbian and Russian use different forms of the Cyrillic be, there is no CJK font with
+    // localized Han forms available to test with
+    let attrs_list = AttrsList::new(&Attrs::new().family(Family::Serif));
+    let line = ShapeLine::new(font_system, text, &attrs_list, Shaping::Advanced, 8);
+    line.spans[0].words[0].glyphs[0].glyph_id
+}
+
+#[test]
+fn set_locale_changes_default_language() {
+    let mut font_system = FontSystem::new();
+
+    font_system.set_locale("ru-RU");
+    assert_eq!(font_system.locale(), "ru-RU");
+    let russian = glyph_id(&mut font_system, "б");
+
+    font_system.set_locale("sr-RS");
+    assert_eq!(font_system.locale(), "sr-RS");
+    let serbian = glyph_id(&mut font_system, "б");
+
+    assert_ne!(russian, serbian);
+
+    font_system.set_locale("ru-RU");
+    assert_eq!(glyph_id(&mut font_system, "б"), russian);
+}
+
+#[test]
+fn set_locale_reshapes_lines() {
+    let mut font_system = FontSystem::new();
+    font_system.set_locale("ru-RU");
+    let russian = glyph_id(&mut font_system, "б");
+    font_system.set_locale("sr-RS");
+    let serbian = glyph_id(&mut font_system, "б");
+
+    font_system.set_locale("ru-RU");
+    let mut line = BufferLine::new(
+        "б",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new().family(Family::Serif)),
+        Shaping::Advanced,
+    );
+    let line_glyph = |line: &mut BufferLine, font_system: &mut FontSystem| {
+        line.shape(font_system, 8).spans[0].words[0].glyphs[0].glyph_id
+    };
+    assert_eq!(line_glyph(&mut line, &mut font_system), russian);
+    font_system.set_locale("sr-RS");
+    assert_eq!(line_glyph(&mut line, &mut font_system), serbian);
+}
+
+#[test]
+fn initial_locale_is_not_shaping_language() {
+    let mut font_system = FontSystem::new();
+    let default = glyph_id(&mut font_system, "б");
+    let db = font_system.db().clone();
+
+    // The locale given at creation only orders fallbacks
+    let mut serbian_system = FontSystem::new_with_locale_and_db("sr-RS".into(), db);
+    assert_eq!(glyph_id(&mut serbian_system, "б"), default);
+    serbian_system.set_locale("sr-RS");
+    assert_ne!(glyph_id(&mut serbian_system, "б"), default);
+}
+
+#[test]
+fn shape_is_current_tracks_font_system_settings() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "б",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.shape(&mut font_system, 8);
+    assert!(line.shape_is_current(&font_system, 8));
+
+    font_system.set_locale("sr-RS");
+    assert!(!line.shape_is_current(&font_system, 8));
+    line.shape(&mut font_system, 8);
+    assert!(line.shape_is_current(&font_system, 8));
+
+    // Settings that only change font fallback make the shaping stale too
+    font_system.set_missing_family(MissingFamily::Error);
+    assert!(!line.shape_is_current(&font_system, 8));
+    line.shape(&mut font_system, 8);
+    font_system.set_emoji_fallback(EmojiFallback::None);
+    assert!(!line.shape_is_current(&font_system, 8));
+    line.shape(&mut font_system, 8);
+
+    // Setting the same values keeps the shaping
+    font_system.set_locale("sr-RS");
+    font_system.set_missing_family(MissingFamily::Error);
+    font_system.set_emoji_fallback(EmojiFallback::None);
+    assert!(line.shape_is_current(&font_system, 8));
+}
@@ -0,0 +1,153 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, Color, Family, FontSystem, LineEnding, ShapeLine, Shaping, Weight,
+};
+
+fn font_system_with(fonts: &[&[u8]]) -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    for font in fonts {
+        font_system.db_mut().load_font_data(font.to_vec());
+    }
+    font_system
+}
+
+fn face_id(font_system: &FontSystem, family: &str) -> fontdb::ID {
+    font_system
+        .db()
+        .faces()
+        .find(|face| face.families.iter().any(|(name, _)| name == family))
+        .unwrap()
+        .id
+}
+
+fn buffer_line(text: &str, attrs_list: AttrsList) -> BufferLine {
+    BufferLine::new(text, LineEnding::None, attrs_list, Shaping::Advanced)
+}
+
+fn glyphs(shape: &ShapeLine) -> Vec<(usize, usize, u16, f32)> {
+    shape
+        .spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .flat_map(|word| word.glyphs.iter())
+        .map(|glyph| (glyph.start, glyph.end, glyph.glyph_id, glyph.x_advance))
+        .collect()
+}
+
+#[test]
+fn set_text_diff_reuses_unchanged_words() {
+    let sans = std::fs::read("fonts/NotoSans-Regular.ttf").unwrap();
+    let inter = std::fs::read("fonts/Inter-Regular.ttf").unwrap();
+    let attrs_list = AttrsList::new(&Attrs::new().family(Family::Name("Noto Sans")));
+    let mut line = buffer_line("hello brave new world", attrs_list.clone());
+
+    // Noto Sans has a different ID in each font system, showing which words were shaped again
+    let mut old_font_system = font_system_with(&[&sans]);
+    let mut new_font_system = font_system_with(&[&inter, &sans]);
+    let old_id = face_id(&old_font_system, "Noto Sans");
+    let new_id = face_id(&new_font_system, "Noto Sans");
+    assert_ne!(old_id, new_id);
+
+    line.shape(&mut old_font_system, 8);
+    assert!(line.set_text_diff("hello brave big world", LineEnding::None));
+    assert!(line.shape_opt().is_none());
+    let shape = line.shape(&mut new_font_system, 8);
+
+    for glyph in shape
+        .spans
+        .iter()
+        .flat_map(|span| span.words.iter())
+        .flat_map(|word| word.glyphs.iter())
+    {
+        // The words next to the edit, including the spaces around it, are shaped again
+        let reshaped = (11..16).contains(&glyph.start);
+        assert_eq!(
+            glyph.font_id,
+            if reshaped { new_id } else { old_id },
+            "{glyph:?}"
+        );
+    }
+
+    let full = ShapeLine::new(
+        &mut new_font_system,
+        "hello brave big world",
+        &attrs_list,
+        Shaping::Advanced,
+        8,
+    );
+    assert_eq!(glyphs(line.shape_opt().unwrap()), glyphs(&full));
+}
+
+#[test]
+fn set_text_diff_matches_full_shaping() {
+    let sans = std::fs::read("fonts/NotoSans-Regular.ttf").unwrap();
+    let hebrew = std::fs::read("fonts/NotoSansHebrew.ttf").unwrap();
+    let mut font_system = font_system_with(&[&sans, &hebrew]);
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(4..7, &Attrs::new().weight(Weight::BOLD));
+
+    for (old, new) in [
+        ("abc def ghi", "abc deef ghi"),
+        ("abc def ghi", "abc ghi"),
+        ("abc def ghi", "xabc def ghi"),
+        ("abc def ghi", "abc def ghi!"),
+        ("abc def ghi", "abc שלום ghi"),
+        ("abc def ghi", "שabc def ghi"),
+        ("a\tdef\tghi", "ab\tdef\tghi"),
+        ("abc def ghi", "abc de\u{301}f ghi"),
+        ("abc def ghi", "abc 👩\u{200d}🚀 ghi"),
+        ("abc def ghi", ""),
+        ("שלום עולם abc", "שלום עולמי abc"),
+        ("שלום עולם abc", "שלום עולם abcd"),
+    ] {
+        let mut line = buffer_line(old, attrs_list.clone());
+        line.shape(&mut font_system, 8);
+        assert!(line.set_text_diff(new, LineEnding::None));
+        let attrs_list = line.attrs_list().clone();
+        let shape = line.shape(&mut font_system, 8);
+
+        let full = ShapeLine::new(&mut font_system, new, &attrs_list, Shaping::Advanced, 8);
+        assert_eq!(shape.rtl, full.rtl, "{old:?} -> {new:?}");
+        assert_eq!(
+            format!("{:?}", shape.spans),
+            format!("{:?}", full.spans),
+            "{old:?} -> {new:?}"
+        );
+    }
+}
+
+#[test]
+fn set_text_diff_shifts_attrs() {
+    let red = Attrs::new().color(Color::rgb(0xFF, 0, 0));
+    let bold = Attrs::new().weight(Weight::BOLD);
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(3..5, &bold);
+    attrs_list.add_span(6..8, &red);
+    let mut line = buffer_line("aa bb cc", attrs_list);
+
+    // Typed text takes the attributes before it
+    assert!(line.set_text_diff("aa bbb cc", LineEnding::None));
+    let spans: Vec<_> = line
+        .attrs_list()
+        .spans_iter()
+        .map(|(range, attrs)| (range.clone(), attrs.clone()))
+        .collect();
+    assert_eq!(spans.len(), 2);
+    assert_eq!(spans[0].0, 3..6);
+    assert_eq!(spans[0].1.as_attrs(), bold);
+    assert_eq!(spans[1].0, 7..9);
+    assert_eq!(spans[1].1.as_attrs(), red);
+
+    // Deleted text drops its attributes
+    assert!(line.set_text_diff("aa cc", LineEnding::None));
+    let spans: Vec<_> = line
+        .attrs_list()
+        .spans_iter()
+        .map(|(range, _)| range.clone())
+        .collect();
+    assert_eq!(spans.len(), 1);
+    assert_eq!(spans[0], 3..5);
+
+    assert!(!line.set_text_diff("aa cc", LineEnding::None));
+    assert!(line.set_text_diff("aa cc", LineEnding::Lf));
+}
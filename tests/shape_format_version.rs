@@ -0,0 +1,34 @@
+#![cfg(feature = "serde")]
+
+use cosmic_text::{Attrs, AttrsList, BufferLine, LineEnding, ShapeLine, Shaping};
+
+fn line_json() -> serde_json::Value {
+    let line = BufferLine::new(
+        "versioned",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    serde_json::to_value(&line).unwrap()
+}
+
+#[test]
+fn serde_writes_shape_format_version() {
+    let json = line_json();
+    assert_eq!(json["format_version"], ShapeLine::FORMAT_VERSION);
+
+    let restored: BufferLine = serde_json::from_value(json).unwrap();
+    assert_eq!(restored.text(), "versioned");
+}
+
+#[test]
+fn serde_rejects_other_shape_format_version() {
+    let mut json = line_json();
+    json["format_version"] = (ShapeLine::FORMAT_VERSION + 1).into();
+    let err = serde_json::from_value::<BufferLine>(json).unwrap_err();
+    assert!(err.to_string().contains("format version"));
+
+    let mut json = line_json();
+    json.as_object_mut().unwrap().remove("format_version");
+    assert!(serde_json::from_value::<BufferLine>(json).is_err());
+}
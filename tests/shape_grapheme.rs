@@ -0,0 +1,27 @@
+use cosmic_text::{Attrs, FontSystem};
+
+#[test]
+fn shape_grapheme_advances() {
+    let mut font_system = FontSystem::new();
+    let attrs = Attrs::new();
+
+    let narrow = font_system.shape_grapheme("a", &attrs);
+    assert!(narrow.single_grapheme);
+    assert_eq!(narrow.glyph_count, 1);
+    assert!(narrow.font_id_opt.is_some());
+    assert!(narrow.advance > 0.0 && narrow.advance < 1.0);
+
+    // Wide CJK grapheme, shaped with the fallback font if there is one
+    let wide = font_system.shape_grapheme("漢", &attrs);
+    assert!(wide.single_grapheme);
+    assert_eq!(wide.glyph_count, 1);
+    assert!(wide.advance > 0.0);
+
+    // A ZWJ sequence is a single grapheme even without an emoji font to ligate it
+    let family = font_system.shape_grapheme("👨\u{200D}👩\u{200D}👧", &attrs);
+    assert!(family.single_grapheme);
+    assert!(family.glyph_count >= 1);
+    assert!(family.advance > 0.0);
+
+    assert!(!font_system.shape_grapheme("ab", &attrs).single_grapheme);
+}
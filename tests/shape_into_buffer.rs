@@ -0,0 +1,25 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn shape_into_buffer_matches_buffer_line() {
+    let mut font_system = FontSystem::new();
+    let text = "measure\tthis text";
+    let attrs_list = AttrsList::new(&Attrs::new());
+
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        attrs_list.clone(),
+        Shaping::Advanced,
+    );
+    let expected = line.layout(&mut font_system, 14.0, None, Wrap::None, None, 4)[0].w;
+
+    let shape = font_system.shape_into_buffer(text, &attrs_list, Shaping::Advanced, 4);
+    let width = shape.layout(14.0, None, Wrap::None, None, None)[0].w;
+    assert_eq!(width, expected);
+
+    // The next call reuses the same line
+    let shape = font_system.shape_into_buffer("a", &attrs_list, Shaping::Advanced, 4);
+    assert_eq!(shape.spans.len(), 1);
+    assert!(shape.layout(14.0, None, Wrap::None, None, None)[0].w < expected);
+}
@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping};
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn shape_is_current_tracks_tab_width() {
+    let mut font_system = FontSystem::new();
+
+    let mut tabbed = line("a\tb");
+    assert!(!tabbed.shape_is_current(&font_system, 4));
+    tabbed.shape(&mut font_system, 4);
+    assert!(tabbed.shape_is_current(&font_system, 4));
+    assert!(!tabbed.shape_is_current(&font_system, 8));
+
+    // Shaping with a new tab width reshapes instead of returning the stale cache
+    let width_4: f32 = tabbed.shape_opt().unwrap().spans[0]
+        .words
+        .iter()
+        .map(|w| w.width(14.0))
+        .sum();
+    tabbed.shape(&mut font_system, 8);
+    assert!(tabbed.shape_is_current(&font_system, 8));
+    let width_8: f32 = tabbed.shape_opt().unwrap().spans[0]
+        .words
+        .iter()
+        .map(|w| w.width(14.0))
+        .sum();
+    assert!(width_8 > width_4);
+
+    // Lines without tabs do not depend on the tab width
+    let mut plain = line("ab");
+    plain.shape(&mut font_system, 4);
+    assert!(plain.shape_is_current(&font_system, 8));
+
+    tabbed.reset_shaping();
+    assert!(!tabbed.shape_is_current(&font_system, 8));
+}
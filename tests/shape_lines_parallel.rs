@@ -0,0 +1,66 @@
+#![cfg(feature = "rayon")]
+
+use cosmic_text::{fontdb, Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+const LINES: [&str; 4] = [
+    "The quick brown fox jumps over the lazy dog",
+    "Съешь же ещё этих мягких французских булок",
+    "مرحبا بالعالم والعالم",
+    "tabs\tand\tmarks e\u{301}\u{302} and emoji \u{1F600}",
+];
+
+fn glyphs(buffer: &Buffer) -> Vec<(usize, usize, fontdb::ID, f32)> {
+    buffer
+        .layout_runs()
+        .flat_map(|run| run.glyphs.iter())
+        .map(|glyph| (glyph.start, glyph.end, glyph.font_id, glyph.x))
+        .collect()
+}
+
+fn buffer(font_system: &mut FontSystem) -> Buffer {
+    let text = (0..200)
+        .map(|i| LINES[i % LINES.len()])
+        .collect::<Vec<_>>()
+        .join("\n");
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, Some(300.0), None);
+    buffer.set_text(font_system, &text, &Attrs::new(), Shaping::Advanced);
+    for line in buffer.lines.iter_mut() {
+        line.reset_shaping();
+    }
+    buffer
+}
+
+#[test]
+fn shape_lines_parallel_matches_serial() {
+    let mut font_system = FontSystem::new();
+
+    let mut serial = buffer(&mut font_system);
+    serial.shape_until_scroll(&mut font_system, false);
+
+    // Fonts are loaded now, so the workers can shape every line
+    let mut parallel = buffer(&mut font_system);
+    parallel.shape_lines_parallel(&mut font_system);
+    for line_i in 0..parallel.lines.len() {
+        assert!(parallel.lines[line_i].shape_opt().is_some());
+    }
+    parallel.shape_until_scroll(&mut font_system, false);
+    assert_eq!(glyphs(&parallel), glyphs(&serial));
+}
+
+#[test]
+fn shape_lines_parallel_loads_missing_fonts() {
+    let mut font_system = FontSystem::new();
+    let mut serial = buffer(&mut font_system);
+    serial.shape_until_scroll(&mut font_system, false);
+
+    // No fonts are loaded, so lines are shaped again on the calling thread
+    let mut parallel = buffer(&mut font_system);
+    let mut font_system = FontSystem::new();
+    parallel.shape_lines_parallel(&mut font_system);
+    for line_i in 0..parallel.lines.len() {
+        assert!(parallel.lines[line_i].shape_opt().is_some());
+    }
+    parallel.shape_until_scroll(&mut font_system, false);
+    assert_eq!(glyphs(&parallel), glyphs(&serial));
+}
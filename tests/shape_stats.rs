@@ -0,0 +1,49 @@
+#![cfg(feature = "profiling")]
+
+use cosmic_text::{Attrs, AttrsList, Buffer, BufferLine, FontSystem, LineEnding, Metrics, Shaping};
+
+#[test]
+fn shape_stats_unshaped() {
+    let mut buffer = Buffer::new_empty(Metrics::new(14.0, 20.0));
+    buffer.lines.push(BufferLine::new(
+        "abc",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    ));
+    assert!(buffer.lines[0].last_shape_stats().is_none());
+    assert_eq!(buffer.slowest_shaped_line(), None);
+    assert_eq!(buffer.total_shape_stats(), Default::default());
+}
+
+#[test]
+fn shape_stats_populated() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    buffer.set_text(
+        &mut font_system,
+        "Hello world\nשלום abc",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    // Setting the text shapes the visible lines
+    let first = buffer.lines[0].last_shape_stats().unwrap();
+    assert_eq!(first.bytes, 11);
+    assert_eq!(first.runs, 1);
+    assert_eq!(first.glyphs, 11);
+    assert!(first.duration.as_nanos() > 0);
+    let second = buffer.lines[1].last_shape_stats().unwrap();
+    assert_eq!(second.bytes, "שלום abc".len());
+    assert_eq!(second.runs, 2);
+
+    let total = buffer.total_shape_stats();
+    assert_eq!(total.bytes, first.bytes + second.bytes);
+    assert_eq!(total.glyphs, first.glyphs + second.glyphs);
+    assert_eq!(total.duration, first.duration + second.duration);
+    let (slowest, stats) = buffer.slowest_shaped_line().unwrap();
+    assert_eq!(buffer.lines[slowest].last_shape_stats(), Some(stats));
+
+    // Cached shaping is not recorded
+    buffer.lines[0].shape(&mut font_system, 8);
+    assert_eq!(buffer.lines[0].last_shape_stats(), Some(first));
+}
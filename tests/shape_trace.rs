@@ -0,0 +1,48 @@
+#![cfg(feature = "shape-trace")]
+
+use cosmic_text::{
+    fontdb, Attrs, AttrsList, Family, FeatureTag, FontSystem, ShapeLine, ShapeTraceTable, Shaping,
+};
+
+/// Only Noto Sans from the repository fonts, which has standard ligatures
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    let font = std::fs::read("fonts/NotoSans-Regular.ttf").unwrap();
+    font_system.db_mut().load_font_data(font);
+    font_system
+}
+
+#[test]
+fn shape_trace_liga() {
+    let mut font_system = font_system();
+    let attrs_list = AttrsList::new(&Attrs::new().family(Family::Name("Noto Sans")));
+    let (shape, trace) = ShapeLine::build_with_trace(
+        &mut font_system,
+        "office fly",
+        &attrs_list,
+        Shaping::Advanced,
+        8,
+    );
+    assert!(trace.applied(FeatureTag::STANDARD_LIGATURES), "{trace:?}");
+
+    let step = trace
+        .steps
+        .iter()
+        .find(|step| step.feature == FeatureTag::STANDARD_LIGATURES && step.range == (0..6))
+        .unwrap();
+    assert_eq!(step.table, ShapeTraceTable::Gsub);
+    // The ligature merges glyphs
+    assert!(step.glyphs_with.len() < step.glyphs_without.len());
+    let glyph_count: usize = shape.spans[0].words[0].glyphs.len();
+    assert_eq!(step.glyphs_with.len(), glyph_count);
+}
+
+#[test]
+fn shape_trace_no_ligature() {
+    let mut font_system = font_system();
+    let attrs_list = AttrsList::new(&Attrs::new().family(Family::Name("Noto Sans")));
+    let (_, trace) =
+        ShapeLine::build_with_trace(&mut font_system, "abc", &attrs_list, Shaping::Advanced, 8);
+    assert!(!trace.applied(FeatureTag::STANDARD_LIGATURES));
+}
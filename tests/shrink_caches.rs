@@ -0,0 +1,55 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn shrink_caches_releases_capacity() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "The quick brown fox jumps over the lazy dog. ".repeat(20),
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let long_len = line
+        .layout(&mut font_system, 14.0, Some(100.0), Wrap::Word, None, 8)
+        .len();
+    assert!(long_len > 10, "{long_len}");
+
+    // The short line reuses the allocations of the long one
+    line.set_text("Short", LineEnding::None, AttrsList::new(&Attrs::new()));
+    line.layout(&mut font_system, 14.0, Some(100.0), Wrap::Word, None, 8);
+    let layout = line.layout_opt().unwrap();
+    assert_eq!(layout.len(), 1);
+    assert!(layout.capacity() >= long_len);
+    let glyphs = layout[0].glyphs.len();
+
+    line.shrink_caches();
+    let layout = line.layout_opt().expect("layout was invalidated");
+    assert!(layout.capacity() < long_len, "{}", layout.capacity());
+    assert_eq!(layout[0].glyphs.len(), glyphs);
+    assert_eq!(layout[0].glyphs.capacity(), glyphs);
+    assert!(line.shape_opt().is_some());
+}
+
+#[test]
+fn shrink_caches_drops_invalidated_caches() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "The quick brown fox jumps over the lazy dog. ".repeat(20),
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let long_len = line
+        .layout(&mut font_system, 14.0, Some(100.0), Wrap::Word, None, 8)
+        .len();
+
+    // Shrinking before the next layout releases the allocations kept for reuse
+    line.set_text("Short", LineEnding::None, AttrsList::new(&Attrs::new()));
+    line.shrink_caches();
+    assert!(line.shape_opt().is_none());
+    assert!(line.layout_opt().is_none());
+    line.layout(&mut font_system, 14.0, Some(100.0), Wrap::Word, None, 8);
+    let layout = line.layout_opt().unwrap();
+    assert_eq!(layout.len(), 1);
+    assert!(layout.capacity() < long_len, "{}", layout.capacity());
+}
@@ -0,0 +1,45 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+fn natural_width(font_system: &mut FontSystem, line: &mut BufferLine, font_size: f32) -> f32 {
+    line.reset_layout();
+    line.layout(font_system, font_size, None, Wrap::None, None, 8)[0].w
+}
+
+#[test]
+fn shrink_factor_to_fit_overflowing() {
+    let mut font_system = FontSystem::new();
+    let mut line = line("The quick brown fox jumps over the lazy dog");
+    let natural = natural_width(&mut font_system, &mut line, 20.0);
+    let target = natural * 0.6;
+
+    let factor = line.shrink_factor_to_fit(&mut font_system, 20.0, target, 8);
+    assert!(factor < 1.0, "{factor}");
+    assert!((natural * factor - target).abs() < 0.01, "{factor}");
+
+    // Scaling the font size by the factor scales the advances the same way
+    let scaled = natural_width(&mut font_system, &mut line, 20.0 * factor);
+    assert!((scaled - target).abs() < 0.5, "{scaled} {target}");
+}
+
+#[test]
+fn shrink_factor_to_fit_fits() {
+    let mut font_system = FontSystem::new();
+    let mut line = line("Short");
+    assert_eq!(
+        line.shrink_factor_to_fit(&mut font_system, 20.0, 500.0, 8),
+        1.0
+    );
+    assert_eq!(
+        line.shrink_factor_to_fit(&mut font_system, 20.0, 0.0, 8),
+        0.0
+    );
+}
@@ -0,0 +1,44 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn single_line_joins_paragraphs() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(20.0), None);
+    buffer.set_text("hello\nworld", &Attrs::new(), Shaping::Advanced);
+    buffer.set_single_line(true);
+    buffer.shape_until_scroll(false);
+
+    // The text itself still has two lines
+    assert_eq!(buffer.lines.len(), 2);
+    assert_eq!(buffer.lines[0].text(), "hello");
+
+    // Both lines are drawn unwrapped on the same visual line
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert_eq!(runs.len(), 2);
+    assert_eq!(runs[0].line_top, runs[1].line_top);
+
+    // A space is drawn at the join, between "hello" and "world"
+    let join = runs[0].glyphs.last().unwrap();
+    assert_eq!((join.start, join.end), (5, 5));
+    let hello_end = runs[0].glyphs[4].x + runs[0].glyphs[4].w;
+    assert!(join.x >= hello_end);
+    assert!(join.w > 0.0);
+    assert_eq!(runs[1].glyphs[0].x, join.x + join.w);
+
+    // Clicking on "world" hits the second line
+    let world_x = runs[1].glyphs[1].x + 1.0;
+    assert_eq!(
+        buffer
+            .hit(world_x, 10.0)
+            .map(|cursor| (cursor.line, cursor.index)),
+        Some((1, 1))
+    );
+
+    // Turning it off wraps and stacks the lines again
+    buffer.set_single_line(false);
+    let runs: Vec<_> = buffer.layout_runs().collect();
+    assert!(runs.len() > 2);
+    assert!(runs[0].line_top < runs[runs.len() - 1].line_top);
+}
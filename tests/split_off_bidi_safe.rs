@@ -0,0 +1,66 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping};
+
+const TEXT: &str = "abc \u{2067}שלום עולם\u{2069} def";
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+// The direction of the line, and whether the glyphs of each Hebrew letter are right-to-left
+fn directions(font_system: &mut FontSystem, line: &mut BufferLine) -> (bool, Vec<bool>) {
+    let text = line.text().to_string();
+    let shape = line.shape(font_system, 8);
+    let hebrew = shape
+        .spans
+        .iter()
+        .flat_map(|span| span.words.iter().map(move |word| (span.level, word)))
+        .flat_map(|(level, word)| word.glyphs.iter().map(move |glyph| (level, glyph)))
+        .filter(|(_, glyph)| {
+            let c = text[glyph.start..].chars().next().unwrap();
+            ('\u{5D0}'..='\u{5EA}').contains(&c)
+        })
+        .map(|(level, _)| level.is_rtl())
+        .collect();
+    (shape.rtl, hebrew)
+}
+
+#[test]
+fn split_off_bidi_safe_rli() {
+    let mut font_system = FontSystem::new();
+    let index = TEXT.find('ע').unwrap();
+
+    // Naive splitting makes the Hebrew text the first strong text of the new line
+    let mut naive = line(TEXT);
+    let mut naive_tail = naive.split_off(index);
+    assert!(naive_tail.shape(&mut font_system, 8).rtl);
+
+    let mut head = line(TEXT);
+    let mut tail = head.split_off_bidi_safe(index);
+    assert_eq!(head.text(), "abc \u{2067}שלום \u{2069}");
+    assert_eq!(tail.text(), "\u{2067}עולם\u{2069} def");
+
+    for line in [&mut head, &mut tail] {
+        let (rtl, hebrew) = directions(&mut font_system, line);
+        assert!(!rtl, "{:?}", line.text());
+        assert_eq!(hebrew, [true; 4], "{:?}", line.text());
+    }
+}
+
+#[test]
+fn split_off_bidi_safe_fsi() {
+    // A first strong isolate is reopened with the direction it resolved to
+    let mut head = line("x \u{2068}שלום abc\u{2069} y");
+    let tail = head.split_off_bidi_safe(head.text().find('a').unwrap());
+    assert_eq!(head.text(), "x \u{2068}שלום \u{2069}");
+    assert_eq!(tail.text(), "\u{2067}abc\u{2069} y");
+
+    // Closed isolates are left alone
+    let mut head = line(TEXT);
+    let tail = head.split_off_bidi_safe(TEXT.find(" def").unwrap());
+    assert_eq!(tail.text(), " def");
+}
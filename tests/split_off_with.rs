@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use cosmic_text::{
+    Align, Attrs, AttrsList, AttrsOwned, BufferLine, Color, DefaultsPolicy, FoldState, FontSystem,
+    LineEnding, Shaping, Wrap,
+};
+
+fn line() -> BufferLine {
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(2..8, &Attrs::new().color(Color::rgb(0xFF, 0, 0)));
+    BufferLine::new(
+        "abcdefghij",
+        LineEnding::None,
+        attrs_list,
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn split_off_with_policies() {
+    let red = AttrsOwned::new(&Attrs::new().color(Color::rgb(0xFF, 0, 0)));
+    let plain = AttrsOwned::new(&Attrs::new());
+
+    for policy in [
+        DefaultsPolicy::FromOriginalDefaults,
+        DefaultsPolicy::FromSplitPoint,
+    ] {
+        let mut line = line();
+        let new = line.split_off_with(5, policy);
+        assert_eq!(line.text(), "abcde");
+        assert_eq!(new.text(), "fghij");
+
+        // The remainder of the span is kept either way
+        assert_eq!(AttrsOwned::new(&new.attrs_list().get_span(0)), red);
+        assert_eq!(AttrsOwned::new(&new.attrs_list().get_span(4)), plain);
+
+        let expected_defaults = match policy {
+            DefaultsPolicy::FromOriginalDefaults => &plain,
+            DefaultsPolicy::FromSplitPoint => &red,
+        };
+        assert_eq!(
+            &AttrsOwned::new(&new.attrs_list().defaults()),
+            expected_defaults
+        );
+    }
+
+    // split_off keeps the original defaults
+    let mut line = line();
+    let new = line.split_off(5);
+    assert_eq!(AttrsOwned::new(&new.attrs_list().defaults()), plain);
+}
+
+#[test]
+fn split_off_with_keeps_line_settings() {
+    let mut line = line();
+    line.set_layout_cache(true);
+    line.set_fold_state(FoldState::Collapsed(2));
+    let invalidations = Arc::new(AtomicUsize::new(0));
+    let counter = invalidations.clone();
+    line.set_invalidation_logger(Some(Arc::new(move |_| {
+        counter.fetch_add(1, Ordering::Relaxed);
+    })));
+
+    let mut new = line.split_off_with(5, DefaultsPolicy::FromOriginalDefaults);
+    assert!(new.layout_cache());
+    // The folded lines follow the new line
+    assert_eq!(new.fold_state(), FoldState::Collapsed(2));
+    assert_eq!(line.fold_state(), FoldState::None);
+
+    let mut font_system = FontSystem::new();
+    new.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+    invalidations.store(0, Ordering::Relaxed);
+    new.set_align(Some(Align::Center));
+    assert!(invalidations.load(Ordering::Relaxed) > 0);
+}
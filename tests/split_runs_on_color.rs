@@ -0,0 +1,88 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, Color, Family, FontSystem, LineEnding, Shaping, Wrap,
+};
+
+const RED: Color = Color::rgb(0xFF, 0, 0);
+const BLUE: Color = Color::rgb(0, 0, 0xFF);
+
+fn line(text: &str, split: usize) -> BufferLine {
+    let mut attrs_list = AttrsList::new(&Attrs::new().color(RED));
+    attrs_list.add_span(split..text.len(), &Attrs::new().color(BLUE));
+    BufferLine::new(text, LineEnding::None, attrs_list, Shaping::Advanced)
+}
+
+#[test]
+fn split_runs_on_color_breaks_ligature() {
+    let mut font_system = FontSystem::new();
+
+    // Without splitting, "fi" is shaped as one run and forms a ligature in the first color
+    let mut merged = line("fi", 1);
+    let glyphs = &merged.layout(&mut font_system, 14.0, None, Wrap::None, None, 8)[0].glyphs;
+    assert_eq!(glyphs.len(), 1);
+    assert_eq!(glyphs[0].color_opt, Some(RED));
+    assert_eq!(glyphs[0].start..glyphs[0].end, 0..2);
+
+    let mut split = line("fi", 1);
+    assert!(split.set_split_runs_on_color(true));
+    assert!(!split.set_split_runs_on_color(true));
+    let layout = split.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+    let glyphs = &layout[0].glyphs;
+    assert_eq!(glyphs.len(), 2);
+    assert_eq!(
+        glyphs
+            .iter()
+            .map(|glyph| (glyph.start, glyph.end, glyph.color_opt))
+            .collect::<Vec<_>>(),
+        [(0, 1, Some(RED)), (1, 2, Some(BLUE))]
+    );
+    assert_eq!(layout[0].color_runs(Color::rgb(0, 0, 0)).count(), 2);
+}
+
+#[test]
+fn split_runs_on_color_monospace_cells() {
+    let mut font_system = FontSystem::new();
+    let text = "a=>b";
+    let mut attrs_list = AttrsList::new(&Attrs::new().family(Family::Monospace).color(RED));
+    attrs_list.add_span(2..4, &Attrs::new().family(Family::Monospace).color(BLUE));
+    let mut line = BufferLine::new(text, LineEnding::None, attrs_list, Shaping::Advanced);
+    line.set_split_runs_on_color(true);
+
+    // Every cell keeps its own glyph, color and the grid width
+    let layout = line.layout(&mut font_system, 14.0, None, Wrap::None, Some(8.0), 8);
+    let glyphs = &layout[0].glyphs;
+    assert_eq!(glyphs.len(), text.len());
+    for (i, glyph) in glyphs.iter().enumerate() {
+        assert_eq!(glyph.start..glyph.end, i..i + 1);
+        assert_eq!(glyph.color_opt, Some(if i < 2 { RED } else { BLUE }));
+        assert_eq!(glyph.w, 8.0);
+    }
+}
+
+#[test]
+fn split_runs_on_color_update_attrs_in_place() {
+    let mut font_system = FontSystem::new();
+    // The second letter has its own span, of the same color at first
+    let mut attrs_list = AttrsList::new(&Attrs::new().color(RED));
+    attrs_list.add_span(1..2, &Attrs::new().color(RED));
+    let mut line = BufferLine::new("fi", LineEnding::None, attrs_list, Shaping::Advanced);
+    line.set_split_runs_on_color(true);
+    assert_eq!(
+        line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8)[0]
+            .glyphs
+            .len(),
+        1
+    );
+
+    // Changing the color of the span splits the ligature, so the line is reshaped
+    let mut attrs_list = AttrsList::new(&Attrs::new().color(RED));
+    attrs_list.add_span(1..2, &Attrs::new().color(BLUE));
+    assert!(line.update_attrs_in_place(attrs_list));
+    let glyphs = &line.layout(&mut font_system, 14.0, None, Wrap::None, None, 8)[0].glyphs;
+    assert_eq!(
+        glyphs
+            .iter()
+            .map(|glyph| (glyph.start, glyph.end, glyph.color_opt))
+            .collect::<Vec<_>>(),
+        [(0, 1, Some(RED)), (1, 2, Some(BLUE))]
+    );
+}
@@ -0,0 +1,190 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, FontSystem, LayoutLine, LineEnding, Shaping, TabStops, Wrap,
+};
+
+fn layout(text: &str, width_opt: Option<f32>, tab_stops: TabStops) -> Vec<LayoutLine> {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_tab_stops(Some(tab_stops));
+    line.layout(&mut font_system, 14.0, width_opt, Wrap::Word, None, 8)
+        .to_vec()
+}
+
+fn x_of(lines: &[LayoutLine], index: usize) -> (usize, f32) {
+    lines
+        .iter()
+        .enumerate()
+        .find_map(|(line_i, line)| {
+            line.glyphs
+                .iter()
+                .find(|glyph| glyph.start == index)
+                .map(|glyph| (line_i, glyph.x))
+        })
+        .unwrap()
+}
+
+#[test]
+fn tab_stops_next_stop() {
+    let tab_stops = TabStops::new(vec![40.0, 120.0, 300.0], 80.0);
+    assert_eq!(tab_stops.next_stop(0.0), 40.0);
+    assert_eq!(tab_stops.next_stop(40.0), 120.0);
+    assert_eq!(tab_stops.next_stop(299.0), 300.0);
+    assert_eq!(tab_stops.next_stop(300.0), 380.0);
+    assert_eq!(tab_stops.next_stop(500.0), 540.0);
+    assert_eq!(TabStops::uniform(50.0).next_stop(75.0), 100.0);
+    assert_eq!(TabStops::default().next_stop(75.0), 75.0);
+}
+
+#[test]
+fn tab_stops_absolute_positions() {
+    let lines = layout(
+        "a\tb\tc\td\te",
+        None,
+        TabStops::new(vec![40.0, 120.0, 300.0], 80.0),
+    );
+    assert_eq!(lines.len(), 1);
+    for (index, x) in [(2, 40.0), (4, 120.0), (6, 300.0), (8, 380.0)] {
+        assert_eq!(x_of(&lines, index), (0, x), "{index}");
+    }
+}
+
+#[test]
+fn tab_stops_wrap() {
+    // The second tab reaches past the width, so the text after it wraps to the next line
+    let lines = layout(
+        "a\tb\tc\td",
+        Some(100.0),
+        TabStops::new(vec![40.0, 120.0], 20.0),
+    );
+    assert_eq!(x_of(&lines, 2), (0, 40.0));
+    assert_eq!(x_of(&lines, 4), (1, 0.0));
+    // Stops are measured from the start of the wrapped line
+    assert_eq!(x_of(&lines, 6).1, 40.0);
+}
+
+#[test]
+fn tab_stops_rtl() {
+    // Measured from the right edge in a right-to-left line
+    let lines = layout("א\tב", Some(200.0), TabStops::uniform(50.0));
+    assert_eq!(lines.len(), 1);
+    let (_, x) = x_of(&lines, 3);
+    let bet = lines[0]
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start == 3)
+        .unwrap();
+    assert!((200.0 - (x + bet.w) - 50.0).abs() < 0.01, "{x}");
+}
+
+#[test]
+fn tab_stops_dot_leader() {
+    let mut font_system = FontSystem::new();
+    let dot_w = font_system.measure_mask('.', 1, &Attrs::new(), 14.0);
+    let tab_stops = TabStops::new(vec![200.0, 300.0], 0.0).with_leaders(vec![Some('.')]);
+    let lines = layout("Chapter\t12\t3", None, tab_stops);
+    assert_eq!(lines.len(), 1);
+    let glyphs = &lines[0].glyphs;
+    let tab = glyphs.iter().find(|glyph| glyph.start == 7).unwrap();
+    let dots: Vec<_> = glyphs
+        .iter()
+        .filter(|glyph| glyph.start == 7 && glyph.end == 7)
+        .collect();
+
+    // The dots tile the tab and end at the stop without overflowing it
+    assert_eq!(dots.len(), (tab.w / dot_w) as usize);
+    assert!(dots.len() > 10);
+    for pair in dots.windows(2) {
+        assert!((pair[1].x - pair[0].x - dot_w).abs() < 0.01);
+    }
+    let end = dots.last().unwrap().x + dot_w;
+    assert!((end - (tab.x + tab.w)).abs() < 0.01, "{end}");
+    assert!((end - 200.0).abs() <= 1.0, "{end}");
+    assert!(dots[0].x >= tab.x);
+
+    // The second stop has no leader
+    assert!(!glyphs
+        .iter()
+        .any(|glyph| glyph.start == 10 && glyph.end == 10));
+}
+
+#[test]
+fn tab_stops_dot_leader_with_markers() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "Chapter\t12",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_tab_stops(Some(
+        TabStops::new(vec![200.0], 0.0).with_leaders(vec![Some('.')]),
+    ));
+    line.set_tab_visualization(Some('→'));
+    let lines = line.layout(&mut font_system, 14.0, None, Wrap::Word, None, 8);
+    let inserted: Vec<_> = lines[0]
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.start == 7 && glyph.end == 7)
+        .collect();
+
+    // One marker and the dots, with no marker for each dot
+    let marker_id = inserted[0].glyph_id;
+    assert_eq!(
+        inserted
+            .iter()
+            .filter(|glyph| glyph.glyph_id == marker_id)
+            .count(),
+        1
+    );
+    assert!(inserted.len() > 10);
+}
+
+#[test]
+fn tab_stops_mixed_bidi() {
+    // The right-to-left run before the tab is measured as it is placed, left of the tab
+    let text = "ab אבג דה\tx";
+    let tab = text.find('\t').unwrap();
+    let lines = layout(text, None, TabStops::new(vec![150.0], 0.0));
+    assert_eq!(lines.len(), 1);
+    let before = lines[0]
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.start < tab)
+        .map(|glyph| glyph.x + glyph.w)
+        .fold(0.0, f32::max);
+    assert!(before < 150.0, "{before}");
+    assert_eq!(x_of(&lines, tab).1, before);
+    assert_eq!(x_of(&lines, tab + 1), (0, 150.0));
+}
+
+#[test]
+fn tab_width_uses_uniform_stops_per_visual_line() {
+    let mut font_system = FontSystem::new();
+    let space_w = font_system.measure_mask(' ', 1, &Attrs::new(), 14.0);
+    let mut line = BufferLine::new(
+        "aaaa bb\tc",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let lines = line
+        .layout(
+            &mut font_system,
+            14.0,
+            Some(space_w * 6.0),
+            Wrap::Word,
+            None,
+            4,
+        )
+        .to_vec();
+
+    // "bb" wraps, and its tab reaches the first stop of the second line
+    let (line_i, x) = x_of(&lines, 8);
+    assert_eq!(line_i, 1);
+    assert!((x - space_w * 4.0).abs() < 1.0, "{x}");
+}
@@ -0,0 +1,93 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, FontSystem, LayoutLine, LineEnding, Shaping, Wrap,
+};
+
+fn layout(text: &str, tab_visualization: Option<char>) -> LayoutLine {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_tab_visualization(tab_visualization);
+    let layout = line.layout(&mut font_system, 14.0, None, Wrap::None, None, 4);
+    assert_eq!(layout.len(), 1);
+    layout[0].clone()
+}
+
+#[test]
+fn tab_visualization_three_tabs() {
+    let text = "a\tb\tc\td";
+    let plain = layout(text, None);
+    let line = layout(text, Some('→'));
+
+    let markers: Vec<_> = line
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.start == glyph.end)
+        .collect();
+    assert_eq!(markers.len(), 3);
+
+    // The other glyphs, including the tabs, keep their advance and cluster mapping
+    let glyphs: Vec<_> = line
+        .glyphs
+        .iter()
+        .filter(|glyph| glyph.start != glyph.end)
+        .map(|glyph| (glyph.start, glyph.end, glyph.x, glyph.w))
+        .collect();
+    let plain_glyphs: Vec<_> = plain
+        .glyphs
+        .iter()
+        .map(|glyph| (glyph.start, glyph.end, glyph.x, glyph.w))
+        .collect();
+    assert_eq!(glyphs, plain_glyphs);
+    assert_eq!(line.w, plain.w);
+
+    // Each marker starts its tab's whitespace
+    for marker in markers {
+        let tab = line
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.start == marker.start && glyph.end > glyph.start)
+            .unwrap();
+        assert_eq!(&text[tab.start..tab.end], "\t");
+        assert_eq!(marker.x, tab.x);
+        assert_eq!(marker.w, 0.0);
+    }
+}
+
+#[test]
+fn tab_visualization_rtl() {
+    let text = "א\tב";
+    let line = layout(text, Some('·'));
+    let tab = line
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start == 2 && glyph.end == 3)
+        .unwrap();
+    let marker = line
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start == glyph.end)
+        .unwrap();
+
+    // The marker is at the right edge of the tab, where right-to-left text starts it
+    assert!(
+        marker.x > tab.x && marker.x < tab.x + tab.w,
+        "{marker:?} {tab:?}"
+    );
+}
+
+#[test]
+fn tab_visualization_unchanged() {
+    let mut line = BufferLine::new(
+        "a\tb",
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    assert!(line.set_tab_visualization(Some('→')));
+    assert!(!line.set_tab_visualization(Some('→')));
+    assert_eq!(line.tab_visualization(), Some('→'));
+}
@@ -0,0 +1,41 @@
+use cosmic_text::{
+    Align, Attrs, AttrsList, BufferLine, FontSystem, LayoutGlyph, LineEnding, Shaping, Wrap,
+};
+
+fn right_edge_of(text: &str, index: usize, ignore: bool) -> f32 {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_align(Some(Align::Right));
+    line.set_ignore_trailing_whitespace_in_align(ignore);
+    let layout = line.layout(&mut font_system, 14.0, Some(300.0), Wrap::Word, None, 8);
+    assert_eq!(layout.len(), 1);
+    let glyph: &LayoutGlyph = layout[0]
+        .glyphs
+        .iter()
+        .find(|glyph| glyph.start == index)
+        .unwrap();
+    glyph.x + glyph.w
+}
+
+#[test]
+fn trailing_tab_hangs_when_right_aligned() {
+    assert!((right_edge_of("abc\t", 2, true) - 300.0).abs() < 1.0);
+    // By default the tab takes part of the aligned width
+    assert!(right_edge_of("abc\t", 2, false) < 295.0);
+}
+
+#[test]
+fn trailing_tab_and_spaces_align_alike() {
+    let tab = right_edge_of("abc\t", 2, true);
+    let spaces = right_edge_of("abc  ", 2, true);
+    let mixed = right_edge_of("abc \t ", 2, true);
+    assert_eq!(tab, spaces);
+    assert_eq!(tab, mixed);
+    // Inner whitespace still counts
+    assert!(right_edge_of("a\tbc", 0, true) < 290.0);
+}
@@ -0,0 +1,50 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+#[test]
+fn trim_wrapped_leading_whitespace() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    let text = "aaaa            bbbb";
+    buffer.set_text(text, &Attrs::new(), Shaping::Advanced);
+    buffer.set_wrap(Wrap::Word);
+    // Wide enough for "aaaa" and a few spaces, but not for "bbbb" after them
+    let word_width = {
+        buffer.set_size(None, None);
+        buffer.line_layout(0).unwrap()[0].glyphs[..4]
+            .iter()
+            .map(|glyph| glyph.w)
+            .sum::<f32>()
+    };
+    buffer.set_size(Some(word_width * 1.5), None);
+
+    let b_start = text.find('b').unwrap();
+    let layout = buffer.line_layout(0).unwrap();
+    assert_eq!(layout.len(), 2);
+    let first = layout[1]
+        .glyphs
+        .iter()
+        .min_by(|a, b| a.x.total_cmp(&b.x))
+        .unwrap();
+    assert_eq!(first.start, b_start);
+    assert_eq!(first.x, 0.0);
+
+    // Clicking at the start of the continuation line goes to the first visible character
+    assert_eq!(
+        buffer
+            .hit(1.0, 30.0)
+            .map(|cursor| (cursor.line, cursor.index)),
+        Some((0, b_start))
+    );
+
+    // Without trimming, the continuation line starts with a gap
+    assert!(buffer.lines[0].set_trim_wrapped_leading_whitespace(false));
+    let layout = buffer.line_layout(0).unwrap();
+    let first = layout[1]
+        .glyphs
+        .iter()
+        .min_by(|a, b| a.x.total_cmp(&b.x))
+        .unwrap();
+    assert!(first.start < b_start);
+    assert_eq!(&text[first.start..first.end], " ");
+}
@@ -0,0 +1,86 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, EllipsisSource, FontSystem, LayoutLine, LineEnding, Overflow,
+    Shaping, Wrap,
+};
+
+const TEXT: &str = "the quick brown fox jumps over the lazy dog";
+
+fn truncated(
+    font_system: &mut FontSystem,
+    text: &str,
+    width: f32,
+    max_lines: usize,
+) -> Vec<LayoutLine> {
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.set_overflow(Overflow::Truncate {
+        max_lines,
+        ellipsis: EllipsisSource::HorizontalEllipsis,
+    });
+    line.layout(font_system, 14.0, Some(width), Wrap::Word, None, 8)
+        .to_vec()
+}
+
+fn ellipsis_count(line: &LayoutLine) -> usize {
+    line.glyphs
+        .iter()
+        .filter(|glyph| glyph.start == glyph.end)
+        .count()
+}
+
+#[test]
+fn truncate_keeps_max_lines() {
+    let mut font_system = FontSystem::new();
+
+    let lines = truncated(&mut font_system, TEXT, 100.0, 2);
+    assert_eq!(lines.len(), 2);
+    assert_eq!(ellipsis_count(&lines[0]), 0);
+    assert_eq!(ellipsis_count(&lines[1]), 1);
+    assert!(lines.iter().all(|line| line.w <= 100.0));
+
+    // The ellipsis comes after the remaining text of the last line
+    let last = &lines[1];
+    let ellipsis = last.glyphs.last().unwrap();
+    assert_eq!(ellipsis.start, ellipsis.end);
+    assert!(last.glyphs[..last.glyphs.len() - 1]
+        .iter()
+        .all(|glyph| glyph.end <= ellipsis.start && glyph.x < ellipsis.x));
+}
+
+#[test]
+fn truncate_leaves_fitting_text() {
+    let mut font_system = FontSystem::new();
+
+    let lines = truncated(&mut font_system, TEXT, 100.0, 100);
+    assert!(lines.len() > 2);
+    assert!(lines.iter().all(|line| ellipsis_count(line) == 0));
+
+    // Zero lines is treated as one
+    let lines = truncated(&mut font_system, TEXT, 100.0, 0);
+    assert_eq!(lines.len(), 1);
+    assert_eq!(ellipsis_count(&lines[0]), 1);
+}
+
+#[test]
+fn truncate_rtl_ellipsis_on_left() {
+    let mut font_system = FontSystem::new();
+
+    let lines = truncated(&mut font_system, "שלום עולם שלום עולם שלום עולם", 60.0, 1);
+    assert_eq!(lines.len(), 1);
+    let first = lines[0].glyphs.first().unwrap();
+    assert_eq!(first.start, first.end);
+    assert!(lines[0].w <= 60.0);
+}
+
+#[test]
+fn truncate_ellipsis_does_not_fit() {
+    let mut font_system = FontSystem::new();
+
+    let lines = truncated(&mut font_system, TEXT, 1.0, 1);
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].glyphs.is_empty());
+}
@@ -0,0 +1,67 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, LineEnding, Shaping, SplitError};
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn try_split_off_flag_emoji() {
+    // Two regional indicators forming a single flag
+    let text = "a🇫🇷b";
+    let mut line = line(text);
+    assert_eq!(
+        line.try_split_off(5).unwrap_err(),
+        SplitError::InsideCluster {
+            index: 5,
+            cluster: 1..9
+        }
+    );
+    // Inside a char as well as between the two indicators
+    assert!(matches!(
+        line.try_split_off(2),
+        Err(SplitError::InsideCluster { .. })
+    ));
+    assert_eq!(line.text(), text);
+
+    let new = line.try_split_off(9).unwrap();
+    assert_eq!(line.text(), "a🇫🇷");
+    assert_eq!(new.text(), "b");
+}
+
+#[test]
+fn try_split_off_devanagari() {
+    // "क्षि" is one cluster: KA, VIRAMA, SSA, VOWEL SIGN I
+    let text = "क्षि न";
+    let mut line = line(text);
+    for index in [3, 6, 9] {
+        assert_eq!(
+            line.try_split_off(index).unwrap_err(),
+            SplitError::InsideCluster {
+                index,
+                cluster: 0..12
+            }
+        );
+    }
+    assert_eq!(line.text(), text);
+
+    let new = line.try_split_off(12).unwrap();
+    assert_eq!(line.text(), "क्षि");
+    assert_eq!(new.text(), " न");
+}
+
+#[test]
+fn try_split_off_bounds() {
+    let mut line = line("abc");
+    assert_eq!(
+        line.try_split_off(4).unwrap_err(),
+        SplitError::OutOfBounds { index: 4, len: 3 }
+    );
+    assert_eq!(line.try_split_off(3).unwrap().text(), "");
+    assert_eq!(line.try_split_off(0).unwrap().text(), "abc");
+    assert_eq!(line.text(), "");
+}
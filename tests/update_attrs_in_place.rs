@@ -0,0 +1,61 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, Color, Family, FontSystem, LineEnding, Shaping, Wrap,
+};
+
+const RED: Color = Color::rgb(0xFF, 0, 0);
+const BLUE: Color = Color::rgb(0, 0, 0xFF);
+
+fn attrs_list(first: Attrs, second: Attrs) -> AttrsList {
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(0..3, &first);
+    attrs_list.add_span(4..7, &second);
+    attrs_list
+}
+
+#[test]
+fn update_attrs_in_place_color_only() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "foo bar",
+        LineEnding::None,
+        attrs_list(Attrs::new().color(RED), Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8);
+
+    assert!(!line.update_attrs_in_place(attrs_list(
+        Attrs::new().color(BLUE),
+        Attrs::new().color(RED).metadata(7),
+    )));
+    assert!(line.shape_is_current(&font_system, 8));
+    assert!(line.layout_opt().is_none());
+
+    let glyphs = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0].glyphs;
+    assert_eq!(glyphs[0].color_opt, Some(BLUE));
+    assert_eq!(glyphs[3].color_opt, None);
+    assert_eq!(glyphs[4].color_opt, Some(RED));
+    assert_eq!(glyphs[4].metadata, 7);
+}
+
+#[test]
+fn update_attrs_in_place_mixed_falls_back() {
+    let mut font_system = FontSystem::new();
+    let mut line = BufferLine::new(
+        "foo bar",
+        LineEnding::None,
+        attrs_list(Attrs::new().color(RED), Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8);
+
+    // One span only changes color, the other changes the font family
+    assert!(line.update_attrs_in_place(attrs_list(
+        Attrs::new().color(BLUE),
+        Attrs::new().family(Family::Monospace),
+    )));
+    assert!(!line.shape_is_current(&font_system, 8));
+
+    let glyphs = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0].glyphs;
+    assert_eq!(glyphs[0].color_opt, Some(BLUE));
+    assert_ne!(glyphs[0].font_id, glyphs[4].font_id);
+}
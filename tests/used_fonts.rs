@@ -0,0 +1,54 @@
+use cosmic_text::{fontdb, Attrs, Buffer, Family, FontSystem, Metrics, Shaping};
+
+/// Fira Mono and Noto Sans Hebrew from the repository fonts
+fn font_system() -> FontSystem {
+    let mut font_system =
+        FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+    for path in ["fonts/FiraMono-Medium.ttf", "fonts/NotoSansHebrew.ttf"] {
+        let font = std::fs::read(path).unwrap();
+        font_system.db_mut().load_font_data(font);
+    }
+    font_system
+}
+
+fn family_name(font_system: &FontSystem, id: cosmic_text::fontdb::ID) -> String {
+    font_system.db().face(id).unwrap().families[0].0.clone()
+}
+
+#[test]
+fn used_fonts_includes_fallbacks() {
+    let mut font_system = font_system();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    // Fira Mono has no Hebrew, which falls back to Noto Sans Hebrew
+    buffer.set_text(
+        &mut font_system,
+        "hello\nשלום hello\nworld",
+        &Attrs::new().family(Family::Name("Fira Mono")),
+        Shaping::Advanced,
+    );
+    let used: Vec<String> = buffer
+        .used_fonts()
+        .into_iter()
+        .map(|id| family_name(&font_system, id))
+        .collect();
+    assert_eq!(used.len(), 2, "{used:?}");
+    assert_eq!(used[0], "Fira Mono");
+    assert_eq!(used[1], "Noto Sans Hebrew");
+}
+
+#[test]
+fn used_fonts_only_laid_out_lines() {
+    let mut buffer = Buffer::new_empty(Metrics::new(14.0, 20.0));
+    let mut font_system = font_system();
+    buffer.set_size(&mut font_system, None, Some(20.0));
+    buffer.set_text(
+        &mut font_system,
+        "hello\n\n\n\nשלום",
+        &Attrs::new().family(Family::Name("Fira Mono")),
+        Shaping::Advanced,
+    );
+    // Only the first lines fit in the height, so the Hebrew line is not laid out
+    assert_eq!(buffer.used_fonts().len(), 1);
+    buffer.line_layout(&mut font_system, 4);
+    assert_eq!(buffer.used_fonts().len(), 2);
+}
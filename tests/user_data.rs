@@ -0,0 +1,62 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, LineEnding, Shaping};
+
+#[derive(Clone, Debug, PartialEq)]
+struct Diagnostics {
+    revision: u64,
+    messages: Vec<String>,
+}
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn user_data_typed_access() {
+    let mut line = line("hello");
+    assert_eq!(line.user_data::<u64>(), None);
+
+    line.set_user_data(Diagnostics {
+        revision: 1,
+        messages: vec!["unused".to_string()],
+    });
+    assert_eq!(line.user_data::<Diagnostics>().unwrap().revision, 1);
+    assert_eq!(line.user_data::<u64>(), None);
+
+    // Clones carry their own copy
+    let mut clone = line.clone();
+    clone.set_user_data(Diagnostics {
+        revision: 2,
+        messages: Vec::new(),
+    });
+    assert_eq!(line.user_data::<Diagnostics>().unwrap().revision, 1);
+    assert_eq!(clone.user_data::<Diagnostics>().unwrap().revision, 2);
+
+    // Survives text changes, but not a reset
+    line.set_text("bye", LineEnding::None, AttrsList::new(&Attrs::new()));
+    assert!(line.user_data::<Diagnostics>().is_some());
+    line.reset();
+    assert!(line.user_data::<Diagnostics>().is_none());
+    assert_eq!(line.metadata(), None);
+}
+
+#[test]
+fn user_data_split_off_and_append() {
+    let mut first = line("hello world");
+    first.set_user_data(1u32);
+
+    // Stays on the original line
+    let mut second = first.split_off(5);
+    assert_eq!(first.user_data::<u32>(), Some(&1));
+    assert_eq!(second.user_data::<u32>(), None);
+
+    // The appended line's data is dropped
+    second.set_user_data(2u32);
+    first.append(second);
+    assert_eq!(first.text(), "hello world");
+    assert_eq!(first.user_data::<u32>(), Some(&1));
+}
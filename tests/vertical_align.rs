@@ -0,0 +1,74 @@
+use cosmic_text::{
+    Attrs, AttrsList, BufferLine, FontSystem, LayoutLine, LineEnding, Metrics, Shaping,
+    VerticalAlign, Wrap,
+};
+
+// "Big" at 40px followed by " small" at the 10px line font size
+fn layout(vertical_align: VerticalAlign) -> LayoutLine {
+    let mut font_system = FontSystem::new();
+    let mut attrs_list = AttrsList::new(&Attrs::new());
+    attrs_list.add_span(0..3, &Attrs::new().metrics(Metrics::new(40.0, 48.0)));
+    let mut line = BufferLine::new("Big small", LineEnding::None, attrs_list, Shaping::Advanced);
+    line.set_vertical_align(vertical_align);
+    let layout = line.layout(&mut font_system, 10.0, None, Wrap::None, None, 8);
+    assert_eq!(layout.len(), 1);
+    layout[0].clone()
+}
+
+// The y of the big and small glyphs, with the ascent and descent of the small glyphs
+fn ys(layout_line: &LayoutLine) -> (f32, f32, f32, f32) {
+    let big = layout_line.glyphs[0].y;
+    let small = layout_line.glyphs.last().unwrap();
+    // Both runs use the same font, so its metrics scale with the font size
+    let scale = small.font_size / layout_line.glyphs[0].font_size;
+    (
+        big,
+        small.y,
+        layout_line.max_ascent * scale,
+        layout_line.max_descent * scale,
+    )
+}
+
+#[test]
+fn vertical_align_baseline() {
+    let layout_line = layout(VerticalAlign::Baseline);
+    assert!(layout_line.glyphs.iter().all(|glyph| glyph.y == 0.0));
+}
+
+#[test]
+fn vertical_align_center() {
+    let layout_line = layout(VerticalAlign::Center);
+    let (big, small, ascent, descent) = ys(&layout_line);
+    assert!(big.abs() < 0.001, "{big}");
+    // The small glyphs are raised so their center is the center of the line
+    assert!(small < 0.0, "{small}");
+    let center = small + (descent - ascent) / 2.0;
+    let line_center = (layout_line.max_descent - layout_line.max_ascent) / 2.0;
+    assert!(
+        (center - line_center).abs() < 0.001,
+        "{center} {line_center}"
+    );
+    // The line box is unchanged
+    let baseline = layout(VerticalAlign::Baseline);
+    assert_eq!(layout_line.max_ascent, baseline.max_ascent);
+    assert_eq!(layout_line.max_descent, baseline.max_descent);
+}
+
+#[test]
+fn vertical_align_top_bottom() {
+    let layout_line = layout(VerticalAlign::Top);
+    let (big, small, ascent, _) = ys(&layout_line);
+    assert!(big.abs() < 0.001, "{big}");
+    assert!(
+        (small - ascent + layout_line.max_ascent).abs() < 0.001,
+        "{small}"
+    );
+
+    let layout_line = layout(VerticalAlign::Bottom);
+    let (big, small, _, descent) = ys(&layout_line);
+    assert!(big.abs() < 0.001, "{big}");
+    assert!(
+        (small + descent - layout_line.max_descent).abs() < 0.001,
+        "{small}"
+    );
+}
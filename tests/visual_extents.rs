@@ -0,0 +1,40 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn visual_extents_mixed_direction() {
+    let mut font_system = FontSystem::new();
+    // "abc " is LTR, followed by a Hebrew run that is reordered right to left
+    let text = "abc \u{5d0}\u{5d1}\u{5d2} def";
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let layout_line = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0];
+
+    let x_of = |index: usize| {
+        let glyph = layout_line
+            .glyphs
+            .iter()
+            .find(|glyph| glyph.start == index)
+            .unwrap();
+        (glyph.x, glyph.x + glyph.w)
+    };
+
+    // From "c" to the first Hebrew letter: the range starts on the left of "c" but the first
+    // Hebrew letter is the rightmost of its run, so the extent covers the whole run
+    let alef = text.find('\u{5d0}').unwrap();
+    let gimel = text.find('\u{5d2}').unwrap();
+    let (left, right) = layout_line.visual_extents(2..alef + 2).unwrap();
+    assert_eq!(left, x_of(2).0);
+    assert_eq!(right, x_of(alef).1);
+    assert!(right > x_of(gimel).1);
+
+    // Only the last Hebrew letter, which is drawn leftmost in its run
+    let (left, right) = layout_line.visual_extents(gimel..gimel + 2).unwrap();
+    assert_eq!((left, right), x_of(gimel));
+    assert!(right < x_of(alef).0);
+
+    assert_eq!(layout_line.visual_extents(100..200), None);
+}
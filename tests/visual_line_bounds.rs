@@ -0,0 +1,57 @@
+use cosmic_text::{
+    Affinity, Attrs, AttrsList, BaseDirection, BufferLine, Direction, FontSystem, LineEnding,
+    Shaping, Wrap,
+};
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn visual_line_bounds_bidi() {
+    let mut font_system = FontSystem::new();
+
+    // LTR paragraph ending in Hebrew: the visual end is the first Hebrew letter
+    let text = "abc \u{5d0}\u{5d1}\u{5d2}";
+    let mut ltr = line(text);
+    ltr.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+    assert_eq!(ltr.visual_line_bounds(0, Affinity::Before), Some((0, 4)));
+
+    // RTL paragraph starting with Latin: the visual start is after the Latin word
+    let text = "abc \u{5d0}\u{5d1}\u{5d2}";
+    let mut rtl = line(text);
+    rtl.set_base_direction(BaseDirection::Explicit(Direction::Rtl));
+    rtl.layout(&mut font_system, 14.0, None, Wrap::None, None, 8);
+    let (start, end) = rtl.visual_line_bounds(5, Affinity::Before).unwrap();
+    assert_eq!(start, 3);
+    assert_ne!(start, 0);
+    assert_eq!(end, text.len());
+
+    assert_eq!(line("abc").visual_line_bounds(0, Affinity::Before), None);
+}
+
+#[test]
+fn visual_line_bounds_wrap_affinity() {
+    let mut font_system = FontSystem::new();
+    let mut wrapped = line("hello world");
+    let layout = wrapped.layout(&mut font_system, 14.0, Some(50.0), Wrap::Word, None, 8);
+    assert_eq!(layout.len(), 2);
+
+    assert_eq!(
+        wrapped.visual_line_bounds(6, Affinity::Before),
+        Some((0, 5))
+    );
+    assert_eq!(
+        wrapped.visual_line_bounds(6, Affinity::After),
+        Some((6, 11))
+    );
+    assert_eq!(
+        wrapped.visual_line_bounds(8, Affinity::Before),
+        Some((6, 11))
+    );
+}
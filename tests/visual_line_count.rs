@@ -0,0 +1,31 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping, Wrap};
+
+#[test]
+fn visual_line_count_wrapping() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_wrap(Wrap::Word);
+    buffer.set_size(Some(80.0), Some(20.0));
+    let text = "one two three four five six seven\nshort\n".repeat(20);
+    buffer.set_text(&text, &Attrs::new(), Shaping::Advanced);
+
+    // Only the lines up to the scroll height are laid out, the rest are estimated
+    let estimate = buffer.visual_line_count();
+    let exact = buffer.visual_line_count_exact();
+    assert!(exact > buffer.lines.len(), "{exact}");
+    assert!(estimate < exact, "{estimate} {exact}");
+
+    // Now that all lines are laid out, the estimate is exact
+    assert_eq!(buffer.visual_line_count(), exact);
+}
+
+#[test]
+fn visual_line_count_no_wrap() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text("a\nb\nc", &Attrs::new(), Shaping::Advanced);
+    assert_eq!(buffer.visual_line_count_exact(), 3);
+    assert_eq!(buffer.visual_line_count(), 3);
+}
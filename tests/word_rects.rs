@@ -0,0 +1,77 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+#[test]
+fn word_rects_simple_sentence() {
+    let mut font_system = FontSystem::new();
+    let text = "Hello, big world.";
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let layout_line = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0];
+    let rects: Vec<_> = layout_line.word_rects(text).collect();
+
+    let ranges: Vec<_> = rects.iter().map(|(range, _)| range.clone()).collect();
+    assert_eq!(ranges, vec![0..5, 7..10, 11..16]);
+
+    for (range, rect) in rects.iter() {
+        assert_eq!(
+            Some((rect.x, rect.right())),
+            layout_line.visual_extents(range.clone())
+        );
+        assert_eq!(rect.y, 0.0);
+        assert_eq!(
+            rect.height,
+            layout_line.max_ascent + layout_line.max_descent
+        );
+    }
+    assert!(rects[0].1.right() < rects[1].1.x);
+    assert!(rects[1].1.right() < rects[2].1.x);
+}
+
+#[test]
+fn word_rects_do_not_cross_lines() {
+    let mut font_system = FontSystem::new();
+    let text = "one two three";
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let layout = line.layout(&mut font_system, 16.0, Some(40.0), Wrap::Word, None, 8);
+    assert!(layout.len() > 1);
+    for layout_line in layout.iter() {
+        for (range, rect) in layout_line.word_rects(text) {
+            assert!(layout_line
+                .glyphs
+                .iter()
+                .any(|glyph| glyph.start == range.start));
+            assert!(rect.x >= 0.0);
+        }
+    }
+    let words: usize = layout
+        .iter()
+        .map(|layout_line| layout_line.word_rects(text).count())
+        .sum();
+    assert_eq!(words, 3);
+}
+
+#[test]
+fn word_rects_rtl() {
+    let mut font_system = FontSystem::new();
+    // Two Hebrew words: the first is drawn to the right of the second
+    let text = "\u{5d0}\u{5d1} \u{5d2}\u{5d3}";
+    let mut line = BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    let layout_line = &line.layout(&mut font_system, 16.0, None, Wrap::None, None, 8)[0];
+    let rects: Vec<_> = layout_line.word_rects(text).collect();
+    assert_eq!(rects.len(), 2);
+    assert!(rects[0].1.x > rects[1].1.right());
+}
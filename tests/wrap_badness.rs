@@ -0,0 +1,53 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+fn line(text: &str) -> BufferLine {
+    BufferLine::new(
+        text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    )
+}
+
+#[test]
+fn wrap_badness_ragged_vs_balanced() {
+    let mut font_system = FontSystem::new();
+    let word = "wrapping";
+    let word_width = line(word).layout(&mut font_system, 14.0, None, Wrap::Word, None, 8)[0].w;
+    let width = word_width + 2.0;
+
+    // Every line is filled by one word
+    let mut balanced = line(&format!("{word} {word} {word}"));
+    let layout = balanced.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8);
+    assert_eq!(layout.len(), 3);
+    let balanced_badness = balanced.wrap_badness();
+
+    // The short first word leaves most of the first line empty
+    let mut ragged = line(&format!("a {word} {word}"));
+    let layout = ragged.layout(&mut font_system, 14.0, Some(width), Wrap::Word, None, 8);
+    assert_eq!(layout.len(), 3);
+    let expected: f32 = layout[..2]
+        .iter()
+        .map(|layout_line| (width - layout_line.w).powi(2))
+        .sum();
+    let ragged_badness = ragged.wrap_badness();
+    assert!((ragged_badness - expected).abs() < 0.01);
+
+    assert!(
+        balanced_badness < ragged_badness,
+        "{balanced_badness} {ragged_badness}"
+    );
+}
+
+#[test]
+fn wrap_badness_zero_without_wrapping() {
+    let mut font_system = FontSystem::new();
+    let mut line = line("one two three four");
+    assert_eq!(line.wrap_badness(), 0.0);
+    line.layout(&mut font_system, 14.0, None, Wrap::Word, None, 8);
+    assert_eq!(line.wrap_badness(), 0.0);
+    line.layout(&mut font_system, 14.0, Some(1000.0), Wrap::Word, None, 8);
+    assert_eq!(line.wrap_badness(), 0.0);
+    line.layout(&mut font_system, 14.0, Some(20.0), Wrap::None, None, 8);
+    assert_eq!(line.wrap_badness(), 0.0);
+}
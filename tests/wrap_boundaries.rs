@@ -0,0 +1,32 @@
+use cosmic_text::{Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn wrap_boundaries_match_layout_lines() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_text(
+        "the quick brown fox jumps over the lazy dog",
+        &Attrs::new(),
+        Shaping::Advanced,
+    );
+    assert!(buffer.lines[0].wrap_boundaries().is_empty());
+
+    buffer.set_size(Some(80.0), None);
+    let layout = buffer.line_layout(0).unwrap().to_vec();
+    assert!(layout.len() > 1);
+    let expected: Vec<usize> = layout[1..]
+        .iter()
+        .map(|layout_line| layout_line.glyphs[0].start)
+        .collect();
+    assert_eq!(buffer.lines[0].wrap_boundaries(), expected);
+
+    let text = buffer.lines[0].text();
+    for boundary in buffer.lines[0].wrap_boundaries() {
+        // Word wrapping breaks after the space
+        assert_eq!(&text[boundary - 1..*boundary], " ");
+    }
+
+    buffer.set_size(None, None);
+    assert!(buffer.lines[0].wrap_boundaries().is_empty());
+}
@@ -0,0 +1,48 @@
+use cosmic_text::{Attrs, AttrsList, BufferLine, FontSystem, LineEnding, Shaping, Wrap};
+
+const TOKEN: &str = "token_a1b2c3d4e5f6g7h8i9j0k1l2m3n4o5p6q7r8s9t0u1v2w3x4";
+
+fn line_texts(font_system: &mut FontSystem, wrap: Wrap) -> Vec<String> {
+    let text = format!("see the docs at {TOKEN} for more details");
+    let mut line = BufferLine::new(
+        &text,
+        LineEnding::None,
+        AttrsList::new(&Attrs::new()),
+        Shaping::Advanced,
+    );
+    line.layout(font_system, 14.0, Some(150.0), wrap, None, 8)
+        .iter()
+        .map(|layout_line| {
+            let start = layout_line.glyphs.iter().map(|glyph| glyph.start).min();
+            let end = layout_line.glyphs.iter().map(|glyph| glyph.end).max();
+            text[start.unwrap_or(0)..end.unwrap_or(0)].to_string()
+        })
+        .collect()
+}
+
+#[test]
+fn word_or_glyph_breaks_only_long_words() {
+    let mut font_system = FontSystem::new();
+
+    // Word wrapping lets the token overflow on its own line
+    let word = line_texts(&mut font_system, Wrap::Word);
+    assert!(word.iter().any(|line| line.trim() == TOKEN));
+
+    // Word or glyph wrapping breaks the token, which starts on its own line
+    let word_or_glyph = line_texts(&mut font_system, Wrap::WordOrGlyph);
+    assert!(word_or_glyph.len() > word.len());
+    let token_start = word_or_glyph
+        .iter()
+        .position(|line| line.starts_with("token_"))
+        .unwrap();
+    assert!(word_or_glyph[token_start].len() < TOKEN.len());
+    // Lines of normal words are still broken between words
+    for line in &word_or_glyph[..token_start] {
+        assert!("see the docs at ".contains(line.as_str()), "{line:?}");
+    }
+    assert!(word_or_glyph.last().unwrap().ends_with("details"));
+    assert_eq!(
+        word_or_glyph.concat().replace(' ', ""),
+        word.concat().replace(' ', "")
+    );
+}
@@ -0,0 +1,33 @@
+use cosmic_text::{Align, Attrs, Buffer, FontSystem, Metrics, Shaping};
+
+#[test]
+fn wrap_width_override_per_line() {
+    let mut font_system = FontSystem::new();
+    let mut buffer = Buffer::new(&mut font_system, Metrics::new(14.0, 20.0));
+    let mut buffer = buffer.borrow_with(&mut font_system);
+    buffer.set_size(Some(1000.0), None);
+    let text = "the quick brown fox jumps over the lazy dog";
+    buffer.set_text(&format!("{text}\n{text}"), &Attrs::new(), Shaping::Advanced);
+
+    assert!(buffer.lines[1].set_wrap_width(Some(100.0)));
+    assert!(!buffer.lines[1].set_wrap_width(Some(100.0)));
+    buffer.lines[1].set_align(Some(Align::Right));
+    buffer.shape_until_scroll(false);
+
+    let wide = buffer.line_layout(0).unwrap();
+    assert_eq!(wide.len(), 1);
+
+    let narrow = buffer.line_layout(1).unwrap();
+    assert!(narrow.len() > 1);
+    for layout_line in narrow {
+        assert!(layout_line.w <= 100.0);
+        // Right aligned within the overridden width (give or take trailing whitespace), not
+        // the buffer width
+        let right = layout_line
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.x + glyph.w)
+            .fold(0.0, f32::max);
+        assert!((right - 100.0).abs() < 5.0, "right edge {right}");
+    }
+}
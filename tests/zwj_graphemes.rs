@@ -0,0 +1,117 @@
+use cosmic_text::{Attrs, Buffer, Cursor, FontSystem, Metrics, Motion, Shaping};
+use unicode_segmentation::UnicodeSegmentation;
+
+const SEQUENCES: [&str; 6] = [
+    // Family: man, woman, girl
+    "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}",
+    // Woman technologist
+    "\u{1F469}\u{200D}\u{1F4BB}",
+    // Flag of France
+    "\u{1F1EB}\u{1F1F7}",
+    // Rainbow flag
+    "\u{1F3F3}\u{FE0F}\u{200D}\u{1F308}",
+    // Woman health worker, where only the staff of Aesculapius has a glyph in the test fonts
+    "\u{1F469}\u{200D}\u{2695}\u{FE0F}",
+    // Woman running, where only the female sign has a glyph in the test fonts
+    "\u{1F3C3}\u{200D}\u{2640}\u{FE0F}",
+];
+
+fn buffer(font_system: &mut FontSystem, text: &str, shaping: Shaping) -> Buffer {
+    let mut buffer = Buffer::new(font_system, Metrics::new(14.0, 20.0));
+    buffer.set_size(font_system, Some(500.0), None);
+    buffer.set_text(font_system, text, &Attrs::new(), shaping);
+    buffer.shape_until_scroll(font_system, false);
+    buffer
+}
+
+fn cases() -> impl Iterator<Item = (&'static str, Shaping)> {
+    [Shaping::Basic, Shaping::Advanced]
+        .into_iter()
+        .flat_map(|shaping| {
+            SEQUENCES
+                .into_iter()
+                .map(move |sequence| (sequence, shaping))
+        })
+}
+
+fn stops(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    motion: Motion,
+    start: usize,
+) -> Vec<usize> {
+    let mut cursor = Cursor::new(0, start);
+    let mut indices = Vec::new();
+    while let Some((next, _)) = buffer.cursor_motion(font_system, cursor, None, motion) {
+        if next.index == cursor.index {
+            break;
+        }
+        cursor = next;
+        indices.push(cursor.index);
+    }
+    indices
+}
+
+#[test]
+fn zwj_sequences_are_single_graphemes() {
+    let mut font_system = FontSystem::new();
+    for (sequence, shaping) in cases() {
+        let text = format!("a{sequence}b");
+        assert_eq!(text.graphemes(true).count(), 3, "{sequence:?} {shaping:?}");
+        let end = 1 + sequence.len();
+
+        let mut buffer = buffer(&mut font_system, &text, shaping);
+        assert_eq!(
+            stops(&mut buffer, &mut font_system, Motion::Next, 0),
+            [1, end, end + 1],
+            "{sequence:?} {shaping:?}"
+        );
+        assert_eq!(
+            stops(&mut buffer, &mut font_system, Motion::Previous, end + 1),
+            [end, 1, 0],
+            "{sequence:?} {shaping:?}"
+        );
+    }
+}
+
+#[test]
+fn zwj_sequences_are_single_clusters() {
+    let mut font_system = FontSystem::new();
+    for (sequence, shaping) in cases() {
+        let text = format!("a{sequence}b");
+        let end = 1 + sequence.len();
+        let buffer = buffer(&mut font_system, &text, shaping);
+
+        // Glyphs from fallback fonts cover the whole sequence, never part of it
+        let run = buffer.layout_runs().next().unwrap();
+        let sequence_glyphs: Vec<_> = run
+            .glyphs
+            .iter()
+            .filter(|glyph| glyph.start >= 1 && glyph.end <= end)
+            .collect();
+        assert!(!sequence_glyphs.is_empty(), "{sequence:?} {shaping:?}");
+        for glyph in sequence_glyphs.iter() {
+            assert_eq!(
+                (glyph.start, glyph.end),
+                (1, end),
+                "{sequence:?} {shaping:?}"
+            );
+        }
+
+        // Clicking anywhere in the sequence places the cursor at one of its edges
+        let left = sequence_glyphs.first().unwrap().x;
+        let right = sequence_glyphs
+            .last()
+            .map(|glyph| glyph.x + glyph.w)
+            .unwrap();
+        let mut x = left + 0.5;
+        while x < right {
+            let cursor = buffer.hit(x, 10.0).unwrap();
+            assert!(
+                cursor.index == 1 || cursor.index == end,
+                "{sequence:?} {shaping:?} {x}"
+            );
+            x += 1.0;
+        }
+    }
+}